@@ -0,0 +1,414 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::error::Error;
+use std::path::PathBuf;
+
+/// Default expiry of `presign`, in seconds
+const DEFAULT_EXPIRY_SECS: u64 = 3_600;
+
+/// Flags without a value, by their short and long name
+const FLAGS: [(Option<char>, &str); 7] = [
+    (Some('r'), "--recursive"),
+    (Some('l'), "--long"),
+    (Some('p'), "--ignore-existing"),
+    (None, "--dry-run"),
+    (None, "--force"),
+    (None, "--delete"),
+    (None, "--json"),
+];
+
+/// Flags followed by a value, I.E. `--expiry 60` or `--expiry=60`
+const VALUE_FLAGS: [&str; 3] = ["--config", "--expiry", "--exclude"];
+
+/// An object, or the objects under a prefix, in a bucket, written as
+/// `s3://bucket/key` or `bucket/key`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemotePath {
+    pub bucket_name: String,
+    pub key: String,
+}
+
+impl RemotePath {
+    /// Parses `s3://bucket/key` or `bucket/key`, where the key may be empty
+    pub fn parse(path: &str) -> Result<Self, Error> {
+        let path = path.strip_prefix("s3://").unwrap_or(path);
+        let (bucket_name, key) = path.split_once('/').unwrap_or((path, ""));
+
+        if bucket_name.is_empty() {
+            return Err(Error::Internal(format!(
+                "`{}` does not name a bucket",
+                path
+            )));
+        }
+
+        Ok(Self {
+            bucket_name: bucket_name.to_string(),
+            key: key.to_string(),
+        })
+    }
+
+    /// Returns the key, or the key joined with the last segment of `name`
+    /// if the key is empty or ends with `/`, I.E. copying into a prefix
+    pub fn key_or_join(&self, name: &str) -> String {
+        if self.key.is_empty() || self.key.ends_with('/') {
+            let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+
+            format!("{}{}", self.key, name)
+        } else {
+            self.key.clone()
+        }
+    }
+}
+
+impl std::fmt::Display for RemotePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "s3://{}/{}", self.bucket_name, self.key)
+    }
+}
+
+/// A source or target of `cp` and `mirror`, remote if it
+/// starts with `s3://`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Local(PathBuf),
+    Remote(RemotePath),
+}
+
+impl Location {
+    pub fn parse(location: &str) -> Result<Self, Error> {
+        match location.starts_with("s3://") {
+            true => RemotePath::parse(location).map(Location::Remote),
+            false => Ok(Location::Local(PathBuf::from(location))),
+        }
+    }
+}
+
+/// The method of the request signed by `presign`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresignMethod {
+    Get,
+    Put,
+    Delete,
+}
+
+/// A command of the `minior` binary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Ls {
+        target: Option<RemotePath>,
+        recursive: bool,
+        long: bool,
+    },
+    Cp {
+        src: Location,
+        dst: Location,
+    },
+    Rm {
+        target: RemotePath,
+        recursive: bool,
+        dry_run: bool,
+    },
+    Mb {
+        bucket_name: String,
+        ignore_existing: bool,
+    },
+    Rb {
+        bucket_name: String,
+        force: bool,
+    },
+    Stat {
+        target: RemotePath,
+    },
+    Presign {
+        method: PresignMethod,
+        target: RemotePath,
+        expiry_secs: u64,
+    },
+    Cat {
+        target: RemotePath,
+    },
+    Mirror {
+        src: Location,
+        dst: Location,
+        delete: bool,
+        dry_run: bool,
+        exclude: Vec<String>,
+    },
+    Du {
+        target: RemotePath,
+    },
+    Help,
+}
+
+/// The parsed arguments of the `minior` binary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cli {
+    pub command: Command,
+    /// Prints JSON instead of text, except for `cat`
+    pub json: bool,
+    /// Overrides the path of the config file
+    pub config: Option<PathBuf>,
+}
+
+/// Arguments split into positionals, flags and flag values
+#[derive(Default)]
+struct Args {
+    positionals: Vec<String>,
+    flags: Vec<&'static str>,
+    values: Vec<(&'static str, String)>,
+}
+
+impl Args {
+    fn split(args: impl IntoIterator<Item = String>) -> Result<Self, Error> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                parsed.positionals.insert(0, "help".to_string());
+                continue;
+            }
+
+            if arg.starts_with("--") {
+                let (name, value) = match arg.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (arg.as_str(), None),
+                };
+
+                if let Some(flag) = VALUE_FLAGS.iter().find(|flag| **flag == name) {
+                    let value = match value {
+                        Some(value) => value,
+                        None => args
+                            .next()
+                            .ok_or(Error::Internal(format!("{} requires a value", flag)))?,
+                    };
+
+                    parsed.values.push((flag, value));
+                } else if let Some((_, flag)) = FLAGS.iter().find(|(_, flag)| *flag == name) {
+                    parsed.flags.push(flag);
+                } else {
+                    return Err(Error::Internal(format!("Unknown flag {}", name)));
+                }
+            } else if arg.len() > 1 && arg.starts_with('-') {
+                for short in arg.chars().skip(1) {
+                    let (_, flag) = FLAGS
+                        .iter()
+                        .find(|(flag, _)| *flag == Some(short))
+                        .ok_or(Error::Internal(format!("Unknown flag -{}", short)))?;
+
+                    parsed.flags.push(flag);
+                }
+            } else {
+                parsed.positionals.push(arg);
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Errors if a flag other than `--json`, `--config` or one of `allowed` was given
+    fn allow(&self, command: &str, allowed: &[&str]) -> Result<(), Error> {
+        let given = self
+            .flags
+            .iter()
+            .copied()
+            .chain(self.values.iter().map(|(flag, _)| *flag));
+
+        for flag in given {
+            if flag != "--json" && flag != "--config" && !allowed.contains(&flag) {
+                return Err(Error::Internal(format!(
+                    "{} does not accept {}",
+                    command, flag
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn flag(&self, flag: &str) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    fn values(&self, flag: &str) -> Vec<String> {
+        self.values
+            .iter()
+            .filter(|(name, _)| *name == flag)
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    fn value(&self, flag: &str) -> Option<String> {
+        self.values(flag).pop()
+    }
+
+    /// Returns the positionals after the command, erroring unless
+    /// there are between `min` and `max`
+    fn operands(&self, command: &str, min: usize, max: usize) -> Result<&[String], Error> {
+        let operands = &self.positionals[1..];
+
+        if operands.len() < min || operands.len() > max {
+            return Err(Error::Internal(format!(
+                "Wrong number of arguments for {}, see `minior help`",
+                command
+            )));
+        }
+
+        Ok(operands)
+    }
+}
+
+impl Cli {
+    /// Parses the arguments of the `minior` binary, without the
+    /// name of the binary
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let cli = Cli::parse(std::env::args().skip(1))?;
+    /// ```
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, Error> {
+        let args = Args::split(args)?;
+
+        let Some(command) = args.positionals.first() else {
+            return Ok(Self {
+                command: Command::Help,
+                json: false,
+                config: None,
+            });
+        };
+
+        let command = match command.as_str() {
+            "ls" => {
+                args.allow(command, &["--recursive", "--long"])?;
+                let operands = args.operands(command, 0, 1)?;
+
+                Command::Ls {
+                    target: operands
+                        .first()
+                        .map(|target| RemotePath::parse(target))
+                        .transpose()?,
+                    recursive: args.flag("--recursive"),
+                    long: args.flag("--long"),
+                }
+            }
+            "cp" => {
+                args.allow(command, &[])?;
+                let operands = args.operands(command, 2, 2)?;
+
+                Command::Cp {
+                    src: Location::parse(&operands[0])?,
+                    dst: Location::parse(&operands[1])?,
+                }
+            }
+            "rm" => {
+                args.allow(command, &["--recursive", "--dry-run"])?;
+                let operands = args.operands(command, 1, 1)?;
+
+                Command::Rm {
+                    target: RemotePath::parse(&operands[0])?,
+                    recursive: args.flag("--recursive"),
+                    dry_run: args.flag("--dry-run"),
+                }
+            }
+            "mb" => {
+                args.allow(command, &["--ignore-existing"])?;
+                let operands = args.operands(command, 1, 1)?;
+
+                Command::Mb {
+                    bucket_name: RemotePath::parse(&operands[0])?.bucket_name,
+                    ignore_existing: args.flag("--ignore-existing"),
+                }
+            }
+            "rb" => {
+                args.allow(command, &["--force"])?;
+                let operands = args.operands(command, 1, 1)?;
+
+                Command::Rb {
+                    bucket_name: RemotePath::parse(&operands[0])?.bucket_name,
+                    force: args.flag("--force"),
+                }
+            }
+            "stat" => {
+                args.allow(command, &[])?;
+                let operands = args.operands(command, 1, 1)?;
+
+                Command::Stat {
+                    target: RemotePath::parse(&operands[0])?,
+                }
+            }
+            "presign" => {
+                args.allow(command, &["--expiry"])?;
+                let operands = args.operands(command, 2, 2)?;
+
+                let method = match operands[0].as_str() {
+                    "get" => PresignMethod::Get,
+                    "put" => PresignMethod::Put,
+                    "delete" => PresignMethod::Delete,
+                    method => {
+                        return Err(Error::Internal(format!(
+                            "Unknown presign method {}, expected get, put or delete",
+                            method
+                        )))
+                    }
+                };
+
+                let expiry_secs = match args.value("--expiry") {
+                    Some(expiry) => expiry.parse().map_err(|_| {
+                        Error::Internal(format!("--expiry {} is not a number of seconds", expiry))
+                    })?,
+                    None => DEFAULT_EXPIRY_SECS,
+                };
+
+                Command::Presign {
+                    method,
+                    target: RemotePath::parse(&operands[1])?,
+                    expiry_secs,
+                }
+            }
+            "cat" => {
+                args.allow(command, &[])?;
+                let operands = args.operands(command, 1, 1)?;
+
+                Command::Cat {
+                    target: RemotePath::parse(&operands[0])?,
+                }
+            }
+            "mirror" => {
+                args.allow(command, &["--delete", "--dry-run", "--exclude"])?;
+                let operands = args.operands(command, 2, 2)?;
+
+                Command::Mirror {
+                    src: Location::parse(&operands[0])?,
+                    dst: Location::parse(&operands[1])?,
+                    delete: args.flag("--delete"),
+                    dry_run: args.flag("--dry-run"),
+                    exclude: args.values("--exclude"),
+                }
+            }
+            "du" => {
+                args.allow(command, &[])?;
+                let operands = args.operands(command, 1, 1)?;
+
+                Command::Du {
+                    target: RemotePath::parse(&operands[0])?,
+                }
+            }
+            "help" => Command::Help,
+            command => {
+                return Err(Error::Internal(format!(
+                    "Unknown command {}, see `minior help`",
+                    command
+                )))
+            }
+        };
+
+        Ok(Self {
+            command,
+            json: args.flag("--json"),
+            config: args.value("--config").map(PathBuf::from),
+        })
+    }
+}
@@ -0,0 +1,677 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{
+    args::{Command, Location, PresignMethod, RemotePath},
+    USAGE,
+};
+use crate::{
+    core::{
+        compose::{ComposeObjectAdditionalOptions, ComposeSource},
+        presign::uri_encode,
+        replicate::{replicate, ReplicateOptions, MAX_COPY_OBJECT_SIZE},
+        sync::{normalize_prefix, SyncOptions, SyncReport},
+    },
+    error::Error,
+    Minio,
+};
+use aws_sdk_s3::{
+    primitives::{DateTime, DateTimeFormat},
+    types::{Delete, MetadataDirective, ObjectIdentifier},
+};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use tokio::{
+    fs::File,
+    io::{AsyncWrite, AsyncWriteExt},
+};
+
+/// Maximum amount of keys of a `DeleteObjects` request
+const MAX_DELETE_KEYS: usize = 1_000;
+
+/// The output of a command, as lines of text or a JSON document
+struct Report {
+    lines: Vec<String>,
+    json: Value,
+}
+
+fn date(date_time: Option<&DateTime>) -> String {
+    date_time
+        .and_then(|date_time| date_time.fmt(DateTimeFormat::DateTime).ok())
+        .unwrap_or_default()
+}
+
+fn not_found(target: &RemotePath) -> Error {
+    Error::Internal(format!("Object {} does not exist", target))
+}
+
+/// Errors unless `target` names an object rather than a prefix
+fn require_key(command: &str, target: &RemotePath) -> Result<(), Error> {
+    match target.key.is_empty() || target.key.ends_with('/') {
+        true => Err(Error::Internal(format!(
+            "{} requires an object, {} is a prefix",
+            command, target
+        ))),
+        false => Ok(()),
+    }
+}
+
+impl Command {
+    /// Runs the command with `minio`, writing its output to `out`
+    /// as text, or as a single JSON document if `json` is `true`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let cli = Cli::parse(["ls", "s3://sharks", "--long"].map(String::from))?;
+    ///
+    /// cli.command.run(&minio, cli.json, &mut tokio::io::stdout()).await?;
+    /// ```
+    pub async fn run<W>(&self, minio: &Minio, json: bool, out: &mut W) -> Result<(), Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        // Boxed as the futures of the commands are too large for the stack in debug builds
+        if let Command::Cat { target } = self {
+            return Box::pin(cat(minio, target, out)).await;
+        }
+
+        let report = Box::pin(self.report(minio)).await?;
+
+        let output = match json {
+            true => format!("{}\n", report.json),
+            false => report
+                .lines
+                .iter()
+                .map(|line| format!("{}\n", line))
+                .collect(),
+        };
+
+        out.write_all(output.as_bytes())
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+        out.flush().await.map_err(|err| Error::StdIo(err.kind()))
+    }
+
+    /// Runs any command but `cat`, which streams instead of reporting
+    async fn report(&self, minio: &Minio) -> Result<Report, Error> {
+        Ok(match self {
+            Command::Ls {
+                target,
+                recursive,
+                long,
+            } => ls(minio, target.as_ref(), *recursive, *long).await?,
+            Command::Cp { src, dst } => cp(minio, src, dst).await?,
+            Command::Rm {
+                target,
+                recursive,
+                dry_run,
+            } => rm(minio, target, *recursive, *dry_run).await?,
+            Command::Mb {
+                bucket_name,
+                ignore_existing,
+            } => mb(minio, bucket_name, *ignore_existing).await?,
+            Command::Rb { bucket_name, force } => rb(minio, bucket_name, *force).await?,
+            Command::Stat { target } => stat(minio, target).await?,
+            Command::Presign {
+                method,
+                target,
+                expiry_secs,
+            } => presign(minio, *method, target, *expiry_secs).await?,
+            Command::Cat { .. } => unreachable!("cat writes to the output directly"),
+            Command::Mirror {
+                src,
+                dst,
+                delete,
+                dry_run,
+                exclude,
+            } => mirror(minio, src, dst, *delete, *dry_run, exclude).await?,
+            Command::Du { target } => du(minio, target).await?,
+            Command::Help => Report {
+                lines: vec![USAGE.trim_end().to_string()],
+                json: json!({ "usage": USAGE }),
+            },
+        })
+    }
+}
+
+async fn ls(
+    minio: &Minio,
+    target: Option<&RemotePath>,
+    recursive: bool,
+    long: bool,
+) -> Result<Report, Error> {
+    let Some(target) = target else {
+        let buckets = minio.list_buckets().await?;
+
+        return Ok(Report {
+            lines: buckets
+                .iter()
+                .map(|bucket| {
+                    let name = bucket.name().unwrap_or_default();
+
+                    match long {
+                        true => format!("{:<20} {}/", date(bucket.creation_date()), name),
+                        false => format!("{}/", name),
+                    }
+                })
+                .collect(),
+            json: Value::Array(
+                buckets
+                    .iter()
+                    .map(|bucket| {
+                        json!({
+                            "bucket": bucket.name(),
+                            "created": date(bucket.creation_date()),
+                        })
+                    })
+                    .collect(),
+            ),
+        });
+    };
+
+    let objects = minio
+        .list_objects_with_prefix(&target.bucket_name, &target.key)
+        .await?;
+
+    // Without `recursive`, keys past the next `/` are grouped as a directory
+    let mut entries = BTreeMap::new();
+
+    for object in &objects {
+        let key = object.key().unwrap_or_default();
+
+        match key[target.key.len()..].find('/') {
+            Some(index) if !recursive => {
+                entries.insert(key[..target.key.len() + index + 1].to_string(), None);
+            }
+            _ => {
+                entries.insert(key.to_string(), Some(object));
+            }
+        }
+    }
+
+    Ok(Report {
+        lines: entries
+            .iter()
+            .map(|(key, object)| match (long, object) {
+                (false, _) => key.clone(),
+                (true, Some(object)) => format!(
+                    "{:<20} {:>12} {}",
+                    date(object.last_modified()),
+                    object.size().unwrap_or_default(),
+                    key
+                ),
+                (true, None) => format!("{:<20} {:>12} {}", "", "DIR", key),
+            })
+            .collect(),
+        json: Value::Array(
+            entries
+                .iter()
+                .map(|(key, object)| match object {
+                    Some(object) => json!({
+                        "key": key,
+                        "size": object.size().unwrap_or_default(),
+                        "last_modified": date(object.last_modified()),
+                        "etag": object.e_tag(),
+                    }),
+                    None => json!({ "key": key, "dir": true }),
+                })
+                .collect(),
+        ),
+    })
+}
+
+fn copied(src: impl std::fmt::Display, dst: impl std::fmt::Display, bytes: usize) -> Report {
+    Report {
+        lines: vec![format!("{} -> {} ({} bytes)", src, dst, bytes)],
+        json: json!({
+            "source": src.to_string(),
+            "target": dst.to_string(),
+            "bytes": bytes,
+        }),
+    }
+}
+
+async fn cp(minio: &Minio, src: &Location, dst: &Location) -> Result<Report, Error> {
+    match (src, dst) {
+        (Location::Local(path), Location::Remote(dst)) => {
+            let dst = RemotePath {
+                bucket_name: dst.bucket_name.clone(),
+                key: dst.key_or_join(&path.to_string_lossy()),
+            };
+            let file = File::open(path)
+                .await
+                .map_err(|err| Error::StdIo(err.kind()))?;
+
+            let bytes = minio
+                .upload_object(&dst.bucket_name, &dst.key, file, None)
+                .await?;
+
+            Ok(copied(path.display(), dst, bytes))
+        }
+        (Location::Remote(src), Location::Local(path)) => {
+            require_key("cp", src)?;
+
+            let path = match tokio::fs::metadata(path).await {
+                Ok(metadata) if metadata.is_dir() => {
+                    path.join(src.key.rsplit('/').next().unwrap_or(&src.key))
+                }
+                _ => path.clone(),
+            };
+
+            let stream = minio
+                .get_object(&src.bucket_name, &src.key, None)
+                .await?
+                .ok_or(not_found(src))?;
+            let mut file = File::create(&path)
+                .await
+                .map_err(|err| Error::StdIo(err.kind()))?;
+
+            let bytes = tokio::io::copy_buf(&mut Box::pin(stream), &mut file)
+                .await
+                .map_err(|err| Error::StdIo(err.kind()))?;
+            file.flush().await.map_err(|err| Error::StdIo(err.kind()))?;
+
+            Ok(copied(src, path.display(), bytes as usize))
+        }
+        (Location::Remote(src), Location::Remote(dst)) => {
+            require_key("cp", src)?;
+
+            let dst = RemotePath {
+                bucket_name: dst.bucket_name.clone(),
+                key: dst.key_or_join(&src.key),
+            };
+            let stat = minio
+                .stat_object(&src.bucket_name, &src.key, None)
+                .await?
+                .ok_or(not_found(src))?;
+            let size = stat.content_length().unwrap_or_default();
+
+            // `CopyObject` keeps the metadata, standard headers and `ETag`,
+            // larger sources are composed with them copied from `stat`
+            if size <= MAX_COPY_OBJECT_SIZE {
+                minio
+                    .client
+                    .copy_object()
+                    .bucket(&dst.bucket_name)
+                    .key(&dst.key)
+                    .copy_source(format!(
+                        "{}/{}",
+                        uri_encode(&src.bucket_name, true),
+                        uri_encode(&src.key, false)
+                    ))
+                    .metadata_directive(MetadataDirective::Copy)
+                    .send()
+                    .await
+                    .map_err(Error::sdk)?;
+
+                return Ok(copied(src, dst, size as usize));
+            }
+
+            let expires = stat
+                .expires_string()
+                .and_then(|expires| DateTime::from_str(expires, DateTimeFormat::HttpDate).ok());
+            let bytes = minio
+                .compose_object(
+                    &dst.bucket_name,
+                    &dst.key,
+                    vec![ComposeSource::new(&src.bucket_name, &src.key)],
+                    Some(ComposeObjectAdditionalOptions {
+                        metadata: stat.metadata,
+                        content_type: stat.content_type,
+                        content_encoding: stat.content_encoding,
+                        cache_control: stat.cache_control,
+                        content_disposition: stat.content_disposition,
+                        expires,
+                        #[cfg(feature = "sse")]
+                        encryption: None,
+                    }),
+                )
+                .await?;
+
+            Ok(copied(src, dst, bytes))
+        }
+        (Location::Local(_), Location::Local(_)) => Err(Error::internal(
+            "cp requires a remote source or target, I.E. s3://bucket/key",
+        )),
+    }
+}
+
+async fn rm(
+    minio: &Minio,
+    target: &RemotePath,
+    recursive: bool,
+    dry_run: bool,
+) -> Result<Report, Error> {
+    let keys = match recursive {
+        // `s3://sharks/images` removes `images/`, not `images-old/`
+        true => minio
+            .list_objects_with_prefix(&target.bucket_name, &normalize_prefix(&target.key))
+            .await?
+            .iter()
+            .filter_map(|object| object.key().map(str::to_string))
+            .collect::<Vec<_>>(),
+        false => {
+            require_key("rm", target)?;
+
+            if !minio
+                .object_exists(&target.bucket_name, &target.key)
+                .await?
+            {
+                return Err(not_found(target));
+            }
+
+            vec![target.key.clone()]
+        }
+    };
+
+    if !dry_run {
+        for keys in keys.chunks(MAX_DELETE_KEYS) {
+            let objects = keys
+                .iter()
+                .map(|key| ObjectIdentifier::builder().key(key).build())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(Error::sdk)?;
+
+            let output = minio
+                .client
+                .delete_objects()
+                .bucket(&target.bucket_name)
+                .delete(
+                    Delete::builder()
+                        .set_objects(Some(objects))
+                        .quiet(true)
+                        .build()
+                        .map_err(Error::sdk)?,
+                )
+                .send()
+                .await
+                .map_err(Error::sdk)?;
+
+            if let Some(error) = output.errors().first() {
+                return Err(Error::Internal(format!(
+                    "Failed to remove s3://{}/{}: {}",
+                    target.bucket_name,
+                    error.key().unwrap_or_default(),
+                    error.message().unwrap_or_default()
+                )));
+            }
+        }
+    }
+
+    let action = match dry_run {
+        true => "Would remove",
+        false => "Removed",
+    };
+
+    Ok(Report {
+        lines: keys
+            .iter()
+            .map(|key| format!("{} s3://{}/{}", action, target.bucket_name, key))
+            .collect(),
+        json: json!({ "removed": keys, "dry_run": dry_run }),
+    })
+}
+
+async fn mb(minio: &Minio, bucket_name: &str, ignore_existing: bool) -> Result<Report, Error> {
+    let created = minio.create_bucket(bucket_name).await?;
+
+    if !created && !ignore_existing {
+        return Err(Error::Internal(format!(
+            "Bucket {} already exists",
+            bucket_name
+        )));
+    }
+
+    Ok(Report {
+        lines: vec![match created {
+            true => format!("Bucket {} created", bucket_name),
+            false => format!("Bucket {} already exists", bucket_name),
+        }],
+        json: json!({ "bucket": bucket_name, "created": created }),
+    })
+}
+
+async fn rb(minio: &Minio, bucket_name: &str, force: bool) -> Result<Report, Error> {
+    if !minio.delete_bucket(bucket_name, force).await? {
+        return Err(Error::Internal(format!(
+            "Bucket {} does not exist",
+            bucket_name
+        )));
+    }
+
+    Ok(Report {
+        lines: vec![format!("Bucket {} removed", bucket_name)],
+        json: json!({ "bucket": bucket_name, "removed": true }),
+    })
+}
+
+async fn stat(minio: &Minio, target: &RemotePath) -> Result<Report, Error> {
+    require_key("stat", target)?;
+
+    let stat = minio
+        .stat_object(&target.bucket_name, &target.key, None)
+        .await?
+        .ok_or(not_found(target))?;
+
+    let metadata = stat
+        .metadata()
+        .map(|metadata| metadata.iter().collect::<BTreeMap<_, _>>())
+        .unwrap_or_default();
+
+    let mut lines = vec![
+        format!("Key: {}", target),
+        format!("Size: {}", stat.content_length().unwrap_or_default()),
+        format!("ETag: {}", stat.e_tag().unwrap_or_default()),
+        format!("Content-Type: {}", stat.content_type().unwrap_or_default()),
+        format!("Last-Modified: {}", date(stat.last_modified())),
+    ];
+
+    if let Some(version_id) = stat.version_id() {
+        lines.push(format!("Version-Id: {}", version_id));
+    }
+
+    for (name, value) in &metadata {
+        lines.push(format!("Metadata: {}={}", name, value));
+    }
+
+    Ok(Report {
+        lines,
+        json: json!({
+            "key": target.to_string(),
+            "size": stat.content_length().unwrap_or_default(),
+            "etag": stat.e_tag(),
+            "content_type": stat.content_type(),
+            "last_modified": date(stat.last_modified()),
+            "version_id": stat.version_id(),
+            "metadata": metadata,
+        }),
+    })
+}
+
+async fn presign(
+    minio: &Minio,
+    method: PresignMethod,
+    target: &RemotePath,
+    expiry_secs: u64,
+) -> Result<Report, Error> {
+    require_key("presign", target)?;
+
+    let (bucket_name, key) = (target.bucket_name.as_str(), target.key.as_str());
+
+    let presigned_url = match method {
+        PresignMethod::Get => minio
+            .get_object_presigned(bucket_name, key, expiry_secs, None)
+            .await?
+            .ok_or(not_found(target))?,
+        PresignMethod::Put => {
+            minio
+                .upload_object_presigned(bucket_name, key, expiry_secs, None)
+                .await?
+        }
+        PresignMethod::Delete => {
+            minio
+                .delete_object_presigned(bucket_name, key, expiry_secs)
+                .await?
+        }
+    };
+
+    Ok(Report {
+        lines: vec![presigned_url.url.clone()],
+        json: serde_json::to_value(&presigned_url)
+            .map_err(|err| Error::Internal(err.to_string()))?,
+    })
+}
+
+async fn cat<W>(minio: &Minio, target: &RemotePath, out: &mut W) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+{
+    require_key("cat", target)?;
+
+    let stream = minio
+        .get_object(&target.bucket_name, &target.key, None)
+        .await?
+        .ok_or(not_found(target))?;
+
+    tokio::io::copy_buf(&mut Box::pin(stream), out)
+        .await
+        .map_err(|err| Error::StdIo(err.kind()))?;
+    out.flush().await.map_err(|err| Error::StdIo(err.kind()))
+}
+
+fn synced(report: SyncReport, dry_run: bool) -> Report {
+    let (copy, remove) = match dry_run {
+        true => ("Would copy", "Would remove"),
+        false => ("Copied", "Removed"),
+    };
+
+    let mut lines = report
+        .transferred
+        .iter()
+        .map(|path| format!("{} {}", copy, path))
+        .chain(
+            report
+                .deleted
+                .iter()
+                .map(|path| format!("{} {}", remove, path)),
+        )
+        .collect::<Vec<_>>();
+    lines.push(format!(
+        "{} copied, {} removed, {} unchanged, {} bytes",
+        report.transferred.len(),
+        report.deleted.len(),
+        report.unchanged,
+        report.bytes_transferred
+    ));
+
+    Report {
+        lines,
+        json: json!({
+            "copied": report.transferred,
+            "removed": report.deleted,
+            "unchanged": report.unchanged,
+            "bytes": report.bytes_transferred,
+            "dry_run": dry_run,
+        }),
+    }
+}
+
+async fn mirror(
+    minio: &Minio,
+    src: &Location,
+    dst: &Location,
+    delete: bool,
+    dry_run: bool,
+    exclude: &[String],
+) -> Result<Report, Error> {
+    let options = SyncOptions {
+        exclude: exclude.to_vec(),
+        delete_extras: delete,
+        dry_run,
+        ..Default::default()
+    };
+
+    match (src, dst) {
+        (Location::Local(dir), Location::Remote(dst)) => {
+            let report = minio
+                .sync_dir_to_prefix(dir, &dst.bucket_name, &dst.key, options)
+                .await?;
+
+            Ok(synced(report, dry_run))
+        }
+        (Location::Remote(src), Location::Local(dir)) => {
+            let report = minio
+                .sync_prefix_to_dir(&src.bucket_name, &src.key, dir, options)
+                .await?;
+
+            Ok(synced(report, dry_run))
+        }
+        (Location::Remote(src), Location::Remote(dst)) => {
+            if delete || dry_run || !exclude.is_empty() {
+                return Err(Error::internal(
+                    "mirror between buckets does not accept --delete, --dry-run or --exclude",
+                ));
+            }
+
+            if !dst.key.is_empty() && dst.key != src.key {
+                return Err(Error::internal(
+                    "mirror between buckets keeps the keys, the target must be a bucket",
+                ));
+            }
+
+            let report = replicate(
+                minio,
+                &src.bucket_name,
+                minio,
+                &dst.bucket_name,
+                ReplicateOptions {
+                    prefix: Some(src.key.clone()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+            let mut lines = report
+                .copied
+                .iter()
+                .map(|key| format!("Copied {}", key))
+                .collect::<Vec<_>>();
+            lines.push(format!(
+                "{} copied, {} unchanged, {} bytes",
+                report.copied.len(),
+                report.skipped,
+                report.bytes_copied
+            ));
+
+            Ok(Report {
+                lines,
+                json: json!({
+                    "copied": report.copied,
+                    "unchanged": report.skipped,
+                    "bytes": report.bytes_copied,
+                }),
+            })
+        }
+        (Location::Local(_), Location::Local(_)) => Err(Error::internal(
+            "mirror requires a remote source or target, I.E. s3://bucket/prefix",
+        )),
+    }
+}
+
+async fn du(minio: &Minio, target: &RemotePath) -> Result<Report, Error> {
+    let usage = minio.usage(&target.bucket_name, &target.key, None).await?;
+
+    Ok(Report {
+        lines: vec![format!(
+            "{}\t{} objects\t{}",
+            usage.total.bytes, usage.total.objects, target
+        )],
+        json: json!({
+            "target": target.to_string(),
+            "bytes": usage.total.bytes,
+            "objects": usage.total.objects,
+        }),
+    })
+}
@@ -0,0 +1,123 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::{core::connection::ConnectionOptions, error::Error, Minio};
+use aws_sdk_s3::config::Credentials;
+use serde::Deserialize;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Connection info of the `minior` binary
+///
+/// Read from the JSON config file at `--config`, `$MINIOR_CONFIG` or
+/// `~/.config/minior/config.json`, with every field overridden by
+/// the environment variables `MINIOR_URL`, `MINIOR_ACCESS_KEY`,
+/// `MINIOR_SECRET_KEY` and `MINIOR_REGION`.
+///
+/// Without an access key, credentials are loaded from the environment
+/// like `Minio::new`.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// // ~/.config/minior/config.json
+/// {
+///     "url": "http://127.0.0.1:9000",
+///     "access_key": "minioadmin",
+///     "secret_key": "minioadmin"
+/// }
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Config {
+    pub url: Option<String>,
+    pub access_key: Option<String>,
+    pub secret_key: Option<String>,
+    pub region: Option<String>,
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("url", &self.url)
+            .field("access_key", &self.access_key)
+            .field(
+                "secret_key",
+                &self.secret_key.as_ref().map(|_| "** redacted **"),
+            )
+            .field("region", &self.region)
+            .finish()
+    }
+}
+
+impl Config {
+    /// Loads the config file, if any, and overrides it with the environment
+    pub async fn load(path: Option<&Path>) -> Result<Self, Error> {
+        let env_path = std::env::var_os("MINIOR_CONFIG").map(PathBuf::from);
+        let default_path = std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".config/minior/config.json"));
+
+        let mut config = match (path, env_path) {
+            (Some(path), _) => Self::read(path).await?,
+            (None, Some(path)) => Self::read(&path).await?,
+            (None, None) => match default_path {
+                Some(path) if path.is_file() => Self::read(&path).await?,
+                _ => Self::default(),
+            },
+        };
+
+        let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        config.url = env("MINIOR_URL").or(config.url);
+        config.access_key = env("MINIOR_ACCESS_KEY").or(config.access_key);
+        config.secret_key = env("MINIOR_SECRET_KEY").or(config.secret_key);
+        config.region = env("MINIOR_REGION").or(config.region);
+
+        Ok(config)
+    }
+
+    async fn read(path: &Path) -> Result<Self, Error> {
+        let contents = tokio::fs::read(path)
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+
+        serde_json::from_slice(&contents).map_err(|err| {
+            Error::Internal(format!(
+                "Config file {} is invalid: {}",
+                path.display(),
+                err
+            ))
+        })
+    }
+
+    /// Constructs a `Minio` from the connection info, using path-style
+    /// requests as MinIO expects
+    pub async fn connect(&self) -> Result<Minio, Error> {
+        let url = self.url.as_deref().ok_or(Error::internal(
+            "No url configured, set MINIOR_URL or `url` in the config file",
+        ))?;
+
+        let credentials = match (&self.access_key, &self.secret_key) {
+            (Some(access_key), Some(secret_key)) => Some(Credentials::new(
+                access_key, secret_key, None, None, "minior",
+            )),
+            (Some(_), None) | (None, Some(_)) => {
+                return Err(Error::internal(
+                    "Both an access key and a secret key must be configured",
+                ))
+            }
+            (None, None) => None,
+        };
+
+        let options = ConnectionOptions {
+            url: url.to_string(),
+            credentials,
+            region: self.region.clone(),
+            force_path_style: true,
+        };
+
+        Ok(Minio::from_options(&options).await)
+    }
+}
@@ -0,0 +1,64 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+mod args;
+mod commands;
+mod config;
+
+pub use args::{Cli, Command, Location, PresignMethod, RemotePath};
+pub use config::Config;
+
+use crate::error::Error;
+use tokio::io::AsyncWriteExt;
+
+/// Usage of the `minior` binary, printed by `minior help`
+pub const USAGE: &str = "\
+Usage: minior [--json] [--config <path>] <command> [arguments]
+
+Commands:
+  ls [-r] [-l] [s3://bucket[/prefix]]      List buckets, or the objects under a prefix
+  cp <src> <dst>                           Copy an object to, from or between buckets
+  rm [-r] [--dry-run] s3://bucket/key      Remove an object, or every object under a prefix
+  mb [-p] s3://bucket                      Make a bucket, -p ignores an existing one
+  rb [--force] s3://bucket                 Remove a bucket, --force removes its objects
+  stat s3://bucket/key                     Show the size, ETag and metadata of an object
+  presign get|put|delete [--expiry <secs>] s3://bucket/key
+                                           Sign a url, expiring in 3600 seconds by default
+  cat s3://bucket/key                      Write an object to stdout
+  mirror [--delete] [--dry-run] [--exclude <glob>]... <src> <dst>
+                                           Sync a directory and a prefix, or two buckets
+  du s3://bucket[/prefix]                  Sum the size of the objects under a prefix
+
+Paths starting with s3:// are remote, others are local.
+
+Connection info is read from the JSON config file at --config, $MINIOR_CONFIG
+or ~/.config/minior/config.json, with the fields `url`, `access_key`,
+`secret_key` and `region`, overridden by $MINIOR_URL, $MINIOR_ACCESS_KEY,
+$MINIOR_SECRET_KEY and $MINIOR_REGION.
+";
+
+/// Runs the `minior` binary with its arguments, without the name of
+/// the binary, writing to stdout
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// minior::cli::run(std::env::args().skip(1)).await?;
+/// ```
+pub async fn run(args: impl IntoIterator<Item = String>) -> Result<(), Error> {
+    let cli = Cli::parse(args)?;
+    let mut stdout = tokio::io::stdout();
+
+    // Help is printed without connecting
+    if cli.command == Command::Help {
+        return stdout
+            .write_all(USAGE.as_bytes())
+            .await
+            .map_err(|err| Error::StdIo(err.kind()));
+    }
+
+    let minio = Config::load(cli.config.as_deref()).await?.connect().await?;
+
+    cli.command.run(&minio, cli.json, &mut stdout).await
+}
@@ -0,0 +1,176 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{from_json, null_as_default, to_json, AccountStatus, AdminClient};
+use crate::error::Error;
+use hyper::Method;
+use serde::{Deserialize, Serialize};
+
+/// A group of users of the MinIO identity provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupInfo {
+    pub name: String,
+    pub status: AccountStatus,
+    /// The access keys of the users of the group
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub members: Vec<String>,
+    /// The policies attached to the group, comma separated
+    #[serde(default)]
+    pub policy: String,
+    /// When the group was last updated, in RFC 3339
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateGroupMembersRequest<'a> {
+    group: &'a str,
+    members: &'a [&'a str],
+    is_remove: bool,
+}
+
+async fn update_group_members(
+    client: &AdminClient,
+    group: &str,
+    members: &[&str],
+    is_remove: bool,
+) -> Result<(), Error> {
+    let credentials = client.credentials().await?;
+    let body = to_json(&UpdateGroupMembersRequest {
+        group,
+        members,
+        is_remove,
+    })?;
+
+    client
+        .send(&credentials, Method::PUT, "update-group-members", &[], body)
+        .await?;
+
+    Ok(())
+}
+
+/// Adds the users `members` to `group`, creating the group if it does not exist
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// add_group_members(&client, "marine-biologists", &["shark-job"]).await?;
+/// ```
+pub async fn add_group_members(
+    client: &AdminClient,
+    group: &str,
+    members: &[&str],
+) -> Result<(), Error> {
+    update_group_members(client, group, members, false).await
+}
+
+/// Removes the users `members` from `group`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// remove_group_members(&client, "marine-biologists", &["shark-job"]).await?;
+/// ```
+pub async fn remove_group_members(
+    client: &AdminClient,
+    group: &str,
+    members: &[&str],
+) -> Result<(), Error> {
+    update_group_members(client, group, members, true).await
+}
+
+/// Removes `group`, which must have no members
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// remove_group(&client, "marine-biologists").await?;
+/// ```
+pub async fn remove_group(client: &AdminClient, group: &str) -> Result<(), Error> {
+    update_group_members(client, group, &[], true).await
+}
+
+/// Returns the names of the groups
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// let groups: Vec<String> = list_groups(&client).await?;
+/// ```
+pub async fn list_groups(client: &AdminClient) -> Result<Vec<String>, Error> {
+    let credentials = client.credentials().await?;
+    let response = client
+        .send(&credentials, Method::GET, "groups", &[], vec![])
+        .await?;
+
+    Ok(from_json::<Option<Vec<String>>>(&response)?.unwrap_or_default())
+}
+
+/// Returns the group `group`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// let group_info: GroupInfo = group_info(&client, "marine-biologists").await?;
+/// ```
+pub async fn group_info(client: &AdminClient, group: &str) -> Result<GroupInfo, Error> {
+    let credentials = client.credentials().await?;
+    let response = client
+        .send(
+            &credentials,
+            Method::GET,
+            "group",
+            &[("group", group)],
+            vec![],
+        )
+        .await?;
+
+    from_json(&response)
+}
+
+/// Enables or disables `group`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// set_group_status(&client, "marine-biologists", AccountStatus::Disabled).await?;
+/// ```
+pub async fn set_group_status(
+    client: &AdminClient,
+    group: &str,
+    status: AccountStatus,
+) -> Result<(), Error> {
+    let credentials = client.credentials().await?;
+
+    client
+        .send(
+            &credentials,
+            Method::PUT,
+            "set-group-status",
+            &[("group", group), ("status", status.as_str())],
+            vec![],
+        )
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,224 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::error::Error;
+use aes_gcm::{
+    aead::{consts::U12, rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::ChaCha20Poly1305;
+use pbkdf2::{pbkdf2_hmac, sha2::Sha256};
+
+/// Size of the random salt the key is derived with
+const SALT_SIZE: usize = 32;
+
+/// Size of the random nonce prefix of the stream
+const NONCE_SIZE: usize = 8;
+
+/// Size of the plaintext of every fragment of the stream but the last
+const FRAGMENT_SIZE: usize = 16 * 1024;
+
+/// Size of the authentication tag of AES-256-GCM and ChaCha20-Poly1305
+const TAG_SIZE: usize = 16;
+
+/// Flag of the associated data of the final fragment
+const FINAL_FRAGMENT: u8 = 0x80;
+
+const ARGON2ID_AES_GCM: u8 = 0x00;
+const ARGON2ID_CHACHA20_POLY1305: u8 = 0x01;
+const PBKDF2_AES_GCM: u8 = 0x02;
+
+const ARGON2ID_TIME: u32 = 1;
+const ARGON2ID_MEMORY_KIB: u32 = 64 * 1024;
+const ARGON2ID_THREADS: u32 = 4;
+const PBKDF2_ROUNDS: u32 = 8_192;
+
+/// Encrypts `data` the way `madmin.EncryptData` does, for the payloads of the
+/// MinIO admin API that carry secrets, with a key derived from `password`,
+/// the secret key of the admin credentials
+///
+/// The output is `salt | algorithm id | nonce | ciphertext`, the key derived
+/// via Argon2id and the ciphertext a stream of AES-256-GCM sealed fragments.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let ciphertext: Vec<u8> = encrypt_data(secret_key, br#"{"status":"enabled"}"#)?;
+/// ```
+pub fn encrypt_data(password: &str, data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut salt = [0u8; SALT_SIZE];
+    OsRng.fill_bytes(&mut salt);
+
+    let mut nonce = [0u8; NONCE_SIZE];
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key(ARGON2ID_AES_GCM, password, &salt)?;
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(Error::sdk)?;
+
+    let mut output = Vec::with_capacity(
+        SALT_SIZE + 1 + NONCE_SIZE + data.len() + (data.len() / FRAGMENT_SIZE + 2) * TAG_SIZE,
+    );
+    output.extend_from_slice(&salt);
+    output.push(ARGON2ID_AES_GCM);
+    output.extend_from_slice(&nonce);
+    output.extend(seal_stream(&cipher, &nonce, data)?);
+
+    Ok(output)
+}
+
+/// Decrypts `data` encrypted by `encrypt_data` or `madmin.EncryptData`,
+/// with any of its algorithms, by `password`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let plaintext: Vec<u8> = decrypt_data(secret_key, &response_body)?;
+/// ```
+pub fn decrypt_data(password: &str, data: &[u8]) -> Result<Vec<u8>, Error> {
+    if data.len() < SALT_SIZE + 1 + NONCE_SIZE + TAG_SIZE {
+        return Err(Error::internal("Encrypted admin payload is too short"));
+    }
+
+    let (salt, rest) = data.split_at(SALT_SIZE);
+    let (id, rest) = (rest[0], &rest[1..]);
+    let (nonce, ciphertext) = rest.split_at(NONCE_SIZE);
+
+    let key = derive_key(id, password, salt)?;
+
+    match id {
+        ARGON2ID_AES_GCM | PBKDF2_AES_GCM => open_stream(
+            &Aes256Gcm::new_from_slice(&key).map_err(Error::sdk)?,
+            nonce,
+            ciphertext,
+        ),
+        _ => open_stream(
+            &ChaCha20Poly1305::new_from_slice(&key).map_err(Error::sdk)?,
+            nonce,
+            ciphertext,
+        ),
+    }
+}
+
+fn derive_key(id: u8, password: &str, salt: &[u8]) -> Result<[u8; 32], Error> {
+    let mut key = [0u8; 32];
+
+    match id {
+        ARGON2ID_AES_GCM | ARGON2ID_CHACHA20_POLY1305 => {
+            let params = Params::new(
+                ARGON2ID_MEMORY_KIB,
+                ARGON2ID_TIME,
+                ARGON2ID_THREADS,
+                Some(key.len()),
+            )
+            .map_err(Error::sdk)?;
+
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password_into(password.as_bytes(), salt, &mut key)
+                .map_err(Error::sdk)?;
+        }
+        PBKDF2_AES_GCM => pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, PBKDF2_ROUNDS, &mut key),
+        _ => {
+            return Err(Error::Internal(format!(
+                "Unknown admin payload encryption algorithm {}",
+                id
+            )))
+        }
+    }
+
+    Ok(key)
+}
+
+/// The nonce of the fragment `sequence_number`
+fn fragment_nonce(nonce: &[u8], sequence_number: u32) -> [u8; 12] {
+    let mut fragment_nonce = [0u8; 12];
+    fragment_nonce[..NONCE_SIZE].copy_from_slice(nonce);
+    fragment_nonce[NONCE_SIZE..].copy_from_slice(&sequence_number.to_le_bytes());
+
+    fragment_nonce
+}
+
+/// The associated data of every fragment, the flag of the fragment
+/// followed by the tag of the empty associated data of the stream
+fn associated_data<A: Aead + AeadCore<NonceSize = U12>>(
+    cipher: &A,
+    nonce: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let tag = cipher
+        .encrypt(&fragment_nonce(nonce, 0).into(), Payload::from(&[][..]))
+        .map_err(Error::sdk)?;
+
+    Ok([&[0u8][..], &tag].concat())
+}
+
+fn seal_stream<A: Aead + AeadCore<NonceSize = U12>>(
+    cipher: &A,
+    nonce: &[u8],
+    data: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let mut associated_data = associated_data(cipher, nonce)?;
+    let mut ciphertext =
+        Vec::with_capacity(data.len() + (data.len() / FRAGMENT_SIZE + 1) * TAG_SIZE);
+
+    // The final fragment is sealed even if empty, and may be a full one
+    let fragments = if data.is_empty() {
+        vec![data]
+    } else {
+        data.chunks(FRAGMENT_SIZE).collect::<Vec<_>>()
+    };
+
+    for (index, fragment) in fragments.iter().enumerate() {
+        if index + 1 == fragments.len() {
+            associated_data[0] = FINAL_FRAGMENT;
+        }
+
+        ciphertext.extend(
+            cipher
+                .encrypt(
+                    &fragment_nonce(nonce, index as u32 + 1).into(),
+                    Payload {
+                        msg: fragment,
+                        aad: &associated_data,
+                    },
+                )
+                .map_err(Error::sdk)?,
+        );
+    }
+
+    Ok(ciphertext)
+}
+
+fn open_stream<A: Aead + AeadCore<NonceSize = U12>>(
+    cipher: &A,
+    nonce: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let mut associated_data = associated_data(cipher, nonce)?;
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+
+    let fragments = ciphertext
+        .chunks(FRAGMENT_SIZE + TAG_SIZE)
+        .collect::<Vec<_>>();
+
+    for (index, fragment) in fragments.iter().enumerate() {
+        if index + 1 == fragments.len() {
+            associated_data[0] = FINAL_FRAGMENT;
+        }
+
+        plaintext.extend(
+            cipher
+                .decrypt(
+                    &fragment_nonce(nonce, index as u32 + 1).into(),
+                    Payload {
+                        msg: fragment,
+                        aad: &associated_data,
+                    },
+                )
+                .map_err(|_| Error::internal("Failed to decrypt the admin payload"))?,
+        );
+    }
+
+    Ok(plaintext)
+}
@@ -0,0 +1,212 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+pub mod groups;
+pub mod madmin;
+pub mod policies;
+pub mod service_accounts;
+pub mod usage;
+pub mod users;
+
+use super::{
+    connection::DEFAULT_REGION,
+    http::{send, Endpoint, TlsOptions},
+    presign::uri_encode,
+};
+use crate::error::Error;
+use aws_sdk_s3::config::{Credentials, ProvideCredentials, SharedCredentialsProvider};
+use hyper::{body::Bytes, Method};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+
+/// Path of the MinIO admin API
+const ADMIN_PATH: &str = "/minio/admin/v3";
+
+/// Status of a user, group or service account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountStatus {
+    /// Responded as `on` for service accounts
+    #[serde(alias = "on")]
+    Enabled,
+    /// Responded as `off` for service accounts
+    #[serde(alias = "off")]
+    Disabled,
+}
+
+impl AccountStatus {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            AccountStatus::Enabled => "enabled",
+            AccountStatus::Disabled => "disabled",
+        }
+    }
+}
+
+/// Client of the admin API of a MinIO server at `/minio/admin/v3`,
+/// signing requests via SigV4 with the credentials of an admin user
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client = AdminClient::new(
+///     "https://minio.example.com",
+///     SharedCredentialsProvider::new(admin_credentials),
+/// )?;
+///
+/// let users: HashMap<String, UserInfo> = list_users(&client).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdminClient {
+    endpoint: Endpoint,
+    credentials_provider: SharedCredentialsProvider,
+    region: String,
+}
+
+impl AdminClient {
+    pub fn new(url: &str, credentials_provider: SharedCredentialsProvider) -> Result<Self, Error> {
+        Ok(Self {
+            endpoint: Endpoint::parse(url)?,
+            credentials_provider,
+            region: DEFAULT_REGION.to_string(),
+        })
+    }
+
+    /// Signs requests for `region` instead of `DEFAULT_REGION`
+    pub fn with_region(mut self, region: &str) -> Self {
+        self.region = region.to_string();
+        self
+    }
+
+    /// Trusts the PEM encoded CA certificates along with the native
+    /// roots, I.E. of a self-signed MinIO
+    pub fn with_ca_certificates(mut self, ca_certificates_pem: Vec<u8>) -> Self {
+        self.endpoint = self.endpoint.with_tls(TlsOptions {
+            ca_certificates_pem: Some(ca_certificates_pem),
+            client_certificate: None,
+        });
+        self
+    }
+
+    /// The credentials requests are signed with, whose secret
+    /// key encrypts and decrypts the payloads carrying secrets
+    pub(crate) async fn credentials(&self) -> Result<Credentials, Error> {
+        self.credentials_provider
+            .provide_credentials()
+            .await
+            .map_err(Error::sdk)
+    }
+
+    /// Sends a request to `path` of the admin API signed with `credentials`,
+    /// returning the body of the response, or an `Error::SdkError` with the
+    /// code and message of the error responded
+    pub(crate) async fn send(
+        &self,
+        credentials: &Credentials,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<Bytes, Error> {
+        self.exchange(credentials, method, path, query, body)
+            .await?
+            .map_err(|error| error.into_error(path))
+    }
+
+    /// Like `send`, but returns `None` if the error responded has the code
+    /// `absent_code`, I.E. of a configuration that is not set
+    pub(crate) async fn send_optional(
+        &self,
+        credentials: &Credentials,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+        absent_code: &str,
+    ) -> Result<Option<Bytes>, Error> {
+        match self
+            .exchange(credentials, method, path, query, body)
+            .await?
+        {
+            Ok(response) => Ok(Some(response)),
+            Err(error) if error.code == absent_code => Ok(None),
+            Err(error) => Err(error.into_error(path)),
+        }
+    }
+
+    async fn exchange(
+        &self,
+        credentials: &Credentials,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<Result<Bytes, AdminError>, Error> {
+        let mut query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, uri_encode(value, true)))
+            .collect::<Vec<_>>();
+        query.sort();
+
+        let path_and_query = if query.is_empty() {
+            format!("{}/{}", ADMIN_PATH, path)
+        } else {
+            format!("{}/{}?{}", ADMIN_PATH, path, query.join("&"))
+        };
+
+        let (status, _, response) = send(
+            &self.endpoint,
+            Some((credentials, &self.region, "s3")),
+            method,
+            &path_and_query,
+            vec![],
+            body,
+        )
+        .await?;
+
+        if !status.is_success() {
+            return Ok(Err(serde_json::from_slice::<AdminError>(&response)
+                .unwrap_or(AdminError {
+                    code: status.to_string(),
+                    message: String::from_utf8_lossy(&response).into_owned(),
+                })));
+        }
+
+        Ok(Ok(response))
+    }
+}
+
+/// The JSON body of an error of the admin API
+#[derive(Deserialize)]
+struct AdminError {
+    #[serde(rename = "Code")]
+    code: String,
+    #[serde(rename = "Message")]
+    message: String,
+}
+
+impl AdminError {
+    fn into_error(self, path: &str) -> Error {
+        Error::SdkError(format!(
+            "{} failed with {}: {}",
+            path, self.code, self.message
+        ))
+    }
+}
+
+pub(crate) fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    serde_json::to_vec(value).map_err(|err| Error::Internal(err.to_string()))
+}
+
+pub(crate) fn from_json<T: DeserializeOwned>(json: &[u8]) -> Result<T, Error> {
+    serde_json::from_slice(json).map_err(|err| Error::Internal(err.to_string()))
+}
+
+/// Deserializes a `null` of the admin API, I.E. a group without members, as the default
+pub(crate) fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
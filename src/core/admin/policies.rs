@@ -0,0 +1,239 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{
+    from_json,
+    madmin::{decrypt_data, encrypt_data},
+    null_as_default, to_json, AdminClient,
+};
+use crate::error::Error;
+use hyper::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The user or group policies are attached to or detached from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyEntity<'a> {
+    User(&'a str),
+    Group(&'a str),
+}
+
+#[derive(Serialize)]
+struct PolicyAssociationRequest<'a> {
+    policies: &'a [&'a str],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PolicyAssociationResponse {
+    #[serde(default, deserialize_with = "null_as_default")]
+    policies_attached: Vec<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    policies_detached: Vec<String>,
+}
+
+/// Creates or replaces the canned policy `name` with the IAM policy JSON `policy`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// add_canned_policy(
+///     &client,
+///     "sharks-readonly",
+///     r#"{
+///         "Version": "2012-10-17",
+///         "Statement": [{
+///             "Effect": "Allow",
+///             "Action": ["s3:GetObject"],
+///             "Resource": ["arn:aws:s3:::sharks/*"]
+///         }]
+///     }"#,
+/// ).await?;
+/// ```
+pub async fn add_canned_policy(
+    client: &AdminClient,
+    name: &str,
+    policy: &str,
+) -> Result<(), Error> {
+    let credentials = client.credentials().await?;
+
+    client
+        .send(
+            &credentials,
+            Method::PUT,
+            "add-canned-policy",
+            &[("name", name)],
+            policy.as_bytes().to_vec(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Removes the canned policy `name`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// remove_canned_policy(&client, "sharks-readonly").await?;
+/// ```
+pub async fn remove_canned_policy(client: &AdminClient, name: &str) -> Result<(), Error> {
+    let credentials = client.credentials().await?;
+
+    client
+        .send(
+            &credentials,
+            Method::DELETE,
+            "remove-canned-policy",
+            &[("name", name)],
+            vec![],
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Returns the IAM policy JSON of the canned policies by their name
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// let policies: HashMap<String, Value> = list_canned_policies(&client).await?;
+/// ```
+pub async fn list_canned_policies(client: &AdminClient) -> Result<HashMap<String, Value>, Error> {
+    let credentials = client.credentials().await?;
+    let response = client
+        .send(
+            &credentials,
+            Method::GET,
+            "list-canned-policies",
+            &[],
+            vec![],
+        )
+        .await?;
+
+    from_json(&response)
+}
+
+/// Returns the IAM policy JSON of the canned policy `name`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// let policy: Value = canned_policy(&client, "sharks-readonly").await?;
+/// ```
+pub async fn canned_policy(client: &AdminClient, name: &str) -> Result<Value, Error> {
+    let credentials = client.credentials().await?;
+    let response = client
+        .send(
+            &credentials,
+            Method::GET,
+            "info-canned-policy",
+            &[("name", name)],
+            vec![],
+        )
+        .await?;
+
+    from_json(&response)
+}
+
+async fn update_policy_association(
+    client: &AdminClient,
+    policies: &[&str],
+    entity: PolicyEntity<'_>,
+    path: &str,
+) -> Result<PolicyAssociationResponse, Error> {
+    let credentials = client.credentials().await?;
+    let (user, group) = match entity {
+        PolicyEntity::User(user) => (Some(user), None),
+        PolicyEntity::Group(group) => (None, Some(group)),
+    };
+
+    let body = encrypt_data(
+        credentials.secret_access_key(),
+        &to_json(&PolicyAssociationRequest {
+            policies,
+            user,
+            group,
+        })?,
+    )?;
+
+    let response = client
+        .send(&credentials, Method::POST, path, &[], body)
+        .await?;
+
+    from_json(&decrypt_data(credentials.secret_access_key(), &response)?)
+}
+
+/// Attaches the canned `policies` to a user or group
+///
+/// Returns the policies attached
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// let attached: Vec<String> = attach_policies(
+///     &client,
+///     &["sharks-readonly"],
+///     PolicyEntity::User("shark-job"),
+/// ).await?;
+/// ```
+pub async fn attach_policies(
+    client: &AdminClient,
+    policies: &[&str],
+    entity: PolicyEntity<'_>,
+) -> Result<Vec<String>, Error> {
+    Ok(
+        update_policy_association(client, policies, entity, "idp/builtin/policy/attach")
+            .await?
+            .policies_attached,
+    )
+}
+
+/// Detaches the canned `policies` from a user or group
+///
+/// Returns the policies detached
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// let detached: Vec<String> = detach_policies(
+///     &client,
+///     &["sharks-readonly"],
+///     PolicyEntity::Group("marine-biologists"),
+/// ).await?;
+/// ```
+pub async fn detach_policies(
+    client: &AdminClient,
+    policies: &[&str],
+    entity: PolicyEntity<'_>,
+) -> Result<Vec<String>, Error> {
+    Ok(
+        update_policy_association(client, policies, entity, "idp/builtin/policy/detach")
+            .await?
+            .policies_detached,
+    )
+}
@@ -0,0 +1,336 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{
+    from_json,
+    madmin::{decrypt_data, encrypt_data},
+    null_as_default, to_json, AccountStatus, AdminClient,
+};
+use crate::error::Error;
+use aws_sdk_s3::{
+    config::Credentials,
+    primitives::{DateTime, DateTimeFormat},
+};
+use hyper::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::SystemTime;
+
+/// Options of a new service account, generating its keys unless set,
+/// owned by the admin user unless `target_user` is set
+#[derive(Debug, Clone, Default)]
+pub struct AddServiceAccountOptions {
+    /// The user owning the service account
+    pub target_user: Option<String>,
+    pub access_key: Option<String>,
+    pub secret_key: Option<String>,
+    /// An IAM policy JSON restricting the service account, which
+    /// inherits the policies of its owner if `None`
+    pub policy: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub expiration: Option<SystemTime>,
+}
+
+/// Changes of a service account, leaving fields that are `None` unchanged
+#[derive(Debug, Clone, Default)]
+pub struct UpdateServiceAccountOptions {
+    pub secret_key: Option<String>,
+    pub status: Option<AccountStatus>,
+    /// An IAM policy JSON restricting the service account
+    pub policy: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub expiration: Option<SystemTime>,
+}
+
+/// A service account of a user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceAccountInfo {
+    #[serde(default)]
+    pub access_key: String,
+    /// The user owning the service account
+    pub parent_user: String,
+    pub account_status: AccountStatus,
+    /// If the service account inherits the policies of its owner
+    pub implied_policy: bool,
+    /// The IAM policy JSON of the service account, only
+    /// returned by `service_account_info`
+    #[serde(default)]
+    pub policy: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// When the service account expires, in RFC 3339
+    #[serde(default)]
+    pub expiration: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddServiceAccountRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    policy: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_user: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    access_key: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret_key: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expiration: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AddServiceAccountResponse {
+    credentials: ServiceAccountCredentials,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServiceAccountCredentials {
+    access_key: String,
+    secret_key: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateServiceAccountRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_policy: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_secret_key: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_status: Option<AccountStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_expiration: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ListServiceAccountsResponse {
+    #[serde(default, deserialize_with = "null_as_default")]
+    accounts: Vec<ServiceAccountInfo>,
+}
+
+/// Parses an IAM policy JSON, so it is embedded as JSON rather than a string
+fn policy_json(policy: Option<&str>) -> Result<Option<Value>, Error> {
+    policy
+        .map(|policy| {
+            serde_json::from_str(policy)
+                .map_err(|err| Error::Internal(format!("Invalid policy JSON: {}", err)))
+        })
+        .transpose()
+}
+
+fn rfc3339(time: Option<SystemTime>) -> Result<Option<String>, Error> {
+    time.map(|time| {
+        DateTime::from(time)
+            .fmt(DateTimeFormat::DateTime)
+            .map_err(Error::sdk)
+    })
+    .transpose()
+}
+
+/// Creates a service account, returning its credentials
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// let credentials: Credentials = add_service_account(
+///     &client,
+///     AddServiceAccountOptions {
+///         target_user: Some("shark-job".to_string()),
+///         name: Some("shark-tagger".to_string()),
+///         ..Default::default()
+///     },
+/// ).await?;
+/// ```
+pub async fn add_service_account(
+    client: &AdminClient,
+    options: AddServiceAccountOptions,
+) -> Result<Credentials, Error> {
+    let credentials = client.credentials().await?;
+    let body = encrypt_data(
+        credentials.secret_access_key(),
+        &to_json(&AddServiceAccountRequest {
+            policy: policy_json(options.policy.as_deref())?,
+            target_user: options.target_user.as_deref(),
+            access_key: options.access_key.as_deref(),
+            secret_key: options.secret_key.as_deref(),
+            name: options.name.as_deref(),
+            description: options.description.as_deref(),
+            expiration: rfc3339(options.expiration)?,
+        })?,
+    )?;
+
+    let response = client
+        .send(&credentials, Method::PUT, "add-service-account", &[], body)
+        .await?;
+    let response: AddServiceAccountResponse =
+        from_json(&decrypt_data(credentials.secret_access_key(), &response)?)?;
+
+    Ok(Credentials::new(
+        response.credentials.access_key,
+        response.credentials.secret_key,
+        None,
+        options.expiration,
+        "MinioServiceAccount",
+    ))
+}
+
+/// Updates the service account `access_key`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// update_service_account(
+///     &client,
+///     "shark-tagger-key",
+///     UpdateServiceAccountOptions {
+///         status: Some(AccountStatus::Disabled),
+///         ..Default::default()
+///     },
+/// ).await?;
+/// ```
+pub async fn update_service_account(
+    client: &AdminClient,
+    access_key: &str,
+    options: UpdateServiceAccountOptions,
+) -> Result<(), Error> {
+    let credentials = client.credentials().await?;
+    let body = encrypt_data(
+        credentials.secret_access_key(),
+        &to_json(&UpdateServiceAccountRequest {
+            new_policy: policy_json(options.policy.as_deref())?,
+            new_secret_key: options.secret_key.as_deref(),
+            new_status: options.status,
+            new_name: options.name.as_deref(),
+            new_description: options.description.as_deref(),
+            new_expiration: rfc3339(options.expiration)?,
+        })?,
+    )?;
+
+    client
+        .send(
+            &credentials,
+            Method::POST,
+            "update-service-account",
+            &[("accessKey", access_key)],
+            body,
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes the service account `access_key`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// delete_service_account(&client, "shark-tagger-key").await?;
+/// ```
+pub async fn delete_service_account(client: &AdminClient, access_key: &str) -> Result<(), Error> {
+    let credentials = client.credentials().await?;
+
+    client
+        .send(
+            &credentials,
+            Method::DELETE,
+            "delete-service-account",
+            &[("accessKey", access_key)],
+            vec![],
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Returns the service accounts of `user`, or of the admin user if `None`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// let service_accounts: Vec<ServiceAccountInfo> = list_service_accounts(
+///     &client,
+///     Some("shark-job"),
+/// ).await?;
+/// ```
+pub async fn list_service_accounts(
+    client: &AdminClient,
+    user: Option<&str>,
+) -> Result<Vec<ServiceAccountInfo>, Error> {
+    let credentials = client.credentials().await?;
+    let query = user.map(|user| vec![("user", user)]).unwrap_or_default();
+
+    let response = client
+        .send(
+            &credentials,
+            Method::GET,
+            "list-service-accounts",
+            &query,
+            vec![],
+        )
+        .await?;
+    let response: ListServiceAccountsResponse =
+        from_json(&decrypt_data(credentials.secret_access_key(), &response)?)?;
+
+    Ok(response.accounts)
+}
+
+/// Returns the service account `access_key`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// let service_account: ServiceAccountInfo = service_account_info(
+///     &client,
+///     "shark-tagger-key",
+/// ).await?;
+/// ```
+pub async fn service_account_info(
+    client: &AdminClient,
+    access_key: &str,
+) -> Result<ServiceAccountInfo, Error> {
+    let credentials = client.credentials().await?;
+    let response = client
+        .send(
+            &credentials,
+            Method::GET,
+            "info-service-account",
+            &[("accessKey", access_key)],
+            vec![],
+        )
+        .await?;
+    let mut service_account: ServiceAccountInfo =
+        from_json(&decrypt_data(credentials.secret_access_key(), &response)?)?;
+
+    service_account.access_key = access_key.to_string();
+
+    Ok(service_account)
+}
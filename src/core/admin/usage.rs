@@ -0,0 +1,165 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{from_json, null_as_default, to_json, AdminClient};
+use crate::error::Error;
+use hyper::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Code of the error responded for a bucket without a quota
+const NO_QUOTA_CODE: &str = "XMinioAdminNoSuchQuotaConfiguration";
+
+/// The scanned usage of every bucket of a MinIO server
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataUsageInfo {
+    /// When the data usage was last scanned, in RFC 3339
+    #[serde(default)]
+    pub last_update: Option<String>,
+    #[serde(default)]
+    pub objects_count: u64,
+    #[serde(default)]
+    pub versions_count: u64,
+    #[serde(default)]
+    pub delete_markers_count: u64,
+    #[serde(default)]
+    pub objects_total_size: u64,
+    #[serde(default)]
+    pub buckets_count: u64,
+    /// The usage of the buckets by their name
+    #[serde(
+        default,
+        rename = "bucketsUsageInfo",
+        deserialize_with = "null_as_default"
+    )]
+    pub buckets_usage: HashMap<String, BucketUsageInfo>,
+}
+
+/// The scanned usage of a bucket
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketUsageInfo {
+    /// The total bytes of the objects
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub objects_count: u64,
+    #[serde(default)]
+    pub versions_count: u64,
+    #[serde(default)]
+    pub delete_markers_count: u64,
+    /// The amount of objects by size range, I.E. `LESS_THAN_1024_B`
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub objects_sizes_histogram: HashMap<String, u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BucketQuota {
+    /// The quota of servers before `size` replaced it
+    #[serde(default)]
+    quota: u64,
+    #[serde(default)]
+    size: u64,
+    #[serde(default, rename = "quotatype")]
+    quota_type: String,
+}
+
+/// Returns the hard quota of the bucket by `bucket_name` in bytes,
+/// or `None` if it has none
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// if let Some(quota) = get_bucket_quota(&client, "sharks").await? {
+///     ...
+/// }
+/// ```
+pub async fn get_bucket_quota(
+    client: &AdminClient,
+    bucket_name: &str,
+) -> Result<Option<u64>, Error> {
+    let credentials = client.credentials().await?;
+    let Some(response) = client
+        .send_optional(
+            &credentials,
+            Method::GET,
+            "get-bucket-quota",
+            &[("bucket", bucket_name)],
+            vec![],
+            NO_QUOTA_CODE,
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let quota: BucketQuota = from_json(&response)?;
+    let size = quota.size.max(quota.quota);
+
+    Ok((size > 0).then_some(size))
+}
+
+/// Sets the hard quota of the bucket by `bucket_name` to `quota` bytes,
+/// rejecting writes past it, or clears it if `None`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// // Note: 10 GiB
+/// set_bucket_quota(&client, "sharks", Some(10 * 1_024 * 1_024 * 1_024)).await?;
+/// ```
+pub async fn set_bucket_quota(
+    client: &AdminClient,
+    bucket_name: &str,
+    quota: Option<u64>,
+) -> Result<(), Error> {
+    let credentials = client.credentials().await?;
+    let size = quota.unwrap_or_default();
+    let body = to_json(&BucketQuota {
+        quota: size,
+        size,
+        quota_type: "hard".to_string(),
+    })?;
+
+    client
+        .send(
+            &credentials,
+            Method::PUT,
+            "set-bucket-quota",
+            &[("bucket", bucket_name)],
+            body,
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Returns the usage of every bucket, as of the last scan of the server
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// let data_usage_info: DataUsageInfo = data_usage_info(&client).await?;
+///
+/// for (bucket_name, bucket_usage) in data_usage_info.buckets_usage {
+///     ...
+/// }
+/// ```
+pub async fn data_usage_info(client: &AdminClient) -> Result<DataUsageInfo, Error> {
+    let credentials = client.credentials().await?;
+    let response = client
+        .send(&credentials, Method::GET, "datausageinfo", &[], vec![])
+        .await?;
+
+    from_json(&response)
+}
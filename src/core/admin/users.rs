@@ -0,0 +1,175 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{
+    from_json,
+    madmin::{decrypt_data, encrypt_data},
+    null_as_default, to_json, AccountStatus, AdminClient,
+};
+use crate::error::Error;
+use hyper::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A user of the MinIO identity provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfo {
+    /// The policies attached to the user, comma separated
+    #[serde(default)]
+    pub policy_name: String,
+    pub status: AccountStatus,
+    /// The groups of the user
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub member_of: Vec<String>,
+    /// When the user was last updated, in RFC 3339
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddUserRequest<'a> {
+    secret_key: &'a str,
+    status: AccountStatus,
+}
+
+/// Creates an enabled user `access_key` with `secret_key`, or updates
+/// the secret key of an existing one
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// add_user(&client, "shark-job", "great-white-secret").await?;
+/// ```
+pub async fn add_user(
+    client: &AdminClient,
+    access_key: &str,
+    secret_key: &str,
+) -> Result<(), Error> {
+    let credentials = client.credentials().await?;
+    let body = encrypt_data(
+        credentials.secret_access_key(),
+        &to_json(&AddUserRequest {
+            secret_key,
+            status: AccountStatus::Enabled,
+        })?,
+    )?;
+
+    client
+        .send(
+            &credentials,
+            Method::PUT,
+            "add-user",
+            &[("accessKey", access_key)],
+            body,
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Removes the user `access_key`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// remove_user(&client, "shark-job").await?;
+/// ```
+pub async fn remove_user(client: &AdminClient, access_key: &str) -> Result<(), Error> {
+    let credentials = client.credentials().await?;
+
+    client
+        .send(
+            &credentials,
+            Method::DELETE,
+            "remove-user",
+            &[("accessKey", access_key)],
+            vec![],
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Returns the users by their access key
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// for (access_key, user_info) in list_users(&client).await? {
+///     ...
+/// }
+/// ```
+pub async fn list_users(client: &AdminClient) -> Result<HashMap<String, UserInfo>, Error> {
+    let credentials = client.credentials().await?;
+    let response = client
+        .send(&credentials, Method::GET, "list-users", &[], vec![])
+        .await?;
+
+    from_json(&decrypt_data(credentials.secret_access_key(), &response)?)
+}
+
+/// Returns the user `access_key`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// let user_info: UserInfo = user_info(&client, "shark-job").await?;
+/// ```
+pub async fn user_info(client: &AdminClient, access_key: &str) -> Result<UserInfo, Error> {
+    let credentials = client.credentials().await?;
+    let response = client
+        .send(
+            &credentials,
+            Method::GET,
+            "user-info",
+            &[("accessKey", access_key)],
+            vec![],
+        )
+        .await?;
+
+    from_json(&response)
+}
+
+/// Enables or disables the user `access_key`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: AdminClient = ...;
+///
+/// set_user_status(&client, "shark-job", AccountStatus::Disabled).await?;
+/// ```
+pub async fn set_user_status(
+    client: &AdminClient,
+    access_key: &str,
+    status: AccountStatus,
+) -> Result<(), Error> {
+    let credentials = client.credentials().await?;
+
+    client
+        .send(
+            &credentials,
+            Method::PUT,
+            "set-user-status",
+            &[("accessKey", access_key), ("status", status.as_str())],
+            vec![],
+        )
+        .await?;
+
+    Ok(())
+}
@@ -5,10 +5,21 @@ use crate::error::Error;
 use aws_sdk_s3::{
     error::SdkError,
     operation::{head_bucket::HeadBucketError, head_object::HeadObjectError},
-    types::{Bucket, Object},
+    types::{Bucket, BucketVersioningStatus, Object, VersioningConfiguration},
     Client,
 };
 
+#[cfg(feature = "sse")]
+use super::sse::Encryption;
+#[cfg(feature = "sse")]
+use aws_sdk_s3::{
+    error::ProvideErrorMetadata,
+    types::{
+        ServerSideEncryption, ServerSideEncryptionByDefault, ServerSideEncryptionConfiguration,
+        ServerSideEncryptionRule,
+    },
+};
+
 /// Returns a vector of `Bucket`s from the client
 ///
 /// ---
@@ -56,6 +67,49 @@ pub async fn list_bucket_objects(client: &Client, bucket_name: &str) -> Result<V
     Ok(response.contents().to_owned())
 }
 
+/// Returns a vector of every `Object` in the bucket by `bucket_name`
+/// whose key starts with `prefix`, following continuation tokens
+/// past the 1,000 objects of a single page
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// for object in list_objects_with_prefix(&client, "sharks", "great-whites/").await? {
+///     ...
+/// }
+/// ```
+pub async fn list_objects_with_prefix(
+    client: &Client,
+    bucket_name: &str,
+    prefix: &str,
+) -> Result<Vec<Object>, Error> {
+    let mut objects = vec![];
+    let mut continuation_token = None;
+
+    loop {
+        let response = client
+            .list_objects_v2()
+            .bucket(bucket_name)
+            .prefix(prefix)
+            .set_continuation_token(continuation_token)
+            .send()
+            .await
+            .map_err(Error::sdk)?;
+
+        objects.extend_from_slice(response.contents());
+
+        match response.next_continuation_token() {
+            Some(token) if response.is_truncated().unwrap_or_default() => {
+                continuation_token = Some(token.to_string());
+            }
+            _ => return Ok(objects),
+        }
+    }
+}
+
 /// Returns true if a bucket by `bucket_name` exists
 ///
 /// ---
@@ -214,3 +268,155 @@ pub async fn delete_bucket(
 
     Ok(true)
 }
+
+/// Enables versioning of a bucket by `bucket_name`, or suspends it
+/// if `enabled` is `false`, keeping the versions stored so far
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// set_bucket_versioning(&client, "sharks", true).await?;
+/// ```
+pub async fn set_bucket_versioning(
+    client: &Client,
+    bucket_name: &str,
+    enabled: bool,
+) -> Result<(), Error> {
+    let status = if enabled {
+        BucketVersioningStatus::Enabled
+    } else {
+        BucketVersioningStatus::Suspended
+    };
+
+    client
+        .put_bucket_versioning()
+        .bucket(bucket_name)
+        .versioning_configuration(VersioningConfiguration::builder().status(status).build())
+        .send()
+        .await
+        .map_err(Error::sdk)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "sse")]
+/// Returns the default `Encryption` of new objects in a bucket by `bucket_name`
+///
+/// Returns `Ok(None)` if the bucket has no default encryption.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// let encryption: Option<Encryption> = get_bucket_encryption(&client, "sharks").await?;
+/// ```
+pub async fn get_bucket_encryption(
+    client: &Client,
+    bucket_name: &str,
+) -> Result<Option<Encryption>, Error> {
+    match client
+        .get_bucket_encryption()
+        .bucket(bucket_name)
+        .send()
+        .await
+    {
+        Ok(response) => Ok(response
+            .server_side_encryption_configuration()
+            .and_then(|configuration| configuration.rules().first())
+            .and_then(|rule| rule.apply_server_side_encryption_by_default())
+            .and_then(|by_default| match by_default.sse_algorithm() {
+                ServerSideEncryption::Aes256 => Some(Encryption::SseS3),
+                ServerSideEncryption::AwsKms => Some(Encryption::SseKms {
+                    key_id: by_default.kms_master_key_id().map(str::to_string),
+                    context: None,
+                }),
+                _ => None,
+            })),
+        Err(sdk_err) => match sdk_err.code() {
+            Some("ServerSideEncryptionConfigurationNotFoundError") => Ok(None),
+            _ => Err(Error::sdk(sdk_err)),
+        },
+    }
+}
+
+#[cfg(feature = "sse")]
+/// Sets the default `Encryption` of new objects in a bucket by `bucket_name`
+///
+/// Only `Encryption::SseS3` and `Encryption::SseKms` can be a bucket default,
+/// the `context` of `Encryption::SseKms` is ignored.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// set_bucket_encryption(&client, "sharks", Encryption::SseS3).await?;
+/// ```
+pub async fn set_bucket_encryption(
+    client: &Client,
+    bucket_name: &str,
+    encryption: Encryption,
+) -> Result<(), Error> {
+    let by_default = match encryption {
+        Encryption::SseS3 => {
+            ServerSideEncryptionByDefault::builder().sse_algorithm(ServerSideEncryption::Aes256)
+        }
+        Encryption::SseKms { key_id, .. } => ServerSideEncryptionByDefault::builder()
+            .sse_algorithm(ServerSideEncryption::AwsKms)
+            .set_kms_master_key_id(key_id),
+        Encryption::SseC { .. } => {
+            return Err(Error::internal(
+                "SseC can not be the default encryption of a bucket",
+            ))
+        }
+    }
+    .build()
+    .map_err(Error::sdk)?;
+
+    let configuration = ServerSideEncryptionConfiguration::builder()
+        .rules(
+            ServerSideEncryptionRule::builder()
+                .apply_server_side_encryption_by_default(by_default)
+                .build(),
+        )
+        .build()
+        .map_err(Error::sdk)?;
+
+    client
+        .put_bucket_encryption()
+        .bucket(bucket_name)
+        .server_side_encryption_configuration(configuration)
+        .send()
+        .await
+        .map_err(Error::sdk)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "sse")]
+/// Removes the default encryption of new objects in a bucket by `bucket_name`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// delete_bucket_encryption(&client, "sharks").await?;
+/// ```
+pub async fn delete_bucket_encryption(client: &Client, bucket_name: &str) -> Result<(), Error> {
+    client
+        .delete_bucket_encryption()
+        .bucket(bucket_name)
+        .send()
+        .await
+        .map_err(Error::sdk)?;
+
+    Ok(())
+}
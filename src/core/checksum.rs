@@ -0,0 +1,385 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::error::Error;
+use aws_sdk_s3::{
+    operation::complete_multipart_upload::CompleteMultipartUploadOutput, types::ChecksumAlgorithm,
+};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "checksum")]
+use aws_smithy_checksums::{http::HttpChecksum, ChecksumAlgorithm as HttpChecksumAlgorithm};
+#[cfg(feature = "checksum")]
+use aws_smithy_types::base64;
+#[cfg(feature = "checksum")]
+use md5::{Digest, Md5};
+#[cfg(feature = "checksum")]
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+#[cfg(feature = "checksum")]
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// Checksum algorithm to verify the integrity of an object with
+///
+/// - `Crc32`, `Crc32C`, `Sha256`: Sent as `x-amz-checksum-*` headers,
+///   the server rejects mismatching bodies and stores the checksum
+///   with the object. Multipart uploads store a composite checksum
+///   of the part checksums.
+///
+/// - `Md5`: Sent as the `Content-MD5` header, the server rejects
+///   mismatching bodies, and gets are verified against the `ETag`.
+///   *Note*: The `ETag` of objects encrypted via SSE-KMS or SSE-C
+///   is not their MD5, so those can not be verified on get.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let additional_options = UploadObjectAdditionalOptions {
+///     checksum: Some(Checksum::Crc32C),
+///     ..Default::default()
+/// };
+/// ```
+#[cfg(feature = "checksum")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checksum {
+    Crc32,
+    Crc32C,
+    Sha256,
+    Md5,
+}
+
+/// The checksum headers a body is uploaded with
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ChecksumHeaders {
+    pub(crate) algorithm: Option<ChecksumAlgorithm>,
+    pub(crate) crc32: Option<String>,
+    pub(crate) crc32_c: Option<String>,
+    pub(crate) sha256: Option<String>,
+    pub(crate) content_md5: Option<String>,
+}
+
+impl ChecksumHeaders {
+    /// Returns an error if the checksums the server computed for the body
+    /// do not match the ones sent
+    pub(crate) fn verify(
+        &self,
+        crc32: Option<&str>,
+        crc32_c: Option<&str>,
+        sha256: Option<&str>,
+    ) -> Result<(), Error> {
+        for (name, sent, returned) in [
+            ("CRC32", &self.crc32, crc32),
+            ("CRC32C", &self.crc32_c, crc32_c),
+            ("SHA256", &self.sha256, sha256),
+        ] {
+            if let Some(sent) = sent {
+                if returned != Some(sent.as_str()) {
+                    return Err(Error::Internal(format!(
+                        "Server returned {} checksum {:?}, expected {}",
+                        name, returned, sent
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The checksums of the parts of a multipart upload by their part number
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PartChecksums {
+    pub(crate) algorithm: Option<ChecksumAlgorithm>,
+    pub(crate) values: BTreeMap<usize, String>,
+}
+
+impl PartChecksums {
+    /// Records the checksum of the part by `part_number` from its `headers`
+    pub(crate) fn insert(&mut self, part_number: usize, headers: &ChecksumHeaders) {
+        let value = match self.algorithm {
+            Some(ChecksumAlgorithm::Crc32) => &headers.crc32,
+            Some(ChecksumAlgorithm::Crc32C) => &headers.crc32_c,
+            Some(ChecksumAlgorithm::Sha256) => &headers.sha256,
+            _ => &None,
+        };
+
+        if let Some(value) = value {
+            self.values.insert(part_number, value.clone());
+        }
+    }
+
+    /// Returns an error if the checksum the server stored for the completed
+    /// multipart upload does not match the composite of the part checksums
+    ///
+    /// A server returning no checksum is not a mismatch, as every part
+    /// checksum was already verified by its `UploadPart` response.
+    #[cfg(feature = "checksum")]
+    pub(crate) fn verify(&self, output: &CompleteMultipartUploadOutput) -> Result<(), Error> {
+        let (checksum, stored) = match self.algorithm {
+            Some(ChecksumAlgorithm::Crc32) => (Checksum::Crc32, output.checksum_crc32()),
+            Some(ChecksumAlgorithm::Crc32C) => (Checksum::Crc32C, output.checksum_crc32_c()),
+            Some(ChecksumAlgorithm::Sha256) => (Checksum::Sha256, output.checksum_sha256()),
+            _ => return Ok(()),
+        };
+
+        let Some(stored) = stored else {
+            return Ok(());
+        };
+
+        let expected = checksum.composite(self.values.values().map(String::as_str))?;
+
+        if stored != expected {
+            return Err(Error::Internal(format!(
+                "Server stored {:?} checksum {}, expected {}",
+                checksum, stored, expected
+            )));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "checksum"))]
+    pub(crate) fn verify(&self, _output: &CompleteMultipartUploadOutput) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Incrementally computes the digest of a `Checksum`
+#[cfg(feature = "checksum")]
+pub(crate) enum Hasher {
+    Http(Box<dyn HttpChecksum>),
+    Md5(Md5),
+}
+
+#[cfg(feature = "checksum")]
+impl Hasher {
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Http(checksum) => checksum.update(bytes),
+            Hasher::Md5(md5) => md5.update(bytes),
+        }
+    }
+
+    pub(crate) fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Http(checksum) => checksum.finalize().to_vec(),
+            Hasher::Md5(md5) => md5.finalize().to_vec(),
+        }
+    }
+}
+
+#[cfg(feature = "checksum")]
+impl Checksum {
+    /// Returns the S3 `ChecksumAlgorithm` of the checksum,
+    /// `None` for `Md5` which is sent as `Content-MD5`
+    pub(crate) fn algorithm(self) -> Option<ChecksumAlgorithm> {
+        match self {
+            Checksum::Crc32 => Some(ChecksumAlgorithm::Crc32),
+            Checksum::Crc32C => Some(ChecksumAlgorithm::Crc32C),
+            Checksum::Sha256 => Some(ChecksumAlgorithm::Sha256),
+            Checksum::Md5 => None,
+        }
+    }
+
+    pub(crate) fn hasher(self) -> Hasher {
+        match self {
+            Checksum::Crc32 => Hasher::Http(HttpChecksumAlgorithm::Crc32.into_impl()),
+            Checksum::Crc32C => Hasher::Http(HttpChecksumAlgorithm::Crc32c.into_impl()),
+            Checksum::Sha256 => Hasher::Http(HttpChecksumAlgorithm::Sha256.into_impl()),
+            Checksum::Md5 => Hasher::Md5(Md5::new()),
+        }
+    }
+
+    /// Returns the headers to upload `bytes` with
+    pub(crate) fn headers(self, bytes: &[u8]) -> ChecksumHeaders {
+        let mut hasher = self.hasher();
+        hasher.update(bytes);
+
+        let value = Some(base64::encode(hasher.finalize()));
+
+        let mut headers = ChecksumHeaders {
+            algorithm: self.algorithm(),
+            ..Default::default()
+        };
+
+        match self {
+            Checksum::Crc32 => headers.crc32 = value,
+            Checksum::Crc32C => headers.crc32_c = value,
+            Checksum::Sha256 => headers.sha256 = value,
+            Checksum::Md5 => headers.content_md5 = value,
+        }
+
+        headers
+    }
+
+    /// Encodes a `digest` the way the server returns it, I.E.
+    /// hex for the `ETag` of `Md5`, and base64 otherwise
+    fn encode(self, digest: &[u8]) -> String {
+        match self {
+            Checksum::Md5 => digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            _ => base64::encode(digest),
+        }
+    }
+
+    /// Returns the composite checksum of a multipart upload
+    /// from the base64 `part_checksums`, I.E. the checksum of
+    /// their concatenated digests suffixed by the part count
+    pub(crate) fn composite<'c>(
+        self,
+        part_checksums: impl ExactSizeIterator<Item = &'c str>,
+    ) -> Result<String, Error> {
+        let part_count = part_checksums.len();
+        let mut hasher = self.hasher();
+
+        for part_checksum in part_checksums {
+            hasher.update(&base64::decode(part_checksum).map_err(Error::sdk)?);
+        }
+
+        Ok(format!(
+            "{}-{}",
+            self.encode(&hasher.finalize()),
+            part_count
+        ))
+    }
+}
+
+/// Verifies a stream against the checksum the server stored for it,
+/// which is the composite of the checksums of parts of `part_sizes`
+/// if those are not empty
+#[cfg(feature = "checksum")]
+pub(crate) struct ChecksumVerifier {
+    checksum: Checksum,
+    expected: String,
+    part_sizes: Vec<usize>,
+    part_index: usize,
+    part_remaining: usize,
+    hasher: Hasher,
+    part_digests: Vec<u8>,
+}
+
+#[cfg(feature = "checksum")]
+impl ChecksumVerifier {
+    pub(crate) fn new(checksum: Checksum, expected: String, part_sizes: Vec<usize>) -> Self {
+        Self {
+            checksum,
+            expected,
+            part_remaining: part_sizes.first().copied().unwrap_or_default(),
+            part_sizes,
+            part_index: 0,
+            hasher: checksum.hasher(),
+            part_digests: vec![],
+        }
+    }
+
+    fn update(&mut self, mut bytes: &[u8]) {
+        if self.part_sizes.is_empty() {
+            self.hasher.update(bytes);
+            return;
+        }
+
+        while !bytes.is_empty() {
+            let is_last_part = self.part_index + 1 >= self.part_sizes.len();
+            let len = if is_last_part {
+                bytes.len()
+            } else {
+                self.part_remaining.min(bytes.len())
+            };
+
+            self.hasher.update(&bytes[..len]);
+            self.part_remaining = self.part_remaining.saturating_sub(len);
+            bytes = &bytes[len..];
+
+            if self.part_remaining == 0 && !is_last_part {
+                let hasher = std::mem::replace(&mut self.hasher, self.checksum.hasher());
+                self.part_digests.extend(hasher.finalize());
+
+                self.part_index += 1;
+                self.part_remaining = self.part_sizes[self.part_index];
+            }
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        let actual = if self.part_sizes.is_empty() {
+            self.checksum.encode(&self.hasher.finalize())
+        } else {
+            let mut part_digests = self.part_digests;
+            part_digests.extend(self.hasher.finalize());
+
+            let mut hasher = self.checksum.hasher();
+            hasher.update(&part_digests);
+
+            format!(
+                "{}-{}",
+                self.checksum.encode(&hasher.finalize()),
+                self.part_sizes.len()
+            )
+        };
+
+        if actual != self.expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{:?} checksum {} does not match the stored {}",
+                    self.checksum, actual, self.expected
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Passes a stream `R` through, erroring at EOF if it does not
+/// match the checksum of the `ChecksumVerifier`, if any
+#[cfg(feature = "checksum")]
+pub(crate) struct ChecksumReader<R> {
+    inner: Pin<Box<R>>,
+    verifier: Option<ChecksumVerifier>,
+}
+
+#[cfg(feature = "checksum")]
+impl<R> ChecksumReader<R> {
+    pub(crate) fn new(inner: R, verifier: Option<ChecksumVerifier>) -> Self {
+        Self {
+            inner: Box::pin(inner),
+            verifier,
+        }
+    }
+}
+
+#[cfg(feature = "checksum")]
+impl<R> AsyncRead for ChecksumReader<R>
+where
+    R: AsyncRead,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let filled = buf.filled().len();
+        let has_capacity = buf.remaining() > 0;
+
+        ready!(this.inner.as_mut().poll_read(cx, buf))?;
+
+        let read = &buf.filled()[filled..];
+
+        if !read.is_empty() {
+            if let Some(verifier) = this.verifier.as_mut() {
+                verifier.update(read);
+            }
+        } else if has_capacity {
+            if let Some(verifier) = this.verifier.take() {
+                verifier.finish()?;
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
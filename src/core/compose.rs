@@ -0,0 +1,385 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{checksum::PartChecksums, presign::uri_encode, sse::SseHeaders, upload::util::*};
+use crate::{error::Error, ETag};
+use aws_sdk_s3::{
+    error::SdkError, operation::head_object::HeadObjectError, primitives::DateTime, Client,
+};
+use std::collections::HashMap;
+
+#[cfg(feature = "sse")]
+use super::sse::Encryption;
+
+/// Minimum size of every part except the last, in bytes
+const MIN_PART_SIZE: usize = 5_242_880;
+
+/// Maximum size of a single copied part, in bytes
+const MAX_PART_SIZE: u64 = 5_368_709_120;
+
+/// Maximum size of a composed object, in bytes
+const MAX_OBJECT_SIZE: u64 = 5_497_558_138_880;
+
+/// Maximum amount of parts in a multipart upload
+const MAX_PARTS: usize = 10_000;
+
+/// Represents a source object for `compose_object`
+///
+/// `range` is an inclusive byte range `(start, end)` of the
+/// source object, defaulting to the whole object if `None`.
+///
+/// `version_id` selects a specific version of the source object,
+/// defaulting to the latest if `None`.
+///
+/// With the `sse` feature, `encryption` must be the `Encryption::SseC`
+/// key the source object was encrypted with, if any.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let source = ComposeSource::new("logs", "shard-0001.log");
+///
+/// let ranged_source = ComposeSource {
+///     range: Some((0, 5_242_879)),
+///     ..ComposeSource::new("logs", "shard-0002.log")
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct ComposeSource {
+    pub bucket_name: String,
+    pub object_name: String,
+    pub range: Option<(usize, usize)>,
+    pub version_id: Option<String>,
+    #[cfg(feature = "sse")]
+    pub encryption: Option<Encryption>,
+}
+
+impl ComposeSource {
+    /// Constructs a `ComposeSource` for the whole object by `bucket_name`
+    /// and `object_name`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let source = ComposeSource::new("logs", "shard-0001.log");
+    /// ```
+    pub fn new(bucket_name: &str, object_name: &str) -> Self {
+        Self {
+            bucket_name: bucket_name.to_string(),
+            object_name: object_name.to_string(),
+            range: None,
+            version_id: None,
+            #[cfg(feature = "sse")]
+            encryption: None,
+        }
+    }
+
+    /// Returns the value of the `x-amz-copy-source` header for this source
+    fn copy_source(&self) -> String {
+        let mut copy_source = format!(
+            "{}/{}",
+            uri_encode(&self.bucket_name, true),
+            uri_encode(&self.object_name, false)
+        );
+
+        if let Some(ref version_id) = self.version_id {
+            copy_source.push_str(&format!("?versionId={}", uri_encode(version_id, true)));
+        }
+
+        copy_source
+    }
+
+    /// Returns the `SseHeaders` to read this source with
+    #[cfg(feature = "sse")]
+    fn sse(&self) -> SseHeaders {
+        SseHeaders::from(self.encryption.as_ref())
+    }
+
+    /// Returns the `SseHeaders` to read this source with
+    #[cfg(not(feature = "sse"))]
+    fn sse(&self) -> SseHeaders {
+        SseHeaders::default()
+    }
+}
+
+/// Additional options for `compose_object`
+/// to set the user `metadata` and the standard
+/// headers of the composed object
+///
+/// With the `sse` feature, `encryption` sets the
+/// server-side `Encryption` of the composed object.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let additional_options = ComposeObjectAdditionalOptions {
+///     encryption: Some(Encryption::SseS3),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ComposeObjectAdditionalOptions {
+    pub metadata: Option<HashMap<String, String>>,
+    pub content_type: Option<String>,
+    pub content_encoding: Option<String>,
+    pub cache_control: Option<String>,
+    pub content_disposition: Option<String>,
+    pub expires: Option<DateTime>,
+    #[cfg(feature = "sse")]
+    pub encryption: Option<Encryption>,
+}
+
+/// A single `UploadPartCopy` call, copying the inclusive byte range
+/// `(start, end)` from `source_index`
+struct CopyPart {
+    source_index: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Returns the size of a source object, or an error if it does not exist
+async fn source_size(client: &Client, source: &ComposeSource) -> Result<usize, Error> {
+    let sse = source.sse();
+
+    match client
+        .head_object()
+        .bucket(&source.bucket_name)
+        .key(&source.object_name)
+        .set_version_id(source.version_id.clone())
+        .set_sse_customer_algorithm(sse.customer_algorithm)
+        .set_sse_customer_key(sse.customer_key)
+        .set_sse_customer_key_md5(sse.customer_key_md5)
+        .send()
+        .await
+    {
+        Ok(response) => Ok(response.content_length.unwrap_or_default().max(0) as usize),
+        Err(sdk_err) => match sdk_err {
+            SdkError::ServiceError(ref err, ..) => match err.err() {
+                HeadObjectError::NotFound(_) => Err(Error::Internal(format!(
+                    "Compose source {}/{} does not exist",
+                    source.bucket_name, source.object_name
+                ))),
+                _ => Err(Error::sdk(sdk_err)),
+            },
+
+            _ => Err(Error::sdk(sdk_err)),
+        },
+    }
+}
+
+/// Validates the sources against the multipart part-size rules and splits
+/// them into `CopyPart`s, returning the parts and the total size
+async fn plan_copy_parts(
+    client: &Client,
+    sources: &[ComposeSource],
+) -> Result<(Vec<CopyPart>, usize), Error> {
+    if sources.is_empty() {
+        return Err(Error::internal(
+            "compose_object requires at least one source",
+        ));
+    }
+
+    let mut copy_parts = vec![];
+    let mut total_size = 0;
+
+    for (source_index, source) in sources.iter().enumerate() {
+        let object_size = source_size(client, source).await?;
+
+        let (start, end) = match source.range {
+            Some((start, end)) => {
+                if start > end || end >= object_size {
+                    return Err(Error::Internal(format!(
+                        "Compose source {}/{} has an invalid range {}-{} for an object of {} bytes",
+                        source.bucket_name, source.object_name, start, end, object_size
+                    )));
+                }
+
+                (start, end)
+            }
+            None => {
+                if object_size == 0 {
+                    return Err(Error::Internal(format!(
+                        "Compose source {}/{} is empty",
+                        source.bucket_name, source.object_name
+                    )));
+                }
+
+                (0, object_size - 1)
+            }
+        };
+
+        let size = end - start + 1;
+
+        if size < MIN_PART_SIZE && source_index != sources.len() - 1 {
+            return Err(Error::Internal(format!(
+                "Compose source {}/{} is {} bytes, only the last source may be smaller than {} bytes",
+                source.bucket_name, source.object_name, size, MIN_PART_SIZE
+            )));
+        }
+
+        total_size += size;
+
+        // Split evenly so no part exceeds `MAX_PART_SIZE`, and no part falls
+        // below `MIN_PART_SIZE` unless the whole source does.
+        let part_count = (size as u64).div_ceil(MAX_PART_SIZE) as usize;
+        let part_size = size / part_count;
+        let remainder = size % part_count;
+
+        let mut part_start = start;
+
+        for part_index in 0..part_count {
+            let part_len = part_size + usize::from(part_index < remainder);
+
+            copy_parts.push(CopyPart {
+                source_index,
+                start: part_start,
+                end: part_start + part_len - 1,
+            });
+
+            part_start += part_len;
+        }
+    }
+
+    if total_size as u64 > MAX_OBJECT_SIZE {
+        return Err(Error::Internal(format!(
+            "Composed object would be {} bytes, exceeding the maximum of {} bytes",
+            total_size, MAX_OBJECT_SIZE
+        )));
+    }
+
+    if copy_parts.len() > MAX_PARTS {
+        return Err(Error::Internal(format!(
+            "Composed object would require {} parts, exceeding the maximum of {}",
+            copy_parts.len(),
+            MAX_PARTS
+        )));
+    }
+
+    Ok((copy_parts, total_size))
+}
+
+/// Composes an object named `object_name` in the bucket named `bucket_name`
+/// from multiple `ComposeSource`s, server-side, via `UploadPartCopy`
+///
+/// Every source except the last must be at least `5_242_880` bytes, sources
+/// larger than `5_368_709_120` bytes are split into multiple parts.
+///
+/// The multipart upload is aborted if any part fails to copy.
+///
+/// Returns the total amount of bytes composed
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// let bytes_composed: usize = compose_object(
+///     &client,
+///     "logs",
+///     "merged.log",
+///     vec![
+///         ComposeSource::new("logs", "shard-0001.log"),
+///         ComposeSource::new("logs", "shard-0002.log"),
+///     ],
+///     ComposeObjectAdditionalOptions::default(),
+/// ).await?;
+/// ```
+pub async fn compose_object(
+    client: &Client,
+    bucket_name: &str,
+    object_name: &str,
+    sources: Vec<ComposeSource>,
+    ComposeObjectAdditionalOptions {
+        metadata,
+        content_type,
+        content_encoding,
+        cache_control,
+        content_disposition,
+        expires,
+        #[cfg(feature = "sse")]
+        encryption,
+    }: ComposeObjectAdditionalOptions,
+) -> Result<usize, Error> {
+    let content = ContentHeaders {
+        content_type,
+        content_encoding,
+        cache_control,
+        content_disposition,
+        expires,
+    };
+
+    #[cfg(feature = "sse")]
+    let sse = SseHeaders::from(encryption.as_ref());
+    #[cfg(not(feature = "sse"))]
+    let sse = SseHeaders::default();
+
+    let (copy_parts, total_size) = plan_copy_parts(client, &sources).await?;
+
+    let upload_id = start_multipart_upload(
+        client,
+        bucket_name,
+        object_name,
+        metadata,
+        &content,
+        &sse,
+        None,
+    )
+    .await?;
+
+    let mut e_tags = vec![];
+
+    for (
+        index,
+        CopyPart {
+            source_index,
+            start,
+            end,
+        },
+    ) in copy_parts.into_iter().enumerate()
+    {
+        let part_number = index + 1;
+
+        match upload_part_copy(
+            client,
+            bucket_name,
+            object_name,
+            &upload_id,
+            part_number,
+            CopySourceRange {
+                copy_source: &sources[source_index].copy_source(),
+                range: (start, end),
+                sse: &sources[source_index].sse(),
+            },
+            &sse,
+        )
+        .await
+        {
+            Ok(e_tag) => e_tags.push(ETag { e_tag, part_number }),
+            Err(err) => {
+                abort_multipart_upload(client, bucket_name, object_name, &upload_id).await?;
+                return Err(err);
+            }
+        }
+    }
+
+    if let Err(err) = complete_multipart_upload(
+        client,
+        e_tags,
+        bucket_name,
+        object_name,
+        &upload_id,
+        &PartChecksums::default(),
+        &sse,
+    )
+    .await
+    {
+        abort_multipart_upload(client, bucket_name, object_name, &upload_id).await?;
+        return Err(err);
+    }
+
+    Ok(total_size)
+}
@@ -0,0 +1,138 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::error::Error;
+use async_compression::tokio::bufread::{GzipDecoder, GzipEncoder, ZstdDecoder, ZstdEncoder};
+use std::{
+    collections::HashMap,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncBufRead, AsyncRead, BufReader, ReadBuf};
+
+/// The metadata key marking the `Compression` of an object
+pub const METADATA_COMPRESSION: &str = "minior-compression";
+
+/// Compression of an object, applied client-side while uploading
+/// and reverted by `get_object` when the object is marked with it
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let additional_options = UploadObjectAdditionalOptions {
+///     compression: Some(Compression::Zstd),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the name the compression is marked with in the object's metadata
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    /// Returns the `Compression` an object is marked with in its `metadata`, if any
+    pub fn from_metadata(
+        metadata: Option<&HashMap<String, String>>,
+    ) -> Result<Option<Self>, Error> {
+        match metadata.and_then(|metadata| metadata.get(METADATA_COMPRESSION)) {
+            Some(value) => match value.as_str() {
+                "gzip" => Ok(Some(Compression::Gzip)),
+                "zstd" => Ok(Some(Compression::Zstd)),
+                _ => Err(Error::Internal(format!(
+                    "Object is compressed with an unsupported algorithm {}",
+                    value
+                ))),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+/// Compresses a stream `R` with a `Compression`, or passes it through as-is
+pub(crate) enum CompressingReader<R> {
+    Raw(Pin<Box<R>>),
+    Gzip(Pin<Box<GzipEncoder<BufReader<R>>>>),
+    Zstd(Pin<Box<ZstdEncoder<BufReader<R>>>>),
+}
+
+impl<R> CompressingReader<R>
+where
+    R: AsyncRead,
+{
+    pub(crate) fn new(inner: R, compression: Option<Compression>) -> Self {
+        match compression {
+            Some(Compression::Gzip) => {
+                Self::Gzip(Box::pin(GzipEncoder::new(BufReader::new(inner))))
+            }
+            Some(Compression::Zstd) => {
+                Self::Zstd(Box::pin(ZstdEncoder::new(BufReader::new(inner))))
+            }
+            None => Self::Raw(Box::pin(inner)),
+        }
+    }
+}
+
+impl<R> AsyncRead for CompressingReader<R>
+where
+    R: AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Raw(inner) => inner.as_mut().poll_read(cx, buf),
+            Self::Gzip(inner) => inner.as_mut().poll_read(cx, buf),
+            Self::Zstd(inner) => inner.as_mut().poll_read(cx, buf),
+        }
+    }
+}
+
+/// Decompresses a stream `R` with a `Compression`, or passes it through as-is
+pub(crate) enum DecompressingReader<R> {
+    Raw(Pin<Box<R>>),
+    Gzip(Pin<Box<GzipDecoder<R>>>),
+    Zstd(Pin<Box<ZstdDecoder<R>>>),
+}
+
+impl<R> DecompressingReader<R>
+where
+    R: AsyncBufRead,
+{
+    pub(crate) fn new(inner: R, compression: Option<Compression>) -> Self {
+        match compression {
+            Some(Compression::Gzip) => Self::Gzip(Box::pin(GzipDecoder::new(inner))),
+            Some(Compression::Zstd) => Self::Zstd(Box::pin(ZstdDecoder::new(inner))),
+            None => Self::Raw(Box::pin(inner)),
+        }
+    }
+}
+
+impl<R> AsyncRead for DecompressingReader<R>
+where
+    R: AsyncBufRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Raw(inner) => inner.as_mut().poll_read(cx, buf),
+            Self::Gzip(inner) => inner.as_mut().poll_read(cx, buf),
+            Self::Zstd(inner) => inner.as_mut().poll_read(cx, buf),
+        }
+    }
+}
@@ -0,0 +1,453 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::error::Error;
+use aws_credential_types::provider::{error::CredentialsError, future};
+use aws_sdk_s3::config::{Credentials, ProvideCredentials, SharedCredentialsProvider};
+use std::{
+    fmt::Debug,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+/// Credentials are refreshed this long before they expire
+pub const EXPIRY_BUFFER: Duration = Duration::from_secs(60);
+
+/// Default interval `FileCredentials` checks its file for changes at
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Provides the credentials `Minio` signs requests with, I.E. leased
+/// from Vault, and consulted again by `CredentialsCache` once they expire
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// #[derive(Debug)]
+/// struct VaultCredentials { ... }
+///
+/// impl CredentialsProvider for VaultCredentials {
+///     async fn provide_credentials(&self) -> Result<Credentials, Error> {
+///         let lease = self.vault.read("minio/creds/app").await?;
+///
+///         Ok(Credentials::new(
+///             lease.access_key,
+///             lease.secret_key,
+///             None,
+///             Some(lease.expires_at),
+///             "vault",
+///         ))
+///     }
+/// }
+/// ```
+pub trait CredentialsProvider: Debug + Send + Sync {
+    /// Returns the current credentials, which are cached until their
+    /// `expiry`, or indefinitely without one, unless `cache_ttl` is shorter
+    fn provide_credentials(&self) -> impl Future<Output = Result<Credentials, Error>> + Send;
+
+    /// Returns how long credentials are cached at most, regardless of their expiry
+    fn cache_ttl(&self) -> Option<Duration> {
+        None
+    }
+}
+
+type ProvideFuture<'a> = Pin<Box<dyn Future<Output = Result<Credentials, Error>> + Send + 'a>>;
+
+/// Object-safe `CredentialsProvider`, so `CredentialsCache` can swap providers
+trait DynCredentialsProvider: Debug + Send + Sync {
+    fn provide(&self) -> ProvideFuture<'_>;
+
+    fn cache_ttl(&self) -> Option<Duration>;
+}
+
+impl<P: CredentialsProvider> DynCredentialsProvider for P {
+    fn provide(&self) -> ProvideFuture<'_> {
+        Box::pin(self.provide_credentials())
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        CredentialsProvider::cache_ttl(self)
+    }
+}
+
+#[derive(Debug)]
+struct CacheState {
+    provider: Arc<dyn DynCredentialsProvider>,
+    cached: Option<(Credentials, Option<SystemTime>)>,
+}
+
+/// Caches the credentials of a `CredentialsProvider` until they are
+/// within `EXPIRY_BUFFER` of expiring, and lets the provider or the
+/// credentials be swapped at runtime without rebuilding the client
+///
+/// Every `Minio` constructor installs one as the credentials provider of
+/// its client, see `Minio::set_credentials` and `Minio::set_credentials_provider`.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let cache = CredentialsCache::new(FileCredentials::new("/vault/secrets/minio"));
+///
+/// let credentials: Credentials = cache.credentials().await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct CredentialsCache {
+    state: Arc<Mutex<CacheState>>,
+    /// Held while consulting the provider, so concurrent callers
+    /// wait for a single refresh instead of each starting one
+    refresh: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl CredentialsCache {
+    pub fn new(provider: impl CredentialsProvider + 'static) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(CacheState {
+                provider: Arc::new(provider),
+                cached: None,
+            })),
+            refresh: Arc::default(),
+        }
+    }
+
+    /// Returns the cached credentials, consulting the provider if there
+    /// are none or they are about to expire
+    pub async fn credentials(&self) -> Result<Credentials, Error> {
+        if let Some(credentials) = self.cached()? {
+            return Ok(credentials);
+        }
+
+        let _refresh = self.refresh.lock().await;
+
+        // Another caller may have refreshed while this one waited
+        if let Some(credentials) = self.cached()? {
+            return Ok(credentials);
+        }
+
+        let provider = self.lock()?.provider.clone();
+
+        let fetched_at = SystemTime::now();
+        let credentials = provider.provide().await?;
+
+        let expiry = credentials
+            .expiry()
+            .map(|expiry| expiry.checked_sub(EXPIRY_BUFFER).unwrap_or(expiry));
+        let ttl = provider.cache_ttl().map(|ttl| fetched_at + ttl);
+        let valid_until = match (expiry, ttl) {
+            (Some(expiry), Some(ttl)) => Some(expiry.min(ttl)),
+            (expiry, ttl) => expiry.or(ttl),
+        };
+
+        let mut state = self.lock()?;
+
+        // The provider may have been swapped while providing
+        if Arc::ptr_eq(&state.provider, &provider) {
+            state.cached = Some((credentials.clone(), valid_until));
+        }
+
+        Ok(credentials)
+    }
+
+    /// Returns the cached credentials, if they are not about to expire
+    fn cached(&self) -> Result<Option<Credentials>, Error> {
+        let state = self.lock()?;
+
+        Ok(state
+            .cached
+            .as_ref()
+            .filter(|(_, valid_until)| {
+                valid_until.is_none_or(|valid_until| SystemTime::now() < valid_until)
+            })
+            .map(|(credentials, _)| credentials.clone()))
+    }
+
+    /// Replaces the provider, dropping the cached credentials
+    pub fn set_provider(&self, provider: impl CredentialsProvider + 'static) -> Result<(), Error> {
+        let mut state = self.lock()?;
+
+        state.provider = Arc::new(provider);
+        state.cached = None;
+
+        Ok(())
+    }
+
+    /// Replaces the provider with `credentials`, until the provider is replaced again
+    pub fn set_credentials(&self, credentials: Credentials) -> Result<(), Error> {
+        self.set_provider(StaticCredentials::from(credentials))
+    }
+
+    /// Drops the cached credentials, so the provider is consulted on the next request
+    pub fn invalidate(&self) -> Result<(), Error> {
+        self.lock()?.cached = None;
+
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, CacheState>, Error> {
+        self.state
+            .lock()
+            .map_err(|_| Error::internal("CredentialsCache lock was poisoned"))
+    }
+}
+
+impl ProvideCredentials for CredentialsCache {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::new(async move {
+            self.credentials()
+                .await
+                .map_err(CredentialsError::provider_error)
+        })
+    }
+}
+
+/// A `CredentialsProvider` of static keys
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let provider = StaticCredentials::new("minioadmin", "minioadmin", None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StaticCredentials {
+    credentials: Credentials,
+}
+
+impl StaticCredentials {
+    pub fn new(access_key: &str, secret_key: &str, session_token: Option<&str>) -> Self {
+        Self {
+            credentials: Credentials::new(
+                access_key,
+                secret_key,
+                session_token.map(str::to_string),
+                None,
+                "minior",
+            ),
+        }
+    }
+}
+
+impl From<Credentials> for StaticCredentials {
+    fn from(credentials: Credentials) -> Self {
+        Self { credentials }
+    }
+}
+
+impl CredentialsProvider for StaticCredentials {
+    async fn provide_credentials(&self) -> Result<Credentials, Error> {
+        Ok(self.credentials.clone())
+    }
+}
+
+/// Bridges the credentials providers of the AWS SDK, I.E. the default chain
+/// `Minio::new` loads from environment
+impl CredentialsProvider for SharedCredentialsProvider {
+    async fn provide_credentials(&self) -> Result<Credentials, Error> {
+        ProvideCredentials::provide_credentials(self)
+            .await
+            .map_err(Error::sdk)
+    }
+}
+
+/// A `CredentialsProvider` reading an AWS shared credentials file,
+/// re-read whenever its modification time changes, I.E. rendered by
+/// the Vault agent on rotation
+///
+/// The file is checked every `DEFAULT_POLL_INTERVAL`, and is expected to
+/// have `aws_access_key_id`, `aws_secret_access_key` and optionally
+/// `aws_session_token` under its profile, `default` unless set:
+///
+/// ```text
+/// [default]
+/// aws_access_key_id = minioadmin
+/// aws_secret_access_key = minioadmin
+/// ```
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let provider = FileCredentials::new("/vault/secrets/minio")
+///     .with_profile("prod")
+///     .with_poll_interval(Duration::from_secs(60));
+/// ```
+#[derive(Debug)]
+pub struct FileCredentials {
+    path: PathBuf,
+    profile: String,
+    poll_interval: Duration,
+    last_read: Mutex<Option<(SystemTime, Credentials)>>,
+}
+
+impl FileCredentials {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            profile: "default".to_string(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            last_read: Mutex::new(None),
+        }
+    }
+
+    /// Reads the keys of the profile named `profile`
+    pub fn with_profile(mut self, profile: &str) -> Self {
+        self.profile = profile.to_string();
+        self
+    }
+
+    /// Checks the file for changes every `poll_interval`
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    fn parse(&self, contents: &str) -> Result<Credentials, Error> {
+        let mut section = "default";
+        let mut access_key = None;
+        let mut secret_key = None;
+        let mut session_token = None;
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let header = header.trim();
+                section = header.strip_prefix("profile ").unwrap_or(header).trim();
+                continue;
+            }
+
+            if section != self.profile {
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                let value = Some(value.trim().to_string());
+
+                match key.trim() {
+                    "aws_access_key_id" => access_key = value,
+                    "aws_secret_access_key" => secret_key = value,
+                    "aws_session_token" => session_token = value,
+                    _ => {}
+                }
+            }
+        }
+
+        match (access_key, secret_key) {
+            (Some(access_key), Some(secret_key)) => Ok(Credentials::new(
+                access_key,
+                secret_key,
+                session_token,
+                None,
+                "FileCredentials",
+            )),
+            _ => Err(Error::Internal(format!(
+                "Credentials file {} has no aws_access_key_id and aws_secret_access_key in profile {}",
+                self.path.display(),
+                self.profile
+            ))),
+        }
+    }
+}
+
+impl CredentialsProvider for FileCredentials {
+    async fn provide_credentials(&self) -> Result<Credentials, Error> {
+        let modified = tokio::fs::metadata(&self.path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .map_err(|err| Error::StdIo(err.kind()))?;
+
+        if let Ok(last_read) = self.last_read.lock() {
+            if let Some((last_modified, credentials)) = &*last_read {
+                if *last_modified == modified {
+                    return Ok(credentials.clone());
+                }
+            }
+        }
+
+        let contents = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+        let credentials = self.parse(&contents)?;
+
+        if let Ok(mut last_read) = self.last_read.lock() {
+            *last_read = Some((modified, credentials.clone()));
+        }
+
+        Ok(credentials)
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        Some(self.poll_interval)
+    }
+}
+
+/// A `CredentialsProvider` calling an async closure
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let provider = FnCredentials::new(move || {
+///     let vault = vault.clone();
+///
+///     async move {
+///         let lease = vault.read("minio/creds/app").await?;
+///
+///         Ok(Credentials::new(lease.access_key, lease.secret_key, None, Some(lease.expires_at), "vault"))
+///     }
+/// });
+/// ```
+pub struct FnCredentials<F> {
+    provide: F,
+    cache_ttl: Option<Duration>,
+}
+
+impl<F, Fut> FnCredentials<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Credentials, Error>> + Send,
+{
+    pub fn new(provide: F) -> Self {
+        Self {
+            provide,
+            cache_ttl: None,
+        }
+    }
+
+    /// Caches credentials for at most `cache_ttl`, I.E. for
+    /// credentials without an expiry
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = Some(cache_ttl);
+        self
+    }
+}
+
+impl<F> Debug for FnCredentials<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FnCredentials")
+            .field("cache_ttl", &self.cache_ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F, Fut> CredentialsProvider for FnCredentials<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Credentials, Error>> + Send,
+{
+    fn provide_credentials(&self) -> impl Future<Output = Result<Credentials, Error>> + Send {
+        (self.provide)()
+    }
+
+    fn cache_ttl(&self) -> Option<Duration> {
+        self.cache_ttl
+    }
+}
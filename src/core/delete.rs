@@ -1,10 +1,8 @@
 // Authors: Robert Lopez
 // License: MIT (See `LICENSE.md`)
+use super::presign::PresignedUrl;
 use crate::error::Error;
-use aws_sdk_s3::{
-    presigning::{PresignedRequest, PresigningConfig},
-    Client,
-};
+use aws_sdk_s3::{presigning::PresigningConfig, Client};
 use std::time::Duration;
 
 /// Deletes a object from a bucket by `bucket_name` and `object_name`
@@ -37,7 +35,7 @@ pub async fn delete_object(
     Ok(())
 }
 
-/// Generates a `PresignedRequest` from a bucket by `bucket_name` and `object_name`
+/// Generates a `PresignedUrl` from a bucket by `bucket_name` and `object_name`
 /// to delete the object.
 ///
 /// ---
@@ -46,7 +44,7 @@ pub async fn delete_object(
 ///
 /// let client: Client = ...;
 ///
-/// let request: PresignedRequest = delete_object_presigned(
+/// let request: PresignedUrl = delete_object_presigned(
 ///     &client,
 ///     "sharks",
 ///     "shark.jpg",
@@ -58,7 +56,7 @@ pub async fn delete_object_presigned(
     bucket_name: &str,
     object_name: &str,
     presigned_expiry_secs: u64,
-) -> Result<PresignedRequest, Error> {
+) -> Result<PresignedUrl, Error> {
     let presigning_config = PresigningConfig::builder()
         .expires_in(Duration::from_secs(presigned_expiry_secs))
         .build()
@@ -70,5 +68,6 @@ pub async fn delete_object_presigned(
         .key(object_name)
         .presigned(presigning_config)
         .await
+        .map(PresignedUrl::from)
         .map_err(Error::sdk)
 }
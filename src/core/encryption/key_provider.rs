@@ -0,0 +1,132 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::error::Error;
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use std::future::Future;
+
+/// Size of the nonce prepended to keys wrapped by `StaticKeyProvider`
+const WRAP_NONCE_SIZE: usize = 12;
+
+/// Provides the key-encryption keys that wrap the per-object data keys
+/// of `EncryptedMinio`, I.E. backed by a KMS, HSM or a local key
+///
+/// `key_id` is stored alongside the wrapped data key in the object's
+/// metadata, and passed back to `unwrap_key`, so keys can be rotated.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// struct KmsKeyProvider { ... }
+///
+/// impl KeyProvider for KmsKeyProvider {
+///     fn key_id(&self) -> &str {
+///         &self.key_id
+///     }
+///
+///     async fn wrap_key(&self, data_key: &[u8]) -> Result<Vec<u8>, Error> {
+///         self.kms.encrypt(&self.key_id, data_key).await
+///     }
+///
+///     async fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> Result<Vec<u8>, Error> {
+///         self.kms.decrypt(key_id, wrapped_key).await
+///     }
+/// }
+/// ```
+pub trait KeyProvider: Send + Sync {
+    /// Returns the id of the key-encryption key new data keys are wrapped with
+    fn key_id(&self) -> &str;
+
+    /// Wraps a new `data_key` with the key-encryption key by `key_id`
+    fn wrap_key(&self, data_key: &[u8]) -> impl Future<Output = Result<Vec<u8>, Error>> + Send;
+
+    /// Unwraps a `wrapped_key` with the key-encryption key by `key_id`
+    fn unwrap_key(
+        &self,
+        key_id: &str,
+        wrapped_key: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, Error>> + Send;
+}
+
+/// A `KeyProvider` wrapping data keys via AES-256-GCM with a single
+/// static 256-bit key-encryption `key`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let key_encryption_key: [u8; 32] = ...;
+///
+/// let key_provider = StaticKeyProvider::new("tenant-1337", key_encryption_key);
+/// ```
+pub struct StaticKeyProvider {
+    key_id: String,
+    key: [u8; 32],
+}
+
+impl StaticKeyProvider {
+    /// Constructs a `StaticKeyProvider` from the key-encryption `key`
+    /// identified by `key_id`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let key_provider = StaticKeyProvider::new("tenant-1337", key_encryption_key);
+    /// ```
+    pub fn new(key_id: &str, key: [u8; 32]) -> Self {
+        Self {
+            key_id: key_id.to_string(),
+            key,
+        }
+    }
+}
+
+impl KeyProvider for StaticKeyProvider {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    async fn wrap_key(&self, data_key: &[u8]) -> Result<Vec<u8>, Error> {
+        let cipher = Aes256Gcm::new_from_slice(&self.key).map_err(Error::sdk)?;
+
+        let mut nonce = [0; WRAP_NONCE_SIZE];
+        OsRng.fill_bytes(&mut nonce);
+
+        let mut wrapped_key = nonce.to_vec();
+        wrapped_key.extend(
+            cipher
+                .encrypt(&Nonce::from(nonce), data_key)
+                .map_err(|_| Error::internal("Failed to wrap the data key"))?,
+        );
+
+        Ok(wrapped_key)
+    }
+
+    async fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> Result<Vec<u8>, Error> {
+        if key_id != self.key_id {
+            return Err(Error::Internal(format!(
+                "StaticKeyProvider has no key-encryption key by id {}",
+                key_id
+            )));
+        }
+
+        if wrapped_key.len() < WRAP_NONCE_SIZE {
+            return Err(Error::internal("Wrapped data key is truncated"));
+        }
+
+        let (nonce, wrapped_key) = wrapped_key.split_at(WRAP_NONCE_SIZE);
+        let nonce: [u8; WRAP_NONCE_SIZE] = nonce
+            .try_into()
+            .map_err(|_| Error::internal("Wrapped data key is truncated"))?;
+
+        Aes256Gcm::new_from_slice(&self.key)
+            .map_err(Error::sdk)?
+            .decrypt(&Nonce::from(nonce), wrapped_key)
+            .map_err(|_| Error::internal("Failed to unwrap the data key"))
+    }
+}
@@ -0,0 +1,301 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+pub mod key_provider;
+pub(crate) mod stream;
+
+use crate::{
+    core::{
+        get::{get_object, stat_object, GetObjectAdditionalOptions, StatObjectAdditionalOptions},
+        upload::upload_object::{upload_object, UploadObjectAdditionalOptions},
+    },
+    error::Error,
+};
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use aws_sdk_s3::Client;
+use aws_smithy_types::base64;
+use key_provider::KeyProvider;
+use std::{collections::HashMap, sync::Arc};
+use stream::*;
+use tokio::io::{AsyncBufRead, AsyncRead, BufReader};
+
+/// Default amount of plaintext bytes per encrypted chunk
+pub const DEFAULT_CHUNK_SIZE: usize = 65_536;
+
+/// The algorithm objects are encrypted with, stored in their metadata
+const ALGORITHM: &str = "AES-256-GCM-CHUNKED";
+
+const METADATA_ALGORITHM: &str = "minior-encryption-algorithm";
+const METADATA_KEY_ID: &str = "minior-encryption-key-id";
+const METADATA_WRAPPED_KEY: &str = "minior-encryption-wrapped-key";
+const METADATA_NONCE_PREFIX: &str = "minior-encryption-nonce-prefix";
+const METADATA_CHUNK_SIZE: &str = "minior-encryption-chunk-size";
+
+/// Returns the plaintext size of an object encrypted in chunks of
+/// `chunk_size`, from its `encrypted_size`
+fn plaintext_size(encrypted_size: usize, chunk_size: usize) -> Result<usize, Error> {
+    let chunk_count = encrypted_size.div_ceil(chunk_size + TAG_SIZE);
+
+    encrypted_size
+        .checked_sub(chunk_count * TAG_SIZE)
+        .filter(|_| chunk_count > 0)
+        .ok_or(Error::internal("Encrypted object is truncated"))
+}
+
+/// Returns the value of the metadata `key`, or an error if it is missing
+fn metadata_value<'mv>(
+    metadata: &'mv HashMap<String, String>,
+    key: &str,
+) -> Result<&'mv str, Error> {
+    metadata
+        .get(key)
+        .map(String::as_str)
+        .ok_or(Error::Internal(format!(
+            "Object is missing the {} metadata, it is not client-side encrypted",
+            key
+        )))
+}
+
+/// Upload a object named `object_name` to the bucket named `bucket_name`,
+/// encrypting the stream `S` client-side with AES-256-GCM in chunks of
+/// `chunk_size` plaintext bytes
+///
+/// A new data key is generated per object, and stored in the object's
+/// metadata wrapped by the `key_provider`.
+///
+/// Returns the total amount of plaintext bytes uploaded
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+/// let key_provider = StaticKeyProvider::new("tenant-1337", key_encryption_key);
+/// let shark_image: tokio::fs::File = ...;
+///
+/// let bytes_uploaded: usize = upload_object_encrypted(
+///     Arc::new(client),
+///     &key_provider,
+///     "sharks",
+///     "shark.jpg",
+///     shark_image,
+///     DEFAULT_CHUNK_SIZE,
+///     UploadObjectAdditionalOptions::default(),
+/// ).await?;
+/// ```
+pub async fn upload_object_encrypted<K, S>(
+    client: Arc<Client>,
+    key_provider: &K,
+    bucket_name: &str,
+    object_name: &str,
+    stream: S,
+    chunk_size: usize,
+    mut additional_options: UploadObjectAdditionalOptions,
+) -> Result<usize, Error>
+where
+    K: KeyProvider,
+    S: AsyncRead + Unpin,
+{
+    if chunk_size == 0 {
+        return Err(Error::internal("chunk_size must be greater than 0"));
+    }
+
+    #[cfg(feature = "compression")]
+    if additional_options.compression.is_some() {
+        return Err(Error::internal(
+            "Client-side encrypted objects can not be compressed",
+        ));
+    }
+
+    let mut data_key = [0; 32];
+    OsRng.fill_bytes(&mut data_key);
+
+    let mut nonce_prefix = [0; NONCE_PREFIX_SIZE];
+    OsRng.fill_bytes(&mut nonce_prefix);
+
+    let wrapped_key = key_provider.wrap_key(&data_key).await?;
+
+    let metadata = additional_options.metadata.get_or_insert_with(HashMap::new);
+
+    for (key, value) in [
+        (METADATA_ALGORITHM, ALGORITHM.to_string()),
+        (METADATA_KEY_ID, key_provider.key_id().to_string()),
+        (METADATA_WRAPPED_KEY, base64::encode(wrapped_key)),
+        (METADATA_NONCE_PREFIX, base64::encode(nonce_prefix)),
+        (METADATA_CHUNK_SIZE, chunk_size.to_string()),
+    ] {
+        metadata.insert(key.to_string(), value);
+    }
+
+    let mut encrypting_reader = EncryptingReader::new(stream, &data_key, nonce_prefix, chunk_size);
+
+    upload_object(
+        client,
+        bucket_name,
+        object_name,
+        &mut encrypting_reader,
+        additional_options,
+    )
+    .await?;
+
+    Ok(encrypting_reader.plaintext_bytes)
+}
+
+/// Returns a stream decrypting an object by `bucket_name` and `object_name`,
+/// that was uploaded via `upload_object_encrypted`
+///
+/// `range` of `additional_options` is an inclusive plaintext byte range,
+/// only the chunks overlapping it are downloaded. `checksum` is not
+/// supported, as every chunk is authenticated by AES-256-GCM instead.
+///
+/// Returns `Ok(None)` if the object does not exist.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+/// let key_provider = StaticKeyProvider::new("tenant-1337", key_encryption_key);
+///
+/// let stream: Option<impl AsyncBufRead> = get_object_encrypted(
+///     &client,
+///     &key_provider,
+///     "sharks",
+///     "shark.jpg",
+///     GetObjectAdditionalOptions::default(),
+/// ).await?;
+/// ```
+pub async fn get_object_encrypted<K>(
+    client: &Client,
+    key_provider: &K,
+    bucket_name: &str,
+    object_name: &str,
+    GetObjectAdditionalOptions {
+        range,
+        version_id,
+        #[cfg(feature = "sse")]
+        encryption,
+        #[cfg(feature = "checksum")]
+        checksum,
+        #[cfg(feature = "compression")]
+            raw: _,
+    }: GetObjectAdditionalOptions,
+) -> Result<Option<impl AsyncBufRead>, Error>
+where
+    K: KeyProvider,
+{
+    #[cfg(feature = "checksum")]
+    if checksum.is_some() {
+        return Err(Error::internal(
+            "A checksum can not be verified for a client-side encrypted object",
+        ));
+    }
+
+    let stat = match stat_object(
+        client,
+        bucket_name,
+        object_name,
+        StatObjectAdditionalOptions {
+            version_id: version_id.clone(),
+            #[cfg(feature = "sse")]
+            encryption: encryption.clone(),
+        },
+    )
+    .await?
+    {
+        Some(stat) => stat,
+        None => return Ok(None),
+    };
+
+    let metadata = stat.metadata.unwrap_or_default();
+
+    if metadata_value(&metadata, METADATA_ALGORITHM)? != ALGORITHM {
+        return Err(Error::internal(
+            "Object is encrypted with an unsupported algorithm",
+        ));
+    }
+
+    let chunk_size = metadata_value(&metadata, METADATA_CHUNK_SIZE)?
+        .parse::<usize>()
+        .ok()
+        .filter(|chunk_size| *chunk_size > 0)
+        .ok_or(Error::internal("Object has an invalid chunk size"))?;
+
+    let nonce_prefix: [u8; NONCE_PREFIX_SIZE] =
+        base64::decode(metadata_value(&metadata, METADATA_NONCE_PREFIX)?)
+            .map_err(Error::sdk)?
+            .try_into()
+            .map_err(|_| Error::internal("Object has an invalid nonce prefix"))?;
+
+    let data_key: [u8; 32] = key_provider
+        .unwrap_key(
+            metadata_value(&metadata, METADATA_KEY_ID)?,
+            &base64::decode(metadata_value(&metadata, METADATA_WRAPPED_KEY)?)
+                .map_err(Error::sdk)?,
+        )
+        .await?
+        .try_into()
+        .map_err(|_| Error::internal("Unwrapped data key is not 256 bits"))?;
+
+    let encrypted_size = stat.content_length.unwrap_or_default().max(0) as usize;
+    let size = plaintext_size(encrypted_size, chunk_size)?;
+    let encrypted_chunk_size = chunk_size + TAG_SIZE;
+    let last_chunk_index = encrypted_size.div_ceil(encrypted_chunk_size) - 1;
+
+    let (start, end) = match range {
+        Some((start, end)) => {
+            if start > end || start >= size {
+                return Err(Error::Internal(format!(
+                    "Invalid range {}-{} for an object of {} bytes",
+                    start, end, size
+                )));
+            }
+
+            (start, end.min(size - 1))
+        }
+        None => (0, size.saturating_sub(1)),
+    };
+
+    let first_chunk_index = start / chunk_size;
+    let end_chunk_index = end / chunk_size;
+
+    let chunk_index = |index: usize| {
+        u32::try_from(index).map_err(|_| Error::internal("Object has too many chunks"))
+    };
+
+    let stream = get_object(
+        client,
+        bucket_name,
+        object_name,
+        GetObjectAdditionalOptions {
+            range: Some((
+                first_chunk_index * encrypted_chunk_size,
+                ((end_chunk_index + 1) * encrypted_chunk_size).min(encrypted_size) - 1,
+            )),
+            version_id,
+            #[cfg(feature = "sse")]
+            encryption,
+            #[cfg(feature = "checksum")]
+            checksum: None,
+            #[cfg(feature = "compression")]
+            raw: true,
+        },
+    )
+    .await?;
+
+    Ok(match stream {
+        Some(stream) => Some(BufReader::new(DecryptingReader::new(
+            stream,
+            &data_key,
+            nonce_prefix,
+            chunk_size,
+            DecryptingRange {
+                chunk_index: chunk_index(first_chunk_index)?,
+                last_chunk_index: chunk_index(last_chunk_index)?,
+                skip: start - first_chunk_index * chunk_size,
+                remaining: if size == 0 { 0 } else { end - start + 1 },
+            },
+        ))),
+        None => None,
+    })
+}
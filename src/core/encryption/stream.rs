@@ -0,0 +1,290 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Key, Nonce,
+};
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// Size of the random per-object nonce prefix
+pub(crate) const NONCE_PREFIX_SIZE: usize = 7;
+
+/// Size of the authentication tag appended to every chunk
+pub(crate) const TAG_SIZE: usize = 16;
+
+/// Size of the buffer reads from the inner stream are made with
+const READ_BUFFER_SIZE: usize = 8_192;
+
+/// Returns the nonce of the chunk by `chunk_index`, I.E. the STREAM
+/// construction of `nonce_prefix`, the big-endian `chunk_index` and
+/// a flag marking the `last` chunk, so chunks can not be reordered
+/// or the object truncated
+fn chunk_nonce(nonce_prefix: &[u8; NONCE_PREFIX_SIZE], chunk_index: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0; 12];
+    nonce[..NONCE_PREFIX_SIZE].copy_from_slice(nonce_prefix);
+    nonce[NONCE_PREFIX_SIZE..11].copy_from_slice(&chunk_index.to_be_bytes());
+    nonce[11] = u8::from(last);
+
+    nonce
+}
+
+/// Copies as much of `output` from `output_index` into `buf` as fits,
+/// returning the new `output_index`
+fn copy_output(output: &[u8], output_index: usize, buf: &mut ReadBuf<'_>) -> usize {
+    let len = (output.len() - output_index).min(buf.remaining());
+    buf.put_slice(&output[output_index..output_index + len]);
+
+    output_index + len
+}
+
+/// Reads up to `limit` bytes from `inner` into `buffer`,
+/// returning `true` if `inner` reached EOF
+fn poll_fill<R>(
+    inner: Pin<&mut R>,
+    cx: &mut Context<'_>,
+    buffer: &mut Vec<u8>,
+    limit: usize,
+) -> Poll<io::Result<bool>>
+where
+    R: AsyncRead + ?Sized,
+{
+    let mut read_buffer = [0; READ_BUFFER_SIZE];
+    let mut read_buf = ReadBuf::new(&mut read_buffer[..limit.min(READ_BUFFER_SIZE)]);
+
+    ready!(inner.poll_read(cx, &mut read_buf))?;
+
+    buffer.extend_from_slice(read_buf.filled());
+
+    Poll::Ready(Ok(read_buf.filled().is_empty()))
+}
+
+/// Encrypts a plaintext stream `R` into AES-256-GCM chunks of
+/// `chunk_size` plaintext bytes, each followed by its tag
+pub(crate) struct EncryptingReader<R> {
+    inner: R,
+    cipher: Aes256Gcm,
+    nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+    chunk_size: usize,
+    chunk_index: u32,
+    plaintext: Vec<u8>,
+    output: Vec<u8>,
+    output_index: usize,
+    eof: bool,
+    finished: bool,
+    pub(crate) plaintext_bytes: usize,
+}
+
+impl<R> EncryptingReader<R> {
+    pub(crate) fn new(
+        inner: R,
+        data_key: &[u8; 32],
+        nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+        chunk_size: usize,
+    ) -> Self {
+        Self {
+            inner,
+            cipher: Aes256Gcm::new(&Key::<Aes256Gcm>::from(*data_key)),
+            nonce_prefix,
+            chunk_size,
+            chunk_index: 0,
+            plaintext: vec![],
+            output: vec![],
+            output_index: 0,
+            eof: false,
+            finished: false,
+            plaintext_bytes: 0,
+        }
+    }
+}
+
+impl<R> AsyncRead for EncryptingReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+
+        loop {
+            if this.output_index < this.output.len() {
+                this.output_index = copy_output(&this.output, this.output_index, buf);
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.finished {
+                return Poll::Ready(Ok(()));
+            }
+
+            // A chunk is only known not to be the last once a byte past it was read
+            if !this.eof && this.plaintext.len() <= this.chunk_size {
+                let before = this.plaintext.len();
+                let limit = this.chunk_size + 1 - before;
+
+                this.eof = ready!(poll_fill(
+                    Pin::new(&mut this.inner),
+                    cx,
+                    &mut this.plaintext,
+                    limit
+                ))?;
+                this.plaintext_bytes += this.plaintext.len() - before;
+
+                continue;
+            }
+
+            let last = this.plaintext.len() <= this.chunk_size;
+            let chunk_len = this.plaintext.len().min(this.chunk_size);
+            let chunk = this.plaintext.drain(..chunk_len).collect::<Vec<u8>>();
+
+            this.output = this
+                .cipher
+                .encrypt(
+                    &Nonce::from(chunk_nonce(&this.nonce_prefix, this.chunk_index, last)),
+                    chunk.as_slice(),
+                )
+                .map_err(|_| io::Error::other("Failed to encrypt chunk"))?;
+            this.output_index = 0;
+
+            this.chunk_index = this
+                .chunk_index
+                .checked_add(1)
+                .ok_or(io::Error::other("Exceeded the maximum amount of chunks"))?;
+            this.finished = last;
+        }
+    }
+}
+
+/// Decrypts a stream `R` of AES-256-GCM chunks starting at the chunk by
+/// `chunk_index`, skipping `skip` plaintext bytes and yielding at most
+/// `remaining` plaintext bytes
+pub(crate) struct DecryptingReader<R> {
+    inner: Pin<Box<R>>,
+    cipher: Aes256Gcm,
+    nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+    chunk_size: usize,
+    chunk_index: u32,
+    last_chunk_index: u32,
+    skip: usize,
+    remaining: usize,
+    ciphertext: Vec<u8>,
+    output: Vec<u8>,
+    output_index: usize,
+    eof: bool,
+}
+
+/// Where a `DecryptingReader` starts and stops within the object
+pub(crate) struct DecryptingRange {
+    pub(crate) chunk_index: u32,
+    pub(crate) last_chunk_index: u32,
+    pub(crate) skip: usize,
+    pub(crate) remaining: usize,
+}
+
+impl<R> DecryptingReader<R> {
+    pub(crate) fn new(
+        inner: R,
+        data_key: &[u8; 32],
+        nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+        chunk_size: usize,
+        DecryptingRange {
+            chunk_index,
+            last_chunk_index,
+            skip,
+            remaining,
+        }: DecryptingRange,
+    ) -> Self {
+        Self {
+            inner: Box::pin(inner),
+            cipher: Aes256Gcm::new(&Key::<Aes256Gcm>::from(*data_key)),
+            nonce_prefix,
+            chunk_size,
+            chunk_index,
+            last_chunk_index,
+            skip,
+            remaining,
+            ciphertext: vec![],
+            output: vec![],
+            output_index: 0,
+            eof: false,
+        }
+    }
+}
+
+impl<R> AsyncRead for DecryptingReader<R>
+where
+    R: AsyncRead,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+
+        loop {
+            if this.output_index < this.output.len() {
+                this.output_index = copy_output(&this.output, this.output_index, buf);
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.remaining == 0 {
+                return Poll::Ready(Ok(()));
+            }
+
+            let encrypted_chunk_size = this.chunk_size + TAG_SIZE;
+
+            if !this.eof && this.ciphertext.len() < encrypted_chunk_size {
+                let limit = encrypted_chunk_size - this.ciphertext.len();
+
+                this.eof = ready!(poll_fill(
+                    this.inner.as_mut(),
+                    cx,
+                    &mut this.ciphertext,
+                    limit
+                ))?;
+
+                continue;
+            }
+
+            if this.ciphertext.is_empty() || this.chunk_index > this.last_chunk_index {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Encrypted object is truncated",
+                )));
+            }
+
+            let last = this.chunk_index == this.last_chunk_index;
+
+            let mut plaintext = this
+                .cipher
+                .decrypt(
+                    &Nonce::from(chunk_nonce(&this.nonce_prefix, this.chunk_index, last)),
+                    this.ciphertext.as_slice(),
+                )
+                .map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "Failed to decrypt chunk")
+                })?;
+
+            this.ciphertext.clear();
+            this.chunk_index += 1;
+
+            let skip = this.skip.min(plaintext.len());
+            plaintext.drain(..skip);
+            plaintext.truncate(this.remaining);
+
+            this.skip -= skip;
+            this.remaining -= plaintext.len();
+
+            this.output = plaintext;
+            this.output_index = 0;
+        }
+    }
+}
@@ -1,15 +1,169 @@
 // Authors: Robert Lopez
 // License: MIT (See `LICENSE.md`)
+use super::{
+    presign::{presigning_client, PresignedUrl},
+    sse::SseHeaders,
+};
 use crate::error::Error;
 use aws_sdk_s3::{
     error::SdkError,
-    operation::get_object::GetObjectError,
-    presigning::{PresignedRequest, PresigningConfig},
+    operation::{
+        get_object::GetObjectError,
+        head_object::{HeadObjectError, HeadObjectOutput},
+    },
+    presigning::PresigningConfig,
     Client,
 };
 use std::time::Duration;
 use tokio::io::AsyncBufRead;
 
+#[cfg(feature = "checksum")]
+use super::checksum::{Checksum, ChecksumReader, ChecksumVerifier};
+#[cfg(feature = "compression")]
+use super::compression::{Compression, DecompressingReader};
+#[cfg(feature = "sse")]
+use super::sse::Encryption;
+#[cfg(feature = "checksum")]
+use aws_sdk_s3::{
+    operation::get_object::GetObjectOutput,
+    types::{ChecksumMode, ObjectAttributes},
+};
+#[cfg(any(feature = "checksum", feature = "compression"))]
+use tokio::io::BufReader;
+
+/// Additional options for `get_object` to get an inclusive byte
+/// `range` `(start, end)` or a specific `version_id` of the object
+///
+/// With the `sse` feature, `encryption` must be the `Encryption::SseC`
+/// key the object was encrypted with, if any.
+///
+/// With the `checksum` feature, `checksum` verifies the stream against
+/// the `Checksum` the server stored for the object, erroring at EOF if
+/// they do not match. It can not be combined with a `range`.
+///
+/// With the `compression` feature, objects marked with a `Compression`
+/// are decompressed, unless `raw` is set to get the stored bytes as-is.
+/// Compressed objects can only be got with a `range` if `raw` is set.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let additional_options = GetObjectAdditionalOptions {
+///     range: Some((0, 1_023)),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GetObjectAdditionalOptions {
+    pub range: Option<(usize, usize)>,
+    pub version_id: Option<String>,
+    #[cfg(feature = "sse")]
+    pub encryption: Option<Encryption>,
+    #[cfg(feature = "checksum")]
+    pub checksum: Option<Checksum>,
+    #[cfg(feature = "compression")]
+    pub raw: bool,
+}
+
+/// Returns the sizes of the parts of an object uploaded via multipart
+#[cfg(feature = "checksum")]
+async fn object_part_sizes(
+    client: &Client,
+    bucket_name: &str,
+    object_name: &str,
+    version_id: Option<String>,
+    sse: &SseHeaders,
+) -> Result<Vec<usize>, Error> {
+    let mut part_sizes = vec![];
+    let mut part_number_marker = None;
+
+    loop {
+        let response = client
+            .get_object_attributes()
+            .bucket(bucket_name)
+            .key(object_name)
+            .set_version_id(version_id.clone())
+            .object_attributes(ObjectAttributes::ObjectParts)
+            .set_part_number_marker(part_number_marker)
+            .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+            .set_sse_customer_key(sse.customer_key.clone())
+            .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+            .send()
+            .await
+            .map_err(Error::sdk)?;
+
+        let object_parts = response.object_parts().ok_or(Error::internal(
+            "object_parts was None on get_object_attributes",
+        ))?;
+
+        for part in object_parts.parts() {
+            part_sizes.push(
+                part.size()
+                    .ok_or(Error::internal("size was None on get_object_attributes"))?
+                    .max(0) as usize,
+            );
+        }
+
+        if !object_parts.is_truncated().unwrap_or_default() {
+            break;
+        }
+
+        part_number_marker = object_parts.next_part_number_marker().map(str::to_string);
+
+        if part_number_marker.is_none() {
+            break;
+        }
+    }
+
+    Ok(part_sizes)
+}
+
+/// Returns a `ChecksumVerifier` for the `checksum` the server stored
+/// for the object of `response`
+#[cfg(feature = "checksum")]
+async fn checksum_verifier(
+    client: &Client,
+    bucket_name: &str,
+    object_name: &str,
+    version_id: Option<String>,
+    sse: &SseHeaders,
+    checksum: Checksum,
+    response: &GetObjectOutput,
+) -> Result<ChecksumVerifier, Error> {
+    let stored = match checksum {
+        Checksum::Crc32 => response.checksum_crc32(),
+        Checksum::Crc32C => response.checksum_crc32_c(),
+        Checksum::Sha256 => response.checksum_sha256(),
+        Checksum::Md5 => response.e_tag().map(|e_tag| e_tag.trim_matches('"')),
+    }
+    .ok_or(Error::Internal(format!(
+        "Object has no stored {:?} checksum",
+        checksum
+    )))?
+    .to_string();
+
+    let part_count = match stored.rsplit_once('-') {
+        Some((_, part_count)) => part_count
+            .parse::<usize>()
+            .map_err(|_| Error::Internal(format!("Invalid stored checksum {}", stored)))?,
+        None => return Ok(ChecksumVerifier::new(checksum, stored, vec![])),
+    };
+
+    let part_sizes = object_part_sizes(client, bucket_name, object_name, version_id, sse).await?;
+
+    if part_sizes.len() != part_count {
+        return Err(Error::Internal(format!(
+            "Object has {} parts, but its checksum {} is of {}",
+            part_sizes.len(),
+            stored,
+            part_count
+        )));
+    }
+
+    Ok(ChecksumVerifier::new(checksum, stored, part_sizes))
+}
+
 /// Returns a stream for an object by `bucket_name` and `object_name`
 ///
 /// Returns `Ok(None)` if the object does not exist.
@@ -24,32 +178,223 @@ use tokio::io::AsyncBufRead;
 ///     &client,
 ///     "sharks",
 ///     "shark.jpg",
+///     GetObjectAdditionalOptions::default(),
 /// ).await?;
 /// ```
 pub async fn get_object(
     client: &Client,
     bucket_name: &str,
     object_name: &str,
+    GetObjectAdditionalOptions {
+        range,
+        version_id,
+        #[cfg(feature = "sse")]
+        encryption,
+        #[cfg(feature = "checksum")]
+        checksum,
+        #[cfg(feature = "compression")]
+        raw,
+    }: GetObjectAdditionalOptions,
 ) -> Result<Option<impl AsyncBufRead>, Error> {
-    match client
+    #[cfg(feature = "sse")]
+    let sse = SseHeaders::from(encryption.as_ref());
+    #[cfg(not(feature = "sse"))]
+    let sse = SseHeaders::default();
+
+    #[cfg(feature = "checksum")]
+    if checksum.is_some() && range.is_some() {
+        return Err(Error::internal(
+            "A checksum can not be verified for a ranged get",
+        ));
+    }
+
+    #[cfg(feature = "checksum")]
+    let checksum_mode = checksum.map(|_| ChecksumMode::Enabled);
+    #[cfg(not(feature = "checksum"))]
+    let checksum_mode = None;
+
+    let response = match client
         .get_object()
         .bucket(bucket_name)
         .key(object_name)
+        .set_range(range.map(|(start, end)| format!("bytes={}-{}", start, end)))
+        .set_version_id(version_id.clone())
+        .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+        .set_sse_customer_key(sse.customer_key.clone())
+        .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+        .set_checksum_mode(checksum_mode)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(sdk_err) => {
+            return match sdk_err {
+                SdkError::ServiceError(ref err, ..) => match err.err() {
+                    GetObjectError::NoSuchKey(_) => Ok(None),
+                    _ => Err(Error::sdk(sdk_err)),
+                },
+                _ => Err(Error::sdk(sdk_err)),
+            }
+        }
+    };
+
+    #[cfg(feature = "compression")]
+    let compression = if raw {
+        None
+    } else {
+        let compression = Compression::from_metadata(response.metadata())?;
+
+        if compression.is_some() && range.is_some() {
+            return Err(Error::internal(
+                "A range of a compressed object can only be got raw",
+            ));
+        }
+
+        compression
+    };
+
+    #[cfg(feature = "checksum")]
+    let stream = {
+        let verifier = match checksum {
+            Some(checksum) => Some(
+                checksum_verifier(
+                    client,
+                    bucket_name,
+                    object_name,
+                    version_id,
+                    &sse,
+                    checksum,
+                    &response,
+                )
+                .await?,
+            ),
+            None => None,
+        };
+
+        BufReader::new(ChecksumReader::new(
+            response.body.into_async_read(),
+            verifier,
+        ))
+    };
+    #[cfg(not(feature = "checksum"))]
+    let stream = response.body.into_async_read();
+
+    #[cfg(feature = "compression")]
+    let stream = BufReader::new(DecompressingReader::new(stream, compression));
+
+    Ok(Some(stream))
+}
+
+/// Additional options for `stat_object` to stat a specific `version_id`
+/// of the object
+///
+/// With the `sse` feature, `encryption` must be the `Encryption::SseC`
+/// key the object was encrypted with, if any.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let additional_options = StatObjectAdditionalOptions {
+///     version_id: Some("3HL4kqtJlcpXroDTDmJ+rmSpXd3dIbrHY".to_string()),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StatObjectAdditionalOptions {
+    pub version_id: Option<String>,
+    #[cfg(feature = "sse")]
+    pub encryption: Option<Encryption>,
+}
+
+/// Returns the metadata of an object by `bucket_name` and `object_name`,
+/// I.E. its size, `ETag`, `Content-Type` and server-side encryption
+///
+/// Returns `Ok(None)` if the object does not exist.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// let stat: Option<HeadObjectOutput> = stat_object(
+///     &client,
+///     "sharks",
+///     "shark.jpg",
+///     StatObjectAdditionalOptions::default(),
+/// ).await?;
+/// ```
+pub async fn stat_object(
+    client: &Client,
+    bucket_name: &str,
+    object_name: &str,
+    StatObjectAdditionalOptions {
+        version_id,
+        #[cfg(feature = "sse")]
+        encryption,
+    }: StatObjectAdditionalOptions,
+) -> Result<Option<HeadObjectOutput>, Error> {
+    #[cfg(feature = "sse")]
+    let sse = SseHeaders::from(encryption.as_ref());
+    #[cfg(not(feature = "sse"))]
+    let sse = SseHeaders::default();
+
+    match client
+        .head_object()
+        .bucket(bucket_name)
+        .key(object_name)
+        .set_version_id(version_id)
+        .set_sse_customer_algorithm(sse.customer_algorithm)
+        .set_sse_customer_key(sse.customer_key)
+        .set_sse_customer_key_md5(sse.customer_key_md5)
         .send()
         .await
     {
-        Ok(response) => Ok(Some(response.body.into_async_read())),
+        Ok(response) => Ok(Some(response)),
         Err(sdk_err) => match sdk_err {
             SdkError::ServiceError(ref err, ..) => match err.err() {
-                GetObjectError::NoSuchKey(_) => Ok(None),
+                HeadObjectError::NotFound(_) => Ok(None),
                 _ => Err(Error::sdk(sdk_err)),
             },
+
             _ => Err(Error::sdk(sdk_err)),
         },
     }
 }
 
-/// Generates a `PresignedRequest` from a bucket by `bucket_name` and `object_name`
+/// Additional options for `get_object_presigned` to override
+/// the response headers of the presigned get, and to sign for
+/// a different `endpoint_url` than the client was built with
+///
+/// With the `sse` feature, `encryption` signs the `Encryption::SseC`
+/// key the object was encrypted with, if any, into the `headers`
+/// of the `PresignedUrl`.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let additional_options = GetObjectPresignedAdditionalOptions {
+///     response_content_disposition: Some("attachment; filename=\"shark.jpg\"".to_string()),
+///     response_content_type: Some("image/jpeg".to_string()),
+///     endpoint_url: Some("https://cdn.example.com".to_string()),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GetObjectPresignedAdditionalOptions {
+    pub response_cache_control: Option<String>,
+    pub response_content_disposition: Option<String>,
+    pub response_content_encoding: Option<String>,
+    pub response_content_language: Option<String>,
+    pub response_content_type: Option<String>,
+    pub endpoint_url: Option<String>,
+    #[cfg(feature = "sse")]
+    pub encryption: Option<Encryption>,
+}
+
+/// Generates a `PresignedUrl` from a bucket by `bucket_name` and `object_name`
 /// to get the object.
 ///
 /// Returns `Ok(None)` if the object does not exist.
@@ -60,11 +405,12 @@ pub async fn get_object(
 ///
 /// let client: Client = ...;
 ///
-/// let request: Option<PresignedRequest> = get_object_presigned(
+/// let request: Option<PresignedUrl> = get_object_presigned(
 ///     &client,
 ///     "sharks",
 ///     "shark.jpg",
 ///     3_600,
+///     GetObjectPresignedAdditionalOptions::default(),
 /// ).await?;
 /// ```
 pub async fn get_object_presigned(
@@ -72,20 +418,43 @@ pub async fn get_object_presigned(
     bucket_name: &str,
     object_name: &str,
     presigned_expiry_secs: u64,
-) -> Result<Option<PresignedRequest>, Error> {
+    GetObjectPresignedAdditionalOptions {
+        response_cache_control,
+        response_content_disposition,
+        response_content_encoding,
+        response_content_language,
+        response_content_type,
+        endpoint_url,
+        #[cfg(feature = "sse")]
+        encryption,
+    }: GetObjectPresignedAdditionalOptions,
+) -> Result<Option<PresignedUrl>, Error> {
+    #[cfg(feature = "sse")]
+    let sse = SseHeaders::from(encryption.as_ref());
+    #[cfg(not(feature = "sse"))]
+    let sse = SseHeaders::default();
+
     let presigning_config = PresigningConfig::builder()
         .expires_in(Duration::from_secs(presigned_expiry_secs))
         .build()
         .map_err(Error::sdk)?;
 
-    match client
+    match presigning_client(client, endpoint_url.as_deref())
         .get_object()
         .bucket(bucket_name)
         .key(object_name)
+        .set_response_cache_control(response_cache_control)
+        .set_response_content_disposition(response_content_disposition)
+        .set_response_content_encoding(response_content_encoding)
+        .set_response_content_language(response_content_language)
+        .set_response_content_type(response_content_type)
+        .set_sse_customer_algorithm(sse.customer_algorithm)
+        .set_sse_customer_key(sse.customer_key)
+        .set_sse_customer_key_md5(sse.customer_key_md5)
         .presigned(presigning_config)
         .await
     {
-        Ok(request) => Ok(Some(request)),
+        Ok(request) => Ok(Some(request.into())),
         Err(sdk_err) => match sdk_err {
             SdkError::ServiceError(ref err, ..) => match err.err() {
                 GetObjectError::NoSuchKey(_) => Ok(None),
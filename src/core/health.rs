@@ -0,0 +1,132 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::http::{send, Endpoint};
+use crate::error::Error;
+use aws_sdk_s3::Client;
+use hyper::{HeaderMap, Method, StatusCode};
+use std::time::{Duration, Instant};
+
+/// A health check of the unauthenticated MinIO health API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthCheck {
+    /// If the server is running, at `/minio/health/live`
+    Live,
+    /// If the server is ready to serve requests, at `/minio/health/ready`
+    Ready,
+    /// If the cluster has write quorum, at `/minio/health/cluster`
+    Cluster,
+    /// If the cluster has read quorum, at `/minio/health/cluster/read`
+    ClusterRead,
+    /// If the cluster keeps write quorum when the server is taken down
+    /// for maintenance, at `/minio/health/cluster?maintenance=true`
+    Maintenance,
+}
+
+impl HealthCheck {
+    fn path_and_query(&self) -> &'static str {
+        match self {
+            HealthCheck::Live => "/minio/health/live",
+            HealthCheck::Ready => "/minio/health/ready",
+            HealthCheck::Cluster => "/minio/health/cluster",
+            HealthCheck::ClusterRead => "/minio/health/cluster/read",
+            HealthCheck::Maintenance => "/minio/health/cluster?maintenance=true",
+        }
+    }
+}
+
+/// The status responded to a `HealthCheck`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthStatus {
+    /// If the check passed, I.E. responded `200 OK`
+    pub healthy: bool,
+    /// The http status responded
+    pub status_code: u16,
+    /// The `x-minio-server-status` responded, I.E. `offline`
+    /// while the server is starting
+    pub server_status: Option<String>,
+    /// The drives needed for write quorum, responded by
+    /// `HealthCheck::Cluster` and `HealthCheck::Maintenance`
+    pub write_quorum: Option<u32>,
+    /// The drives being healed, if any
+    pub healing_drives: Option<u32>,
+    /// If the cluster uses the default storage class parity
+    pub storage_class_defaults: Option<bool>,
+    /// If the server can be taken down for maintenance without losing
+    /// write quorum, only set by `HealthCheck::Maintenance`
+    pub safe_for_maintenance: Option<bool>,
+}
+
+fn header<T: std::str::FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    headers.get(name)?.to_str().ok()?.parse().ok()
+}
+
+/// Runs `check` against the MinIO server at the S3 API Url `url`
+///
+/// Returns an `Err` only if the server could not be reached,
+/// an unhealthy server is an `Ok` whose `healthy` is false.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let status: HealthStatus = health("http://127.0.0.1:9000", HealthCheck::Cluster).await?;
+///
+/// if !status.healthy {
+///     ...
+/// }
+/// ```
+pub async fn health(url: &str, check: HealthCheck) -> Result<HealthStatus, Error> {
+    endpoint_health(&Endpoint::parse(url)?, check).await
+}
+
+/// Runs `check` against the MinIO server at `endpoint`
+pub(crate) async fn endpoint_health(
+    endpoint: &Endpoint,
+    check: HealthCheck,
+) -> Result<HealthStatus, Error> {
+    let (status, headers, _) = send(
+        endpoint,
+        None,
+        Method::GET,
+        check.path_and_query(),
+        vec![],
+        vec![],
+    )
+    .await?;
+
+    Ok(HealthStatus {
+        healthy: status == StatusCode::OK,
+        status_code: status.as_u16(),
+        server_status: header(&headers, "x-minio-server-status"),
+        write_quorum: header(&headers, "x-minio-write-quorum"),
+        healing_drives: header(&headers, "x-minio-healing-drives"),
+        storage_class_defaults: header(&headers, "x-minio-storage-class-defaults"),
+        safe_for_maintenance: (check == HealthCheck::Maintenance)
+            .then_some(status == StatusCode::OK),
+    })
+}
+
+/// Verifies the client reaches the server and its credentials are accepted
+/// by listing the buckets, failing with `ErrorKind::TimedOut` past `timeout`
+///
+/// Returns the round trip time
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// let round_trip: Duration = ping(&client, Duration::from_secs(2)).await?;
+/// ```
+pub async fn ping(client: &Client, timeout: Duration) -> Result<Duration, Error> {
+    let start = Instant::now();
+
+    tokio::time::timeout(timeout, client.list_buckets().send())
+        .await
+        .map_err(|_| Error::StdIo(std::io::ErrorKind::TimedOut))?
+        .map_err(Error::sdk)?;
+
+    Ok(start.elapsed())
+}
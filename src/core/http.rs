@@ -0,0 +1,357 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::error::Error;
+use aws_sdk_s3::config::Credentials;
+use aws_sigv4::{
+    http_request::{sign, PayloadChecksumKind, SignableBody, SignableRequest, SigningSettings},
+    sign::v4,
+};
+use http_body_util::Full;
+use hyper::{
+    body::{Bytes, Incoming},
+    Method, Request, Response,
+};
+use hyper_util::rt::TokioIo;
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    rustls::{
+        crypto::aws_lc_rs,
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName},
+        ClientConfig, RootCertStore,
+    },
+    TlsConnector,
+};
+
+/// How long a request to the MinIO STS, admin or health API may take,
+/// or a notification stream may take to respond its headers
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The scheme, host and port of a S3 API Url, and the `TlsOptions`
+/// of its https connections
+///
+/// The TLS configuration is built on the first https request, and is
+/// shared by the clones of the endpoint.
+///
+/// Requests to the MinIO STS, admin, health and notification APIs are
+/// sent over this endpoint rather than the `HttpClient` of the SDK
+/// client, as that client cannot serve them:
+///
+/// - `AssumeRoleWithCertificate` authenticates with a client certificate,
+///   which the TLS context of the SDK `HttpClient` cannot present.
+/// - An `HttpClient` only hands out its connector for the
+///   `RuntimeComponents` of an operation, which the SDK orchestrator
+///   builds for S3 operations alone.
+#[derive(Debug, Clone)]
+pub(crate) struct Endpoint {
+    pub(crate) https: bool,
+    host: String,
+    port: u16,
+    authority: String,
+    tls: TlsOptions,
+    tls_config: Arc<OnceLock<Arc<ClientConfig>>>,
+}
+
+impl Endpoint {
+    pub(crate) fn parse(url: &str) -> Result<Self, Error> {
+        let invalid = || Error::Internal(format!("Invalid url {}", url));
+
+        let (scheme, rest) = url.split_once("://").ok_or_else(invalid)?;
+        let https = match scheme {
+            "https" => true,
+            "http" => false,
+            _ => return Err(invalid()),
+        };
+
+        let authority = rest.split('/').next().unwrap_or(rest);
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => {
+                (host, port.parse().map_err(|_| invalid())?)
+            }
+            _ => (authority, if https { 443 } else { 80 }),
+        };
+
+        if host.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            https,
+            host: host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+            port,
+            authority: authority.to_string(),
+            tls: TlsOptions::default(),
+            tls_config: Arc::default(),
+        })
+    }
+
+    /// Connects with `tls` instead of the native roots alone
+    pub(crate) fn with_tls(mut self, tls: TlsOptions) -> Self {
+        self.tls = tls;
+        self.tls_config = Arc::default();
+        self
+    }
+
+    /// The TLS configuration of the endpoint, built once from its `TlsOptions`
+    pub(crate) fn tls_config(&self) -> Result<Arc<ClientConfig>, Error> {
+        if let Some(tls_config) = self.tls_config.get() {
+            return Ok(tls_config.clone());
+        }
+
+        let tls_config = Arc::new(tls_config(&self.tls)?);
+
+        Ok(self.tls_config.get_or_init(|| tls_config).clone())
+    }
+
+    /// The url of `path_and_query` on the endpoint
+    pub(crate) fn url(&self, path_and_query: &str) -> String {
+        format!(
+            "{}://{}{}",
+            if self.https { "https" } else { "http" },
+            self.authority,
+            path_and_query
+        )
+    }
+}
+
+/// The certificates of a https connection
+#[derive(Clone, Default)]
+pub(crate) struct TlsOptions {
+    /// PEM encoded CA certificates trusted along with the native roots
+    pub(crate) ca_certificates_pem: Option<Vec<u8>>,
+    /// The PEM encoded client certificate and its private key
+    pub(crate) client_certificate: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl fmt::Debug for TlsOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsOptions")
+            .field("ca_certificates_pem", &self.ca_certificates_pem.is_some())
+            .field("client_certificate", &self.client_certificate.is_some())
+            .finish()
+    }
+}
+
+#[cfg(any(feature = "sts", feature = "admin", feature = "health"))]
+/// Sends a request of `method` to `path_and_query` on the endpoint, signed via
+/// SigV4 for `service` if `signer` is `Some`, returning its status, headers and body
+pub(crate) async fn send(
+    endpoint: &Endpoint,
+    signer: Option<(&Credentials, &str, &str)>,
+    method: Method,
+    path_and_query: &str,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+) -> Result<(hyper::StatusCode, hyper::HeaderMap, Bytes), Error> {
+    use http_body_util::BodyExt;
+
+    let request = build_request(endpoint, signer, method, path_and_query, headers, body)?;
+
+    tokio::time::timeout(REQUEST_TIMEOUT, async {
+        let (parts, body) = connect_and_send(endpoint, request).await?.into_parts();
+        let body = body.collect().await.map_err(Error::sdk)?.to_bytes();
+
+        Ok((parts.status, parts.headers, body))
+    })
+    .await
+    .map_err(|_| Error::StdIo(std::io::ErrorKind::TimedOut))?
+}
+
+#[cfg(feature = "notifications")]
+/// Like `send`, but returns once the headers are received with the body
+/// still streaming, which may be read for as long as the server writes it
+pub(crate) async fn open(
+    endpoint: &Endpoint,
+    signer: Option<(&Credentials, &str, &str)>,
+    method: Method,
+    path_and_query: &str,
+    headers: Vec<(String, String)>,
+) -> Result<Response<Incoming>, Error> {
+    let request = build_request(endpoint, signer, method, path_and_query, headers, vec![])?;
+
+    tokio::time::timeout(REQUEST_TIMEOUT, connect_and_send(endpoint, request))
+        .await
+        .map_err(|_| Error::StdIo(std::io::ErrorKind::TimedOut))?
+}
+
+fn build_request(
+    endpoint: &Endpoint,
+    signer: Option<(&Credentials, &str, &str)>,
+    method: Method,
+    path_and_query: &str,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+) -> Result<Request<Full<Bytes>>, Error> {
+    let mut headers = [
+        vec![("host".to_string(), endpoint.authority.clone())],
+        headers,
+    ]
+    .concat();
+
+    if let Some((credentials, region, service)) = signer {
+        headers = sign_request(
+            credentials,
+            region,
+            service,
+            method.as_str(),
+            &endpoint.url(path_and_query),
+            headers,
+            &body,
+        )?;
+    }
+
+    let mut request = Request::builder().method(method).uri(path_and_query);
+    for (name, value) in &headers {
+        request = request.header(name, value);
+    }
+
+    request
+        .body(Full::new(Bytes::from(body)))
+        .map_err(Error::sdk)
+}
+
+#[cfg(any(feature = "sts", feature = "notifications"))]
+/// Returns the unescaped text of the first element named `tag`
+pub(crate) fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", tag))?;
+
+    Some(
+        xml[start..end]
+            .trim()
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// Signs the request via SigV4, returning `headers` with the signature
+fn sign_request(
+    credentials: &Credentials,
+    region: &str,
+    service: &str,
+    method: &str,
+    url: &str,
+    mut headers: Vec<(String, String)>,
+    body: &[u8],
+) -> Result<Vec<(String, String)>, Error> {
+    let mut settings = SigningSettings::default();
+    settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+
+    let identity = credentials.clone().into();
+
+    let signing_params = v4::SigningParams::builder()
+        .identity(&identity)
+        .region(region)
+        .name(service)
+        .time(SystemTime::now())
+        .settings(settings)
+        .build()
+        .map_err(Error::sdk)?
+        .into();
+
+    let signable_request = SignableRequest::new(
+        method,
+        url,
+        headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str())),
+        SignableBody::Bytes(body),
+    )
+    .map_err(Error::sdk)?;
+
+    let (signing_instructions, _) = sign(signable_request, &signing_params)
+        .map_err(Error::sdk)?
+        .into_parts();
+
+    headers.extend(
+        signing_instructions
+            .headers()
+            .map(|(name, value)| (name.to_string(), value.to_string())),
+    );
+
+    Ok(headers)
+}
+
+/// Sends `request` over TLS for https endpoints, on a connection of
+/// its own, as described on `Endpoint`
+async fn connect_and_send(
+    endpoint: &Endpoint,
+    request: Request<Full<Bytes>>,
+) -> Result<Response<Incoming>, Error> {
+    let stream = TcpStream::connect((endpoint.host.as_str(), endpoint.port))
+        .await
+        .map_err(|err| Error::StdIo(err.kind()))?;
+
+    if !endpoint.https {
+        return exchange(stream, request).await;
+    }
+
+    let server_name = ServerName::try_from(endpoint.host.clone()).map_err(Error::sdk)?;
+    let connector = TlsConnector::from(endpoint.tls_config()?);
+    let stream = connector
+        .connect(server_name, stream)
+        .await
+        .map_err(|err| Error::StdIo(err.kind()))?;
+
+    exchange(stream, request).await
+}
+
+/// Sends `request` over `stream`, whose connection lasts until the
+/// response body is read or dropped
+async fn exchange<S>(stream: S, request: Request<Full<Bytes>>) -> Result<Response<Incoming>, Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(Error::sdk)?;
+    tokio::spawn(connection);
+
+    sender.send_request(request).await.map_err(Error::sdk)
+}
+
+fn tls_config(tls: &TlsOptions) -> Result<ClientConfig, Error> {
+    let mut roots = RootCertStore::empty();
+    roots.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+
+    if let Some(ca_certificates_pem) = &tls.ca_certificates_pem {
+        for certificate in CertificateDer::pem_slice_iter(ca_certificates_pem) {
+            roots
+                .add(certificate.map_err(Error::sdk)?)
+                .map_err(Error::sdk)?;
+        }
+    }
+
+    let builder = ClientConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(Error::sdk)?
+        .with_root_certificates(roots);
+
+    match &tls.client_certificate {
+        Some((certificate_pem, private_key_pem)) => {
+            let certificates = CertificateDer::pem_slice_iter(certificate_pem)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(Error::sdk)?;
+            let private_key = PrivateKeyDer::from_pem_slice(private_key_pem).map_err(Error::sdk)?;
+
+            builder
+                .with_client_auth_cert(certificates, private_key)
+                .map_err(Error::sdk)
+        }
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
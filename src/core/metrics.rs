@@ -0,0 +1,473 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::error::ErrorKind;
+use aws_sdk_s3::{
+    config::{
+        interceptors::{BeforeTransmitInterceptorContextRef, FinalizerInterceptorContextRef},
+        ConfigBag, Intercept, RuntimeComponents,
+    },
+    error::BoxError,
+};
+use aws_smithy_runtime_api::{
+    client::{orchestrator::Metadata, retries::RequestAttempts},
+    http::Headers,
+};
+use aws_smithy_types::config_bag::{Storable, StoreReplace};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Upper bounds in milliseconds of the buckets of a `LatencyHistogram`,
+/// latencies above the last bound are counted in an unbounded bucket
+pub const LATENCY_BOUNDS_MS: [u64; 12] = [
+    5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000,
+];
+
+/// Operations whose retried requests are counted as part retries
+const PART_OPERATIONS: [&str; 2] = ["UploadPart", "UploadPartCopy"];
+
+/// Operations whose request bodies are counted as bytes uploaded
+const UPLOAD_OPERATIONS: [&str; 2] = ["PutObject", "UploadPart"];
+
+/// Operations whose response bodies are counted as bytes downloaded
+const DOWNLOAD_OPERATIONS: [&str; 1] = ["GetObject"];
+
+/// A sink receiving the metrics of `Minio` operations and the S3 requests
+/// they send, I.E. to forward them to Prometheus or StatsD
+///
+/// Operations are named after the `core` function they call, such as
+/// `upload_object`, and requests after the S3 operation, such as `UploadPart`.
+///
+/// See `MetricsRegistry` for an in-process sink with a snapshot API.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// struct StatsdSink { ... }
+///
+/// impl MetricsSink for StatsdSink {
+///     fn record_operation(&self, operation: &str, latency: Duration, error: Option<ErrorKind>) {
+///         ...
+///     }
+///
+///     ...
+/// }
+///
+/// let minio = minio.with_metrics(Arc::new(StatsdSink { ... }));
+/// ```
+pub trait MetricsSink: Send + Sync {
+    /// Records a call of `operation` that took `latency`, with the
+    /// `ErrorKind` of its error if it failed
+    fn record_operation(&self, operation: &str, latency: Duration, error: Option<ErrorKind>);
+
+    /// Records an attempt of a S3 request of `operation` that took `latency`,
+    /// with the `status` of its response if it received one
+    fn record_request(&self, operation: &str, latency: Duration, status: Option<u16>);
+
+    /// Records `bytes` of object data sent in the body of a successful
+    /// `PutObject` or `UploadPart` request
+    fn record_bytes_uploaded(&self, operation: &str, bytes: u64);
+
+    /// Records `bytes` of object data received in the body of a successful
+    /// `GetObject` request
+    fn record_bytes_downloaded(&self, operation: &str, bytes: u64);
+
+    /// Records a retried attempt of a `UploadPart` or `UploadPartCopy` request
+    fn record_part_retry(&self, operation: &str);
+}
+
+/// A histogram of latencies, bucketed by `LATENCY_BOUNDS_MS`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LatencyHistogram {
+    /// Counts of latencies per bucket, the last being the unbounded bucket
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub sum: Duration,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; LATENCY_BOUNDS_MS.len() + 1],
+            count: 0,
+            sum: Duration::ZERO,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// Counts `latency` in its bucket
+    pub fn observe(&mut self, latency: Duration) {
+        let millis = latency.as_millis();
+        let bucket = LATENCY_BOUNDS_MS
+            .iter()
+            .position(|bound| millis <= *bound as u128)
+            .unwrap_or(LATENCY_BOUNDS_MS.len());
+
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum += latency;
+    }
+
+    /// Returns the mean latency, or `None` if no latency was observed
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0)
+            .then(|| Duration::from_nanos((self.sum.as_nanos() / self.count as u128) as u64))
+    }
+
+    /// Returns the upper bound of the bucket holding the `quantile`
+    /// (I.E. `0.99`) of latencies
+    ///
+    /// Returns `None` if no latency was observed, or if the quantile
+    /// falls in the unbounded bucket.
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let histogram: LatencyHistogram = ...;
+    ///
+    /// let p99: Option<Duration> = histogram.quantile(0.99);
+    /// ```
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((self.count as f64) * quantile.clamp(0.0, 1.0))
+            .ceil()
+            .max(1.0) as u64;
+        let mut seen = 0;
+
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+
+            if seen >= rank {
+                return LATENCY_BOUNDS_MS
+                    .get(bucket)
+                    .map(|bound| Duration::from_millis(*bound));
+            }
+        }
+
+        None
+    }
+}
+
+/// Metrics of the calls of a `Minio` operation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperationMetrics {
+    pub calls: u64,
+    pub errors: BTreeMap<ErrorKind, u64>,
+    pub latency: LatencyHistogram,
+}
+
+/// Metrics of the attempts of a S3 request, counting retries
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestMetrics {
+    pub attempts: u64,
+    /// Attempts without a response, or with a non-2xx response
+    pub failures: u64,
+    pub bytes_uploaded: u64,
+    pub bytes_downloaded: u64,
+    pub part_retries: u64,
+    pub latency: LatencyHistogram,
+}
+
+/// A point in time copy of the metrics of a `MetricsRegistry`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetricsSnapshot {
+    /// Metrics by operation, I.E. `upload_object`
+    pub operations: BTreeMap<String, OperationMetrics>,
+    /// Metrics by S3 request, I.E. `UploadPart`
+    pub requests: BTreeMap<String, RequestMetrics>,
+}
+
+impl MetricsSnapshot {
+    /// Returns the total bytes sent in the bodies of successful requests
+    pub fn bytes_uploaded(&self) -> u64 {
+        self.requests
+            .values()
+            .map(|request| request.bytes_uploaded)
+            .sum()
+    }
+
+    /// Returns the total bytes received in the bodies of successful requests
+    pub fn bytes_downloaded(&self) -> u64 {
+        self.requests
+            .values()
+            .map(|request| request.bytes_downloaded)
+            .sum()
+    }
+
+    /// Returns the total retried attempts of part uploads and copies
+    pub fn part_retries(&self) -> u64 {
+        self.requests
+            .values()
+            .map(|request| request.part_retries)
+            .sum()
+    }
+
+    /// Returns the total errors of operations of `kind`
+    pub fn errors(&self, kind: ErrorKind) -> u64 {
+        self.operations
+            .values()
+            .filter_map(|operation| operation.errors.get(&kind))
+            .sum()
+    }
+}
+
+/// An in-process `MetricsSink` aggregating metrics in memory, to be read
+/// via `snapshot`, I.E. from a health endpoint
+///
+/// Clones share the same metrics.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let metrics = MetricsRegistry::new();
+/// let minio = Minio::new(url).await.with_metrics(Arc::new(metrics.clone()));
+///
+/// minio.upload_object("sharks", "shark.jpg", shark_image, None).await?;
+///
+/// let snapshot: MetricsSnapshot = metrics.snapshot();
+/// let uploaded: u64 = snapshot.bytes_uploaded();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRegistry {
+    snapshot: Arc<Mutex<MetricsSnapshot>>,
+}
+
+impl MetricsRegistry {
+    /// Constructs an empty `MetricsRegistry`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of the metrics recorded so far
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.lock().clone()
+    }
+
+    /// Clears the metrics recorded so far
+    pub fn reset(&self) {
+        *self.lock() = MetricsSnapshot::default();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MetricsSnapshot> {
+        self.snapshot
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn request(&self, operation: &str, record: impl FnOnce(&mut RequestMetrics)) {
+        record(
+            self.lock()
+                .requests
+                .entry(operation.to_string())
+                .or_default(),
+        );
+    }
+}
+
+impl MetricsSink for MetricsRegistry {
+    fn record_operation(&self, operation: &str, latency: Duration, error: Option<ErrorKind>) {
+        let mut snapshot = self.lock();
+        let metrics = snapshot
+            .operations
+            .entry(operation.to_string())
+            .or_default();
+
+        metrics.calls += 1;
+        metrics.latency.observe(latency);
+
+        if let Some(kind) = error {
+            *metrics.errors.entry(kind).or_default() += 1;
+        }
+    }
+
+    fn record_request(&self, operation: &str, latency: Duration, status: Option<u16>) {
+        self.request(operation, |metrics| {
+            metrics.attempts += 1;
+            metrics.latency.observe(latency);
+
+            if !status.is_some_and(|status| (200..300).contains(&status)) {
+                metrics.failures += 1;
+            }
+        });
+    }
+
+    fn record_bytes_uploaded(&self, operation: &str, bytes: u64) {
+        self.request(operation, |metrics| metrics.bytes_uploaded += bytes);
+    }
+
+    fn record_bytes_downloaded(&self, operation: &str, bytes: u64) {
+        self.request(operation, |metrics| metrics.bytes_downloaded += bytes);
+    }
+
+    fn record_part_retry(&self, operation: &str) {
+        self.request(operation, |metrics| metrics.part_retries += 1);
+    }
+}
+
+/// The start of the current attempt of a request
+#[derive(Debug, Clone, Copy)]
+struct AttemptStart(Instant);
+
+impl Storable for AttemptStart {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The request sent by the current attempt, which is consumed by the
+/// time the attempt completes
+#[derive(Debug, Clone, Copy)]
+struct SentRequest {
+    body_bytes: Option<u64>,
+}
+
+impl Storable for SentRequest {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Returns the positive length in the header `name`
+fn content_length(headers: &Headers, name: &str) -> Option<u64> {
+    headers
+        .get(name)
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|bytes| *bytes > 0)
+}
+
+/// An interceptor recording the attempts of every request sent by the SDK
+/// to a `MetricsSink`, with their latency, status and the bytes of object
+/// data in their bodies, as well as the retried attempts of part uploads
+/// and copies
+///
+/// Attached to the client by `Minio::with_metrics`, and can be attached to
+/// a client built otherwise to record the requests of `core` functions.
+/// Bytes are only counted for `PutObject`, `UploadPart` and `GetObject`,
+/// leaving out the XML bodies of other requests. They are read from the
+/// `Content-Length` headers, so the body of a `get_object` is counted
+/// even if the stream is not read to the end.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let metrics = MetricsRegistry::new();
+///
+/// let config = aws_sdk_s3::config::Builder::from(&sdk_config)
+///     .interceptor(MetricsInterceptor::new(Arc::new(metrics.clone())))
+///     .build();
+///
+/// let client = Client::from_conf(config);
+/// ```
+#[derive(Clone)]
+pub struct MetricsInterceptor {
+    sink: Arc<dyn MetricsSink>,
+}
+
+impl std::fmt::Debug for MetricsInterceptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsInterceptor").finish_non_exhaustive()
+    }
+}
+
+impl MetricsInterceptor {
+    /// Constructs a `MetricsInterceptor` recording to `sink`
+    pub fn new(sink: Arc<dyn MetricsSink>) -> Self {
+        Self { sink }
+    }
+}
+
+impl Intercept for MetricsInterceptor {
+    fn name(&self) -> &'static str {
+        "MetricsInterceptor"
+    }
+
+    fn read_before_attempt(
+        &self,
+        _context: &BeforeTransmitInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        cfg.interceptor_state()
+            .store_put(AttemptStart(Instant::now()));
+
+        let operation = cfg.load::<Metadata>().map(Metadata::name);
+        let attempts = cfg.load::<RequestAttempts>().map(RequestAttempts::attempts);
+
+        if let (Some(operation), Some(2..)) = (operation, attempts) {
+            if PART_OPERATIONS.contains(&operation) {
+                self.sink.record_part_retry(operation);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_before_transmit(
+        &self,
+        context: &BeforeTransmitInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let request = context.request();
+        let headers = request.headers();
+
+        // Streamed uploads are aws-chunked, their decoded length is the payload
+        cfg.interceptor_state().store_put(SentRequest {
+            body_bytes: content_length(headers, "x-amz-decoded-content-length")
+                .or_else(|| content_length(headers, "content-length")),
+        });
+
+        Ok(())
+    }
+
+    fn read_after_attempt(
+        &self,
+        context: &FinalizerInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let Some(operation) = cfg.load::<Metadata>().map(Metadata::name) else {
+            return Ok(());
+        };
+        let latency = cfg
+            .load::<AttemptStart>()
+            .map(|AttemptStart(start)| start.elapsed())
+            .unwrap_or_default();
+        let response = context.response();
+
+        self.sink.record_request(
+            operation,
+            latency,
+            response.map(|response| response.status().as_u16()),
+        );
+
+        let Some(response) = response.filter(|response| response.status().is_success()) else {
+            return Ok(());
+        };
+
+        if UPLOAD_OPERATIONS.contains(&operation) {
+            if let Some(bytes) = cfg.load::<SentRequest>().and_then(|sent| sent.body_bytes) {
+                self.sink.record_bytes_uploaded(operation, bytes);
+            }
+        }
+
+        if DOWNLOAD_OPERATIONS.contains(&operation) {
+            if let Some(bytes) = content_length(response.headers(), "content-length") {
+                self.sink.record_bytes_downloaded(operation, bytes);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,60 @@
 pub mod bucket;
+pub mod checksum;
+pub mod compose;
+pub mod connection;
+pub mod credentials;
 pub mod delete;
 pub mod get;
+pub mod presign;
+pub mod sse;
+pub mod storage;
 pub mod upload;
+pub mod usage;
 
 #[cfg(feature = "pagination_iter")]
 pub mod pagination_iter;
+
+#[cfg(any(
+    feature = "post_policy",
+    feature = "presigned_multipart",
+    feature = "notifications"
+))]
+pub(crate) mod signing;
+
+#[cfg(feature = "encryption")]
+pub mod encryption;
+
+#[cfg(feature = "compression")]
+pub mod compression;
+
+#[cfg(feature = "sync")]
+pub mod sync;
+
+#[cfg(feature = "replicate")]
+pub mod replicate;
+
+#[cfg(feature = "tracing")]
+pub mod telemetry;
+
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
+#[cfg(any(
+    feature = "sts",
+    feature = "admin",
+    feature = "health",
+    feature = "notifications"
+))]
+pub(crate) mod http;
+
+#[cfg(feature = "sts")]
+pub mod sts;
+
+#[cfg(feature = "admin")]
+pub mod admin;
+
+#[cfg(feature = "health")]
+pub mod health;
+
+#[cfg(feature = "notifications")]
+pub mod notifications;
@@ -0,0 +1,587 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{
+    http::{open, xml_value, Endpoint},
+    presign::uri_encode,
+};
+use crate::error::Error;
+use aws_sdk_s3::config::{ProvideCredentials, SharedCredentialsProvider};
+use futures_core::Stream;
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, Method, Response};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// How often the server is asked to write a keep-alive
+const PING_INTERVAL_SECS: u64 = 10;
+
+/// How long the stream may go without even a keep-alive before reconnecting
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The first delay before reconnecting, doubled after every failed attempt
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How many events may be buffered ahead of the consumer
+const CHANNEL_CAPACITY: usize = 256;
+
+/// The name of a bucket notification event, I.E. `s3:ObjectCreated:Put`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EventType {
+    /// `s3:ObjectCreated:*`, only used to listen to every variant
+    ObjectCreatedAll,
+    /// `s3:ObjectCreated:Put`
+    ObjectCreatedPut,
+    /// `s3:ObjectCreated:Post`
+    ObjectCreatedPost,
+    /// `s3:ObjectCreated:Copy`
+    ObjectCreatedCopy,
+    /// `s3:ObjectCreated:CompleteMultipartUpload`
+    ObjectCreatedCompleteMultipartUpload,
+    /// `s3:ObjectRemoved:*`, only used to listen to every variant
+    ObjectRemovedAll,
+    /// `s3:ObjectRemoved:Delete`
+    ObjectRemovedDelete,
+    /// `s3:ObjectRemoved:DeleteMarkerCreated`
+    ObjectRemovedDeleteMarkerCreated,
+    /// `s3:ObjectAccessed:*`, only used to listen to every variant
+    ObjectAccessedAll,
+    /// `s3:ObjectAccessed:Get`
+    ObjectAccessedGet,
+    /// `s3:ObjectAccessed:Head`
+    ObjectAccessedHead,
+    /// Any other event by its name, I.E. `s3:ObjectCreated:PutTagging`
+    Other(String),
+}
+
+impl EventType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EventType::ObjectCreatedAll => "s3:ObjectCreated:*",
+            EventType::ObjectCreatedPut => "s3:ObjectCreated:Put",
+            EventType::ObjectCreatedPost => "s3:ObjectCreated:Post",
+            EventType::ObjectCreatedCopy => "s3:ObjectCreated:Copy",
+            EventType::ObjectCreatedCompleteMultipartUpload => {
+                "s3:ObjectCreated:CompleteMultipartUpload"
+            }
+            EventType::ObjectRemovedAll => "s3:ObjectRemoved:*",
+            EventType::ObjectRemovedDelete => "s3:ObjectRemoved:Delete",
+            EventType::ObjectRemovedDeleteMarkerCreated => "s3:ObjectRemoved:DeleteMarkerCreated",
+            EventType::ObjectAccessedAll => "s3:ObjectAccessed:*",
+            EventType::ObjectAccessedGet => "s3:ObjectAccessed:Get",
+            EventType::ObjectAccessedHead => "s3:ObjectAccessed:Head",
+            EventType::Other(name) => name,
+        }
+    }
+
+    /// If the event is any `s3:ObjectCreated:` event
+    pub fn is_object_created(&self) -> bool {
+        self.as_str().starts_with("s3:ObjectCreated:")
+    }
+
+    /// If the event is any `s3:ObjectRemoved:` event
+    pub fn is_object_removed(&self) -> bool {
+        self.as_str().starts_with("s3:ObjectRemoved:")
+    }
+
+    /// If the event is any `s3:ObjectAccessed:` event
+    pub fn is_object_accessed(&self) -> bool {
+        self.as_str().starts_with("s3:ObjectAccessed:")
+    }
+}
+
+impl From<&str> for EventType {
+    fn from(name: &str) -> Self {
+        match name {
+            "s3:ObjectCreated:*" => EventType::ObjectCreatedAll,
+            "s3:ObjectCreated:Put" => EventType::ObjectCreatedPut,
+            "s3:ObjectCreated:Post" => EventType::ObjectCreatedPost,
+            "s3:ObjectCreated:Copy" => EventType::ObjectCreatedCopy,
+            "s3:ObjectCreated:CompleteMultipartUpload" => {
+                EventType::ObjectCreatedCompleteMultipartUpload
+            }
+            "s3:ObjectRemoved:*" => EventType::ObjectRemovedAll,
+            "s3:ObjectRemoved:Delete" => EventType::ObjectRemovedDelete,
+            "s3:ObjectRemoved:DeleteMarkerCreated" => EventType::ObjectRemovedDeleteMarkerCreated,
+            "s3:ObjectAccessed:*" => EventType::ObjectAccessedAll,
+            "s3:ObjectAccessed:Get" => EventType::ObjectAccessedGet,
+            "s3:ObjectAccessed:Head" => EventType::ObjectAccessedHead,
+            name => EventType::Other(name.to_string()),
+        }
+    }
+}
+
+/// A bucket notification event of a single object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationEvent {
+    pub event_name: EventType,
+    /// When the event happened, in RFC 3339
+    pub event_time: String,
+    pub region: String,
+    pub bucket_name: String,
+    pub object: NotificationObject,
+    /// The access key of the user whose request caused the event
+    pub principal_id: String,
+    /// The address of the client whose request caused the event
+    pub source_host: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// The object of a `NotificationEvent`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationObject {
+    /// The unescaped key of the object
+    pub key: String,
+    /// The size in bytes, absent for removed objects
+    pub size: Option<u64>,
+    pub e_tag: Option<String>,
+    pub content_type: Option<String>,
+    pub version_id: Option<String>,
+    pub user_metadata: HashMap<String, String>,
+    /// Orders the events of the same key
+    pub sequencer: String,
+}
+
+#[derive(Deserialize)]
+struct RawNotification {
+    #[serde(rename = "Records", default)]
+    records: Option<Vec<RawEvent>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawEvent {
+    event_name: String,
+    #[serde(default)]
+    event_time: String,
+    #[serde(default)]
+    aws_region: String,
+    #[serde(default)]
+    user_identity: RawIdentity,
+    s3: RawS3,
+    #[serde(default)]
+    source: RawSource,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct RawIdentity {
+    #[serde(default)]
+    principal_id: String,
+}
+
+#[derive(Deserialize)]
+struct RawS3 {
+    bucket: RawBucket,
+    object: RawObject,
+}
+
+#[derive(Deserialize)]
+struct RawBucket {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawObject {
+    key: String,
+    #[serde(default)]
+    size: Option<u64>,
+    #[serde(default)]
+    e_tag: Option<String>,
+    #[serde(default)]
+    content_type: Option<String>,
+    #[serde(default)]
+    version_id: Option<String>,
+    #[serde(default)]
+    user_metadata: Option<HashMap<String, String>>,
+    #[serde(default)]
+    sequencer: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct RawSource {
+    #[serde(default)]
+    host: Option<String>,
+    #[serde(default)]
+    user_agent: Option<String>,
+}
+
+impl From<RawEvent> for NotificationEvent {
+    fn from(raw: RawEvent) -> Self {
+        let non_empty = |value: Option<String>| value.filter(|value| !value.is_empty());
+
+        Self {
+            event_name: EventType::from(raw.event_name.as_str()),
+            event_time: raw.event_time,
+            region: raw.aws_region,
+            bucket_name: raw.s3.bucket.name,
+            object: NotificationObject {
+                key: query_unescape(&raw.s3.object.key),
+                size: raw.s3.object.size,
+                e_tag: non_empty(raw.s3.object.e_tag),
+                content_type: non_empty(raw.s3.object.content_type),
+                version_id: non_empty(raw.s3.object.version_id),
+                user_metadata: raw.s3.object.user_metadata.unwrap_or_default(),
+                sequencer: raw.s3.object.sequencer,
+            },
+            principal_id: raw.user_identity.principal_id,
+            source_host: non_empty(raw.source.host),
+            user_agent: non_empty(raw.source.user_agent),
+        }
+    }
+}
+
+/// Unescapes a key escaped as a query value, I.E. `shark+tank%2F1.jpg`
+fn query_unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                unescaped.push(byte);
+                index += 3;
+            }
+            (b'+', _) => {
+                unescaped.push(b' ');
+                index += 1;
+            }
+            (byte, _) => {
+                unescaped.push(byte);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+/// Parses a line of the notification stream into its events,
+/// where a blank line is a keep-alive without any
+fn parse_line(line: &[u8]) -> Result<Vec<NotificationEvent>, Error> {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return Ok(vec![]);
+    }
+
+    let notification: RawNotification = serde_json::from_slice(line.trim_ascii())
+        .map_err(|err| Error::Internal(format!("Invalid notification: {}", err)))?;
+
+    Ok(notification
+        .records
+        .unwrap_or_default()
+        .into_iter()
+        .map(NotificationEvent::from)
+        .collect())
+}
+
+/// A `Stream` of the events of `listen_bucket_notifications`,
+/// which stops listening once dropped
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let mut stream: NotificationStream = ...;
+///
+/// while let Some(event) = stream.next().await {
+///     let event: NotificationEvent = event?;
+///     ...
+/// }
+/// ```
+#[derive(Debug)]
+pub struct NotificationStream {
+    receiver: mpsc::Receiver<Result<NotificationEvent, Error>>,
+    listener: Option<JoinHandle<()>>,
+}
+
+impl NotificationStream {
+    /// A stream yielding only `err`, for a listener that could not start
+    pub(crate) fn failed(err: Error) -> Self {
+        let (sender, receiver) = mpsc::channel(1);
+        let _ = sender.try_send(Err(err));
+
+        Self {
+            receiver,
+            listener: None,
+        }
+    }
+
+    /// Waits for the next event, the same as `StreamExt::next`
+    pub async fn next(&mut self) -> Option<Result<NotificationEvent, Error>> {
+        self.receiver.recv().await
+    }
+}
+
+impl Stream for NotificationStream {
+    type Item = Result<NotificationEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for NotificationStream {
+    fn drop(&mut self) {
+        if let Some(listener) = &self.listener {
+            listener.abort();
+        }
+    }
+}
+
+/// Why a connection of the `Listener` ended
+enum Disconnect {
+    /// The server closed the stream, or it went idle
+    Closed,
+    /// The connection failed, which is worth retrying
+    Failed(Error),
+    /// The server rejected the request, I.E. `NoSuchBucket`
+    Rejected(Error),
+    /// The `NotificationStream` was dropped
+    Dropped,
+}
+
+struct Listener {
+    endpoint: Endpoint,
+    credentials_provider: SharedCredentialsProvider,
+    region: String,
+    path_and_query: String,
+    sender: mpsc::Sender<Result<NotificationEvent, Error>>,
+}
+
+impl Listener {
+    /// Listens until the `NotificationStream` is dropped or the server rejects
+    /// the request, reconnecting with an exponential backoff
+    async fn run(self) {
+        let mut backoff = INITIAL_BACKOFF;
+
+        loop {
+            match self.listen(&mut backoff).await {
+                Disconnect::Closed => {}
+                Disconnect::Failed(err) => {
+                    if self.sender.send(Err(err)).await.is_err() {
+                        return;
+                    }
+                }
+                Disconnect::Rejected(err) => {
+                    let _ = self.sender.send(Err(err)).await;
+                    return;
+                }
+                Disconnect::Dropped => return,
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    /// Forwards the events of a single connection, resetting
+    /// `backoff` once the server accepts the request
+    async fn listen(&self, backoff: &mut Duration) -> Disconnect {
+        let response = match self.connect().await {
+            Ok(response) => response,
+            Err(err) => return Disconnect::Failed(err),
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response
+                .into_body()
+                .collect()
+                .await
+                .map(|body| body.to_bytes())
+                .unwrap_or_default();
+            let body = String::from_utf8_lossy(&body);
+            let err = Error::SdkError(format!(
+                "ListenBucketNotification failed with {}: {}",
+                status,
+                xml_value(&body, "Code").unwrap_or_default()
+            ));
+
+            return match status.is_client_error() {
+                true => Disconnect::Rejected(err),
+                false => Disconnect::Failed(err),
+            };
+        }
+
+        *backoff = INITIAL_BACKOFF;
+
+        let mut body = response.into_body();
+        let mut buffer = vec![];
+
+        loop {
+            let frame = match tokio::time::timeout(IDLE_TIMEOUT, body.frame()).await {
+                Ok(Some(Ok(frame))) => frame,
+                Ok(None) | Ok(Some(Err(_))) | Err(_) => return Disconnect::Closed,
+            };
+            let Ok(data) = frame.into_data() else {
+                continue;
+            };
+            buffer.extend_from_slice(&data);
+
+            while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let events = match parse_line(&line) {
+                    Ok(events) => events.into_iter().map(Ok).collect(),
+                    Err(err) => vec![Err(err)],
+                };
+
+                for event in events {
+                    if self.sender.send(event).await.is_err() {
+                        return Disconnect::Dropped;
+                    }
+                }
+            }
+
+            // Note: Keep-alives are written without a newline
+            if buffer.iter().all(u8::is_ascii_whitespace) {
+                buffer.clear();
+            }
+        }
+    }
+
+    async fn connect(&self) -> Result<Response<Incoming>, Error> {
+        let credentials = self
+            .credentials_provider
+            .provide_credentials()
+            .await
+            .map_err(Error::sdk)?;
+
+        open(
+            &self.endpoint,
+            Some((&credentials, &self.region, "s3")),
+            Method::GET,
+            &self.path_and_query,
+            vec![],
+        )
+        .await
+    }
+}
+
+/// Listens to the events of the bucket by `bucket_name` on the MinIO server at the
+/// S3 API Url `url`, via the MinIO ListenBucketNotification extension, of the objects
+/// whose keys start with `prefix` and end with `suffix`, of every `EventType` in `events`
+///
+/// The connection is kept open via keep-alives, and reconnected with an exponential
+/// backoff if it drops. Failed reconnection attempts are yielded as an `Err` while
+/// retrying, whereas a request rejected by the server, I.E. for a bucket that does
+/// not exist or an invalid `url`, ends the stream after its `Err`.
+///
+/// Events that happen while reconnecting are not replayed.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let credentials_provider: SharedCredentialsProvider = ...;
+///
+/// let mut stream: NotificationStream = listen_bucket_notifications(
+///     "http://127.0.0.1:9000",
+///     credentials_provider,
+///     "us-east-1",
+///     "sharks",
+///     "images/",
+///     ".jpg",
+///     &[EventType::ObjectCreatedAll],
+/// );
+///
+/// while let Some(event) = stream.next().await {
+///     let event: NotificationEvent = event?;
+///
+///     generate_thumbnail(&event.bucket_name, &event.object.key).await?;
+/// }
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn listen_bucket_notifications(
+    url: &str,
+    credentials_provider: SharedCredentialsProvider,
+    region: &str,
+    bucket_name: &str,
+    prefix: &str,
+    suffix: &str,
+    events: &[EventType],
+) -> NotificationStream {
+    match Endpoint::parse(url) {
+        Ok(endpoint) => listen_endpoint_notifications(
+            endpoint,
+            credentials_provider,
+            region,
+            bucket_name,
+            prefix,
+            suffix,
+            events,
+        ),
+        Err(err) => NotificationStream::failed(err),
+    }
+}
+
+/// Like `listen_bucket_notifications`, on the MinIO server at `endpoint`
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn listen_endpoint_notifications(
+    endpoint: Endpoint,
+    credentials_provider: SharedCredentialsProvider,
+    region: &str,
+    bucket_name: &str,
+    prefix: &str,
+    suffix: &str,
+    events: &[EventType],
+) -> NotificationStream {
+    if bucket_name.is_empty() {
+        return NotificationStream::failed(Error::internal(
+            "Cannot listen to a bucket without a name",
+        ));
+    }
+
+    let mut query = events
+        .iter()
+        .map(|event| ("events", event.as_str()))
+        .collect::<Vec<_>>();
+    let ping = PING_INTERVAL_SECS.to_string();
+    query.push(("ping", &ping));
+    if !prefix.is_empty() {
+        query.push(("prefix", prefix));
+    }
+    if !suffix.is_empty() {
+        query.push(("suffix", suffix));
+    }
+    query.sort();
+
+    let path_and_query = format!(
+        "/{}?{}",
+        uri_encode(bucket_name, true),
+        query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, uri_encode(value, true)))
+            .collect::<Vec<_>>()
+            .join("&")
+    );
+
+    let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+    let listener = Listener {
+        endpoint,
+        credentials_provider,
+        region: region.to_string(),
+        path_and_query,
+        sender,
+    };
+
+    NotificationStream {
+        receiver,
+        listener: Some(tokio::spawn(listener.run())),
+    }
+}
@@ -0,0 +1,166 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use aws_sdk_s3::{
+    presigning::PresignedRequest,
+    primitives::{DateTime, DateTimeFormat},
+    Client,
+};
+use std::{borrow::Cow, collections::HashMap};
+
+/// Returns a client signing for `endpoint_url` if provided, I.E. a public
+/// hostname (CDN or ingress) differing from the one `client` was built with
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// let presigning_client = presigning_client(&client, Some("https://cdn.example.com"));
+/// ```
+pub(crate) fn presigning_client<'pc>(
+    client: &'pc Client,
+    endpoint_url: Option<&str>,
+) -> Cow<'pc, Client> {
+    match endpoint_url {
+        Some(endpoint_url) => Cow::Owned(Client::from_conf(
+            client
+                .config()
+                .to_builder()
+                .endpoint_url(endpoint_url)
+                .build(),
+        )),
+        None => Cow::Borrowed(client),
+    }
+}
+
+/// Percent-encodes `value` per RFC 3986 as S3 expects, leaving
+/// path separators intact unless `encode_slash` is `true`
+pub(crate) fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+/// Decodes the `%XX` escapes of a value encoded by `uri_encode`,
+/// returning `None` if an escape or the decoded UTF-8 is invalid
+pub(crate) fn uri_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(index + 1..index + 3)?).ok()?;
+
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+/// A presigned request, owned by minior so it can be serialized
+/// *(With the `serde` feature)* and handed to clients as-is.
+///
+/// `headers` must be sent with the request, and `expires_at` is the
+/// unix timestamp in seconds after which the request is rejected,
+/// if it could be determined from the `url`.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let minio: Minio = ...;
+///
+/// let presigned_url: PresignedUrl = minio.upload_object_presigned(
+///     "sharks",
+///     "shark.jpg",
+///     3_600,
+///     None,
+/// ).await?;
+///
+/// let response_body = serde_json::to_string(&presigned_url)?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PresignedUrl {
+    pub method: String,
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub expires_at: Option<u64>,
+}
+
+impl From<PresignedRequest> for PresignedUrl {
+    fn from(request: PresignedRequest) -> Self {
+        Self {
+            method: request.method().to_string(),
+            url: request.uri().to_string(),
+            headers: request
+                .headers()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            expires_at: expires_at(request.uri()),
+        }
+    }
+}
+
+/// Determines when a SigV4 presigned `url` expires from its
+/// `X-Amz-Date` and `X-Amz-Expires` query parameters
+fn expires_at(url: &str) -> Option<u64> {
+    let (_, query) = url.split_once('?')?;
+
+    let mut amz_date = None;
+    let mut amz_expires = None;
+
+    for pair in query.split('&') {
+        match pair.split_once('=') {
+            Some(("X-Amz-Date", value)) => amz_date = Some(value),
+            Some(("X-Amz-Expires", value)) => amz_expires = value.parse::<u64>().ok(),
+            _ => {}
+        }
+    }
+
+    let signed_at = parse_amz_date(amz_date?)?;
+
+    u64::try_from(signed_at.secs())
+        .ok()?
+        .checked_add(amz_expires?)
+}
+
+/// Parses a SigV4 `X-Amz-Date`, I.E. `20240101T000000Z`
+pub(crate) fn parse_amz_date(amz_date: &str) -> Option<DateTime> {
+    if amz_date.len() != 16 || !amz_date.is_ascii() {
+        return None;
+    }
+
+    // `20240101T000000Z` -> `2024-01-01T00:00:00Z`
+    DateTime::from_str(
+        &format!(
+            "{}-{}-{}T{}:{}:{}Z",
+            &amz_date[0..4],
+            &amz_date[4..6],
+            &amz_date[6..8],
+            &amz_date[9..11],
+            &amz_date[11..13],
+            &amz_date[13..15]
+        ),
+        DateTimeFormat::DateTime,
+    )
+    .ok()
+}
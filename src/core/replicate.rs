@@ -0,0 +1,404 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{
+    bucket::list_objects_with_prefix,
+    compose::{compose_object, ComposeObjectAdditionalOptions, ComposeSource},
+    get::{get_object, GetObjectAdditionalOptions},
+    presign::{uri_decode, uri_encode},
+    upload::upload_object::{upload_object, UploadObjectAdditionalOptions},
+};
+use crate::{error::Error, Minio};
+use aws_sdk_s3::{
+    primitives::{DateTime, DateTimeFormat},
+    types::{MetadataDirective, Tagging, TaggingDirective},
+    Client,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{
+    fs,
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    sync::{Mutex, Semaphore},
+    task::JoinSet,
+};
+
+/// Maximum size of an object copied by a single `CopyObject`, in bytes
+pub(crate) const MAX_COPY_OBJECT_SIZE: i64 = 5_368_709_120;
+
+/// How `replicate` copies objects to the destination
+///
+/// - `Auto`: `ServerSideCopy` if the source and destination `Minio`
+///   share the same `Client`, `Stream` otherwise.
+///
+/// - `ServerSideCopy`: The destination copies the objects from the
+///   source bucket itself, which must be in the same cluster and
+///   readable with the credentials of the destination.
+///
+/// - `Stream`: Objects are downloaded from the source and uploaded
+///   to the destination, I.E. across clusters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplicateMode {
+    #[default]
+    Auto,
+    ServerSideCopy,
+    Stream,
+}
+
+/// Options for `replicate`
+///
+/// `prefix` limits the objects replicated to the keys starting with it.
+///
+/// `checkpoint` is a file recording every replicated object, which are
+/// skipped when `replicate` is run again after an interruption. It is
+/// removed once every object was replicated.
+///
+/// Default `concurrency` is `4`, and cannot be lower than `1`
+/// *(Overwrites to `1` if lower)*
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let options = ReplicateOptions {
+///     mode: ReplicateMode::Stream,
+///     checkpoint: Some(PathBuf::from("./sharks.checkpoint")),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ReplicateOptions {
+    pub mode: ReplicateMode,
+    pub prefix: Option<String>,
+    pub checkpoint: Option<PathBuf>,
+    pub concurrency: Option<usize>,
+}
+
+/// Summary of a `replicate`, with the sorted keys that were copied
+///
+/// `skipped` counts the objects already identical in the destination,
+/// and the ones recorded in the checkpoint of an earlier run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplicateReport {
+    pub copied: Vec<String>,
+    pub skipped: usize,
+    pub bytes_copied: usize,
+}
+
+/// Appends replicated keys to the checkpoint file, one per line,
+/// percent-encoded so keys holding line breaks stay on their line
+struct Checkpoint {
+    file: Mutex<fs::File>,
+}
+
+impl Checkpoint {
+    /// Opens the checkpoint file at `path`, returning the keys
+    /// recorded by an earlier run
+    async fn open(path: &Path) -> Result<(Self, HashSet<String>), Error> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+
+        let mut replicated = HashSet::new();
+        let mut lines = BufReader::new(file).lines();
+
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?
+        {
+            if !line.is_empty() {
+                replicated.insert(uri_decode(&line).ok_or(Error::Internal(format!(
+                    "Invalid key in the checkpoint {}",
+                    path.display()
+                )))?);
+            }
+        }
+
+        let file = lines.into_inner().into_inner();
+
+        Ok((
+            Self {
+                file: Mutex::new(file),
+            },
+            replicated,
+        ))
+    }
+
+    async fn record(&self, object_name: &str) -> Result<(), Error> {
+        let mut file = self.file.lock().await;
+
+        file.write_all(format!("{}\n", uri_encode(object_name, true)).as_bytes())
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+        file.flush().await.map_err(|err| Error::StdIo(err.kind()))
+    }
+}
+
+/// Copies an object from the source to the destination, preserving
+/// its user metadata, standard headers and tags, and returns its size
+async fn replicate_object(
+    src_client: Arc<Client>,
+    src_bucket: &str,
+    dst_client: Arc<Client>,
+    dst_bucket: &str,
+    object_name: &str,
+    size: i64,
+    server_side_copy: bool,
+) -> Result<usize, Error> {
+    if server_side_copy && size <= MAX_COPY_OBJECT_SIZE {
+        dst_client
+            .copy_object()
+            .bucket(dst_bucket)
+            .key(object_name)
+            .copy_source(format!(
+                "{}/{}",
+                uri_encode(src_bucket, true),
+                uri_encode(object_name, false)
+            ))
+            .metadata_directive(MetadataDirective::Copy)
+            .tagging_directive(TaggingDirective::Copy)
+            .send()
+            .await
+            .map_err(Error::sdk)?;
+
+        return Ok(size as usize);
+    }
+
+    let head = src_client
+        .head_object()
+        .bucket(src_bucket)
+        .key(object_name)
+        .send()
+        .await
+        .map_err(Error::sdk)?;
+    let expires = head
+        .expires_string()
+        .and_then(|expires| DateTime::from_str(expires, DateTimeFormat::HttpDate).ok());
+
+    let tags = src_client
+        .get_object_tagging()
+        .bucket(src_bucket)
+        .key(object_name)
+        .send()
+        .await
+        .map_err(Error::sdk)?
+        .tag_set()
+        .to_vec();
+
+    let bytes = if server_side_copy {
+        compose_object(
+            &dst_client,
+            dst_bucket,
+            object_name,
+            vec![ComposeSource::new(src_bucket, object_name)],
+            ComposeObjectAdditionalOptions {
+                metadata: head.metadata,
+                content_type: head.content_type,
+                content_encoding: head.content_encoding,
+                cache_control: head.cache_control,
+                content_disposition: head.content_disposition,
+                expires,
+                #[cfg(feature = "sse")]
+                encryption: None,
+            },
+        )
+        .await?
+    } else {
+        #[cfg(feature = "compression")]
+        let get_options = GetObjectAdditionalOptions {
+            raw: true,
+            ..Default::default()
+        };
+        #[cfg(not(feature = "compression"))]
+        let get_options = GetObjectAdditionalOptions::default();
+
+        let stream = get_object(&src_client, src_bucket, object_name, get_options)
+            .await?
+            .ok_or(Error::Internal(format!(
+                "Object {} no longer exists",
+                object_name
+            )))?;
+
+        upload_object(
+            dst_client.clone(),
+            dst_bucket,
+            object_name,
+            Box::pin(stream),
+            UploadObjectAdditionalOptions {
+                metadata: head.metadata,
+                content_type: head.content_type,
+                content_encoding: head.content_encoding,
+                cache_control: head.cache_control,
+                content_disposition: head.content_disposition,
+                expires,
+                ..Default::default()
+            },
+        )
+        .await?
+    };
+
+    if !tags.is_empty() {
+        dst_client
+            .put_object_tagging()
+            .bucket(dst_bucket)
+            .key(object_name)
+            .tagging(
+                Tagging::builder()
+                    .set_tag_set(Some(tags))
+                    .build()
+                    .map_err(Error::sdk)?,
+            )
+            .send()
+            .await
+            .map_err(Error::sdk)?;
+    }
+
+    Ok(bytes)
+}
+
+/// Replicates the objects of the bucket by `src_bucket` of `src` to the
+/// bucket by `dst_bucket` of `dst`, which may be a different cluster,
+/// preserving their user metadata and tags
+///
+/// Objects of the same size and `ETag` in the destination are skipped.
+/// *Note*: The `ETag` of a multipart upload depends on its part sizes,
+/// so streamed objects uploaded with other part sizes are copied again.
+///
+/// Returns a `ReplicateReport` of the objects copied and skipped
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let old_cluster: Minio = ...;
+/// let new_cluster: Minio = ...;
+///
+/// let report: ReplicateReport = replicate(
+///     &old_cluster,
+///     "sharks",
+///     &new_cluster,
+///     "sharks",
+///     ReplicateOptions {
+///         checkpoint: Some(PathBuf::from("./sharks.checkpoint")),
+///         ..Default::default()
+///     },
+/// ).await?;
+/// ```
+pub async fn replicate(
+    src: &Minio,
+    src_bucket: &str,
+    dst: &Minio,
+    dst_bucket: &str,
+    ReplicateOptions {
+        mode,
+        prefix,
+        checkpoint,
+        concurrency,
+    }: ReplicateOptions,
+) -> Result<ReplicateReport, Error> {
+    let server_side_copy = match mode {
+        ReplicateMode::Auto => Arc::ptr_eq(&src.client, &dst.client),
+        ReplicateMode::ServerSideCopy => true,
+        ReplicateMode::Stream => false,
+    };
+    let prefix = prefix.unwrap_or_default();
+
+    let (checkpoint_file, replicated) = match checkpoint {
+        Some(ref path) => {
+            let (checkpoint_file, replicated) = Checkpoint::open(path).await?;
+            (Some(Arc::new(checkpoint_file)), replicated)
+        }
+        None => (None, HashSet::new()),
+    };
+
+    let dst_objects = list_objects_with_prefix(&dst.client, dst_bucket, &prefix)
+        .await?
+        .into_iter()
+        .filter_map(|object| Some((object.key()?.to_string(), object)))
+        .collect::<HashMap<_, _>>();
+
+    let mut src_objects = list_objects_with_prefix(&src.client, src_bucket, &prefix).await?;
+    src_objects.sort_by(|a, b| a.key().cmp(&b.key()));
+
+    let mut report = ReplicateReport::default();
+    let semaphore = Arc::new(Semaphore::new(concurrency.unwrap_or(4).max(1)));
+    let mut join_set = JoinSet::new();
+
+    for object in src_objects {
+        let Some(object_name) = object.key().map(str::to_string) else {
+            continue;
+        };
+
+        let is_identical = dst_objects.get(&object_name).is_some_and(|dst_object| {
+            dst_object.size() == object.size() && dst_object.e_tag() == object.e_tag()
+        });
+
+        if is_identical || replicated.contains(&object_name) {
+            report.skipped += 1;
+            continue;
+        }
+
+        report.copied.push(object_name.clone());
+
+        let src_client = src.client.clone();
+        let dst_client = dst.client.clone();
+        let src_bucket = src_bucket.to_string();
+        let dst_bucket = dst_bucket.to_string();
+        let semaphore = semaphore.clone();
+        let checkpoint_file = checkpoint_file.clone();
+
+        join_set.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .map_err(|_| Error::AcquireError)?;
+
+            let bytes = replicate_object(
+                src_client,
+                &src_bucket,
+                dst_client,
+                &dst_bucket,
+                &object_name,
+                object.size().unwrap_or_default(),
+                server_side_copy,
+            )
+            .await?;
+
+            if let Some(checkpoint_file) = checkpoint_file {
+                checkpoint_file.record(&object_name).await?;
+            }
+
+            Ok(bytes)
+        });
+    }
+
+    // Stop the remaining copies once one fails, keeping the checkpoint
+    while let Some(result) = join_set.join_next().await {
+        match result
+            .map_err(|_| Error::JoinError)
+            .and_then(|result| result)
+        {
+            Ok(bytes) => report.bytes_copied += bytes,
+            Err(err) => {
+                join_set.abort_all();
+                return Err(err);
+            }
+        }
+    }
+
+    if let Some(ref path) = checkpoint {
+        fs::remove_file(path)
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,183 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::connection::DEFAULT_REGION;
+use aws_sdk_s3::Client;
+
+#[cfg(any(feature = "post_policy", feature = "presigned_multipart"))]
+use crate::error::Error;
+#[cfg(any(feature = "post_policy", feature = "presigned_multipart"))]
+use aws_sdk_s3::presigning::PresigningConfig;
+#[cfg(any(feature = "post_policy", feature = "presigned_multipart"))]
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "post_policy")]
+use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+
+#[cfg(feature = "presigned_multipart")]
+use super::presign::{uri_encode, PresignedUrl};
+#[cfg(feature = "presigned_multipart")]
+use aws_sdk_s3::config::Credentials;
+#[cfg(feature = "presigned_multipart")]
+use aws_sigv4::{
+    http_request::{
+        sign, PercentEncodingMode, SignableBody, SignableRequest, SignatureLocation,
+        SigningSettings, UriPathNormalizationMode,
+    },
+    sign::v4,
+};
+#[cfg(feature = "presigned_multipart")]
+use std::time::UNIX_EPOCH;
+
+/// Returns the region the client signs requests for, `DEFAULT_REGION`
+/// if the client has none configured
+pub(crate) fn region(client: &Client) -> String {
+    client
+        .config()
+        .region()
+        .map(|region| region.to_string())
+        .unwrap_or(DEFAULT_REGION.to_string())
+}
+
+#[cfg(feature = "post_policy")]
+/// Formats `time` as a SigV4 `x-amz-date`, I.E. `20240101T000000Z`
+pub(crate) fn amz_date(time: SystemTime) -> Result<String, Error> {
+    Ok(iso8601(time)?.replace(['-', ':'], ""))
+}
+
+#[cfg(feature = "post_policy")]
+/// Formats `time` as a SigV4 credential scope date, I.E. `20240101`
+pub(crate) fn amz_date_stamp(time: SystemTime) -> Result<String, Error> {
+    Ok(amz_date(time)?[..8].to_string())
+}
+
+#[cfg(feature = "post_policy")]
+/// Formats `time` to the second as ISO 8601, I.E. `2024-01-01T00:00:00Z`
+pub(crate) fn iso8601(time: SystemTime) -> Result<String, Error> {
+    DateTime::from_secs(DateTime::from(time).secs())
+        .fmt(DateTimeFormat::DateTime)
+        .map_err(Error::sdk)
+}
+
+/// Strips the query from a presigned `uri`, leaving the resource URL
+#[cfg(any(feature = "post_policy", feature = "presigned_multipart"))]
+fn strip_query(uri: &str) -> String {
+    uri.split('?').next().unwrap_or(uri).to_string()
+}
+
+/// Resolves the URL of a bucket by `bucket_name`, respecting the client's
+/// endpoint and addressing style
+#[cfg(feature = "post_policy")]
+pub(crate) async fn bucket_url(client: &Client, bucket_name: &str) -> Result<String, Error> {
+    let presigning_config = PresigningConfig::builder()
+        .expires_in(Duration::from_secs(1))
+        .build()
+        .map_err(Error::sdk)?;
+
+    let request = client
+        .list_object_versions()
+        .bucket(bucket_name)
+        .presigned(presigning_config)
+        .await
+        .map_err(Error::sdk)?;
+
+    Ok(strip_query(request.uri()))
+}
+
+/// Resolves the encoded URL of an object by `bucket_name` and `object_name`,
+/// respecting the client's endpoint and addressing style
+#[cfg(feature = "presigned_multipart")]
+pub(crate) async fn object_url(
+    client: &Client,
+    bucket_name: &str,
+    object_name: &str,
+) -> Result<String, Error> {
+    let presigning_config = PresigningConfig::builder()
+        .expires_in(Duration::from_secs(1))
+        .build()
+        .map_err(Error::sdk)?;
+
+    let request = client
+        .head_object()
+        .bucket(bucket_name)
+        .key(object_name)
+        .presigned(presigning_config)
+        .await
+        .map_err(Error::sdk)?;
+
+    Ok(strip_query(request.uri()))
+}
+
+/// Presigns a S3 request with `method` to the encoded `url` via SigV4
+/// query parameters, for operations the SDK can not presign itself
+///
+/// `headers` are signed, and returned as the `headers` of the `PresignedUrl`
+/// the client must send
+#[cfg(feature = "presigned_multipart")]
+pub(crate) fn presign_request(
+    credentials: &Credentials,
+    region: &str,
+    method: &str,
+    url: &str,
+    headers: Vec<(String, String)>,
+    presigned_expiry_secs: u64,
+) -> Result<PresignedUrl, Error> {
+    let now = SystemTime::now();
+
+    let mut settings = SigningSettings::default();
+    settings.signature_location = SignatureLocation::QueryParams;
+    settings.expires_in = Some(Duration::from_secs(presigned_expiry_secs));
+    settings.percent_encoding_mode = PercentEncodingMode::Single;
+    settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+
+    let identity = credentials.clone().into();
+
+    let signing_params = v4::SigningParams::builder()
+        .identity(&identity)
+        .region(region)
+        .name("s3")
+        .time(now)
+        .settings(settings)
+        .build()
+        .map_err(Error::sdk)?
+        .into();
+
+    let signable_request = SignableRequest::new(
+        method,
+        url,
+        headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str())),
+        SignableBody::UnsignedPayload,
+    )
+    .map_err(Error::sdk)?;
+
+    let (signing_instructions, _) = sign(signable_request, &signing_params)
+        .map_err(Error::sdk)?
+        .into_parts();
+
+    let mut presigned_url = url.to_string();
+
+    for (name, value) in signing_instructions.params() {
+        presigned_url.push(if presigned_url.contains('?') {
+            '&'
+        } else {
+            '?'
+        });
+        presigned_url.push_str(&format!("{}={}", name, uri_encode(value, true)));
+    }
+
+    Ok(PresignedUrl {
+        method: method.to_string(),
+        url: presigned_url,
+        headers: signing_instructions
+            .headers()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .chain(headers)
+            .collect(),
+        expires_at: now
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|since_epoch| since_epoch.as_secs() + presigned_expiry_secs),
+    })
+}
@@ -0,0 +1,127 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use aws_sdk_s3::types::ServerSideEncryption;
+
+#[cfg(feature = "sse")]
+use aws_smithy_types::base64;
+#[cfg(feature = "sse")]
+use md5::{Digest, Md5};
+#[cfg(feature = "sse")]
+use std::fmt;
+
+/// Server-side encryption of an object at rest
+///
+/// - `SseS3`: Encrypted with a key managed by the server
+///
+/// - `SseKms`: Encrypted with a KMS key by `key_id`, defaulting to the
+///   server's default key if `None`. `context` is an optional JSON object
+///   of the KMS encryption context.
+///
+/// - `SseC`: Encrypted with a 256-bit customer provided `key`, that must be
+///   provided again to read the object. *Note*: Minio only accepts `SseC`
+///   over TLS.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let encryption = Encryption::SseKms {
+///     key_id: Some("my-minio-key".to_string()),
+///     context: Some("{\"tenant\":\"sharks\"}".to_string()),
+/// };
+///
+/// let customer_key: [u8; 32] = ...;
+/// let encryption = Encryption::SseC { key: customer_key };
+/// ```
+#[cfg(feature = "sse")]
+#[derive(Clone, PartialEq, Eq)]
+pub enum Encryption {
+    SseS3,
+    SseKms {
+        key_id: Option<String>,
+        context: Option<String>,
+    },
+    SseC {
+        key: [u8; 32],
+    },
+}
+
+#[cfg(feature = "sse")]
+impl fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encryption::SseS3 => write!(f, "SseS3"),
+            Encryption::SseKms { key_id, context } => f
+                .debug_struct("SseKms")
+                .field("key_id", key_id)
+                .field("context", context)
+                .finish(),
+            Encryption::SseC { .. } => f
+                .debug_struct("SseC")
+                .field("key", &"*** redacted ***")
+                .finish(),
+        }
+    }
+}
+
+/// The values of the server-side encryption headers of a request,
+/// all `None` if the request is not encrypted
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SseHeaders {
+    pub(crate) server_side_encryption: Option<ServerSideEncryption>,
+    pub(crate) ssekms_key_id: Option<String>,
+    pub(crate) ssekms_encryption_context: Option<String>,
+    pub(crate) customer_algorithm: Option<String>,
+    pub(crate) customer_key: Option<String>,
+    pub(crate) customer_key_md5: Option<String>,
+}
+
+impl SseHeaders {
+    #[cfg(feature = "presigned_multipart")]
+    /// The `Encryption::SseC` headers, to sign into a presigned request
+    pub(crate) fn customer_headers(&self) -> Vec<(String, String)> {
+        [
+            (
+                "x-amz-server-side-encryption-customer-algorithm",
+                &self.customer_algorithm,
+            ),
+            (
+                "x-amz-server-side-encryption-customer-key",
+                &self.customer_key,
+            ),
+            (
+                "x-amz-server-side-encryption-customer-key-md5",
+                &self.customer_key_md5,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.clone()?)))
+        .collect()
+    }
+}
+
+#[cfg(feature = "sse")]
+impl From<Option<&Encryption>> for SseHeaders {
+    fn from(encryption: Option<&Encryption>) -> Self {
+        match encryption {
+            None => SseHeaders::default(),
+            Some(Encryption::SseS3) => SseHeaders {
+                server_side_encryption: Some(ServerSideEncryption::Aes256),
+                ..Default::default()
+            },
+            Some(Encryption::SseKms { key_id, context }) => SseHeaders {
+                server_side_encryption: Some(ServerSideEncryption::AwsKms),
+                ssekms_key_id: key_id.clone(),
+                ssekms_encryption_context: context.as_ref().map(base64::encode),
+                ..Default::default()
+            },
+            Some(Encryption::SseC { key }) => SseHeaders {
+                customer_algorithm: Some(ServerSideEncryption::Aes256.as_str().to_string()),
+                customer_key: Some(base64::encode(key)),
+                customer_key_md5: Some(base64::encode(Md5::digest(key))),
+                ..Default::default()
+            },
+        }
+    }
+}
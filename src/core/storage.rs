@@ -0,0 +1,352 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{
+    checksum::{ChecksumHeaders, PartChecksums},
+    get::{
+        GetObjectAdditionalOptions, GetObjectPresignedAdditionalOptions,
+        StatObjectAdditionalOptions,
+    },
+    presign::PresignedUrl,
+    sse::SseHeaders,
+    upload::{
+        upload_object::UploadObjectAdditionalOptions,
+        upload_object_presigned::UploadObjectPresignedAdditionalOptions, util,
+    },
+};
+use crate::{error::Error, ETag, Minio};
+use aws_sdk_s3::{
+    operation::head_object::HeadObjectOutput,
+    types::{Bucket, Object},
+};
+use std::{future::Future, pin::Pin};
+use tokio::io::{AsyncBufRead, AsyncRead};
+
+/// The stream of an object returned by `ObjectStorage::get_object`
+pub type ObjectStream = Pin<Box<dyn AsyncBufRead + Send>>;
+
+/// The operations of an S3 compatible object storage, implemented
+/// by `Minio`, and by `InMemoryStorage` with the `testing` feature
+/// so code written against this trait can be tested offline
+///
+/// See the methods of `Minio` for the behavior of every operation.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// async fn archive(storage: &impl ObjectStorage, report: &[u8]) -> Result<(), Error> {
+///     storage.create_bucket("reports").await?;
+///     storage.upload_object("reports", "report.json", report, None).await?;
+///
+///     Ok(())
+/// }
+///
+/// let minio: Minio = ...;
+/// archive(&minio, b"{}").await?;
+/// ```
+pub trait ObjectStorage {
+    /// Returns `false` if the bucket already existed
+    fn create_bucket(&self, bucket_name: &str) -> impl Future<Output = Result<bool, Error>> + Send;
+
+    /// Returns `false` if the bucket did not exist
+    fn delete_bucket(
+        &self,
+        bucket_name: &str,
+        delete_objects: bool,
+    ) -> impl Future<Output = Result<bool, Error>> + Send;
+
+    fn bucket_exists(&self, bucket_name: &str) -> impl Future<Output = Result<bool, Error>> + Send;
+
+    fn list_buckets(&self) -> impl Future<Output = Result<Vec<Bucket>, Error>> + Send;
+
+    fn list_bucket_objects(
+        &self,
+        bucket_name: &str,
+    ) -> impl Future<Output = Result<Vec<Object>, Error>> + Send;
+
+    fn set_bucket_versioning(
+        &self,
+        bucket_name: &str,
+        enabled: bool,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    fn object_exists(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+    ) -> impl Future<Output = Result<bool, Error>> + Send;
+
+    /// Returns the total amount of bytes uploaded
+    fn upload_object<S>(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        stream: S,
+        additional_options: Option<UploadObjectAdditionalOptions>,
+    ) -> impl Future<Output = Result<usize, Error>> + Send
+    where
+        S: AsyncRead + Unpin + Send;
+
+    /// Returns `Ok(None)` if the object does not exist
+    fn get_object(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        additional_options: Option<GetObjectAdditionalOptions>,
+    ) -> impl Future<Output = Result<Option<ObjectStream>, Error>> + Send;
+
+    /// Returns `Ok(None)` if the object does not exist
+    fn stat_object(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        additional_options: Option<StatObjectAdditionalOptions>,
+    ) -> impl Future<Output = Result<Option<HeadObjectOutput>, Error>> + Send;
+
+    fn delete_object(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Returns the upload id of a new multipart upload
+    fn start_multipart_upload(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+    ) -> impl Future<Output = Result<String, Error>> + Send;
+
+    /// Returns the `ETag` of the uploaded part
+    fn upload_part(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        upload_id: &str,
+        part_number: usize,
+        bytes: Vec<u8>,
+    ) -> impl Future<Output = Result<ETag, Error>> + Send;
+
+    fn complete_multipart_upload(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        upload_id: &str,
+        e_tags: Vec<ETag>,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    fn abort_multipart_upload(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        upload_id: &str,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Returns `Ok(None)` if the object does not exist
+    fn get_object_presigned(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        presigned_expiry_secs: u64,
+        additional_options: Option<GetObjectPresignedAdditionalOptions>,
+    ) -> impl Future<Output = Result<Option<PresignedUrl>, Error>> + Send;
+
+    fn upload_object_presigned(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        presigned_expiry_secs: u64,
+        additional_options: Option<UploadObjectPresignedAdditionalOptions>,
+    ) -> impl Future<Output = Result<PresignedUrl, Error>> + Send;
+
+    fn delete_object_presigned(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        presigned_expiry_secs: u64,
+    ) -> impl Future<Output = Result<PresignedUrl, Error>> + Send;
+}
+
+impl ObjectStorage for Minio {
+    async fn create_bucket(&self, bucket_name: &str) -> Result<bool, Error> {
+        Minio::create_bucket(self, bucket_name).await
+    }
+
+    async fn delete_bucket(&self, bucket_name: &str, delete_objects: bool) -> Result<bool, Error> {
+        Minio::delete_bucket(self, bucket_name, delete_objects).await
+    }
+
+    async fn bucket_exists(&self, bucket_name: &str) -> Result<bool, Error> {
+        Minio::bucket_exists(self, bucket_name).await
+    }
+
+    async fn list_buckets(&self) -> Result<Vec<Bucket>, Error> {
+        Minio::list_buckets(self).await
+    }
+
+    async fn list_bucket_objects(&self, bucket_name: &str) -> Result<Vec<Object>, Error> {
+        Minio::list_bucket_objects(self, bucket_name).await
+    }
+
+    async fn set_bucket_versioning(&self, bucket_name: &str, enabled: bool) -> Result<(), Error> {
+        Minio::set_bucket_versioning(self, bucket_name, enabled).await
+    }
+
+    async fn object_exists(&self, bucket_name: &str, object_name: &str) -> Result<bool, Error> {
+        Minio::object_exists(self, bucket_name, object_name).await
+    }
+
+    async fn upload_object<S>(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        stream: S,
+        additional_options: Option<UploadObjectAdditionalOptions>,
+    ) -> Result<usize, Error>
+    where
+        S: AsyncRead + Unpin + Send,
+    {
+        Minio::upload_object(self, bucket_name, object_name, stream, additional_options).await
+    }
+
+    async fn get_object(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        additional_options: Option<GetObjectAdditionalOptions>,
+    ) -> Result<Option<ObjectStream>, Error> {
+        Ok(
+            Minio::get_object(self, bucket_name, object_name, additional_options)
+                .await?
+                .map(|stream| Box::pin(stream) as ObjectStream),
+        )
+    }
+
+    async fn stat_object(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        additional_options: Option<StatObjectAdditionalOptions>,
+    ) -> Result<Option<HeadObjectOutput>, Error> {
+        Minio::stat_object(self, bucket_name, object_name, additional_options).await
+    }
+
+    async fn delete_object(&self, bucket_name: &str, object_name: &str) -> Result<(), Error> {
+        Minio::delete_object(self, bucket_name, object_name).await
+    }
+
+    async fn start_multipart_upload(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+    ) -> Result<String, Error> {
+        util::start_multipart_upload(
+            &self.client,
+            bucket_name,
+            object_name,
+            None,
+            &util::ContentHeaders::default(),
+            &SseHeaders::default(),
+            None,
+        )
+        .await
+    }
+
+    async fn upload_part(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        upload_id: &str,
+        part_number: usize,
+        bytes: Vec<u8>,
+    ) -> Result<ETag, Error> {
+        let e_tag = util::upload_part(
+            &self.client,
+            bucket_name,
+            object_name,
+            upload_id,
+            part_number,
+            bytes,
+            util::PartHeaders {
+                sse: &SseHeaders::default(),
+                checksum: &ChecksumHeaders::default(),
+            },
+        )
+        .await?;
+
+        Ok(ETag { e_tag, part_number })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        upload_id: &str,
+        e_tags: Vec<ETag>,
+    ) -> Result<(), Error> {
+        util::complete_multipart_upload(
+            &self.client,
+            e_tags,
+            bucket_name,
+            object_name,
+            upload_id,
+            &PartChecksums::default(),
+            &SseHeaders::default(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        upload_id: &str,
+    ) -> Result<(), Error> {
+        util::abort_multipart_upload(&self.client, bucket_name, object_name, upload_id).await
+    }
+
+    async fn get_object_presigned(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        presigned_expiry_secs: u64,
+        additional_options: Option<GetObjectPresignedAdditionalOptions>,
+    ) -> Result<Option<PresignedUrl>, Error> {
+        Minio::get_object_presigned(
+            self,
+            bucket_name,
+            object_name,
+            presigned_expiry_secs,
+            additional_options,
+        )
+        .await
+    }
+
+    async fn upload_object_presigned(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        presigned_expiry_secs: u64,
+        additional_options: Option<UploadObjectPresignedAdditionalOptions>,
+    ) -> Result<PresignedUrl, Error> {
+        Minio::upload_object_presigned(
+            self,
+            bucket_name,
+            object_name,
+            presigned_expiry_secs,
+            additional_options,
+        )
+        .await
+    }
+
+    async fn delete_object_presigned(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        presigned_expiry_secs: u64,
+    ) -> Result<PresignedUrl, Error> {
+        Minio::delete_object_presigned(self, bucket_name, object_name, presigned_expiry_secs).await
+    }
+}
@@ -0,0 +1,280 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{
+    connection::DEFAULT_REGION,
+    credentials::CredentialsProvider,
+    http::{send, xml_value, Endpoint, TlsOptions},
+    presign::uri_encode,
+};
+use crate::error::Error;
+use aws_sdk_s3::{
+    config::Credentials,
+    primitives::{DateTime, DateTimeFormat},
+};
+use hyper::Method;
+use std::{path::PathBuf, time::SystemTime};
+
+/// API version of the STS requests
+const STS_VERSION: &str = "2011-06-15";
+
+/// The identity exchanged with the MinIO STS API for temporary credentials
+#[derive(Clone)]
+pub enum StsIdentity {
+    /// `AssumeRole`, signed with the keys of a MinIO user
+    AssumeRole { credentials: Credentials },
+    /// `AssumeRoleWithWebIdentity`, with an OpenID Connect token
+    WebIdentity { token: String },
+    /// `AssumeRoleWithWebIdentity`, with an OpenID Connect token re-read from
+    /// `path` on every request, I.E. a Kubernetes projected service account token
+    WebIdentityTokenFile { path: PathBuf },
+    /// `AssumeRoleWithLDAPIdentity`, with the credentials of an LDAP user
+    Ldap { username: String, password: String },
+    /// `AssumeRoleWithCertificate`, authenticated by the PEM encoded TLS
+    /// client certificate and its private key, requiring a https url
+    Certificate {
+        certificate_pem: Vec<u8>,
+        private_key_pem: Vec<u8>,
+    },
+}
+
+impl std::fmt::Debug for StsIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AssumeRole { credentials } => f
+                .debug_struct("AssumeRole")
+                .field("credentials", credentials)
+                .finish(),
+            Self::WebIdentity { .. } => f
+                .debug_struct("WebIdentity")
+                .field("token", &"** redacted **")
+                .finish(),
+            Self::WebIdentityTokenFile { path } => f
+                .debug_struct("WebIdentityTokenFile")
+                .field("path", path)
+                .finish(),
+            Self::Ldap { username, .. } => f
+                .debug_struct("Ldap")
+                .field("username", username)
+                .field("password", &"** redacted **")
+                .finish(),
+            Self::Certificate { .. } => f.debug_struct("Certificate").finish_non_exhaustive(),
+        }
+    }
+}
+
+impl StsIdentity {
+    fn action(&self) -> &'static str {
+        match self {
+            Self::AssumeRole { .. } => "AssumeRole",
+            Self::WebIdentity { .. } | Self::WebIdentityTokenFile { .. } => {
+                "AssumeRoleWithWebIdentity"
+            }
+            Self::Ldap { .. } => "AssumeRoleWithLDAPIdentity",
+            Self::Certificate { .. } => "AssumeRoleWithCertificate",
+        }
+    }
+}
+
+/// Options of a STS request
+#[derive(Debug, Clone, Default)]
+pub struct StsOptions {
+    /// Lifetime of the credentials, from 900 seconds up to the maximum
+    /// of the server, which defaults to 3600 seconds
+    pub duration_secs: Option<u32>,
+    /// An IAM policy JSON further restricting the credentials
+    pub policy: Option<String>,
+    /// The role to assume, for servers with multiple OpenID providers
+    pub role_arn: Option<String>,
+    /// The region `AssumeRole` is signed for, `DEFAULT_REGION` if `None`
+    pub region: Option<String>,
+    /// PEM encoded CA certificates trusted along with the native roots,
+    /// I.E. of a self-signed MinIO
+    pub ca_certificates_pem: Option<Vec<u8>>,
+}
+
+/// Exchanges `identity` for temporary credentials with the STS API
+/// of the MinIO server at the S3 API Url `url`
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let identity = StsIdentity::Ldap {
+///     username: "shark-job".to_string(),
+///     password: ldap_password,
+/// };
+///
+/// let credentials: Credentials = assume_role(
+///     "https://minio.example.com",
+///     &identity,
+///     &StsOptions {
+///         duration_secs: Some(900),
+///         ..Default::default()
+///     },
+/// ).await?;
+/// ```
+pub async fn assume_role(
+    url: &str,
+    identity: &StsIdentity,
+    options: &StsOptions,
+) -> Result<Credentials, Error> {
+    let endpoint = Endpoint::parse(url)?.with_tls(TlsOptions {
+        ca_certificates_pem: options.ca_certificates_pem.clone(),
+        client_certificate: match identity {
+            StsIdentity::Certificate {
+                certificate_pem,
+                private_key_pem,
+            } => Some((certificate_pem.clone(), private_key_pem.clone())),
+            _ => None,
+        },
+    });
+    let action = identity.action();
+
+    let mut params = vec![
+        ("Action", action.to_string()),
+        ("Version", STS_VERSION.to_string()),
+    ];
+
+    if let Some(duration_secs) = options.duration_secs {
+        params.push(("DurationSeconds", duration_secs.to_string()));
+    }
+    if let Some(policy) = &options.policy {
+        params.push(("Policy", policy.clone()));
+    }
+    if let Some(role_arn) = &options.role_arn {
+        params.push(("RoleArn", role_arn.clone()));
+    }
+
+    match identity {
+        StsIdentity::WebIdentity { token } => {
+            params.push(("WebIdentityToken", token.clone()));
+        }
+        StsIdentity::WebIdentityTokenFile { path } => {
+            let token = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|err| Error::StdIo(err.kind()))?;
+
+            params.push(("WebIdentityToken", token.trim().to_string()));
+        }
+        StsIdentity::Ldap { username, password } => {
+            params.push(("LDAPUsername", username.clone()));
+            params.push(("LDAPPassword", password.clone()));
+        }
+        StsIdentity::Certificate { .. } if !endpoint.https => {
+            return Err(Error::internal(
+                "AssumeRoleWithCertificate requires a https url",
+            ));
+        }
+        StsIdentity::AssumeRole { .. } | StsIdentity::Certificate { .. } => {}
+    }
+
+    let body = params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, uri_encode(value, true)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let region = options.region.as_deref().unwrap_or(DEFAULT_REGION);
+    let signer = match identity {
+        StsIdentity::AssumeRole { credentials } => Some((credentials, region, "sts")),
+        _ => None,
+    };
+
+    let (status, _, response) = send(
+        &endpoint,
+        signer,
+        Method::POST,
+        "/",
+        vec![(
+            "content-type".to_string(),
+            "application/x-www-form-urlencoded".to_string(),
+        )],
+        body.into_bytes(),
+    )
+    .await?;
+    let response = String::from_utf8_lossy(&response);
+
+    if !status.is_success() {
+        return Err(Error::SdkError(format!(
+            "{} failed with {}: {}: {}",
+            action,
+            status,
+            xml_value(&response, "Code").unwrap_or_default(),
+            xml_value(&response, "Message").unwrap_or_default()
+        )));
+    }
+
+    parse_credentials(&response)
+}
+
+/// A `CredentialsProvider` exchanging an identity for temporary credentials
+/// with the MinIO STS API, so `Minio` assumes the role again once they expire
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let minio = Minio::new(url).await;
+///
+/// minio.set_credentials_provider(
+///     StsCredentials::new(url, StsIdentity::WebIdentityTokenFile {
+///         path: "/var/run/secrets/tokens/minio".into(),
+///     })
+///     .with_options(StsOptions {
+///         duration_secs: Some(900),
+///         ..Default::default()
+///     }),
+/// )?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct StsCredentials {
+    url: String,
+    identity: StsIdentity,
+    options: StsOptions,
+}
+
+impl StsCredentials {
+    pub fn new(url: &str, identity: StsIdentity) -> Self {
+        Self {
+            url: url.to_string(),
+            identity,
+            options: StsOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: StsOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl CredentialsProvider for StsCredentials {
+    async fn provide_credentials(&self) -> Result<Credentials, Error> {
+        assume_role(&self.url, &self.identity, &self.options).await
+    }
+}
+
+/// Parses the `Credentials` of a STS response
+fn parse_credentials(xml: &str) -> Result<Credentials, Error> {
+    let value = |tag: &str| {
+        xml_value(xml, tag).ok_or(Error::Internal(format!("STS response has no {}", tag)))
+    };
+
+    let expiration = value("Expiration")?;
+    let expiry = DateTime::from_str(&expiration, DateTimeFormat::DateTime)
+        .ok()
+        .and_then(|expiry| SystemTime::try_from(expiry).ok())
+        .ok_or(Error::Internal(format!(
+            "STS response has an invalid Expiration {}",
+            expiration
+        )))?;
+
+    Ok(Credentials::new(
+        value("AccessKeyId")?,
+        value("SecretAccessKey")?,
+        xml_value(xml, "SessionToken"),
+        Some(expiry),
+        "MinioSts",
+    ))
+}
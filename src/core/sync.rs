@@ -0,0 +1,700 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{
+    bucket::list_objects_with_prefix,
+    delete::delete_object,
+    get::{get_object, GetObjectAdditionalOptions},
+    upload::upload_object::{upload_object, UploadObjectAdditionalOptions},
+};
+use crate::error::Error;
+use aws_sdk_s3::{types::Object, Client};
+use glob::{MatchOptions, Pattern};
+use md5::{Digest, Md5};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, UNIX_EPOCH},
+};
+use tokio::{
+    fs,
+    io::{self, AsyncReadExt, AsyncWriteExt},
+    sync::{mpsc::UnboundedSender, Semaphore},
+    task::JoinSet,
+};
+
+/// How a local file is compared with an object of the same
+/// size to decide whether it changed
+///
+/// - `Checksum`: The MD5 of the file against the `ETag` of the object,
+///   falling back to `ModifiedTime` for multipart uploads whose `ETag`
+///   is not an MD5. *Note*: The `ETag` of objects encrypted via SSE-KMS
+///   or SSE-C is not their MD5 either, use `ModifiedTime` for those.
+///
+/// - `ModifiedTime`: The destination changed if the source is newer,
+///   I.E. the file when uploading, and the object when downloading.
+///
+/// - `Size`: Files of the same size never changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncCompare {
+    #[default]
+    Checksum,
+    ModifiedTime,
+    Size,
+}
+
+/// Options for `sync_dir_to_prefix` and `sync_prefix_to_dir`
+///
+/// `include` and `exclude` are glob patterns matched against paths
+/// relative to the directory and prefix, I.E. `images/*.jpg`, where
+/// `*` does not match `/` but `**` does. Paths are synced if they match
+/// any of `include` (or it is empty), and none of `exclude`.
+///
+/// `delete_extras` deletes the destination files or objects with
+/// no matching source, and `dry_run` only reports what would be synced.
+///
+/// `progress` is sent a `SyncProgress` after every completed transfer.
+///
+/// Default `concurrency` is `4`, and cannot be lower than `1`
+/// *(Overwrites to `1` if lower)*
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let options = SyncOptions {
+///     exclude: vec!["**/*.tmp".to_string()],
+///     delete_extras: true,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SyncOptions {
+    pub compare: SyncCompare,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub delete_extras: bool,
+    pub dry_run: bool,
+    pub concurrency: Option<usize>,
+    pub progress: Option<UnboundedSender<SyncProgress>>,
+}
+
+/// Progress of a sync, sent after a file or object by its relative
+/// `path` of `bytes` completed transferring, as the `completed`
+/// of the `total` transfers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncProgress {
+    pub path: String,
+    pub bytes: usize,
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Summary of a sync, with the sorted paths relative to the
+/// directory and prefix that were transferred and deleted
+///
+/// With `dry_run`, the ones that would have been.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub transferred: Vec<String>,
+    pub deleted: Vec<String>,
+    pub unchanged: usize,
+    pub bytes_transferred: usize,
+}
+
+/// The `include` and `exclude` globs of `SyncOptions`
+pub(crate) struct SyncFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl SyncFilter {
+    pub(crate) fn new(include: &[String], exclude: &[String]) -> Result<Self, Error> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    Pattern::new(pattern).map_err(|err| {
+                        Error::Internal(format!("Invalid glob pattern {}: {}", pattern, err))
+                    })
+                })
+                .collect::<Result<Vec<Pattern>, Error>>()
+        };
+
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+
+        (self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| pattern.matches_with(path, options)))
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| pattern.matches_with(path, options))
+    }
+}
+
+/// A file found walking a local directory
+#[derive(Debug, Clone)]
+pub(crate) struct LocalFile {
+    pub(crate) path: PathBuf,
+    pub(crate) size: u64,
+    pub(crate) modified_secs: i64,
+}
+
+/// Returns the files under `root` by their `/` separated path relative to it,
+/// following symlinks to files but not to directories
+pub(crate) async fn walk_dir(root: &Path) -> Result<BTreeMap<String, LocalFile>, Error> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![(root.to_path_buf(), String::new())];
+
+    while let Some((dir, relative_dir)) = dirs.pop() {
+        let mut entries = fs::read_dir(&dir)
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?
+        {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_str().ok_or(Error::Internal(format!(
+                "Path {:?} is not valid UTF-8",
+                entry.path()
+            )))?;
+            let relative_path = format!("{}{}", relative_dir, file_name);
+
+            let file_type = entry
+                .file_type()
+                .await
+                .map_err(|err| Error::StdIo(err.kind()))?;
+
+            if file_type.is_dir() {
+                dirs.push((entry.path(), format!("{}/", relative_path)));
+                continue;
+            }
+
+            let metadata = fs::metadata(entry.path())
+                .await
+                .map_err(|err| Error::StdIo(err.kind()))?;
+
+            if metadata.is_file() {
+                let modified_secs = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                    .map(|duration| duration.as_secs() as i64)
+                    .unwrap_or_default();
+
+                files.insert(
+                    relative_path,
+                    LocalFile {
+                        path: entry.path(),
+                        size: metadata.len(),
+                        modified_secs,
+                    },
+                );
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Returns `prefix` ending with a `/`, unless empty
+pub(crate) fn normalize_prefix(prefix: &str) -> String {
+    if prefix.is_empty() || prefix.ends_with('/') {
+        prefix.to_string()
+    } else {
+        format!("{}/", prefix)
+    }
+}
+
+/// Returns the objects under `prefix` matching the `filter`
+/// by their key relative to it
+pub(crate) async fn list_remote_objects(
+    client: &Client,
+    bucket_name: &str,
+    prefix: &str,
+    filter: &SyncFilter,
+) -> Result<BTreeMap<String, Object>, Error> {
+    Ok(list_objects_with_prefix(client, bucket_name, prefix)
+        .await?
+        .into_iter()
+        .filter_map(|object| {
+            let relative_path = object.key()?.strip_prefix(prefix)?.to_string();
+
+            (!relative_path.is_empty()
+                && !relative_path.ends_with('/')
+                && filter.matches(&relative_path))
+            .then_some((relative_path, object))
+        })
+        .collect())
+}
+
+/// Returns the hex MD5 of the file at `path`
+async fn file_md5(path: &Path) -> Result<String, Error> {
+    let mut file = fs::File::open(path)
+        .await
+        .map_err(|err| Error::StdIo(err.kind()))?;
+
+    let mut md5 = Md5::new();
+    let mut buffer = vec![0; 100_000];
+
+    loop {
+        let bytes_read = file
+            .read(&mut buffer[..])
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+
+        if bytes_read == 0 {
+            break;
+        }
+
+        md5.update(&buffer[..bytes_read]);
+    }
+
+    Ok(md5
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// The side of a sync being copied from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SyncSource {
+    Local,
+    Remote,
+}
+
+/// Returns true if the `local_file` differs from the `object` by `compare`,
+/// where `ModifiedTime` only considers the `source` being newer a change
+async fn is_changed(
+    compare: SyncCompare,
+    source: SyncSource,
+    local_file: &LocalFile,
+    object: &Object,
+) -> Result<bool, Error> {
+    if object.size() != Some(local_file.size as i64) {
+        return Ok(true);
+    }
+
+    let is_source_newer = || match object.last_modified() {
+        Some(last_modified) => match source {
+            SyncSource::Local => local_file.modified_secs > last_modified.secs(),
+            SyncSource::Remote => last_modified.secs() > local_file.modified_secs,
+        },
+        None => true,
+    };
+
+    match compare {
+        SyncCompare::Size => Ok(false),
+        SyncCompare::ModifiedTime => Ok(is_source_newer()),
+        SyncCompare::Checksum => match object.e_tag().map(|e_tag| e_tag.trim_matches('"')) {
+            Some(e_tag) if !e_tag.contains('-') => Ok(file_md5(&local_file.path).await? != e_tag),
+            _ => Ok(is_source_newer()),
+        },
+    }
+}
+
+/// Sends the `SyncProgress` of every completed transfer, if requested
+#[derive(Clone)]
+struct ProgressReporter {
+    sender: Option<UnboundedSender<SyncProgress>>,
+    completed: Arc<AtomicUsize>,
+    total: usize,
+}
+
+impl ProgressReporter {
+    fn new(sender: Option<UnboundedSender<SyncProgress>>, total: usize) -> Self {
+        Self {
+            sender,
+            completed: Arc::new(AtomicUsize::new(0)),
+            total,
+        }
+    }
+
+    fn report(&self, path: String, bytes: usize) {
+        if let Some(ref sender) = self.sender {
+            // The receiver being dropped only means nobody is watching anymore
+            let _ = sender.send(SyncProgress {
+                path,
+                bytes,
+                completed: self.completed.fetch_add(1, Ordering::SeqCst) + 1,
+                total: self.total,
+            });
+        }
+    }
+}
+
+/// Uploads the files of `local_dir` under `prefix` in the bucket by
+/// `bucket_name`, skipping the ones that did not change by the
+/// `SyncCompare` of the `SyncOptions`
+///
+/// Returns a `SyncReport` of the files uploaded and objects deleted
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// let report: SyncReport = sync_dir_to_prefix(
+///     client,
+///     Path::new("./shark_images"),
+///     "sharks",
+///     "images/",
+///     SyncOptions::default(),
+/// ).await?;
+/// ```
+pub async fn sync_dir_to_prefix(
+    client: Arc<Client>,
+    local_dir: &Path,
+    bucket_name: &str,
+    prefix: &str,
+    SyncOptions {
+        compare,
+        include,
+        exclude,
+        delete_extras,
+        dry_run,
+        concurrency,
+        progress,
+    }: SyncOptions,
+) -> Result<SyncReport, Error> {
+    let filter = SyncFilter::new(&include, &exclude)?;
+    let prefix = normalize_prefix(prefix);
+
+    let local_files = walk_dir(local_dir).await?;
+    let mut remote_objects = list_remote_objects(&client, bucket_name, &prefix, &filter).await?;
+
+    let mut report = SyncReport::default();
+    let mut uploads = vec![];
+
+    for (relative_path, local_file) in local_files {
+        if !filter.matches(&relative_path) {
+            continue;
+        }
+
+        let changed = match remote_objects.remove(&relative_path) {
+            Some(object) => is_changed(compare, SyncSource::Local, &local_file, &object).await?,
+            None => true,
+        };
+
+        if changed {
+            report.bytes_transferred += local_file.size as usize;
+            report.transferred.push(relative_path.clone());
+            uploads.push((relative_path, local_file));
+        } else {
+            report.unchanged += 1;
+        }
+    }
+
+    if delete_extras {
+        report.deleted = remote_objects.into_keys().collect();
+    }
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    let semaphore = Arc::new(Semaphore::new(concurrency.unwrap_or(4).max(1)));
+    let progress = ProgressReporter::new(progress, uploads.len());
+    let mut join_set = JoinSet::new();
+
+    for (relative_path, local_file) in uploads {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        let progress = progress.clone();
+        let bucket_name = bucket_name.to_string();
+        let object_name = format!("{}{}", prefix, relative_path);
+
+        join_set.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .map_err(|_| Error::AcquireError)?;
+
+            let file = fs::File::open(&local_file.path)
+                .await
+                .map_err(|err| Error::StdIo(err.kind()))?;
+
+            let bytes = upload_object(
+                client,
+                &bucket_name,
+                &object_name,
+                file,
+                UploadObjectAdditionalOptions::default(),
+            )
+            .await?;
+
+            progress.report(relative_path, bytes);
+
+            Ok(())
+        });
+    }
+
+    // Stop the remaining uploads once one fails
+    while let Some(result) = join_set.join_next().await {
+        if let Err(err) = result
+            .map_err(|_| Error::JoinError)
+            .and_then(|result| result)
+        {
+            join_set.abort_all();
+            return Err(err);
+        }
+    }
+
+    for relative_path in report.deleted.iter() {
+        delete_object(
+            &client,
+            bucket_name,
+            &format!("{}{}", prefix, relative_path),
+        )
+        .await?;
+    }
+
+    Ok(report)
+}
+
+/// Returns the path under `local_dir` of an object by its `/` separated
+/// `relative_path`, refusing keys that would escape `local_dir`
+fn local_path(local_dir: &Path, relative_path: &str) -> Result<PathBuf, Error> {
+    let mut path = local_dir.to_path_buf();
+
+    for component in relative_path.split('/') {
+        if component.is_empty() || component == "." || component == ".." {
+            return Err(Error::Internal(format!(
+                "Object key {} can not be written under a directory",
+                relative_path
+            )));
+        }
+
+        path.push(component);
+    }
+
+    Ok(path)
+}
+
+/// The temporary file of a download, removed when dropped unless the
+/// download was renamed into place, including when it is aborted
+struct TempFile(Option<PathBuf>);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if let Some(path) = self.0.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Downloads an object to `path` via a temporary file in the same
+/// directory, which is renamed over `path` once it is complete,
+/// setting its modified time to `modified_secs`
+async fn download_object(
+    client: &Client,
+    bucket_name: &str,
+    object_name: &str,
+    path: &Path,
+    modified_secs: Option<i64>,
+) -> Result<usize, Error> {
+    let (parent, file_name) = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(file_name)) => (parent, file_name.to_string_lossy()),
+        _ => return Err(Error::internal("Download path has no parent directory")),
+    };
+
+    fs::create_dir_all(parent)
+        .await
+        .map_err(|err| Error::StdIo(err.kind()))?;
+
+    #[cfg(feature = "compression")]
+    let get_options = GetObjectAdditionalOptions {
+        raw: true,
+        ..Default::default()
+    };
+    #[cfg(not(feature = "compression"))]
+    let get_options = GetObjectAdditionalOptions::default();
+
+    let stream = get_object(client, bucket_name, object_name, get_options)
+        .await?
+        .ok_or(Error::Internal(format!(
+            "Object {} no longer exists",
+            object_name
+        )))?;
+    tokio::pin!(stream);
+
+    let temp_path = parent.join(format!(".{}.minior-tmp", file_name));
+    let mut temp_file = TempFile(Some(temp_path.clone()));
+
+    let result = async {
+        let mut file = fs::File::create(&temp_path).await?;
+        let bytes = io::copy(&mut stream, &mut file).await?;
+        file.flush().await?;
+
+        if let Some(modified_secs) = modified_secs {
+            let file = file.into_std().await;
+            file.set_modified(UNIX_EPOCH + Duration::from_secs(modified_secs.max(0) as u64))?;
+        }
+
+        fs::rename(&temp_path, path).await?;
+
+        Ok(bytes as usize)
+    }
+    .await;
+
+    if result.is_ok() {
+        temp_file.0 = None;
+    }
+
+    result.map_err(|err: std::io::Error| Error::StdIo(err.kind()))
+}
+
+/// Downloads the objects under `prefix` in the bucket by `bucket_name`
+/// to `local_dir`, skipping the ones that did not change by the
+/// `SyncCompare` of the `SyncOptions`
+///
+/// Objects are written atomically via a temporary file renamed over
+/// the destination, with their `last_modified` as modified time.
+///
+/// Returns a `SyncReport` of the objects downloaded and files deleted
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// let report: SyncReport = sync_prefix_to_dir(
+///     client,
+///     "sharks",
+///     "images/",
+///     Path::new("./shark_images"),
+///     SyncOptions::default(),
+/// ).await?;
+/// ```
+pub async fn sync_prefix_to_dir(
+    client: Arc<Client>,
+    bucket_name: &str,
+    prefix: &str,
+    local_dir: &Path,
+    SyncOptions {
+        compare,
+        include,
+        exclude,
+        delete_extras,
+        dry_run,
+        concurrency,
+        progress,
+    }: SyncOptions,
+) -> Result<SyncReport, Error> {
+    let filter = SyncFilter::new(&include, &exclude)?;
+    let prefix = normalize_prefix(prefix);
+
+    let remote_objects = list_remote_objects(&client, bucket_name, &prefix, &filter).await?;
+    let mut local_files = match fs::metadata(local_dir).await {
+        Ok(_) => walk_dir(local_dir).await?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+        Err(err) => return Err(Error::StdIo(err.kind())),
+    };
+    local_files.retain(|relative_path, _| filter.matches(relative_path));
+
+    let mut report = SyncReport::default();
+    let mut downloads = vec![];
+
+    for (relative_path, object) in remote_objects {
+        let path = local_path(local_dir, &relative_path)?;
+
+        let changed = match local_files.remove(&relative_path) {
+            Some(local_file) => {
+                is_changed(compare, SyncSource::Remote, &local_file, &object).await?
+            }
+            None => true,
+        };
+
+        if changed {
+            report.bytes_transferred += object.size().unwrap_or_default() as usize;
+            report.transferred.push(relative_path.clone());
+            downloads.push((relative_path, path, object));
+        } else {
+            report.unchanged += 1;
+        }
+    }
+
+    if delete_extras {
+        report.deleted = local_files.keys().cloned().collect();
+    }
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    let semaphore = Arc::new(Semaphore::new(concurrency.unwrap_or(4).max(1)));
+    let progress = ProgressReporter::new(progress, downloads.len());
+    let mut join_set = JoinSet::new();
+
+    for (relative_path, path, object) in downloads {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        let progress = progress.clone();
+        let bucket_name = bucket_name.to_string();
+        let object_name = format!("{}{}", prefix, relative_path);
+
+        join_set.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .map_err(|_| Error::AcquireError)?;
+
+            let bytes = download_object(
+                &client,
+                &bucket_name,
+                &object_name,
+                &path,
+                object
+                    .last_modified()
+                    .map(|last_modified| last_modified.secs()),
+            )
+            .await?;
+
+            progress.report(relative_path, bytes);
+
+            Ok(())
+        });
+    }
+
+    // Stop the remaining downloads once one fails
+    while let Some(result) = join_set.join_next().await {
+        if let Err(err) = result
+            .map_err(|_| Error::JoinError)
+            .and_then(|result| result)
+        {
+            join_set.abort_all();
+            return Err(err);
+        }
+    }
+
+    for relative_path in report.deleted.iter() {
+        fs::remove_file(&local_files[relative_path].path)
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,63 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use aws_sdk_s3::{
+    config::{
+        interceptors::FinalizerInterceptorContextRef, ConfigBag, Intercept, RuntimeComponents,
+    },
+    error::BoxError,
+};
+use aws_smithy_runtime_api::client::orchestrator::Metadata;
+
+/// An interceptor emitting an event for every request sent by the SDK,
+/// with its operation, status and the request ids of S3, inside the
+/// span of the `Minio` method that sent it
+///
+/// Attached to the client of `Minio::new`, and can be attached to a
+/// client built otherwise. Urls and headers are never recorded, so
+/// neither credentials nor presigned query strings are logged.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let config = aws_sdk_s3::config::Builder::from(&sdk_config)
+///     .interceptor(TracingInterceptor)
+///     .build();
+///
+/// let client = Client::from_conf(config);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingInterceptor;
+
+impl Intercept for TracingInterceptor {
+    fn name(&self) -> &'static str {
+        "TracingInterceptor"
+    }
+
+    fn read_after_attempt(
+        &self,
+        context: &FinalizerInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let operation = cfg.load::<Metadata>().map(Metadata::name);
+
+        match context.response() {
+            Some(response) => {
+                let header = |name: &str| response.headers().get(name);
+
+                tracing::debug!(
+                    operation,
+                    status = response.status().as_u16(),
+                    request_id = header("x-amz-request-id"),
+                    extended_request_id = header("x-amz-id-2"),
+                    "S3 request completed"
+                );
+            }
+            None => tracing::debug!(operation, "S3 request failed without a response"),
+        }
+
+        Ok(())
+    }
+}
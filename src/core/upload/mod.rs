@@ -1,5 +1,8 @@
 pub mod upload_object;
 pub mod upload_object_multi;
 pub mod upload_object_multi_presigned;
+pub mod upload_object_multi_presigned_session;
 pub mod upload_object_presigned;
-mod util;
+#[cfg(feature = "post_policy")]
+pub mod upload_object_presigned_post;
+pub(crate) mod util;
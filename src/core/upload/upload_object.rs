@@ -2,11 +2,21 @@
 // License: MIT (See `LICENSE.md`)
 
 use super::util::*;
-use crate::{error::Error, ETag};
-use aws_sdk_s3::Client;
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc,
+use crate::{
+    core::{
+        checksum::{ChecksumHeaders, PartChecksums},
+        sse::SseHeaders,
+    },
+    error::Error,
+    ETag,
+};
+use aws_sdk_s3::{primitives::DateTime, Client};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 use tokio::{
     io::{AsyncRead, AsyncReadExt},
@@ -14,6 +24,13 @@ use tokio::{
     task::JoinHandle,
 };
 
+#[cfg(feature = "checksum")]
+use crate::core::checksum::Checksum;
+#[cfg(feature = "compression")]
+use crate::core::compression::{CompressingReader, Compression, METADATA_COMPRESSION};
+#[cfg(feature = "sse")]
+use crate::core::sse::Encryption;
+
 struct SpawnUploadFutureOptions {
     bytes: Vec<u8>,
     client: Arc<Client>,
@@ -22,11 +39,14 @@ struct SpawnUploadFutureOptions {
     upload_id: String,
     object_name: String,
     bucket_name: String,
+    sse: SseHeaders,
+    checksum: ChecksumHeaders,
 }
 
 struct UploadPartResult {
     part_number: usize,
     e_tag_result: Result<String, Error>,
+    checksum: ChecksumHeaders,
 }
 
 /// Spawn a JoinHandle uploading bytes
@@ -39,9 +59,21 @@ async fn spawn_upload_future(
         upload_id,
         object_name,
         bucket_name,
+        sse,
+        checksum,
     }: SpawnUploadFutureOptions,
 ) -> JoinHandle<Result<UploadPartResult, Error>> {
-    tokio::spawn(async move {
+    #[cfg(feature = "tracing")]
+    let span = tracing::info_span!(
+        "upload_part",
+        bucket = bucket_name,
+        key = object_name,
+        upload_id,
+        part_number = tracing::field::Empty,
+        bytes = bytes.len(),
+    );
+
+    let future = async move {
         let _ = semaphore
             .clone()
             .acquire_owned()
@@ -50,6 +82,9 @@ async fn spawn_upload_future(
 
         let part_number = counter.fetch_add(1, Ordering::SeqCst);
 
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("part_number", part_number);
+
         let result = upload_part(
             &client,
             &bucket_name,
@@ -57,24 +92,68 @@ async fn spawn_upload_future(
             &upload_id,
             part_number,
             bytes,
+            PartHeaders {
+                sse: &sse,
+                checksum: &checksum,
+            },
         )
         .await;
 
+        #[cfg(feature = "tracing")]
+        if let Err(ref err) = result {
+            tracing::error!(error = %err, "Part upload failed");
+        }
+
         Ok(UploadPartResult {
             part_number,
             e_tag_result: result,
+            checksum,
         })
-    })
+    };
+
+    #[cfg(feature = "tracing")]
+    let future = tracing::Instrument::instrument(future, span);
+
+    tokio::spawn(future)
 }
 
 /// Additional options for `upload_object` to
 /// control the `buffer_size`, `data_part_size`,
-/// and the `semaphore_permits`
+/// and the `semaphore_permits`, and to set the
+/// user `metadata` and the standard headers of
+/// the object
+///
+/// With the `sse` feature, `encryption` sets the
+/// server-side `Encryption` of the object.
+///
+/// With the `checksum` feature, `checksum` sends the
+/// `Checksum` of every part, verifying the ones the
+/// server computed and the composite checksum of a
+/// multipart upload. As the composite checksum is only
+/// known once the upload is completed, the object is
+/// deleted if the server returns one that does not
+/// match.
+///
+/// With the `compression` feature, `compression`
+/// compresses the stream while uploading, marking
+/// the object to be decompressed by `get_object`.
 #[derive(Default)]
 pub struct UploadObjectAdditionalOptions {
     pub buffer_size: Option<usize>,
     pub data_part_size: Option<usize>,
     pub semaphore_permits: Option<usize>,
+    pub metadata: Option<HashMap<String, String>>,
+    pub content_type: Option<String>,
+    pub content_encoding: Option<String>,
+    pub cache_control: Option<String>,
+    pub content_disposition: Option<String>,
+    pub expires: Option<DateTime>,
+    #[cfg(feature = "sse")]
+    pub encryption: Option<Encryption>,
+    #[cfg(feature = "checksum")]
+    pub checksum: Option<Checksum>,
+    #[cfg(feature = "compression")]
+    pub compression: Option<Compression>,
 }
 
 /// Upload a object named `object_name` to the bucket named `bucket_name` via
@@ -92,7 +171,8 @@ pub struct UploadObjectAdditionalOptions {
 /// Will automatically convert to a multipart upload if over `data_part_size`
 /// bytes
 ///
-/// Returns the total amount of bytes uploaded
+/// Returns the total amount of bytes uploaded, I.E. after compression
+/// with the `compression` feature
 ///
 /// ---
 /// Example Usage:
@@ -114,11 +194,23 @@ pub async fn upload_object<S>(
     client: Arc<Client>,
     bucket_name: &str,
     object_name: &str,
-    mut stream: S,
+    stream: S,
     UploadObjectAdditionalOptions {
         buffer_size,
         data_part_size,
         semaphore_permits,
+        metadata,
+        content_type,
+        content_encoding,
+        cache_control,
+        content_disposition,
+        expires,
+        #[cfg(feature = "sse")]
+        encryption,
+        #[cfg(feature = "checksum")]
+        checksum,
+        #[cfg(feature = "compression")]
+        compression,
     }: UploadObjectAdditionalOptions,
 ) -> Result<usize, Error>
 where
@@ -131,6 +223,51 @@ where
     let data_part_size = data_part_size.unwrap_or(5_242_880).max(5_242_880);
     let semaphore_permits = semaphore_permits.unwrap_or(4).max(1);
 
+    let content = ContentHeaders {
+        content_type,
+        content_encoding,
+        cache_control,
+        content_disposition,
+        expires,
+    };
+
+    #[cfg(feature = "sse")]
+    let sse = SseHeaders::from(encryption.as_ref());
+    #[cfg(not(feature = "sse"))]
+    let sse = SseHeaders::default();
+
+    #[cfg(feature = "checksum")]
+    let checksum_headers = |bytes: &[u8]| {
+        checksum
+            .map(|checksum| checksum.headers(bytes))
+            .unwrap_or_default()
+    };
+    #[cfg(not(feature = "checksum"))]
+    let checksum_headers = |_: &[u8]| ChecksumHeaders::default();
+
+    #[cfg(feature = "checksum")]
+    let checksum_algorithm = checksum.and_then(Checksum::algorithm);
+    #[cfg(not(feature = "checksum"))]
+    let checksum_algorithm = None;
+
+    #[cfg(feature = "compression")]
+    let metadata = match compression {
+        Some(compression) => {
+            let mut metadata = metadata.unwrap_or_default();
+            metadata.insert(
+                METADATA_COMPRESSION.to_string(),
+                compression.as_str().to_string(),
+            );
+
+            Some(metadata)
+        }
+        None => metadata,
+    };
+    #[cfg(feature = "compression")]
+    let mut stream = CompressingReader::new(stream, compression);
+    #[cfg(not(feature = "compression"))]
+    let mut stream = stream;
+
     let mut upload_id = None;
 
     let semaphore = Arc::new(Semaphore::new(semaphore_permits));
@@ -152,7 +289,19 @@ where
 
         if bytes_read == 0 {
             if join_handles.is_empty() && data_part_buffer.len() < data_part_size {
-                upload(&client, &bucket_name, &object_name, data_part_buffer).await?;
+                let checksum = checksum_headers(&data_part_buffer);
+
+                upload(
+                    &client,
+                    &bucket_name,
+                    &object_name,
+                    data_part_buffer,
+                    metadata,
+                    &content,
+                    &sse,
+                    &checksum,
+                )
+                .await?;
 
                 return Ok(total_bytes);
             }
@@ -165,8 +314,18 @@ where
 
         if data_part_buffer.len() >= data_part_size {
             if upload_id.is_none() {
-                upload_id =
-                    Some(start_multipart_upload(&client, &bucket_name, &object_name).await?);
+                upload_id = Some(
+                    start_multipart_upload(
+                        &client,
+                        &bucket_name,
+                        &object_name,
+                        metadata.clone(),
+                        &content,
+                        &sse,
+                        checksum_algorithm.clone(),
+                    )
+                    .await?,
+                );
             }
 
             if let Some(ref upload_id) = upload_id {
@@ -175,13 +334,15 @@ where
 
                 join_handles.push(
                     spawn_upload_future(SpawnUploadFutureOptions {
-                        bytes,
                         client: client.clone(),
                         counter: counter.clone(),
                         semaphore: semaphore.clone(),
                         upload_id: upload_id.clone(),
                         object_name: object_name.clone(),
                         bucket_name: bucket_name.clone(),
+                        sse: sse.clone(),
+                        checksum: checksum_headers(&bytes),
+                        bytes,
                     })
                     .await,
                 );
@@ -199,6 +360,7 @@ where
 
     join_handles.push(
         spawn_upload_future(SpawnUploadFutureOptions {
+            checksum: checksum_headers(&bytes),
             bytes,
             client: client.clone(),
             counter,
@@ -206,11 +368,16 @@ where
             upload_id: upload_id.clone(),
             object_name: object_name.clone(),
             bucket_name: bucket_name.clone(),
+            sse: sse.clone(),
         })
         .await,
     );
 
     let mut e_tags = vec![];
+    let mut part_checksums = PartChecksums {
+        algorithm: checksum_algorithm,
+        ..Default::default()
+    };
 
     for join_handle in join_handles {
         match join_handle.await {
@@ -218,9 +385,11 @@ where
                 Ok(UploadPartResult {
                     part_number,
                     e_tag_result,
+                    checksum,
                 }) => match e_tag_result {
                     Ok(e_tag) => {
                         e_tags.push(ETag { e_tag, part_number });
+                        part_checksums.insert(part_number, &checksum);
                     }
                     Err(err) => {
                         abort_multipart_upload(&client, &bucket_name, &object_name, &upload_id)
@@ -240,7 +409,25 @@ where
         }
     }
 
-    complete_multipart_upload(&client, e_tags, &bucket_name, &object_name, &upload_id).await?;
+    let output = complete_multipart_upload(
+        &client,
+        e_tags,
+        &bucket_name,
+        &object_name,
+        &upload_id,
+        &part_checksums,
+        &sse,
+    )
+    .await?;
+
+    verify_completed_upload(
+        &client,
+        &bucket_name,
+        &object_name,
+        &part_checksums,
+        &output,
+    )
+    .await?;
 
     Ok(total_bytes)
 }
@@ -2,9 +2,50 @@
 // License: MIT (See `LICENSE.md`)
 
 use super::util::*;
-use crate::{error::Error, ETag};
+use crate::{
+    core::{checksum::PartChecksums, sse::SseHeaders},
+    error::Error,
+    ETag,
+};
 use aws_sdk_s3::Client;
 
+#[cfg(feature = "checksum")]
+use crate::core::checksum::Checksum;
+#[cfg(not(feature = "checksum"))]
+use crate::core::checksum::ChecksumHeaders;
+#[cfg(feature = "sse")]
+use crate::core::sse::Encryption;
+
+/// Additional options for `UploadManager::new`
+///
+/// With the `sse` feature, `encryption` sets the
+/// server-side `Encryption` of the object.
+///
+/// With the `checksum` feature, `checksum` sends the
+/// `Checksum` of every part, verifying the ones the
+/// server computed and the composite checksum on
+/// `complete`. As the composite checksum is only
+/// known once the upload is completed, the object is
+/// deleted if the server returns one that does not
+/// match.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let additional_options = UploadManagerAdditionalOptions {
+///     encryption: Some(Encryption::SseS3),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UploadManagerAdditionalOptions {
+    #[cfg(feature = "sse")]
+    pub encryption: Option<Encryption>,
+    #[cfg(feature = "checksum")]
+    pub checksum: Option<Checksum>,
+}
+
 /// Struct to manage a multipart upload manually.
 ///
 /// *Note*: `Minio::upload_object` will automatically manage a
@@ -21,6 +62,7 @@ use aws_sdk_s3::Client;
 ///     &client,
 ///     "sharks",
 ///     "shark.jpg",
+///     UploadManagerAdditionalOptions::default(),
 /// ).await?;
 ///
 /// let part_bytes: Vec<u8> = ...;
@@ -37,6 +79,10 @@ pub struct UploadManager<'um> {
     pub bucket_name: &'um str,
     pub object_name: &'um str,
     pub bytes_uploaded: usize,
+    sse: SseHeaders,
+    #[cfg(feature = "checksum")]
+    checksum: Option<Checksum>,
+    part_checksums: PartChecksums,
 }
 
 impl<'um> UploadManager<'um> {
@@ -53,15 +99,40 @@ impl<'um> UploadManager<'um> {
     ///     &client,
     ///     "sharks",
     ///     "shark.jpg",
-    ///     3_600,
+    ///     UploadManagerAdditionalOptions::default(),
     /// ).await?;
     /// ```
     pub async fn new(
         client: &Client,
         bucket_name: &'um str,
         object_name: &'um str,
+        UploadManagerAdditionalOptions {
+            #[cfg(feature = "sse")]
+            encryption,
+            #[cfg(feature = "checksum")]
+            checksum,
+        }: UploadManagerAdditionalOptions,
     ) -> Result<UploadManager<'um>, Error> {
-        let upload_id = start_multipart_upload(client, bucket_name, object_name).await?;
+        #[cfg(feature = "sse")]
+        let sse = SseHeaders::from(encryption.as_ref());
+        #[cfg(not(feature = "sse"))]
+        let sse = SseHeaders::default();
+
+        #[cfg(feature = "checksum")]
+        let checksum_algorithm = checksum.and_then(Checksum::algorithm);
+        #[cfg(not(feature = "checksum"))]
+        let checksum_algorithm = None;
+
+        let upload_id = start_multipart_upload(
+            client,
+            bucket_name,
+            object_name,
+            None,
+            &ContentHeaders::default(),
+            &sse,
+            checksum_algorithm.clone(),
+        )
+        .await?;
 
         Ok(UploadManager {
             e_tags: vec![],
@@ -70,6 +141,13 @@ impl<'um> UploadManager<'um> {
             bucket_name,
             object_name,
             bytes_uploaded: 0,
+            sse,
+            #[cfg(feature = "checksum")]
+            checksum,
+            part_checksums: PartChecksums {
+                algorithm: checksum_algorithm,
+                ..Default::default()
+            },
         })
     }
 
@@ -90,6 +168,14 @@ impl<'um> UploadManager<'um> {
         self.part_index += 1;
         self.bytes_uploaded += bytes.len();
 
+        #[cfg(feature = "checksum")]
+        let checksum = self
+            .checksum
+            .map(|checksum| checksum.headers(&bytes))
+            .unwrap_or_default();
+        #[cfg(not(feature = "checksum"))]
+        let checksum = ChecksumHeaders::default();
+
         let e_tag = upload_part(
             client,
             self.bucket_name,
@@ -97,10 +183,15 @@ impl<'um> UploadManager<'um> {
             &self.upload_id,
             part_number,
             bytes,
+            PartHeaders {
+                sse: &self.sse,
+                checksum: &checksum,
+            },
         )
         .await?;
 
         self.e_tags.push(ETag { e_tag, part_number });
+        self.part_checksums.insert(part_number, &checksum);
 
         Ok(())
     }
@@ -135,12 +226,23 @@ impl<'um> UploadManager<'um> {
     /// let bytes_uploaded: usize = upload_manager.complete(&client).await?;
     /// ```
     pub async fn complete(&self, client: &Client) -> Result<usize, Error> {
-        complete_multipart_upload(
+        let output = complete_multipart_upload(
             client,
             self.e_tags.clone(),
             self.bucket_name,
             self.object_name,
             &self.upload_id,
+            &self.part_checksums,
+            &self.sse,
+        )
+        .await?;
+
+        verify_completed_upload(
+            client,
+            self.bucket_name,
+            self.object_name,
+            &self.part_checksums,
+            &output,
         )
         .await?;
 
@@ -2,8 +2,38 @@
 // License: MIT (See `LICENSE.md`)
 
 use super::util::*;
-use crate::{error::Error, ETag};
-use aws_sdk_s3::{presigning::PresignedRequest, Client};
+use crate::{
+    core::{checksum::PartChecksums, presign::PresignedUrl, sse::SseHeaders},
+    error::Error,
+    ETag,
+};
+use aws_sdk_s3::Client;
+
+#[cfg(feature = "sse")]
+use crate::core::sse::Encryption;
+
+/// Additional options for `PresignedUploadManager::new`
+/// and `PresignedMultipartSession::new`
+///
+/// With the `sse` feature, `encryption` sets the server-side `Encryption`
+/// of the object. The `Encryption::SseC` key is bound to the presigned part
+/// requests as signed `headers` the client must send.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let customer_key: [u8; 32] = ...;
+///
+/// let additional_options = PresignedMultipartAdditionalOptions {
+///     encryption: Some(Encryption::SseC { key: customer_key }),
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PresignedMultipartAdditionalOptions {
+    #[cfg(feature = "sse")]
+    pub encryption: Option<Encryption>,
+}
 
 /// Struct to manage a presigned multipart upload
 ///
@@ -17,10 +47,11 @@ use aws_sdk_s3::{presigning::PresignedRequest, Client};
 ///     &client,
 ///     "sharks",
 ///     "shark.jpg",
+///     PresignedMultipartAdditionalOptions::default(),
 /// ).await?;
 ///
 /// let (
-///     part_request: PresignedRequest,
+///     part_request: PresignedUrl,
 ///     part_number: usize,
 /// ) = upload_manager.next_part(&client, 1_337).await?;
 ///
@@ -42,6 +73,8 @@ pub struct PresignedUploadManager<'pum> {
     pub part_index: usize,
     pub bucket_name: &'pum str,
     pub object_name: &'pum str,
+    #[cfg(feature = "sse")]
+    pub encryption: Option<Encryption>,
 }
 
 impl<'pum> PresignedUploadManager<'pum> {
@@ -58,25 +91,51 @@ impl<'pum> PresignedUploadManager<'pum> {
     ///     &client,
     ///     "sharks",
     ///     "shark.jpg",
-    ///     3_600,
+    ///     PresignedMultipartAdditionalOptions::default(),
     /// ).await?;
     /// ```
     pub async fn new(
         client: &Client,
         bucket_name: &'pum str,
         object_name: &'pum str,
+        #[cfg_attr(not(feature = "sse"), allow(unused_variables))]
+        additional_options: PresignedMultipartAdditionalOptions,
     ) -> Result<PresignedUploadManager<'pum>, Error> {
-        let upload_id = start_multipart_upload(client, bucket_name, object_name).await?;
-
-        Ok(PresignedUploadManager {
-            upload_id,
+        let upload_manager = PresignedUploadManager {
+            upload_id: String::new(),
             part_index: 0,
             bucket_name,
             object_name,
+            #[cfg(feature = "sse")]
+            encryption: additional_options.encryption,
+        };
+
+        let upload_id = start_multipart_upload(
+            client,
+            bucket_name,
+            object_name,
+            None,
+            &ContentHeaders::default(),
+            &upload_manager.sse(),
+            None,
+        )
+        .await?;
+
+        Ok(PresignedUploadManager {
+            upload_id,
+            ..upload_manager
         })
     }
 
-    /// Obtain a new part PresignedRequest and its part number
+    /// The server-side encryption headers of the upload
+    fn sse(&self) -> SseHeaders {
+        #[cfg(feature = "sse")]
+        return SseHeaders::from(self.encryption.as_ref());
+        #[cfg(not(feature = "sse"))]
+        SseHeaders::default()
+    }
+
+    /// Obtain a new part `PresignedUrl` and its part number
     ///
     /// ---
     /// Example Usage:
@@ -85,7 +144,7 @@ impl<'pum> PresignedUploadManager<'pum> {
     /// let mut upload_manager: PresignedUploadManager = ...;
     ///
     /// let (
-    ///     part_request: PresignedRequest,
+    ///     part_request: PresignedUrl,
     ///     part_number: usize,
     /// ) = upload_manager.next_part(&client, 1_337).await?;
     /// ```
@@ -93,7 +152,7 @@ impl<'pum> PresignedUploadManager<'pum> {
         &mut self,
         client: &Client,
         presigned_expiry_secs: u64,
-    ) -> Result<(PresignedRequest, usize), Error> {
+    ) -> Result<(PresignedUrl, usize), Error> {
         let part_number = self.part_index + 1;
         self.part_index += 1;
 
@@ -105,8 +164,10 @@ impl<'pum> PresignedUploadManager<'pum> {
                 &self.upload_id,
                 part_number,
                 presigned_expiry_secs,
+                &self.sse(),
             )
-            .await?,
+            .await?
+            .into(),
             part_number,
         ))
     }
@@ -149,7 +210,11 @@ impl<'pum> PresignedUploadManager<'pum> {
             self.bucket_name,
             self.object_name,
             &self.upload_id,
+            &PartChecksums::default(),
+            &self.sse(),
         )
-        .await
+        .await?;
+
+        Ok(())
     }
 }
@@ -0,0 +1,416 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{upload_object_multi_presigned::PresignedMultipartAdditionalOptions, util::*};
+use crate::{
+    core::{checksum::PartChecksums, presign::PresignedUrl, sse::SseHeaders},
+    error::Error,
+    ETag,
+};
+use aws_sdk_s3::Client;
+
+#[cfg(feature = "sse")]
+use crate::core::sse::Encryption;
+
+#[cfg(feature = "presigned_multipart")]
+use crate::core::{
+    presign::uri_encode,
+    signing::{object_url, presign_request, region},
+};
+#[cfg(feature = "presigned_multipart")]
+use aws_sdk_s3::config::Credentials;
+
+/// Owned, serializable *(With the `serde` feature)* state of a presigned
+/// multipart upload, that can be kept in a session store between requests
+///
+/// Parts are uploaded by the client via presigned requests, and reconciled
+/// via `ListParts`, so the client does not have to report `ETag`s.
+///
+/// With the `sse` feature, `encryption` is not serialized, so an
+/// `Encryption::SseC` key is never written to the session store,
+/// and must be set again on a restored session.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// let mut session = PresignedMultipartSession::new(
+///     &client,
+///     "sharks",
+///     "shark.jpg",
+///     PresignedMultipartAdditionalOptions::default(),
+/// ).await?;
+///
+/// let part_requests: Vec<(PresignedUrl, usize)> = session.next_parts(
+///     &client,
+///     4,
+///     3_600,
+/// ).await?;
+///
+/// ... // Store the session, client uploads the parts
+///
+/// session.complete(&client).await?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PresignedMultipartSession {
+    pub upload_id: String,
+    pub part_index: usize,
+    pub bucket_name: String,
+    pub object_name: String,
+    #[cfg(feature = "sse")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub encryption: Option<Encryption>,
+}
+
+/// A presigned request to complete a multipart upload, along with
+/// the `body` the client must send with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PresignedMultipartCompletion {
+    pub request: PresignedUrl,
+    pub body: String,
+}
+
+/// Escapes `value` for use as XML text
+#[cfg(feature = "presigned_multipart")]
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl PresignedMultipartSession {
+    /// Construct a new PresignedMultipartSession, starting a
+    /// multipart upload.
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let client: Client = ...;
+    ///
+    /// let mut session = PresignedMultipartSession::new(
+    ///     &client,
+    ///     "sharks",
+    ///     "shark.jpg",
+    ///     PresignedMultipartAdditionalOptions::default(),
+    /// ).await?;
+    /// ```
+    pub async fn new(
+        client: &Client,
+        bucket_name: &str,
+        object_name: &str,
+        #[cfg_attr(not(feature = "sse"), allow(unused_variables))]
+        additional_options: PresignedMultipartAdditionalOptions,
+    ) -> Result<PresignedMultipartSession, Error> {
+        let session = PresignedMultipartSession {
+            upload_id: String::new(),
+            part_index: 0,
+            bucket_name: bucket_name.to_string(),
+            object_name: object_name.to_string(),
+            #[cfg(feature = "sse")]
+            encryption: additional_options.encryption,
+        };
+
+        let upload_id = start_multipart_upload(
+            client,
+            bucket_name,
+            object_name,
+            None,
+            &ContentHeaders::default(),
+            &session.sse(),
+            None,
+        )
+        .await?;
+
+        Ok(PresignedMultipartSession {
+            upload_id,
+            ..session
+        })
+    }
+
+    /// The server-side encryption headers of the upload
+    fn sse(&self) -> SseHeaders {
+        #[cfg(feature = "sse")]
+        return SseHeaders::from(self.encryption.as_ref());
+        #[cfg(not(feature = "sse"))]
+        SseHeaders::default()
+    }
+
+    /// Obtain a `PresignedUrl` for a part by `part_number`, I.E. to
+    /// retry a part whose request expired
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let session: PresignedMultipartSession = ...;
+    ///
+    /// let part_request: PresignedUrl = session.part(&client, 3, 3_600).await?;
+    /// ```
+    pub async fn part(
+        &self,
+        client: &Client,
+        part_number: usize,
+        presigned_expiry_secs: u64,
+    ) -> Result<PresignedUrl, Error> {
+        Ok(upload_part_presigned(
+            client,
+            &self.bucket_name,
+            &self.object_name,
+            &self.upload_id,
+            part_number,
+            presigned_expiry_secs,
+            &self.sse(),
+        )
+        .await?
+        .into())
+    }
+
+    /// Obtain the next `count` part `PresignedUrl`s and their part numbers
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let mut session: PresignedMultipartSession = ...;
+    ///
+    /// for (part_request, part_number) in session.next_parts(&client, 4, 3_600).await? {
+    ///     ...
+    /// }
+    /// ```
+    pub async fn next_parts(
+        &mut self,
+        client: &Client,
+        count: usize,
+        presigned_expiry_secs: u64,
+    ) -> Result<Vec<(PresignedUrl, usize)>, Error> {
+        let mut part_requests = Vec::with_capacity(count);
+
+        for part_number in self.part_index + 1..=self.part_index + count {
+            part_requests.push((
+                self.part(client, part_number, presigned_expiry_secs)
+                    .await?,
+                part_number,
+            ));
+        }
+
+        self.part_index += count;
+
+        Ok(part_requests)
+    }
+
+    /// Lists the `ETag`s of the parts uploaded so far via `ListParts`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let session: PresignedMultipartSession = ...;
+    ///
+    /// let uploaded_parts: Vec<ETag> = session.list_parts(&client).await?;
+    /// ```
+    pub async fn list_parts(&self, client: &Client) -> Result<Vec<ETag>, Error> {
+        list_parts(
+            client,
+            &self.bucket_name,
+            &self.object_name,
+            &self.upload_id,
+            &self.sse(),
+        )
+        .await
+    }
+
+    /// Abort the multipart upload
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let session: PresignedMultipartSession = ...;
+    ///
+    /// session.abort(&client).await?;
+    /// ```
+    pub async fn abort(&self, client: &Client) -> Result<(), Error> {
+        abort_multipart_upload(
+            client,
+            &self.bucket_name,
+            &self.object_name,
+            &self.upload_id,
+        )
+        .await
+    }
+
+    /// Complete the multipart upload, using the parts reconciled
+    /// via `ListParts`
+    ///
+    /// Returns the `ETag`s of the parts the object was completed with
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let session: PresignedMultipartSession = ...;
+    ///
+    /// let completed_parts: Vec<ETag> = session.complete(&client).await?;
+    /// ```
+    pub async fn complete(&self, client: &Client) -> Result<Vec<ETag>, Error> {
+        let e_tags = self.list_parts(client).await?;
+
+        if e_tags.is_empty() {
+            return Err(Error::internal(
+                "No parts were uploaded for the multipart upload",
+            ));
+        }
+
+        complete_multipart_upload(
+            client,
+            e_tags.clone(),
+            &self.bucket_name,
+            &self.object_name,
+            &self.upload_id,
+            &PartChecksums::default(),
+            &self.sse(),
+        )
+        .await?;
+
+        Ok(e_tags)
+    }
+
+    #[cfg(feature = "presigned_multipart")]
+    /// Obtain a `PresignedUrl` for the client to list the uploaded parts
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let session: PresignedMultipartSession = ...;
+    /// let credentials: Credentials = ...;
+    ///
+    /// let list_request: PresignedUrl = session.list_parts_presigned(
+    ///     &client,
+    ///     &credentials,
+    ///     3_600,
+    /// ).await?;
+    /// ```
+    pub async fn list_parts_presigned(
+        &self,
+        client: &Client,
+        credentials: &Credentials,
+        presigned_expiry_secs: u64,
+    ) -> Result<PresignedUrl, Error> {
+        self.presign(client, credentials, "GET", presigned_expiry_secs)
+            .await
+    }
+
+    #[cfg(feature = "presigned_multipart")]
+    /// Obtain a `PresignedUrl` for the client to abort the multipart upload
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let session: PresignedMultipartSession = ...;
+    /// let credentials: Credentials = ...;
+    ///
+    /// let abort_request: PresignedUrl = session.abort_presigned(
+    ///     &client,
+    ///     &credentials,
+    ///     3_600,
+    /// ).await?;
+    /// ```
+    pub async fn abort_presigned(
+        &self,
+        client: &Client,
+        credentials: &Credentials,
+        presigned_expiry_secs: u64,
+    ) -> Result<PresignedUrl, Error> {
+        self.presign(client, credentials, "DELETE", presigned_expiry_secs)
+            .await
+    }
+
+    #[cfg(feature = "presigned_multipart")]
+    /// Obtain a `PresignedMultipartCompletion` for the client to complete
+    /// the multipart upload, using the parts reconciled via `ListParts`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let session: PresignedMultipartSession = ...;
+    /// let credentials: Credentials = ...;
+    ///
+    /// let completion: PresignedMultipartCompletion = session.complete_presigned(
+    ///     &client,
+    ///     &credentials,
+    ///     3_600,
+    /// ).await?;
+    ///
+    /// // Client sends `completion.body` to `completion.request.url`
+    /// ```
+    pub async fn complete_presigned(
+        &self,
+        client: &Client,
+        credentials: &Credentials,
+        presigned_expiry_secs: u64,
+    ) -> Result<PresignedMultipartCompletion, Error> {
+        let e_tags = self.list_parts(client).await?;
+
+        if e_tags.is_empty() {
+            return Err(Error::internal(
+                "No parts were uploaded for the multipart upload",
+            ));
+        }
+
+        let mut body = String::from(
+            "<CompleteMultipartUpload xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">",
+        );
+
+        for ETag { e_tag, part_number } in e_tags {
+            body.push_str(&format!(
+                "<Part><ETag>{}</ETag><PartNumber>{}</PartNumber></Part>",
+                escape_xml(&e_tag),
+                part_number
+            ));
+        }
+
+        body.push_str("</CompleteMultipartUpload>");
+
+        Ok(PresignedMultipartCompletion {
+            request: self
+                .presign(client, credentials, "POST", presigned_expiry_secs)
+                .await?,
+            body,
+        })
+    }
+
+    #[cfg(feature = "presigned_multipart")]
+    /// Presigns a request with `method` on the multipart upload
+    async fn presign(
+        &self,
+        client: &Client,
+        credentials: &Credentials,
+        method: &str,
+        presigned_expiry_secs: u64,
+    ) -> Result<PresignedUrl, Error> {
+        let url = format!(
+            "{}?uploadId={}",
+            object_url(client, &self.bucket_name, &self.object_name).await?,
+            uri_encode(&self.upload_id, true)
+        );
+
+        presign_request(
+            credentials,
+            &region(client),
+            method,
+            &url,
+            self.sse().customer_headers(),
+            presigned_expiry_secs,
+        )
+    }
+}
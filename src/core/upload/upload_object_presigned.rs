@@ -1,14 +1,51 @@
 // Authors: Robert Lopez
 // License: MIT (See `LICENSE.md`)
 
-use crate::error::Error;
-use aws_sdk_s3::{
-    presigning::{PresignedRequest, PresigningConfig},
-    Client,
+use crate::{
+    core::{
+        presign::{presigning_client, PresignedUrl},
+        sse::SseHeaders,
+    },
+    error::Error,
 };
-use std::time::Duration;
+use aws_sdk_s3::{presigning::PresigningConfig, Client};
+use std::{collections::HashMap, time::Duration};
 
-/// Obtain a `PresignedRequest` for a object upload
+#[cfg(feature = "sse")]
+use crate::core::sse::Encryption;
+
+/// Additional options for `upload_object_presigned` to bind the
+/// `Content-Type`, `Content-MD5` and metadata of the upload, and
+/// to sign for a different `endpoint_url` than the client was built with
+///
+/// Bound values are signed `headers` of the `PresignedUrl`, which
+/// must be sent as-is with the upload, so a client can not upload
+/// something else.
+///
+/// With the `sse` feature, `encryption` binds the server-side
+/// `Encryption` of the upload the same way.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let additional_options = UploadObjectPresignedAdditionalOptions {
+///     content_type: Some("image/jpeg".to_string()),
+///     metadata: Some(HashMap::from([("uploaded-by".to_string(), "1337".to_string())])),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UploadObjectPresignedAdditionalOptions {
+    pub content_type: Option<String>,
+    pub content_md5: Option<String>,
+    pub metadata: Option<HashMap<String, String>>,
+    pub endpoint_url: Option<String>,
+    #[cfg(feature = "sse")]
+    pub encryption: Option<Encryption>,
+}
+
+/// Obtain a `PresignedUrl` for a object upload
 ///
 /// ---
 /// Example Usage:
@@ -16,11 +53,12 @@ use std::time::Duration;
 ///
 /// let client: Client = ...;
 ///
-/// let presigned_request: PresignedRequest = upload_presigned(
+/// let presigned_request: PresignedUrl = upload_object_presigned(
 ///     &client,
 ///     "bucket_name",
 ///     "object_name",
 ///     1_337,
+///     UploadObjectPresignedAdditionalOptions::default(),
 /// ).await?;
 /// ```
 pub async fn upload_object_presigned(
@@ -28,17 +66,40 @@ pub async fn upload_object_presigned(
     bucket_name: &str,
     object_name: &str,
     presigned_expiry_secs: u64,
-) -> Result<PresignedRequest, Error> {
+    UploadObjectPresignedAdditionalOptions {
+        content_type,
+        content_md5,
+        metadata,
+        endpoint_url,
+        #[cfg(feature = "sse")]
+        encryption,
+    }: UploadObjectPresignedAdditionalOptions,
+) -> Result<PresignedUrl, Error> {
+    #[cfg(feature = "sse")]
+    let sse = SseHeaders::from(encryption.as_ref());
+    #[cfg(not(feature = "sse"))]
+    let sse = SseHeaders::default();
+
     let presigning_config = PresigningConfig::builder()
         .expires_in(Duration::from_secs(presigned_expiry_secs))
         .build()
         .map_err(Error::sdk)?;
 
-    client
+    presigning_client(client, endpoint_url.as_deref())
         .put_object()
         .bucket(bucket_name)
         .key(object_name)
+        .set_content_type(content_type)
+        .set_content_md5(content_md5)
+        .set_metadata(metadata)
+        .set_server_side_encryption(sse.server_side_encryption)
+        .set_ssekms_key_id(sse.ssekms_key_id)
+        .set_ssekms_encryption_context(sse.ssekms_encryption_context)
+        .set_sse_customer_algorithm(sse.customer_algorithm)
+        .set_sse_customer_key(sse.customer_key)
+        .set_sse_customer_key_md5(sse.customer_key_md5)
         .presigned(presigning_config)
         .await
+        .map(PresignedUrl::from)
         .map_err(Error::sdk)
 }
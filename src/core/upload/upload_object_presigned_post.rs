@@ -0,0 +1,324 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::{core::signing::*, error::Error};
+use aws_sdk_s3::{config::Credentials, Client};
+use aws_sigv4::sign::v4::{calculate_signature, generate_signing_key};
+use aws_smithy_types::base64;
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+/// A condition of a `PostPolicy`, serialized into the policy document
+#[derive(Debug, Clone)]
+enum PostPolicyCondition {
+    Equals(String, String),
+    StartsWith(String, String),
+    ContentLengthRange(usize, usize),
+}
+
+impl PostPolicyCondition {
+    fn to_json(&self) -> String {
+        match self {
+            PostPolicyCondition::Equals(field, value) => format!(
+                "[\"eq\",\"${}\",\"{}\"]",
+                escape_json(field),
+                escape_json(value)
+            ),
+            PostPolicyCondition::StartsWith(field, value) => format!(
+                "[\"starts-with\",\"${}\",\"{}\"]",
+                escape_json(field),
+                escape_json(value)
+            ),
+            PostPolicyCondition::ContentLengthRange(min, max) => {
+                format!("[\"content-length-range\",{},{}]", min, max)
+            }
+        }
+    }
+}
+
+/// Escapes `value` for use inside a JSON string literal
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            character if character.is_control() => {
+                escaped.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+/// Builder for a presigned POST policy, allowing browsers to upload
+/// an object via a HTML form, restricted by the policy's conditions
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let policy = PostPolicy::new("avatars", 3_600)
+///     .key_starts_with("user-1337/")
+///     .content_type_starts_with("image/")
+///     .content_length_range(1, 1_048_576)
+///     .success_action_status(201);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PostPolicy {
+    bucket_name: String,
+    expires_in: Duration,
+    conditions: Vec<PostPolicyCondition>,
+    form_data: HashMap<String, String>,
+}
+
+impl PostPolicy {
+    /// Constructs a `PostPolicy` for the bucket named `bucket_name`,
+    /// expiring after `presigned_expiry_secs`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let policy = PostPolicy::new("avatars", 3_600);
+    /// ```
+    pub fn new(bucket_name: &str, presigned_expiry_secs: u64) -> Self {
+        Self {
+            bucket_name: bucket_name.to_string(),
+            expires_in: Duration::from_secs(presigned_expiry_secs),
+            conditions: vec![PostPolicyCondition::Equals(
+                "bucket".to_string(),
+                bucket_name.to_string(),
+            )],
+            form_data: HashMap::new(),
+        }
+    }
+
+    /// Adds a condition that the field named `field` equals `value`,
+    /// and sets it in the form data
+    fn equals(mut self, field: &str, value: &str) -> Self {
+        self.conditions.push(PostPolicyCondition::Equals(
+            field.to_string(),
+            value.to_string(),
+        ));
+        self.form_data.insert(field.to_string(), value.to_string());
+
+        self
+    }
+
+    /// Adds a condition that the field named `field` starts with `prefix`,
+    /// and sets it in the form data
+    fn starts_with(mut self, field: &str, prefix: &str) -> Self {
+        self.conditions.push(PostPolicyCondition::StartsWith(
+            field.to_string(),
+            prefix.to_string(),
+        ));
+        self.form_data.insert(field.to_string(), prefix.to_string());
+
+        self
+    }
+
+    /// Restricts the upload to the object named `object_name`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let policy = PostPolicy::new("avatars", 3_600).key("user-1337.png");
+    /// ```
+    pub fn key(self, object_name: &str) -> Self {
+        self.equals("key", object_name)
+    }
+
+    /// Restricts the upload to objects named starting with `prefix`
+    ///
+    /// The form's `key` field defaults to `prefix`, and should be
+    /// overwritten by the browser with the full object name.
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let policy = PostPolicy::new("avatars", 3_600).key_starts_with("user-1337/");
+    /// ```
+    pub fn key_starts_with(self, prefix: &str) -> Self {
+        self.starts_with("key", prefix)
+    }
+
+    /// Restricts the size of the uploaded object to between
+    /// `min_bytes` and `max_bytes`, inclusive
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let policy = PostPolicy::new("avatars", 3_600).content_length_range(1, 1_048_576);
+    /// ```
+    pub fn content_length_range(mut self, min_bytes: usize, max_bytes: usize) -> Self {
+        self.conditions
+            .push(PostPolicyCondition::ContentLengthRange(
+                min_bytes, max_bytes,
+            ));
+
+        self
+    }
+
+    /// Restricts the `Content-Type` of the upload to `content_type`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let policy = PostPolicy::new("avatars", 3_600).content_type("image/png");
+    /// ```
+    pub fn content_type(self, content_type: &str) -> Self {
+        self.equals("Content-Type", content_type)
+    }
+
+    /// Restricts the `Content-Type` of the upload to start with `prefix`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let policy = PostPolicy::new("avatars", 3_600).content_type_starts_with("image/");
+    /// ```
+    pub fn content_type_starts_with(self, prefix: &str) -> Self {
+        self.starts_with("Content-Type", prefix)
+    }
+
+    /// Sets the user metadata `key` of the uploaded object to `value`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let policy = PostPolicy::new("avatars", 3_600).metadata("user-id", "1337");
+    /// ```
+    pub fn metadata(self, key: &str, value: &str) -> Self {
+        self.equals(&format!("x-amz-meta-{}", key), value)
+    }
+
+    /// Sets the HTTP status code returned on a successful upload,
+    /// `200`, `201` or `204`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let policy = PostPolicy::new("avatars", 3_600).success_action_status(201);
+    /// ```
+    pub fn success_action_status(self, status: u16) -> Self {
+        self.equals("success_action_status", &status.to_string())
+    }
+
+    /// Sets the URL the browser is redirected to on a successful upload
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let policy = PostPolicy::new("avatars", 3_600)
+    ///     .success_action_redirect("https://example.com/uploaded");
+    /// ```
+    pub fn success_action_redirect(self, url: &str) -> Self {
+        self.equals("success_action_redirect", url)
+    }
+}
+
+/// A signed `PostPolicy`, containing the `url` to POST the form to,
+/// and the `form_data` fields to include before the `file` field
+#[derive(Debug, Clone)]
+pub struct PresignedPostPolicy {
+    pub url: String,
+    pub form_data: HashMap<String, String>,
+}
+
+/// Signs a `PostPolicy` using `credentials`, returning the
+/// `url` and `form_data` for a browser form upload
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+/// let credentials: Credentials = ...;
+///
+/// let policy = PostPolicy::new("avatars", 3_600)
+///     .key("user-1337.png")
+///     .content_type_starts_with("image/");
+///
+/// let presigned_post: PresignedPostPolicy = upload_object_presigned_post(
+///     &client,
+///     &credentials,
+///     policy,
+/// ).await?;
+/// ```
+pub async fn upload_object_presigned_post(
+    client: &Client,
+    credentials: &Credentials,
+    PostPolicy {
+        bucket_name,
+        expires_in,
+        mut conditions,
+        mut form_data,
+    }: PostPolicy,
+) -> Result<PresignedPostPolicy, Error> {
+    let region = region(client);
+    let now = SystemTime::now();
+
+    let amz_credential = format!(
+        "{}/{}/{}/s3/aws4_request",
+        credentials.access_key_id(),
+        amz_date_stamp(now)?,
+        region
+    );
+
+    let mut signing_fields = vec![
+        ("x-amz-algorithm", "AWS4-HMAC-SHA256".to_string()),
+        ("x-amz-credential", amz_credential),
+        ("x-amz-date", amz_date(now)?),
+    ];
+
+    if let Some(session_token) = credentials.session_token() {
+        signing_fields.push(("x-amz-security-token", session_token.to_string()));
+    }
+
+    for (field, value) in signing_fields {
+        conditions.push(PostPolicyCondition::Equals(
+            field.to_string(),
+            value.clone(),
+        ));
+        form_data.insert(field.to_string(), value);
+    }
+
+    let policy = format!(
+        "{{\"expiration\":\"{}\",\"conditions\":[{}]}}",
+        iso8601(now + expires_in)?,
+        conditions
+            .iter()
+            .map(PostPolicyCondition::to_json)
+            .collect::<Vec<String>>()
+            .join(",")
+    );
+    let policy = base64::encode(policy);
+
+    let signing_key = generate_signing_key(credentials.secret_access_key(), now, &region, "s3");
+    let signature = calculate_signature(signing_key, policy.as_bytes());
+
+    form_data.insert("policy".to_string(), policy);
+    form_data.insert("x-amz-signature".to_string(), signature);
+
+    Ok(PresignedPostPolicy {
+        url: bucket_url(client, &bucket_name).await?,
+        form_data,
+    })
+}
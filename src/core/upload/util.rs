@@ -1,41 +1,102 @@
 // Authors: Robert Lopez
 // License: MIT (See `LICENSE.md`)
-use crate::{error::Error, ETag};
+use crate::{
+    core::{
+        checksum::{ChecksumHeaders, PartChecksums},
+        sse::SseHeaders,
+    },
+    error::Error,
+    ETag,
+};
 use aws_sdk_s3::{
+    operation::complete_multipart_upload::CompleteMultipartUploadOutput,
     presigning::{PresignedRequest, PresigningConfig},
-    primitives::ByteStream,
-    types::{CompletedMultipartUpload, CompletedPart},
+    primitives::{ByteStream, DateTime},
+    types::{ChecksumAlgorithm, CompletedMultipartUpload, CompletedPart},
     Client,
 };
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
+
+/// The standard headers of an object, set when it is uploaded
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ContentHeaders {
+    pub(crate) content_type: Option<String>,
+    pub(crate) content_encoding: Option<String>,
+    pub(crate) cache_control: Option<String>,
+    pub(crate) content_disposition: Option<String>,
+    pub(crate) expires: Option<DateTime>,
+}
 
+#[allow(clippy::too_many_arguments)]
 pub async fn upload(
     client: &Client,
     bucket_name: &str,
     object_name: &str,
     bytes: Vec<u8>,
+    metadata: Option<HashMap<String, String>>,
+    content: &ContentHeaders,
+    sse: &SseHeaders,
+    checksum: &ChecksumHeaders,
 ) -> Result<(), Error> {
-    client
+    let response = client
         .put_object()
         .bucket(bucket_name)
         .key(object_name)
         .body(ByteStream::from(bytes))
+        .set_metadata(metadata)
+        .set_content_type(content.content_type.clone())
+        .set_content_encoding(content.content_encoding.clone())
+        .set_cache_control(content.cache_control.clone())
+        .set_content_disposition(content.content_disposition.clone())
+        .set_expires(content.expires)
+        .set_server_side_encryption(sse.server_side_encryption.clone())
+        .set_ssekms_key_id(sse.ssekms_key_id.clone())
+        .set_ssekms_encryption_context(sse.ssekms_encryption_context.clone())
+        .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+        .set_sse_customer_key(sse.customer_key.clone())
+        .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+        .set_checksum_algorithm(checksum.algorithm.clone())
+        .set_checksum_crc32(checksum.crc32.clone())
+        .set_checksum_crc32_c(checksum.crc32_c.clone())
+        .set_checksum_sha256(checksum.sha256.clone())
+        .set_content_md5(checksum.content_md5.clone())
         .send()
         .await
         .map_err(Error::sdk)?;
 
-    Ok(())
+    checksum.verify(
+        response.checksum_crc32(),
+        response.checksum_crc32_c(),
+        response.checksum_sha256(),
+    )
 }
 
 pub async fn start_multipart_upload(
     client: &Client,
     bucket_name: &str,
     object_name: &str,
+    metadata: Option<HashMap<String, String>>,
+    content: &ContentHeaders,
+    sse: &SseHeaders,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
 ) -> Result<String, Error> {
     client
         .create_multipart_upload()
         .bucket(bucket_name)
         .key(object_name)
+        .set_metadata(metadata)
+        .set_content_type(content.content_type.clone())
+        .set_content_encoding(content.content_encoding.clone())
+        .set_cache_control(content.cache_control.clone())
+        .set_content_disposition(content.content_disposition.clone())
+        .set_expires(content.expires)
+        .set_server_side_encryption(sse.server_side_encryption.clone())
+        .set_ssekms_key_id(sse.ssekms_key_id.clone())
+        .set_ssekms_encryption_context(sse.ssekms_encryption_context.clone())
+        .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+        .set_sse_customer_key(sse.customer_key.clone())
+        .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+        .set_checksum_algorithm(checksum_algorithm)
         .send()
         .await
         .map_err(Error::sdk)?
@@ -63,6 +124,12 @@ pub async fn abort_multipart_upload(
     Ok(())
 }
 
+/// The `sse` and `checksum` headers a part is uploaded with via `upload_part`
+pub struct PartHeaders<'ph> {
+    pub sse: &'ph SseHeaders,
+    pub checksum: &'ph ChecksumHeaders,
+}
+
 pub async fn upload_part(
     client: &Client,
     bucket_name: &str,
@@ -70,17 +137,34 @@ pub async fn upload_part(
     upload_id: &str,
     part_number: usize,
     bytes: Vec<u8>,
+    PartHeaders { sse, checksum }: PartHeaders<'_>,
 ) -> Result<String, Error> {
-    client
+    let response = client
         .upload_part()
         .bucket(bucket_name)
         .key(object_name)
         .upload_id(upload_id)
         .part_number(part_number as i32)
         .body(ByteStream::from(bytes))
+        .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+        .set_sse_customer_key(sse.customer_key.clone())
+        .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+        .set_checksum_algorithm(checksum.algorithm.clone())
+        .set_checksum_crc32(checksum.crc32.clone())
+        .set_checksum_crc32_c(checksum.crc32_c.clone())
+        .set_checksum_sha256(checksum.sha256.clone())
+        .set_content_md5(checksum.content_md5.clone())
         .send()
         .await
-        .map_err(Error::sdk)?
+        .map_err(Error::sdk)?;
+
+    checksum.verify(
+        response.checksum_crc32(),
+        response.checksum_crc32_c(),
+        response.checksum_sha256(),
+    )?;
+
+    response
         .e_tag
         .ok_or(Error::internal("e_tag was None on upload_part"))
 }
@@ -92,6 +176,7 @@ pub async fn upload_part_presigned(
     upload_id: &str,
     part_number: usize,
     presigned_expiry_secs: u64,
+    sse: &SseHeaders,
 ) -> Result<PresignedRequest, Error> {
     let presigning_config = PresigningConfig::builder()
         .expires_in(Duration::from_secs(presigned_expiry_secs))
@@ -104,25 +189,132 @@ pub async fn upload_part_presigned(
         .key(object_name)
         .upload_id(upload_id)
         .part_number(part_number as i32)
+        .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+        .set_sse_customer_key(sse.customer_key.clone())
+        .set_sse_customer_key_md5(sse.customer_key_md5.clone())
         .presigned(presigning_config)
         .await
         .map_err(Error::sdk)
 }
 
+/// The inclusive byte `range` `(start, end)` of a `copy_source`
+/// to copy via `upload_part_copy`, read with `sse`
+pub struct CopySourceRange<'csr> {
+    pub copy_source: &'csr str,
+    pub range: (usize, usize),
+    pub sse: &'csr SseHeaders,
+}
+
+pub async fn upload_part_copy(
+    client: &Client,
+    bucket_name: &str,
+    object_name: &str,
+    upload_id: &str,
+    part_number: usize,
+    CopySourceRange {
+        copy_source,
+        range: (start, end),
+        sse: source_sse,
+    }: CopySourceRange<'_>,
+    sse: &SseHeaders,
+) -> Result<String, Error> {
+    client
+        .upload_part_copy()
+        .bucket(bucket_name)
+        .key(object_name)
+        .upload_id(upload_id)
+        .part_number(part_number as i32)
+        .copy_source(copy_source)
+        .copy_source_range(format!("bytes={}-{}", start, end))
+        .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+        .set_sse_customer_key(sse.customer_key.clone())
+        .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+        .set_copy_source_sse_customer_algorithm(source_sse.customer_algorithm.clone())
+        .set_copy_source_sse_customer_key(source_sse.customer_key.clone())
+        .set_copy_source_sse_customer_key_md5(source_sse.customer_key_md5.clone())
+        .send()
+        .await
+        .map_err(Error::sdk)?
+        .copy_part_result
+        .and_then(|copy_part_result| copy_part_result.e_tag)
+        .ok_or(Error::internal("e_tag was None on upload_part_copy"))
+}
+
+pub async fn list_parts(
+    client: &Client,
+    bucket_name: &str,
+    object_name: &str,
+    upload_id: &str,
+    sse: &SseHeaders,
+) -> Result<Vec<ETag>, Error> {
+    let mut e_tags = vec![];
+    let mut part_number_marker = None;
+
+    loop {
+        let response = client
+            .list_parts()
+            .bucket(bucket_name)
+            .key(object_name)
+            .upload_id(upload_id)
+            .set_part_number_marker(part_number_marker)
+            .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+            .set_sse_customer_key(sse.customer_key.clone())
+            .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+            .send()
+            .await
+            .map_err(Error::sdk)?;
+
+        for part in response.parts() {
+            e_tags.push(ETag {
+                e_tag: part
+                    .e_tag()
+                    .ok_or(Error::internal("e_tag was None on list_parts"))?
+                    .to_string(),
+                part_number: part
+                    .part_number()
+                    .ok_or(Error::internal("part_number was None on list_parts"))?
+                    as usize,
+            });
+        }
+
+        if !response.is_truncated().unwrap_or_default() {
+            break;
+        }
+
+        part_number_marker = response.next_part_number_marker;
+
+        if part_number_marker.is_none() {
+            break;
+        }
+    }
+
+    Ok(e_tags)
+}
+
 pub async fn complete_multipart_upload(
     client: &Client,
     e_tags: Vec<ETag>,
     bucket_name: &str,
     object_name: &str,
     upload_id: &str,
-) -> Result<(), Error> {
+    part_checksums: &PartChecksums,
+    sse: &SseHeaders,
+) -> Result<CompleteMultipartUploadOutput, Error> {
     let completed_parts = e_tags
         .into_iter()
         .map(|ETag { e_tag, part_number }| {
-            CompletedPart::builder()
+            let checksum = part_checksums.values.get(&part_number).cloned();
+            let builder = CompletedPart::builder()
                 .e_tag(e_tag)
-                .part_number(part_number as i32)
-                .build()
+                .part_number(part_number as i32);
+
+            match part_checksums.algorithm {
+                Some(ChecksumAlgorithm::Crc32) => builder.set_checksum_crc32(checksum),
+                Some(ChecksumAlgorithm::Crc32C) => builder.set_checksum_crc32_c(checksum),
+                Some(ChecksumAlgorithm::Sha256) => builder.set_checksum_sha256(checksum),
+                _ => builder,
+            }
+            .build()
         })
         .collect::<Vec<CompletedPart>>();
 
@@ -136,9 +328,35 @@ pub async fn complete_multipart_upload(
         .key(object_name)
         .multipart_upload(completed_multipart_upload)
         .upload_id(upload_id)
+        .set_sse_customer_algorithm(sse.customer_algorithm.clone())
+        .set_sse_customer_key(sse.customer_key.clone())
+        .set_sse_customer_key_md5(sse.customer_key_md5.clone())
+        .send()
+        .await
+        .map_err(Error::sdk)
+}
+
+/// Verifies the composite checksum of a completed multipart upload,
+/// deleting the completed object on a mismatch so it is not left committed
+pub async fn verify_completed_upload(
+    client: &Client,
+    bucket_name: &str,
+    object_name: &str,
+    part_checksums: &PartChecksums,
+    output: &CompleteMultipartUploadOutput,
+) -> Result<(), Error> {
+    let Err(err) = part_checksums.verify(output) else {
+        return Ok(());
+    };
+
+    client
+        .delete_object()
+        .bucket(bucket_name)
+        .key(object_name)
+        .set_version_id(output.version_id.clone())
         .send()
         .await
         .map_err(Error::sdk)?;
 
-    Ok(())
+    Err(err)
 }
@@ -0,0 +1,170 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::error::Error;
+use aws_sdk_s3::{
+    primitives::DateTimeFormat,
+    types::{Object, ObjectStorageClass},
+    Client,
+};
+use std::collections::BTreeMap;
+
+/// Interval objects are bucketed by their last modified time in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageInterval {
+    /// Groups as `2024-06-01`
+    Day,
+    /// Groups as `2024-06`
+    Month,
+    /// Groups as `2024`
+    Year,
+}
+
+/// How the objects of a `Usage` are grouped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageGroupBy {
+    /// By the first `depth` `/`-delimited segments of the key after the prefix,
+    /// I.E. `tenants/tenant-1337/` for a depth of 2. Objects with fewer segments
+    /// are grouped under the prefix of their own segments
+    PrefixDepth(usize),
+    /// By storage class, I.E. `STANDARD`
+    StorageClass,
+    /// By the interval of their last modified time
+    LastModified(UsageInterval),
+}
+
+/// Options of `usage`
+#[derive(Debug, Clone, Default)]
+pub struct UsageOptions {
+    /// Also totals the objects by group if set
+    pub group_by: Option<UsageGroupBy>,
+}
+
+/// The amount of objects and their total bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UsageTotals {
+    pub objects: u64,
+    pub bytes: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, bytes: u64) {
+        self.objects += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// The usage of the objects of a bucket under a prefix
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Usage {
+    pub total: UsageTotals,
+    /// Totals by group, empty unless `UsageOptions::group_by` is set
+    pub groups: BTreeMap<String, UsageTotals>,
+}
+
+/// Returns the group of `object` by `group_by`
+fn group_of(object: &Object, prefix: &str, group_by: UsageGroupBy) -> Result<String, Error> {
+    match group_by {
+        UsageGroupBy::PrefixDepth(depth) => {
+            let key = object.key().unwrap_or_default();
+            let relative_key = key.strip_prefix(prefix).unwrap_or(key);
+
+            // The last segment is the name of the object, not a prefix
+            let segments = relative_key.split('/').collect::<Vec<_>>();
+            let segments = &segments[..segments.len().saturating_sub(1).min(depth)];
+
+            Ok(segments.iter().fold(prefix.to_string(), |group, segment| {
+                format!("{}{}/", group, segment)
+            }))
+        }
+        UsageGroupBy::StorageClass => Ok(object
+            .storage_class()
+            .unwrap_or(&ObjectStorageClass::Standard)
+            .as_str()
+            .to_string()),
+        UsageGroupBy::LastModified(interval) => {
+            let Some(last_modified) = object.last_modified() else {
+                return Ok("unknown".to_string());
+            };
+            let last_modified = last_modified
+                .fmt(DateTimeFormat::DateTime)
+                .map_err(Error::sdk)?;
+
+            let length = match interval {
+                UsageInterval::Day => 10,
+                UsageInterval::Month => 7,
+                UsageInterval::Year => 4,
+            };
+
+            Ok(last_modified[..length].to_string())
+        }
+    }
+}
+
+/// Returns the amount and total bytes of the objects in the bucket by
+/// `bucket_name` whose key starts with `prefix`, optionally by group
+///
+/// Aggregates the listing page by page as it streams, so buckets of any
+/// size are totaled without holding their objects in memory.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let client: Client = ...;
+///
+/// let usage: Usage = usage(
+///     &client,
+///     "sharks",
+///     "tenants/",
+///     UsageOptions {
+///         group_by: Some(UsageGroupBy::PrefixDepth(1)),
+///     },
+/// ).await?;
+///
+/// for (tenant_prefix, totals) in usage.groups {
+///     bill(tenant_prefix, totals.bytes);
+/// }
+/// ```
+pub async fn usage(
+    client: &Client,
+    bucket_name: &str,
+    prefix: &str,
+    options: UsageOptions,
+) -> Result<Usage, Error> {
+    let mut usage = Usage::default();
+    let mut continuation_token = None;
+
+    loop {
+        let response = client
+            .list_objects_v2()
+            .bucket(bucket_name)
+            .prefix(prefix)
+            .set_continuation_token(continuation_token)
+            .send()
+            .await
+            .map_err(Error::sdk)?;
+
+        for object in response.contents() {
+            let bytes = object.size().unwrap_or_default().max(0) as u64;
+            usage.total.add(bytes);
+
+            if let Some(group_by) = options.group_by {
+                usage
+                    .groups
+                    .entry(group_of(object, prefix, group_by)?)
+                    .or_default()
+                    .add(bytes);
+            }
+        }
+
+        match response.next_continuation_token() {
+            Some(token) if response.is_truncated().unwrap_or_default() => {
+                continuation_token = Some(token.to_string());
+            }
+            _ => return Ok(usage),
+        }
+    }
+}
@@ -12,6 +12,17 @@ pub enum Error {
     AcquireError,
 }
 
+/// The kind of a `Error`, without the details of the error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorKind {
+    StdIo,
+    SdkError,
+    Internal,
+    JoinError,
+    AcquireError,
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -55,4 +66,24 @@ impl Error {
     {
         Self::SdkError(format!("{:?}", err))
     }
+
+    /// Returns the `ErrorKind` of the error
+    ///
+    /// ---
+    /// Example Usage
+    /// ```
+    ///
+    /// let error: Error = Error::internal("Some internal error");
+    ///
+    /// assert_eq!(error.kind(), ErrorKind::Internal);
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::StdIo(_) => ErrorKind::StdIo,
+            Error::SdkError(_) => ErrorKind::SdkError,
+            Error::Internal(_) => ErrorKind::Internal,
+            Error::JoinError => ErrorKind::JoinError,
+            Error::AcquireError => ErrorKind::AcquireError,
+        }
+    }
 }
@@ -0,0 +1,15 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use std::process::ExitCode;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match minior::cli::run(std::env::args().skip(1)).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("minior: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
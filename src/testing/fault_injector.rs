@@ -0,0 +1,475 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::Minio;
+use aws_sdk_s3::{
+    config::{
+        interceptors::{
+            BeforeDeserializationInterceptorContextMut, BeforeTransmitInterceptorContextMut,
+        },
+        ConfigBag, Intercept, RuntimeComponents,
+    },
+    error::BoxError,
+    Client,
+};
+use aws_smithy_http_client::{tls, Builder};
+use aws_smithy_runtime_api::{
+    client::{
+        http::{
+            HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings,
+            SharedHttpClient, SharedHttpConnector,
+        },
+        orchestrator::{HttpRequest, HttpResponse, Metadata},
+        result::ConnectorError,
+    },
+    http::StatusCode,
+};
+use aws_smithy_types::{
+    body::SdkBody,
+    config_bag::{Storable, StoreReplace},
+};
+use hyper::body::{Body, Bytes, Frame, SizeHint};
+use std::{
+    collections::HashMap,
+    future::Future,
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::Sleep;
+
+/// A fault injected into a request by a `FaultInjector`
+///
+/// - `Error`: Responds with the `status` and S3 error `code`, I.E.
+///   `500` `InternalError`, without the request taking effect.
+///
+/// - `SlowDown`: Responds with `503` `SlowDown`, the throttling error
+///   the SDK retries, without the request taking effect.
+///
+/// - `Latency`: Delays the response by the `Duration`.
+///
+/// - `DropConnection`: Drops the connection after `after_bytes` of the
+///   request body for requests with a body, I.E. uploads, or else after
+///   `after_bytes` of the response body, I.E. gets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    Error { status: u16, code: String },
+    SlowDown,
+    Latency(Duration),
+    DropConnection { after_bytes: usize },
+}
+
+/// Which requests of an operation a `Fault` is injected into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trigger {
+    Nth(usize),
+    Always,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    operation: String,
+    trigger: Trigger,
+    fault: Fault,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    rules: Vec<Rule>,
+    /// Requests sent by the operation name, counting retries
+    requests: HashMap<String, usize>,
+}
+
+/// The fault of the current attempt left to inject into its response
+#[derive(Debug, Clone)]
+struct InjectedFault(Option<Fault>);
+
+impl Storable for InjectedFault {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The error response a faulted request is answered with, attached to
+/// the request for the `FaultyHttpClient`
+#[derive(Debug, Clone)]
+struct InjectedResponse {
+    status: u16,
+    code: String,
+}
+
+/// Wraps the `HttpClient` of a `Minio` attached to a `FaultInjector`,
+/// answering the requests faulted with an `InjectedResponse` without
+/// sending them
+#[derive(Debug)]
+struct FaultyHttpClient(SharedHttpClient);
+
+impl HttpClient for FaultyHttpClient {
+    fn http_connector(
+        &self,
+        settings: &HttpConnectorSettings,
+        components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(FaultyHttpConnector(
+            self.0.http_connector(settings, components),
+        ))
+    }
+}
+
+#[derive(Debug)]
+struct FaultyHttpConnector(SharedHttpConnector);
+
+impl HttpConnector for FaultyHttpConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        match request.extension::<InjectedResponse>() {
+            Some(InjectedResponse { status, code }) => HttpConnectorFuture::ready(
+                error_response(*status, code).map_err(|err| ConnectorError::other(err, None)),
+            ),
+            None => self.0.call(request),
+        }
+    }
+}
+
+/// Wraps a body, delaying its first frame by `delay` and failing as a
+/// dropped connection once more than `remaining_bytes` were read
+struct FaultyBody {
+    inner: SdkBody,
+    delay: Option<Pin<Box<Sleep>>>,
+    remaining_bytes: Option<usize>,
+}
+
+impl FaultyBody {
+    fn wrap(inner: SdkBody, delay: Option<Duration>, remaining_bytes: Option<usize>) -> SdkBody {
+        SdkBody::from_body_1_x(Self {
+            inner,
+            delay: delay.map(|delay| Box::pin(tokio::time::sleep(delay))),
+            remaining_bytes,
+        })
+    }
+}
+
+impl Body for FaultyBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, BoxError>>> {
+        if let Some(delay) = self.delay.as_mut() {
+            if delay.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+
+            self.delay = None;
+        }
+
+        if self.remaining_bytes == Some(0) {
+            return Poll::Ready(Some(Err(Box::new(io::Error::new(
+                io::ErrorKind::ConnectionReset,
+                "Connection dropped by FaultInjector",
+            )))));
+        }
+
+        let frame = match Pin::new(&mut self.inner).poll_frame(cx) {
+            Poll::Ready(Some(Ok(frame))) => frame,
+            poll => return poll,
+        };
+
+        let Some(remaining_bytes) = self.remaining_bytes else {
+            return Poll::Ready(Some(Ok(frame)));
+        };
+
+        match frame.into_data() {
+            Ok(mut data) => {
+                data.truncate(remaining_bytes);
+                self.remaining_bytes = Some(remaining_bytes - data.len());
+
+                Poll::Ready(Some(Ok(Frame::data(data))))
+            }
+            Err(frame) => Poll::Ready(Some(Ok(frame))),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.remaining_bytes != Some(0) && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match self.remaining_bytes {
+            Some(_) => SizeHint::default(),
+            None => Body::size_hint(&self.inner),
+        }
+    }
+}
+
+/// An interceptor injecting faults into the requests of chosen
+/// operations of a `Minio`, to deterministically test error,
+/// retry and abort paths
+///
+/// Operations are named as in the S3 API, I.E. `PutObject`, `UploadPart`
+/// or `CompleteMultipartUpload`, or `*` for every operation. Requests are
+/// counted per operation, including the retries of the SDK, and the
+/// first rule matching a request injects its `Fault`.
+///
+/// Requests faulted with `Fault::Error` or `Fault::SlowDown` are answered
+/// by the client `attach` wraps without being sent, so they have no effect
+/// on the buckets, and do not require a reachable server. Clones share the
+/// same rules and request counts.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let minio: Minio = ...;
+///
+/// let fault_injector = FaultInjector::new()
+///     .fail_nth("UploadPart", 2, Fault::SlowDown)
+///     .always("GetObject", Fault::Latency(Duration::from_millis(250)));
+///
+/// let minio = fault_injector.attach(&minio);
+///
+/// minio.upload_object("sharks", "shark.jpg", shark_image, None).await?;
+///
+/// assert_eq!(fault_injector.requests("UploadPart"), parts + 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FaultInjector {
+    state: Arc<Mutex<State>>,
+}
+
+impl FaultInjector {
+    /// Constructs a FaultInjector without faults
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let fault_injector = FaultInjector::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn rule(self, operation: &str, trigger: Trigger, fault: Fault) -> Self {
+        self.state().rules.push(Rule {
+            operation: operation.to_string(),
+            trigger,
+            fault,
+        });
+
+        self
+    }
+
+    /// Injects the `fault` into the `n`th request of the `operation`, from `1`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let fault_injector = FaultInjector::new().fail_nth("UploadPart", 2, Fault::SlowDown);
+    /// ```
+    pub fn fail_nth(self, operation: &str, n: usize, fault: Fault) -> Self {
+        self.rule(operation, Trigger::Nth(n), fault)
+    }
+
+    /// Injects the `fault` into every request of the `operation`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let fault_injector = FaultInjector::new().always(
+    ///     "GetObject",
+    ///     Fault::DropConnection { after_bytes: 1_024 },
+    /// );
+    /// ```
+    pub fn always(self, operation: &str, fault: Fault) -> Self {
+        self.rule(operation, Trigger::Always, fault)
+    }
+
+    /// Returns the amount of requests sent for the `operation`, including
+    /// retries and the requests faults were injected into
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let fault_injector: FaultInjector = ...;
+    ///
+    /// let retried = fault_injector.requests("PutObject") > 1;
+    /// ```
+    pub fn requests(&self, operation: &str) -> usize {
+        self.state()
+            .requests
+            .get(operation)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns a `Minio` sharing the config and credentials of `minio`,
+    /// with this FaultInjector attached to its client
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let minio: Minio = ...;
+    ///
+    /// let faulty_minio: Minio = FaultInjector::new()
+    ///     .always("PutObject", Fault::SlowDown)
+    ///     .attach(&minio);
+    /// ```
+    pub fn attach(&self, minio: &Minio) -> Minio {
+        let config = minio.client.config();
+
+        // Without a client of its own, the client is the one the SDK defaults to
+        let http_client = config.http_client().unwrap_or_else(|| {
+            Builder::new()
+                .tls_provider(tls::Provider::Rustls(
+                    tls::rustls_provider::CryptoMode::AwsLc,
+                ))
+                .build_https()
+        });
+
+        Minio {
+            client: Arc::new(Client::from_conf(
+                config
+                    .to_builder()
+                    .http_client(FaultyHttpClient(http_client))
+                    .interceptor(self.clone())
+                    .build(),
+            )),
+            credentials_provider: minio.credentials_provider.clone(),
+            credentials_cache: minio.credentials_cache.clone(),
+            endpoint_url: minio.endpoint_url.clone(),
+            #[cfg(any(feature = "health", feature = "notifications"))]
+            endpoint: minio.endpoint.clone(),
+            #[cfg(feature = "metrics")]
+            metrics: minio.metrics.clone(),
+        }
+    }
+
+    /// Counts a request of the `operation`, returning the fault to inject
+    fn next_fault(&self, operation: &str) -> Option<Fault> {
+        let mut state = self.state();
+        let requests = state.requests.entry(operation.to_string()).or_default();
+        *requests += 1;
+        let n = *requests;
+
+        state
+            .rules
+            .iter()
+            .find(|rule| {
+                (rule.operation == operation || rule.operation == "*")
+                    && match rule.trigger {
+                        Trigger::Nth(nth) => nth == n,
+                        Trigger::Always => true,
+                    }
+            })
+            .map(|rule| rule.fault.clone())
+    }
+}
+
+impl Intercept for FaultInjector {
+    fn name(&self) -> &'static str {
+        "FaultInjector"
+    }
+
+    fn modify_before_transmit(
+        &self,
+        context: &mut BeforeTransmitInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let operation = cfg
+            .load::<Metadata>()
+            .map(|metadata| metadata.name().to_string())
+            .unwrap_or_default();
+        let mut fault = self.next_fault(&operation);
+        let request = context.request_mut();
+
+        match fault {
+            // The `FaultyHttpClient` answers the request instead of sending it
+            Some(Fault::Error {
+                ref status,
+                ref code,
+            }) => {
+                request.add_extension(InjectedResponse {
+                    status: *status,
+                    code: code.clone(),
+                });
+
+                fault = None;
+            }
+            Some(Fault::SlowDown) => {
+                request.add_extension(InjectedResponse {
+                    status: 503,
+                    code: "SlowDown".to_string(),
+                });
+
+                fault = None;
+            }
+            Some(Fault::DropConnection { after_bytes })
+                if request.body().content_length() != Some(0) =>
+            {
+                let body = request.take_body();
+                *request.body_mut() = FaultyBody::wrap(body, None, Some(after_bytes));
+
+                fault = None;
+            }
+            _ => {}
+        }
+
+        cfg.interceptor_state().store_put(InjectedFault(fault));
+
+        Ok(())
+    }
+
+    fn modify_before_deserialization(
+        &self,
+        context: &mut BeforeDeserializationInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let Some(InjectedFault(Some(fault))) = cfg.load::<InjectedFault>().cloned() else {
+            return Ok(());
+        };
+
+        let response = context.response_mut();
+
+        match fault {
+            Fault::Error { .. } | Fault::SlowDown => {}
+            Fault::Latency(delay) => {
+                let body = response.take_body();
+                *response.body_mut() = FaultyBody::wrap(body, Some(delay), None);
+            }
+            Fault::DropConnection { after_bytes } => {
+                let body = response.take_body();
+                *response.body_mut() = FaultyBody::wrap(body, None, Some(after_bytes));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns an S3 error response of the `status` and `code`
+fn error_response(status: u16, code: &str) -> Result<HttpResponse, BoxError> {
+    let mut response = HttpResponse::new(
+        StatusCode::try_from(status)?,
+        SdkBody::from(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Error><Code>{}</Code><Message>Injected by FaultInjector</Message></Error>",
+            code
+        )),
+    );
+    response
+        .headers_mut()
+        .insert("content-type", "application/xml");
+
+    Ok(response)
+}
@@ -0,0 +1,617 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::{
+    core::{
+        get::{
+            GetObjectAdditionalOptions, GetObjectPresignedAdditionalOptions,
+            StatObjectAdditionalOptions,
+        },
+        presign::{uri_encode, PresignedUrl},
+        storage::{ObjectStorage, ObjectStream},
+        upload::{
+            upload_object::UploadObjectAdditionalOptions,
+            upload_object_presigned::UploadObjectPresignedAdditionalOptions,
+        },
+    },
+    error::Error,
+    ETag,
+};
+use aws_sdk_s3::{
+    operation::head_object::HeadObjectOutput,
+    primitives::DateTime,
+    types::{Bucket, Object},
+};
+use md5::{Digest, Md5};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Cursor,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::SystemTime,
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Minimum size of every part except the last, in bytes
+const MIN_PART_SIZE: usize = 5_242_880;
+
+/// A version of an object, or a delete marker
+#[derive(Debug, Clone)]
+struct StoredVersion {
+    version_id: Option<String>,
+    bytes: Arc<Vec<u8>>,
+    metadata: HashMap<String, String>,
+    content_type: Option<String>,
+    e_tag: String,
+    last_modified: DateTime,
+    delete_marker: bool,
+}
+
+#[derive(Debug)]
+struct StoredBucket {
+    creation_date: DateTime,
+    versioning: bool,
+    /// The versions of every object by its key, the latest last
+    objects: BTreeMap<String, Vec<StoredVersion>>,
+}
+
+impl StoredBucket {
+    fn latest(&self, object_name: &str) -> Option<&StoredVersion> {
+        self.objects
+            .get(object_name)
+            .and_then(|versions| versions.last())
+            .filter(|version| !version.delete_marker)
+    }
+
+    fn version(&self, object_name: &str, version_id: Option<&str>) -> Option<&StoredVersion> {
+        match version_id {
+            Some(version_id) => self
+                .objects
+                .get(object_name)?
+                .iter()
+                .find(|version| version.version_id.as_deref() == Some(version_id))
+                .filter(|version| !version.delete_marker),
+            None => self.latest(object_name),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MultipartUpload {
+    bucket_name: String,
+    object_name: String,
+    parts: BTreeMap<usize, (String, Vec<u8>)>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    buckets: BTreeMap<String, StoredBucket>,
+    uploads: HashMap<String, MultipartUpload>,
+    next_id: u64,
+}
+
+impl State {
+    fn next_id(&mut self) -> String {
+        self.next_id += 1;
+
+        format!("{:020}", self.next_id)
+    }
+
+    fn bucket(&self, bucket_name: &str) -> Result<&StoredBucket, Error> {
+        self.buckets
+            .get(bucket_name)
+            .ok_or(no_such_bucket(bucket_name))
+    }
+
+    fn bucket_mut(&mut self, bucket_name: &str) -> Result<&mut StoredBucket, Error> {
+        self.buckets
+            .get_mut(bucket_name)
+            .ok_or(no_such_bucket(bucket_name))
+    }
+
+    /// Stores a new latest version of an object, replacing the
+    /// unversioned one unless versioning is enabled
+    fn put(
+        &mut self,
+        bucket_name: &str,
+        object_name: &str,
+        bytes: Vec<u8>,
+        metadata: HashMap<String, String>,
+        content_type: Option<String>,
+        e_tag: String,
+    ) -> Result<(), Error> {
+        let version_id = Some(self.next_id());
+        let bucket = self.bucket_mut(bucket_name)?;
+        let versions = bucket.objects.entry(object_name.to_string()).or_default();
+
+        if !bucket.versioning {
+            versions.retain(|version| version.version_id.is_some());
+        }
+
+        versions.push(StoredVersion {
+            version_id: version_id.filter(|_| bucket.versioning),
+            bytes: Arc::new(bytes),
+            metadata,
+            content_type,
+            e_tag,
+            last_modified: now(),
+            delete_marker: false,
+        });
+
+        Ok(())
+    }
+}
+
+fn no_such_bucket(bucket_name: &str) -> Error {
+    Error::SdkError(format!(
+        "NoSuchBucket: Bucket {} does not exist",
+        bucket_name
+    ))
+}
+
+fn now() -> DateTime {
+    DateTime::from(SystemTime::now())
+}
+
+fn md5_hex(bytes: &[u8]) -> String {
+    Md5::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Returns a stub `PresignedUrl` for the `method`, which only
+/// identifies the request since there is no server to send it to
+fn presigned_url(
+    method: &str,
+    bucket_name: &str,
+    object_name: &str,
+    presigned_expiry_secs: u64,
+    headers: HashMap<String, String>,
+) -> PresignedUrl {
+    PresignedUrl {
+        method: method.to_string(),
+        url: format!(
+            "memory://{}/{}?X-Amz-Expires={}",
+            uri_encode(bucket_name, true),
+            uri_encode(object_name, false),
+            presigned_expiry_secs
+        ),
+        headers,
+        expires_at: u64::try_from(now().secs())
+            .ok()
+            .and_then(|secs| secs.checked_add(presigned_expiry_secs)),
+    }
+}
+
+/// An `ObjectStorage` keeping buckets and objects in memory, to test
+/// code written against `ObjectStorage` without a MinIO server
+///
+/// Supports versioning, user metadata, ranged gets and multipart
+/// uploads with the same `ETag`s and part size limits as S3. Presigned
+/// requests are stubs with a `memory://` url, and server-side
+/// encryption, checksums and compression are accepted but not applied.
+///
+/// Clones share the same buckets.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let storage = InMemoryStorage::new();
+///
+/// storage.create_bucket("sharks").await?;
+/// storage.upload_object("sharks", "shark.jpg", shark_bytes.as_slice(), None).await?;
+///
+/// assert!(storage.object_exists("sharks", "shark.jpg").await?);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryStorage {
+    state: Arc<Mutex<State>>,
+}
+
+impl InMemoryStorage {
+    /// Constructs an empty InMemoryStorage
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let storage = InMemoryStorage::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ObjectStorage for InMemoryStorage {
+    async fn create_bucket(&self, bucket_name: &str) -> Result<bool, Error> {
+        let mut state = self.state();
+
+        if state.buckets.contains_key(bucket_name) {
+            return Ok(false);
+        }
+
+        state.buckets.insert(
+            bucket_name.to_string(),
+            StoredBucket {
+                creation_date: now(),
+                versioning: false,
+                objects: BTreeMap::new(),
+            },
+        );
+
+        Ok(true)
+    }
+
+    async fn delete_bucket(&self, bucket_name: &str, delete_objects: bool) -> Result<bool, Error> {
+        let mut state = self.state();
+
+        let Some(bucket) = state.buckets.get(bucket_name) else {
+            return Ok(false);
+        };
+
+        if !delete_objects && !bucket.objects.is_empty() {
+            return Err(Error::SdkError(format!(
+                "BucketNotEmpty: Bucket {} is not empty",
+                bucket_name
+            )));
+        }
+
+        state.buckets.remove(bucket_name);
+        state
+            .uploads
+            .retain(|_, upload| upload.bucket_name != bucket_name);
+
+        Ok(true)
+    }
+
+    async fn bucket_exists(&self, bucket_name: &str) -> Result<bool, Error> {
+        Ok(self.state().buckets.contains_key(bucket_name))
+    }
+
+    async fn list_buckets(&self) -> Result<Vec<Bucket>, Error> {
+        Ok(self
+            .state()
+            .buckets
+            .iter()
+            .map(|(bucket_name, bucket)| {
+                Bucket::builder()
+                    .name(bucket_name)
+                    .creation_date(bucket.creation_date)
+                    .build()
+            })
+            .collect())
+    }
+
+    async fn list_bucket_objects(&self, bucket_name: &str) -> Result<Vec<Object>, Error> {
+        let state = self.state();
+        let bucket = state.bucket(bucket_name)?;
+
+        Ok(bucket
+            .objects
+            .keys()
+            .filter_map(|object_name| {
+                let version = bucket.latest(object_name)?;
+
+                Some(
+                    Object::builder()
+                        .key(object_name)
+                        .size(version.bytes.len() as i64)
+                        .e_tag(&version.e_tag)
+                        .last_modified(version.last_modified)
+                        .build(),
+                )
+            })
+            .collect())
+    }
+
+    async fn set_bucket_versioning(&self, bucket_name: &str, enabled: bool) -> Result<(), Error> {
+        self.state().bucket_mut(bucket_name)?.versioning = enabled;
+
+        Ok(())
+    }
+
+    async fn object_exists(&self, bucket_name: &str, object_name: &str) -> Result<bool, Error> {
+        Ok(self
+            .state()
+            .buckets
+            .get(bucket_name)
+            .is_some_and(|bucket| bucket.latest(object_name).is_some()))
+    }
+
+    async fn upload_object<S>(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        mut stream: S,
+        additional_options: Option<UploadObjectAdditionalOptions>,
+    ) -> Result<usize, Error>
+    where
+        S: AsyncRead + Unpin + Send,
+    {
+        let mut bytes = vec![];
+        stream
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+
+        let bytes_uploaded = bytes.len();
+        let e_tag = format!("\"{}\"", md5_hex(&bytes));
+        let additional_options = additional_options.unwrap_or_default();
+        let metadata = additional_options.metadata.unwrap_or_default();
+
+        self.state().put(
+            bucket_name,
+            object_name,
+            bytes,
+            metadata,
+            additional_options.content_type,
+            e_tag,
+        )?;
+
+        Ok(bytes_uploaded)
+    }
+
+    async fn get_object(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        additional_options: Option<GetObjectAdditionalOptions>,
+    ) -> Result<Option<ObjectStream>, Error> {
+        let additional_options = additional_options.unwrap_or_default();
+        let state = self.state();
+
+        let Some(version) = state
+            .bucket(bucket_name)?
+            .version(object_name, additional_options.version_id.as_deref())
+        else {
+            return Ok(None);
+        };
+
+        let bytes = match additional_options.range {
+            Some((start, end)) => {
+                if start > end || start >= version.bytes.len() {
+                    return Err(Error::SdkError(format!(
+                        "InvalidRange: Range {}-{} of {} is not satisfiable",
+                        start, end, object_name
+                    )));
+                }
+
+                version.bytes[start..=end.min(version.bytes.len() - 1)].to_vec()
+            }
+            None => version.bytes.to_vec(),
+        };
+
+        Ok(Some(Box::pin(Cursor::new(bytes))))
+    }
+
+    async fn stat_object(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        additional_options: Option<StatObjectAdditionalOptions>,
+    ) -> Result<Option<HeadObjectOutput>, Error> {
+        let additional_options = additional_options.unwrap_or_default();
+        let state = self.state();
+
+        Ok(state
+            .bucket(bucket_name)?
+            .version(object_name, additional_options.version_id.as_deref())
+            .map(|version| {
+                HeadObjectOutput::builder()
+                    .content_length(version.bytes.len() as i64)
+                    .e_tag(&version.e_tag)
+                    .last_modified(version.last_modified)
+                    .set_metadata(Some(version.metadata.clone()))
+                    .set_content_type(version.content_type.clone())
+                    .set_version_id(version.version_id.clone())
+                    .build()
+            }))
+    }
+
+    async fn delete_object(&self, bucket_name: &str, object_name: &str) -> Result<(), Error> {
+        let mut state = self.state();
+        let version_id = Some(state.next_id());
+        let bucket = state.bucket_mut(bucket_name)?;
+
+        let Some(versions) = bucket.objects.get_mut(object_name) else {
+            return Ok(());
+        };
+
+        if !bucket.versioning {
+            versions.retain(|version| version.version_id.is_some());
+
+            if versions.is_empty() {
+                bucket.objects.remove(object_name);
+                return Ok(());
+            }
+        }
+
+        versions.push(StoredVersion {
+            version_id: version_id.filter(|_| bucket.versioning),
+            bytes: Arc::default(),
+            metadata: HashMap::new(),
+            content_type: None,
+            e_tag: String::new(),
+            last_modified: now(),
+            delete_marker: true,
+        });
+
+        Ok(())
+    }
+
+    async fn start_multipart_upload(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+    ) -> Result<String, Error> {
+        let mut state = self.state();
+        state.bucket(bucket_name)?;
+
+        let upload_id = state.next_id();
+        state.uploads.insert(
+            upload_id.clone(),
+            MultipartUpload {
+                bucket_name: bucket_name.to_string(),
+                object_name: object_name.to_string(),
+                parts: BTreeMap::new(),
+            },
+        );
+
+        Ok(upload_id)
+    }
+
+    async fn upload_part(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        upload_id: &str,
+        part_number: usize,
+        bytes: Vec<u8>,
+    ) -> Result<ETag, Error> {
+        let mut state = self.state();
+        let upload = state
+            .uploads
+            .get_mut(upload_id)
+            .filter(|upload| upload.bucket_name == bucket_name && upload.object_name == object_name)
+            .ok_or(Error::SdkError(format!(
+                "NoSuchUpload: Upload {} does not exist",
+                upload_id
+            )))?;
+
+        let e_tag = format!("\"{}\"", md5_hex(&bytes));
+        upload.parts.insert(part_number, (e_tag.clone(), bytes));
+
+        Ok(ETag { e_tag, part_number })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        upload_id: &str,
+        mut e_tags: Vec<ETag>,
+    ) -> Result<(), Error> {
+        let mut state = self.state();
+        let upload = state
+            .uploads
+            .get(upload_id)
+            .filter(|upload| upload.bucket_name == bucket_name && upload.object_name == object_name)
+            .ok_or(Error::SdkError(format!(
+                "NoSuchUpload: Upload {} does not exist",
+                upload_id
+            )))?;
+
+        e_tags.sort_by_key(|e_tag| e_tag.part_number);
+
+        let mut bytes = vec![];
+        let mut part_digests = vec![];
+
+        for (index, ETag { e_tag, part_number }) in e_tags.iter().enumerate() {
+            let (_, part_bytes) = upload
+                .parts
+                .get(part_number)
+                .filter(|(part_e_tag, _)| part_e_tag.trim_matches('"') == e_tag.trim_matches('"'))
+                .ok_or(Error::SdkError(format!(
+                    "InvalidPart: Part {} with ETag {} was not uploaded",
+                    part_number, e_tag
+                )))?;
+
+            if index + 1 < e_tags.len() && part_bytes.len() < MIN_PART_SIZE {
+                return Err(Error::SdkError(format!(
+                    "EntityTooSmall: Part {} is smaller than {} bytes",
+                    part_number, MIN_PART_SIZE
+                )));
+            }
+
+            bytes.extend_from_slice(part_bytes);
+            part_digests.extend(Md5::digest(part_bytes));
+        }
+
+        let e_tag = format!("\"{}-{}\"", md5_hex(&part_digests), e_tags.len());
+
+        state.uploads.remove(upload_id);
+        state.put(bucket_name, object_name, bytes, HashMap::new(), None, e_tag)
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        _bucket_name: &str,
+        _object_name: &str,
+        upload_id: &str,
+    ) -> Result<(), Error> {
+        self.state().uploads.remove(upload_id);
+
+        Ok(())
+    }
+
+    async fn get_object_presigned(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        presigned_expiry_secs: u64,
+        _additional_options: Option<GetObjectPresignedAdditionalOptions>,
+    ) -> Result<Option<PresignedUrl>, Error> {
+        if !self.object_exists(bucket_name, object_name).await? {
+            return Ok(None);
+        }
+
+        Ok(Some(presigned_url(
+            "GET",
+            bucket_name,
+            object_name,
+            presigned_expiry_secs,
+            HashMap::new(),
+        )))
+    }
+
+    async fn upload_object_presigned(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        presigned_expiry_secs: u64,
+        additional_options: Option<UploadObjectPresignedAdditionalOptions>,
+    ) -> Result<PresignedUrl, Error> {
+        let additional_options = additional_options.unwrap_or_default();
+        let mut headers = HashMap::new();
+
+        if let Some(content_type) = additional_options.content_type {
+            headers.insert("content-type".to_string(), content_type);
+        }
+
+        if let Some(content_md5) = additional_options.content_md5 {
+            headers.insert("content-md5".to_string(), content_md5);
+        }
+
+        for (key, value) in additional_options.metadata.unwrap_or_default() {
+            headers.insert(format!("x-amz-meta-{}", key), value);
+        }
+
+        Ok(presigned_url(
+            "PUT",
+            bucket_name,
+            object_name,
+            presigned_expiry_secs,
+            headers,
+        ))
+    }
+
+    async fn delete_object_presigned(
+        &self,
+        bucket_name: &str,
+        object_name: &str,
+        presigned_expiry_secs: u64,
+    ) -> Result<PresignedUrl, Error> {
+        Ok(presigned_url(
+            "DELETE",
+            bucket_name,
+            object_name,
+            presigned_expiry_secs,
+            HashMap::new(),
+        ))
+    }
+}
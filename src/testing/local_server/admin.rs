@@ -0,0 +1,681 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{respond, sigv4, Response, ServerState};
+use crate::core::{
+    admin::madmin::{decrypt_data, encrypt_data},
+    storage::ObjectStorage,
+};
+use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+use hyper::{http::request::Parts, Method, StatusCode};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    time::SystemTime,
+};
+
+/// Path of the MinIO admin API
+pub(super) const ADMIN_PATH: &str = "/minio/admin/v3/";
+
+/// The users, groups, canned policies, service accounts
+/// and bucket quotas of a `LocalS3Server`
+#[derive(Debug, Default)]
+pub(super) struct AdminState {
+    users: BTreeMap<String, User>,
+    groups: BTreeMap<String, Group>,
+    policies: BTreeMap<String, Value>,
+    service_accounts: BTreeMap<String, ServiceAccount>,
+    next_service_account: u64,
+    bucket_quotas: BTreeMap<String, u64>,
+}
+
+#[derive(Debug)]
+struct User {
+    status: String,
+    policies: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+struct Group {
+    status: String,
+    members: BTreeSet<String>,
+    policies: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+struct ServiceAccount {
+    parent_user: String,
+    status: String,
+    policy: Option<Value>,
+    name: Option<String>,
+    description: Option<String>,
+    expiration: Option<String>,
+}
+
+/// An error of the admin API, responded as its JSON body
+struct AdminError(StatusCode, &'static str, String);
+
+type AdminResult = Result<Response, AdminError>;
+
+fn no_such(code: &'static str, name: &str) -> AdminError {
+    AdminError(
+        StatusCode::NOT_FOUND,
+        code,
+        format!("The specified entity {} does not exist", name),
+    )
+}
+
+fn bad_request(message: &str) -> AdminError {
+    AdminError(
+        StatusCode::BAD_REQUEST,
+        "XMinioAdminInvalidArgument",
+        message.to_string(),
+    )
+}
+
+fn respond_json(json: &Value) -> AdminResult {
+    Ok(respond(
+        StatusCode::OK,
+        vec![("content-type".to_string(), "application/json".to_string())],
+        json.to_string().into_bytes(),
+    ))
+}
+
+fn respond_encrypted(state: &ServerState, json: &Value) -> AdminResult {
+    let body =
+        encrypt_data(&state.secret_access_key, json.to_string().as_bytes()).map_err(|err| {
+            AdminError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "InternalError",
+                err.to_string(),
+            )
+        })?;
+
+    Ok(respond(StatusCode::OK, vec![], body))
+}
+
+fn respond_empty() -> AdminResult {
+    Ok(respond(StatusCode::OK, vec![], vec![]))
+}
+
+fn decrypt_json(state: &ServerState, body: &[u8]) -> Result<Value, AdminError> {
+    let body = decrypt_data(&state.secret_access_key, body)
+        .map_err(|err| bad_request(&err.to_string()))?;
+
+    parse_json(&body)
+}
+
+fn parse_json(body: &[u8]) -> Result<Value, AdminError> {
+    serde_json::from_slice(body).map_err(|err| {
+        AdminError(
+            StatusCode::BAD_REQUEST,
+            "XMinioMalformedJSON",
+            err.to_string(),
+        )
+    })
+}
+
+fn string(json: &Value, key: &str) -> Option<String> {
+    json.get(key)
+        .and_then(Value::as_str)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+fn strings(json: &Value, key: &str) -> Vec<String> {
+    json.get(key)
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn joined(policies: &BTreeSet<String>) -> String {
+    policies.iter().cloned().collect::<Vec<_>>().join(",")
+}
+
+/// Handles a request to the admin API, at `path` below `ADMIN_PATH`
+pub(super) async fn route(state: &ServerState, parts: &Parts, path: &str, body: &[u8]) -> Response {
+    let query = sigv4::query_pairs(parts.uri.query())
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    // The storage is read before the admin state is locked, as the lock is not held across awaits
+    let result = match (&parts.method, path) {
+        (&Method::GET, "datausageinfo") => data_usage_info(state).await,
+        _ => match query.get("bucket") {
+            Some(bucket_name)
+                if !state
+                    .storage
+                    .bucket_exists(bucket_name)
+                    .await
+                    .unwrap_or_default() =>
+            {
+                Err(AdminError(
+                    StatusCode::NOT_FOUND,
+                    "NoSuchBucket",
+                    format!("The specified bucket {} does not exist", bucket_name),
+                ))
+            }
+            _ => route_admin_state(state, parts, path, &query, body),
+        },
+    };
+
+    result.unwrap_or_else(|AdminError(status, code, message)| {
+        respond(
+            status,
+            vec![("content-type".to_string(), "application/json".to_string())],
+            json!({
+                "Code": code,
+                "Message": message,
+                "Resource": format!("{}{}", ADMIN_PATH, path),
+            })
+            .to_string()
+            .into_bytes(),
+        )
+    })
+}
+
+fn route_admin_state(
+    state: &ServerState,
+    parts: &Parts,
+    path: &str,
+    query: &HashMap<String, String>,
+    body: &[u8],
+) -> AdminResult {
+    let param = |name: &str| {
+        query
+            .get(name)
+            .cloned()
+            .ok_or_else(|| bad_request(&format!("{} is missing", name)))
+    };
+
+    let Ok(mut admin) = state.admin.lock() else {
+        return Err(AdminError(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "InternalError",
+            "The admin state is poisoned".to_string(),
+        ));
+    };
+    let admin = &mut *admin;
+
+    match (&parts.method, path) {
+        (&Method::PUT, "add-user") => param("accessKey").and_then(|access_key| {
+            let request = decrypt_json(state, body)?;
+            string(&request, "secretKey").ok_or_else(|| bad_request("secretKey is missing"))?;
+
+            let user = admin.users.entry(access_key).or_insert(User {
+                status: String::new(),
+                policies: BTreeSet::new(),
+            });
+            user.status = string(&request, "status").unwrap_or("enabled".to_string());
+
+            respond_empty()
+        }),
+        (&Method::DELETE, "remove-user") => param("accessKey").and_then(|access_key| {
+            admin
+                .users
+                .remove(&access_key)
+                .ok_or_else(|| no_such("XMinioAdminNoSuchUser", &access_key))?;
+
+            for group in admin.groups.values_mut() {
+                group.members.remove(&access_key);
+            }
+
+            respond_empty()
+        }),
+        (&Method::GET, "list-users") => {
+            let users = admin
+                .users
+                .keys()
+                .map(|access_key| (access_key.clone(), user_info(admin, access_key)))
+                .collect::<Map<_, _>>();
+
+            respond_encrypted(state, &Value::Object(users))
+        }
+        (&Method::GET, "user-info") => param("accessKey").and_then(|access_key| {
+            if !admin.users.contains_key(&access_key) {
+                return Err(no_such("XMinioAdminNoSuchUser", &access_key));
+            }
+
+            respond_json(&user_info(admin, &access_key))
+        }),
+        (&Method::PUT, "set-user-status") => param("accessKey").and_then(|access_key| {
+            let status = param("status")?;
+
+            admin
+                .users
+                .get_mut(&access_key)
+                .ok_or_else(|| no_such("XMinioAdminNoSuchUser", &access_key))?
+                .status = status;
+
+            respond_empty()
+        }),
+        (&Method::PUT, "update-group-members") => {
+            parse_json(body).and_then(|request| update_group_members(admin, &request))
+        }
+        (&Method::GET, "groups") => respond_json(&json!(admin.groups.keys().collect::<Vec<_>>())),
+        (&Method::GET, "group") => param("group").and_then(|name| {
+            let group = admin
+                .groups
+                .get(&name)
+                .ok_or_else(|| no_such("XMinioAdminNoSuchGroup", &name))?;
+
+            respond_json(&json!({
+                "name": name,
+                "status": group.status,
+                "members": group.members,
+                "policy": joined(&group.policies),
+            }))
+        }),
+        (&Method::PUT, "set-group-status") => param("group").and_then(|name| {
+            let status = param("status")?;
+
+            admin
+                .groups
+                .get_mut(&name)
+                .ok_or_else(|| no_such("XMinioAdminNoSuchGroup", &name))?
+                .status = status;
+
+            respond_empty()
+        }),
+        (&Method::PUT, "add-canned-policy") => param("name").and_then(|name| {
+            admin.policies.insert(name, parse_json(body)?);
+
+            respond_empty()
+        }),
+        (&Method::DELETE, "remove-canned-policy") => param("name").and_then(|name| {
+            admin
+                .policies
+                .remove(&name)
+                .ok_or_else(|| no_such("XMinioAdminNoSuchPolicy", &name))?;
+
+            respond_empty()
+        }),
+        (&Method::GET, "list-canned-policies") => respond_json(&json!(admin.policies)),
+        (&Method::GET, "info-canned-policy") => param("name").and_then(|name| {
+            respond_json(
+                admin
+                    .policies
+                    .get(&name)
+                    .ok_or_else(|| no_such("XMinioAdminNoSuchPolicy", &name))?,
+            )
+        }),
+        (&Method::POST, "idp/builtin/policy/attach") => decrypt_json(state, body)
+            .and_then(|request| update_policy_association(state, admin, &request, true)),
+        (&Method::POST, "idp/builtin/policy/detach") => decrypt_json(state, body)
+            .and_then(|request| update_policy_association(state, admin, &request, false)),
+        (&Method::PUT, "add-service-account") => decrypt_json(state, body)
+            .and_then(|request| add_service_account(state, admin, &request)),
+        (&Method::POST, "update-service-account") => param("accessKey").and_then(|access_key| {
+            let request = decrypt_json(state, body)?;
+            let service_account = admin
+                .service_accounts
+                .get_mut(&access_key)
+                .ok_or_else(|| no_such("XMinioAdminServiceAccountNotFound", &access_key))?;
+
+            match string(&request, "newStatus").as_deref() {
+                Some("enabled") => service_account.status = "on".to_string(),
+                Some("disabled") => service_account.status = "off".to_string(),
+                _ => {}
+            }
+            if let Some(policy) = request.get("newPolicy") {
+                service_account.policy = Some(policy.clone());
+            }
+            if let Some(name) = string(&request, "newName") {
+                service_account.name = Some(name);
+            }
+            if let Some(description) = string(&request, "newDescription") {
+                service_account.description = Some(description);
+            }
+            if let Some(expiration) = string(&request, "newExpiration") {
+                service_account.expiration = Some(expiration);
+            }
+
+            respond_empty()
+        }),
+        (&Method::DELETE, "delete-service-account") => param("accessKey").and_then(|access_key| {
+            admin
+                .service_accounts
+                .remove(&access_key)
+                .ok_or_else(|| no_such("XMinioAdminServiceAccountNotFound", &access_key))?;
+
+            respond_empty()
+        }),
+        (&Method::GET, "list-service-accounts") => {
+            let user = query
+                .get("user")
+                .cloned()
+                .unwrap_or(state.access_key_id.clone());
+            let accounts = admin
+                .service_accounts
+                .iter()
+                .filter(|(_, service_account)| service_account.parent_user == user)
+                .map(|(access_key, service_account)| {
+                    service_account_info(access_key, service_account, false)
+                })
+                .collect::<Vec<_>>();
+
+            respond_encrypted(state, &json!({ "accounts": accounts }))
+        }
+        (&Method::GET, "info-service-account") => param("accessKey").and_then(|access_key| {
+            let service_account = admin
+                .service_accounts
+                .get(&access_key)
+                .ok_or_else(|| no_such("XMinioAdminServiceAccountNotFound", &access_key))?;
+
+            respond_encrypted(
+                state,
+                &service_account_info(&access_key, service_account, true),
+            )
+        }),
+        (&Method::GET, "get-bucket-quota") => param("bucket").and_then(|bucket_name| {
+            let quota = admin.bucket_quotas.get(&bucket_name).ok_or(AdminError(
+                StatusCode::NOT_FOUND,
+                "XMinioAdminNoSuchQuotaConfiguration",
+                "The quota configuration does not exist".to_string(),
+            ))?;
+
+            respond_json(&json!({
+                "quota": quota,
+                "size": quota,
+                "quotatype": "hard",
+            }))
+        }),
+        (&Method::PUT, "set-bucket-quota") => param("bucket").and_then(|bucket_name| {
+            let request = parse_json(body)?;
+            let quota = ["size", "quota"]
+                .iter()
+                .filter_map(|key| request.get(*key).and_then(Value::as_u64))
+                .max()
+                .unwrap_or_default();
+
+            // A quota of 0 clears it
+            if quota == 0 {
+                admin.bucket_quotas.remove(&bucket_name);
+            } else {
+                admin.bucket_quotas.insert(bucket_name, quota);
+            }
+
+            respond_empty()
+        }),
+        _ => Err(AdminError(
+            StatusCode::NOT_IMPLEMENTED,
+            "XMinioAdminNotImplemented",
+            "The admin API is not implemented by LocalS3Server".to_string(),
+        )),
+    }
+}
+
+/// Responds the usage of the buckets as of now, as if the server had just scanned them
+async fn data_usage_info(state: &ServerState) -> AdminResult {
+    let internal_error = |err: crate::error::Error| {
+        AdminError(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "InternalError",
+            err.to_string(),
+        )
+    };
+
+    let mut buckets_usage = Map::new();
+    let (mut objects_count, mut objects_total_size) = (0, 0);
+
+    for bucket in state.storage.list_buckets().await.map_err(internal_error)? {
+        let bucket_name = bucket.name().unwrap_or_default();
+        let objects = state
+            .storage
+            .list_bucket_objects(bucket_name)
+            .await
+            .map_err(internal_error)?;
+        let size = objects
+            .iter()
+            .map(|object| object.size().unwrap_or_default() as u64)
+            .sum::<u64>();
+
+        objects_count += objects.len() as u64;
+        objects_total_size += size;
+        buckets_usage.insert(
+            bucket_name.to_string(),
+            json!({
+                "size": size,
+                "objectsCount": objects.len(),
+                "versionsCount": objects.len(),
+                "deleteMarkersCount": 0,
+            }),
+        );
+    }
+
+    let last_update = DateTime::from(SystemTime::now())
+        .fmt(DateTimeFormat::DateTime)
+        .map_err(|err| {
+            AdminError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "InternalError",
+                err.to_string(),
+            )
+        })?;
+
+    respond_json(&json!({
+        "lastUpdate": last_update,
+        "objectsCount": objects_count,
+        "versionsCount": objects_count,
+        "deleteMarkersCount": 0,
+        "objectsTotalSize": objects_total_size,
+        "bucketsCount": buckets_usage.len(),
+        "bucketsUsageInfo": buckets_usage,
+    }))
+}
+
+fn user_info(admin: &AdminState, access_key: &str) -> Value {
+    let user = &admin.users[access_key];
+    let member_of = admin
+        .groups
+        .iter()
+        .filter(|(_, group)| group.members.contains(access_key))
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+
+    json!({
+        "policyName": joined(&user.policies),
+        "status": user.status,
+        "memberOf": member_of,
+    })
+}
+
+fn update_group_members(admin: &mut AdminState, request: &Value) -> AdminResult {
+    let name = string(request, "group").ok_or_else(|| bad_request("group is missing"))?;
+    let members = strings(request, "members");
+    let is_remove = request
+        .get("isRemove")
+        .and_then(Value::as_bool)
+        .unwrap_or_default();
+
+    if let Some(member) = members
+        .iter()
+        .find(|member| !admin.users.contains_key(*member))
+    {
+        return Err(no_such("XMinioAdminNoSuchUser", member));
+    }
+
+    if !is_remove {
+        admin
+            .groups
+            .entry(name)
+            .or_insert(Group {
+                status: "enabled".to_string(),
+                members: BTreeSet::new(),
+                policies: BTreeSet::new(),
+            })
+            .members
+            .extend(members);
+
+        return respond_empty();
+    }
+
+    let group = admin
+        .groups
+        .get_mut(&name)
+        .ok_or_else(|| no_such("XMinioAdminNoSuchGroup", &name))?;
+
+    if members.is_empty() {
+        if !group.members.is_empty() {
+            return Err(AdminError(
+                StatusCode::BAD_REQUEST,
+                "XMinioAdminGroupNotEmpty",
+                format!("The specified group {} is not empty", name),
+            ));
+        }
+
+        admin.groups.remove(&name);
+    } else {
+        for member in &members {
+            group.members.remove(member);
+        }
+    }
+
+    respond_empty()
+}
+
+fn update_policy_association(
+    state: &ServerState,
+    admin: &mut AdminState,
+    request: &Value,
+    attach: bool,
+) -> AdminResult {
+    let policies = strings(request, "policies");
+
+    if let Some(policy) = policies
+        .iter()
+        .find(|policy| !admin.policies.contains_key(*policy))
+    {
+        return Err(no_such("XMinioAdminNoSuchPolicy", policy));
+    }
+
+    let entity_policies = match (string(request, "user"), string(request, "group")) {
+        (Some(user), None) => {
+            &mut admin
+                .users
+                .get_mut(&user)
+                .ok_or_else(|| no_such("XMinioAdminNoSuchUser", &user))?
+                .policies
+        }
+        (None, Some(group)) => {
+            &mut admin
+                .groups
+                .get_mut(&group)
+                .ok_or_else(|| no_such("XMinioAdminNoSuchGroup", &group))?
+                .policies
+        }
+        _ => return Err(bad_request("Exactly one of user or group must be set")),
+    };
+
+    let changed = policies
+        .into_iter()
+        .filter(|policy| {
+            if attach {
+                entity_policies.insert(policy.clone())
+            } else {
+                entity_policies.remove(policy)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if changed.is_empty() {
+        return Err(AdminError(
+            StatusCode::BAD_REQUEST,
+            "XMinioAdminPolicyChangeAlreadyApplied",
+            "The specified policy change is already in effect".to_string(),
+        ));
+    }
+
+    respond_encrypted(
+        state,
+        &if attach {
+            json!({ "policiesAttached": changed })
+        } else {
+            json!({ "policiesDetached": changed })
+        },
+    )
+}
+
+fn add_service_account(
+    state: &ServerState,
+    admin: &mut AdminState,
+    request: &Value,
+) -> AdminResult {
+    let parent_user = string(request, "targetUser").unwrap_or(state.access_key_id.clone());
+
+    if parent_user != state.access_key_id && !admin.users.contains_key(&parent_user) {
+        return Err(no_such("XMinioAdminNoSuchUser", &parent_user));
+    }
+
+    admin.next_service_account += 1;
+    let access_key = string(request, "accessKey")
+        .unwrap_or(format!("SVCACCT{:013}", admin.next_service_account));
+    let secret_key = string(request, "secretKey")
+        .unwrap_or(format!("svcacct-secret-{}", admin.next_service_account));
+
+    if admin.service_accounts.contains_key(&access_key) || admin.users.contains_key(&access_key) {
+        return Err(AdminError(
+            StatusCode::CONFLICT,
+            "XMinioAdminServiceAccountAlreadyExists",
+            format!(
+                "The specified service account {} already exists",
+                access_key
+            ),
+        ));
+    }
+
+    admin.service_accounts.insert(
+        access_key.clone(),
+        ServiceAccount {
+            parent_user,
+            status: "on".to_string(),
+            policy: request.get("policy").cloned(),
+            name: string(request, "name"),
+            description: string(request, "description"),
+            expiration: string(request, "expiration"),
+        },
+    );
+
+    respond_encrypted(
+        state,
+        &json!({
+            "credentials": {
+                "accessKey": access_key,
+                "secretKey": secret_key,
+            }
+        }),
+    )
+}
+
+fn service_account_info(
+    access_key: &str,
+    service_account: &ServiceAccount,
+    with_policy: bool,
+) -> Value {
+    let mut info = json!({
+        "parentUser": service_account.parent_user,
+        "accountStatus": service_account.status,
+        "impliedPolicy": service_account.policy.is_none(),
+        "name": service_account.name,
+        "description": service_account.description,
+        "expiration": service_account.expiration,
+    });
+
+    if with_policy {
+        info["policy"] = json!(service_account
+            .policy
+            .as_ref()
+            .map(Value::to_string)
+            .unwrap_or_default());
+    } else {
+        info["accessKey"] = json!(access_key);
+    }
+
+    info
+}
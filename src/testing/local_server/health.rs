@@ -0,0 +1,49 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{respond, Response, ServerState};
+use hyper::{http::request::Parts, StatusCode};
+use std::sync::atomic::Ordering;
+
+/// Path of the MinIO health API
+pub(super) const HEALTH_PATH: &str = "/minio/health/";
+
+/// Responds to the health check at `check` below `HEALTH_PATH` as a single
+/// drive server, which never keeps write quorum when taken down for maintenance
+pub(super) fn route(state: &ServerState, parts: &Parts, check: &str) -> Response {
+    let healthy = state.healthy.load(Ordering::Relaxed);
+    let maintenance = parts
+        .uri
+        .query()
+        .is_some_and(|query| query.split('&').any(|pair| pair == "maintenance=true"));
+
+    let mut headers = vec![];
+    if !healthy {
+        headers.push(("x-minio-server-status".to_string(), "offline".to_string()));
+    }
+
+    let status = match check {
+        "live" | "ready" | "cluster/read" if healthy => StatusCode::OK,
+        "live" | "ready" | "cluster/read" => StatusCode::SERVICE_UNAVAILABLE,
+        "cluster" => {
+            headers.extend([
+                ("x-minio-write-quorum".to_string(), "1".to_string()),
+                (
+                    "x-minio-storage-class-defaults".to_string(),
+                    "true".to_string(),
+                ),
+            ]);
+
+            if maintenance {
+                StatusCode::PRECONDITION_FAILED
+            } else if healthy {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+        }
+        _ => StatusCode::NOT_FOUND,
+    };
+
+    respond(status, headers, vec![])
+}
@@ -0,0 +1,1333 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "admin")]
+mod admin;
+#[cfg(feature = "health")]
+mod health;
+#[cfg(feature = "notifications")]
+mod notifications;
+mod sigv4;
+#[cfg(feature = "sts")]
+mod sts;
+mod xml;
+
+use super::InMemoryStorage;
+use crate::{
+    core::{
+        credentials::{CredentialsCache, StaticCredentials},
+        get::{GetObjectAdditionalOptions, StatObjectAdditionalOptions},
+        storage::ObjectStorage,
+        upload::upload_object::UploadObjectAdditionalOptions,
+    },
+    error::Error,
+    ETag, Minio,
+};
+use aws_sdk_s3::{
+    config::{BehaviorVersion, Credentials, IdentityCache, Region, SharedCredentialsProvider},
+    operation::head_object::HeadObjectOutput,
+    primitives::{DateTime, DateTimeFormat},
+    Client,
+};
+use http_body_util::{combinators::BoxBody, BodyExt, Full};
+use hyper::{
+    body::{Bytes, Incoming},
+    header::HeaderValue,
+    http::request::Parts,
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+#[cfg(feature = "sts")]
+pub use sts::{LocalStsServer, StsRequest};
+
+type Response = hyper::Response<BoxBody<Bytes, Infallible>>;
+
+/// Default maximum amount of keys returned by a `ListObjectsV2`
+const MAX_KEYS: usize = 1_000;
+
+/// How long to wait before accepting again after a failed accept, I.E.
+/// while the process is out of file descriptors
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(50);
+
+/// State shared by the connections of a `LocalS3Server`
+struct ServerState {
+    storage: InMemoryStorage,
+    access_key_id: String,
+    secret_access_key: String,
+    next_request_id: AtomicU64,
+    #[cfg(feature = "admin")]
+    admin: std::sync::Mutex<admin::AdminState>,
+    #[cfg(feature = "health")]
+    healthy: Arc<std::sync::atomic::AtomicBool>,
+    #[cfg(feature = "notifications")]
+    listeners: notifications::Listeners,
+}
+
+/// An S3 API server on an ephemeral port of `127.0.0.1`, serving the
+/// buckets of an `InMemoryStorage` so `Minio` can be tested end to end
+/// without a MinIO server
+///
+/// Implements the subset of the S3 API minior uses: buckets and their
+/// versioning, put, get *(With ranges and versions)*, head and delete
+/// of objects, multipart uploads *(With part copies)*, `ListObjectsV2`
+/// and `DeleteObjects`.
+/// Requests are rejected unless signed with SigV4, in their
+/// `Authorization` header or as a presigned url, by the credentials
+/// of the server. Other operations respond with `NotImplemented`.
+///
+/// With the `admin` feature, it also stands in for the users, groups,
+/// canned policies, service accounts, bucket quotas and data usage of
+/// the MinIO admin API, with the `health` feature for the MinIO
+/// health API, unauthenticated like MinIO's, and with the `notifications`
+/// feature streams the events of its objects as MinIO's
+/// ListenBucketNotification.
+///
+/// The server stops when dropped.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let server = LocalS3Server::start().await?;
+/// let minio: Minio = server.minio();
+///
+/// minio.create_bucket("sharks").await?;
+/// minio.upload_object("sharks", "shark.jpg", shark_bytes.as_slice(), None).await?;
+///
+/// assert!(server.storage.object_exists("sharks", "shark.jpg").await?);
+/// ```
+#[derive(Debug)]
+pub struct LocalS3Server {
+    /// The buckets served, to seed or inspect them directly
+    pub storage: InMemoryStorage,
+    url: String,
+    credentials: Credentials,
+    #[cfg(feature = "health")]
+    healthy: Arc<std::sync::atomic::AtomicBool>,
+    #[cfg(feature = "notifications")]
+    listeners: notifications::Listeners,
+    join_handle: JoinHandle<()>,
+}
+
+impl LocalS3Server {
+    /// Starts a server accepting the credentials `minioadmin`/`minioadmin`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let server = LocalS3Server::start().await?;
+    /// ```
+    pub async fn start() -> Result<Self, Error> {
+        Self::start_with_credentials("minioadmin", "minioadmin").await
+    }
+
+    /// Starts a server accepting requests signed by the
+    /// `access_key_id` and `secret_access_key`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let server = LocalS3Server::start_with_credentials("sharks", "hammerhead").await?;
+    /// ```
+    pub async fn start_with_credentials(
+        access_key_id: &str,
+        secret_access_key: &str,
+    ) -> Result<Self, Error> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+        let url = format!(
+            "http://{}",
+            listener
+                .local_addr()
+                .map_err(|err| Error::StdIo(err.kind()))?
+        );
+
+        let storage = InMemoryStorage::new();
+        #[cfg(feature = "health")]
+        let healthy = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        #[cfg(feature = "notifications")]
+        let listeners = notifications::listeners();
+        let state = Arc::new(ServerState {
+            storage: storage.clone(),
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            next_request_id: AtomicU64::new(1),
+            #[cfg(feature = "admin")]
+            admin: Default::default(),
+            #[cfg(feature = "health")]
+            healthy: healthy.clone(),
+            #[cfg(feature = "notifications")]
+            listeners: listeners.clone(),
+        });
+
+        let join_handle = tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    tokio::time::sleep(ACCEPT_BACKOFF).await;
+                    continue;
+                };
+                let state = state.clone();
+
+                tokio::spawn(async move {
+                    let service = service_fn(move |request| {
+                        let state = state.clone();
+
+                        async move { Ok::<_, Infallible>(handle(&state, request).await) }
+                    });
+
+                    // Connections closed by the client are not errors of the server
+                    let _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+
+        Ok(Self {
+            storage,
+            url,
+            credentials: Credentials::new(
+                access_key_id,
+                secret_access_key,
+                None,
+                None,
+                "LocalS3Server",
+            ),
+            #[cfg(feature = "health")]
+            healthy,
+            #[cfg(feature = "notifications")]
+            listeners,
+            join_handle,
+        })
+    }
+
+    #[cfg(feature = "health")]
+    /// Fails the health checks of the server if `healthy` is false, as
+    /// a MinIO server that lost quorum, without failing other requests
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// server.set_healthy(false);
+    ///
+    /// assert!(!minio.health(HealthCheck::Ready).await?.healthy);
+    /// ```
+    pub fn set_healthy(&self, healthy: bool) {
+        self.healthy.store(healthy, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "notifications")]
+    /// Ends the bucket notification streams of the server,
+    /// as a MinIO server restarting, so their listeners reconnect
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// server.disconnect_listeners();
+    /// ```
+    pub fn disconnect_listeners(&self) {
+        let _ = self
+            .listeners
+            .send(notifications::ListenerMessage::Disconnect);
+    }
+
+    #[cfg(feature = "notifications")]
+    /// The amount of bucket notification streams open on the server
+    pub fn listener_count(&self) -> usize {
+        self.listeners.receiver_count()
+    }
+
+    /// The url of the server, I.E. `http://127.0.0.1:49152`
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The credentials accepted by the server
+    pub fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+
+    /// Constructs a `Minio` signing requests to the server with its credentials
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let server = LocalS3Server::start().await?;
+    ///
+    /// let minio: Minio = server.minio();
+    /// ```
+    pub fn minio(&self) -> Minio {
+        let credentials_cache =
+            CredentialsCache::new(StaticCredentials::from(self.credentials.clone()));
+
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .endpoint_url(&self.url)
+            .region(Region::new("us-east-1"))
+            .credentials_provider(credentials_cache.clone())
+            .identity_cache(IdentityCache::no_cache())
+            .force_path_style(true);
+
+        #[cfg(feature = "tracing")]
+        let config = config.interceptor(crate::core::telemetry::TracingInterceptor);
+
+        Minio {
+            client: Arc::new(Client::from_conf(config.build())),
+            credentials_provider: Some(SharedCredentialsProvider::new(credentials_cache.clone())),
+            credentials_cache: Some(credentials_cache),
+            endpoint_url: Some(self.url.clone()),
+            #[cfg(any(feature = "health", feature = "notifications"))]
+            endpoint: crate::core::http::Endpoint::parse(&self.url).ok(),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+}
+
+impl Drop for LocalS3Server {
+    fn drop(&mut self) {
+        self.join_handle.abort();
+    }
+}
+
+fn respond(status: StatusCode, headers: Vec<(String, String)>, body: Vec<u8>) -> Response {
+    let mut builder = hyper::Response::builder().status(status);
+
+    for (name, value) in headers {
+        builder = builder.header(name, value);
+    }
+
+    builder
+        .body(Full::new(Bytes::from(body)).boxed())
+        .unwrap_or_else(|_| {
+            let mut response = Response::new(Full::default().boxed());
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            response
+        })
+}
+
+fn respond_xml(status: StatusCode, root: &str, contents: String) -> Response {
+    respond(
+        status,
+        vec![("content-type".to_string(), "application/xml".to_string())],
+        format!(
+            "{}<{} xmlns=\"{}\">{}</{}>",
+            xml::DECLARATION,
+            root,
+            xml::NAMESPACE,
+            contents,
+            root
+        )
+        .into_bytes(),
+    )
+}
+
+fn respond_empty(status: StatusCode) -> Response {
+    respond(status, vec![], vec![])
+}
+
+fn respond_error(status: StatusCode, code: &str, message: &str) -> Response {
+    respond(
+        status,
+        vec![("content-type".to_string(), "application/xml".to_string())],
+        format!(
+            "{}<Error>{}{}</Error>",
+            xml::DECLARATION,
+            xml::element("Code", code),
+            xml::element("Message", message)
+        )
+        .into_bytes(),
+    )
+}
+
+/// Responds with the S3 error of a `Code: message` error of `InMemoryStorage`
+fn respond_storage_error(err: Error) -> Response {
+    let message = err.to_string();
+    let (code, detail) = message
+        .split_once(": ")
+        .unwrap_or(("InternalError", &message));
+
+    let status = match code {
+        "NoSuchBucket" | "NoSuchKey" | "NoSuchUpload" => StatusCode::NOT_FOUND,
+        "BucketNotEmpty" | "BucketAlreadyOwnedByYou" => StatusCode::CONFLICT,
+        "InvalidRange" => StatusCode::RANGE_NOT_SATISFIABLE,
+        "InvalidPart" | "EntityTooSmall" | "InvalidArgument" | "MalformedXML" => {
+            StatusCode::BAD_REQUEST
+        }
+        _ => return respond_error(StatusCode::INTERNAL_SERVER_ERROR, "InternalError", &message),
+    };
+
+    respond_error(status, code, detail)
+}
+
+fn not_implemented() -> Response {
+    respond_error(
+        StatusCode::NOT_IMPLEMENTED,
+        "NotImplemented",
+        "The operation is not implemented by LocalS3Server",
+    )
+}
+
+fn http_date(date_time: &DateTime) -> String {
+    date_time.fmt(DateTimeFormat::HttpDate).unwrap_or_default()
+}
+
+fn iso_date(date_time: &DateTime) -> String {
+    date_time.fmt(DateTimeFormat::DateTime).unwrap_or_default()
+}
+
+/// Decodes an `aws-chunked` body, ignoring chunk signatures and trailers
+fn decode_aws_chunked(body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = vec![];
+    let mut rest = body;
+
+    loop {
+        let line_end = rest.windows(2).position(|window| window == b"\r\n")?;
+        let chunk_size = std::str::from_utf8(&rest[..line_end])
+            .ok()?
+            .split(';')
+            .next()
+            .and_then(|chunk_size| usize::from_str_radix(chunk_size.trim(), 16).ok())?;
+        rest = &rest[line_end + 2..];
+
+        if chunk_size == 0 {
+            return Some(decoded);
+        }
+
+        decoded.extend_from_slice(rest.get(..chunk_size)?);
+        rest = rest.get(chunk_size + 2..)?;
+    }
+}
+
+/// Returns the `x-amz-meta-*` headers of a request as user metadata
+fn request_metadata(parts: &Parts) -> HashMap<String, String> {
+    parts
+        .headers
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((
+                name.as_str().strip_prefix("x-amz-meta-")?.to_string(),
+                value.to_str().ok()?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+fn request_content_type(parts: &Parts) -> Option<String> {
+    parts
+        .headers
+        .get("content-type")
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Returns the headers describing an object in get and head responses
+fn object_headers(stat: &HeadObjectOutput) -> Vec<(String, String)> {
+    let mut headers = vec![("accept-ranges".to_string(), "bytes".to_string())];
+
+    if let Some(e_tag) = stat.e_tag() {
+        headers.push(("etag".to_string(), e_tag.to_string()));
+    }
+
+    if let Some(last_modified) = stat.last_modified() {
+        headers.push(("last-modified".to_string(), http_date(last_modified)));
+    }
+
+    if let Some(version_id) = stat.version_id() {
+        headers.push(("x-amz-version-id".to_string(), version_id.to_string()));
+    }
+
+    if let Some(content_type) = stat.content_type() {
+        headers.push(("content-type".to_string(), content_type.to_string()));
+    }
+
+    for (key, value) in stat.metadata().into_iter().flatten() {
+        headers.push((format!("x-amz-meta-{}", key), value.clone()));
+    }
+
+    headers
+}
+
+/// Parses a `Range: bytes=start-end` header, where `end` may be omitted
+fn parse_range(range: &str, size: usize) -> Option<(usize, usize)> {
+    let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+    let start = start.parse::<usize>().ok()?;
+    let end = match end {
+        "" => size.saturating_sub(1),
+        end => end.parse::<usize>().ok()?,
+    };
+
+    Some((start, end))
+}
+
+async fn handle(state: &ServerState, request: Request<Incoming>) -> Response {
+    let (parts, body) = request.into_parts();
+    let request_id = state.next_request_id.fetch_add(1, Ordering::Relaxed);
+
+    // The health API is unauthenticated
+    #[cfg(feature = "health")]
+    if let Some(check) = parts.uri.path().strip_prefix(health::HEALTH_PATH) {
+        return health::route(state, &parts, check);
+    }
+
+    let mut response = match sigv4::verify(&parts, &state.access_key_id, &state.secret_access_key) {
+        Ok(payload_hash) => match body.collect().await {
+            Ok(body) => route(state, &parts, &payload_hash, body.to_bytes()).await,
+            Err(_) => respond_error(
+                StatusCode::BAD_REQUEST,
+                "IncompleteBody",
+                "The request body could not be read",
+            ),
+        },
+        Err(err) => respond_error(StatusCode::FORBIDDEN, err.code(), err.message()),
+    };
+
+    #[cfg(feature = "notifications")]
+    notifications::publish(state, &parts, response.status()).await;
+
+    if parts.method == Method::HEAD && !response.status().is_success() {
+        *response.body_mut() = Full::default().boxed();
+    }
+
+    if let Ok(request_id) = HeaderValue::from_str(&format!("{:016X}", request_id)) {
+        response
+            .headers_mut()
+            .insert("x-amz-request-id", request_id);
+    }
+
+    response
+}
+
+async fn route(state: &ServerState, parts: &Parts, payload_hash: &str, body: Bytes) -> Response {
+    let is_aws_chunked = payload_hash.starts_with("STREAMING-")
+        || parts
+            .headers
+            .get("content-encoding")
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("aws-chunked"));
+
+    let body = if is_aws_chunked {
+        match decode_aws_chunked(&body) {
+            Some(body) => body,
+            None => {
+                return respond_error(
+                    StatusCode::BAD_REQUEST,
+                    "IncompleteBody",
+                    "The aws-chunked body is malformed",
+                )
+            }
+        }
+    } else {
+        if payload_hash != sigv4::UNSIGNED_PAYLOAD && payload_hash != sigv4::sha256_hex(&body) {
+            return respond_error(
+                StatusCode::BAD_REQUEST,
+                "XAmzContentSHA256Mismatch",
+                "The x-amz-content-sha256 does not match the request body",
+            );
+        }
+
+        body.to_vec()
+    };
+
+    #[cfg(feature = "admin")]
+    if let Some(path) = parts.uri.path().strip_prefix(admin::ADMIN_PATH) {
+        return admin::route(state, parts, path, &body).await;
+    }
+
+    let path = parts.uri.path().trim_start_matches('/');
+    let (bucket_name, object_name) = match path.split_once('/') {
+        Some((bucket_name, object_name)) => (
+            sigv4::uri_decode(bucket_name),
+            sigv4::uri_decode(object_name),
+        ),
+        None => (sigv4::uri_decode(path), String::new()),
+    };
+    // The SDK names the operation of some requests in an `x-id` parameter
+    let query = sigv4::query_pairs(parts.uri.query())
+        .into_iter()
+        .filter(|(key, _)| key != "x-id")
+        .collect::<HashMap<_, _>>();
+
+    let storage = &state.storage;
+
+    if bucket_name.is_empty() {
+        return match parts.method {
+            Method::GET => list_buckets(storage).await,
+            _ => not_implemented(),
+        };
+    }
+
+    if object_name.is_empty() {
+        return match parts.method {
+            Method::PUT if query.contains_key("versioning") => {
+                let enabled = xml::value(&String::from_utf8_lossy(&body), "Status")
+                    .is_some_and(|status| status == "Enabled");
+
+                match storage.set_bucket_versioning(&bucket_name, enabled).await {
+                    Ok(()) => respond_empty(StatusCode::OK),
+                    Err(err) => respond_storage_error(err),
+                }
+            }
+            Method::PUT if query.is_empty() => match storage.create_bucket(&bucket_name).await {
+                Ok(true) => respond(
+                    StatusCode::OK,
+                    vec![("location".to_string(), format!("/{}", bucket_name))],
+                    vec![],
+                ),
+                Ok(false) => respond_error(
+                    StatusCode::CONFLICT,
+                    "BucketAlreadyOwnedByYou",
+                    "The bucket already exists",
+                ),
+                Err(err) => respond_storage_error(err),
+            },
+            Method::HEAD => match storage.bucket_exists(&bucket_name).await {
+                Ok(true) => respond_empty(StatusCode::OK),
+                Ok(false) => respond_empty(StatusCode::NOT_FOUND),
+                Err(err) => respond_storage_error(err),
+            },
+            Method::DELETE if query.is_empty() => {
+                match storage.delete_bucket(&bucket_name, false).await {
+                    Ok(true) => respond_empty(StatusCode::NO_CONTENT),
+                    Ok(false) => respond_error(
+                        StatusCode::NOT_FOUND,
+                        "NoSuchBucket",
+                        "The bucket does not exist",
+                    ),
+                    Err(err) => respond_storage_error(err),
+                }
+            }
+            Method::GET if query.get("list-type").is_some_and(|value| value == "2") => {
+                list_objects_v2(storage, &bucket_name, &query).await
+            }
+            Method::POST if query.contains_key("delete") => {
+                delete_objects(storage, &bucket_name, &String::from_utf8_lossy(&body)).await
+            }
+            #[cfg(feature = "notifications")]
+            Method::GET if query.contains_key("events") => {
+                notifications::listen(state, parts, &bucket_name).await
+            }
+            _ => not_implemented(),
+        };
+    }
+
+    match parts.method {
+        Method::PUT if query.contains_key("uploadId") => {
+            let Some(part_number) = query
+                .get("partNumber")
+                .and_then(|part_number| part_number.parse::<usize>().ok())
+            else {
+                return respond_error(
+                    StatusCode::BAD_REQUEST,
+                    "InvalidArgument",
+                    "The part number is invalid",
+                );
+            };
+
+            if let Some(copy_source) = parts
+                .headers
+                .get("x-amz-copy-source")
+                .and_then(|copy_source| copy_source.to_str().ok())
+            {
+                return upload_part_copy(
+                    storage,
+                    parts,
+                    (&bucket_name, &object_name),
+                    &query["uploadId"],
+                    part_number,
+                    copy_source,
+                )
+                .await;
+            }
+
+            match storage
+                .upload_part(
+                    &bucket_name,
+                    &object_name,
+                    &query["uploadId"],
+                    part_number,
+                    body,
+                )
+                .await
+            {
+                Ok(ETag { e_tag, .. }) => {
+                    respond(StatusCode::OK, vec![("etag".to_string(), e_tag)], vec![])
+                }
+                Err(err) => respond_storage_error(err),
+            }
+        }
+        Method::PUT if query.is_empty() => match parts
+            .headers
+            .get("x-amz-copy-source")
+            .and_then(|copy_source| copy_source.to_str().ok())
+        {
+            Some(copy_source) => {
+                copy_object(storage, parts, &bucket_name, &object_name, copy_source).await
+            }
+            None => put_object(storage, parts, &bucket_name, &object_name, body).await,
+        },
+        Method::POST if query.contains_key("uploads") => {
+            match storage
+                .start_multipart_upload(&bucket_name, &object_name)
+                .await
+            {
+                Ok(upload_id) => respond_xml(
+                    StatusCode::OK,
+                    "InitiateMultipartUploadResult",
+                    format!(
+                        "{}{}{}",
+                        xml::element("Bucket", &bucket_name),
+                        xml::element("Key", &object_name),
+                        xml::element("UploadId", &upload_id)
+                    ),
+                ),
+                Err(err) => respond_storage_error(err),
+            }
+        }
+        Method::POST if query.contains_key("uploadId") => {
+            complete_multipart_upload(
+                storage,
+                &bucket_name,
+                &object_name,
+                &query["uploadId"],
+                &String::from_utf8_lossy(&body),
+            )
+            .await
+        }
+        Method::DELETE if query.contains_key("uploadId") => {
+            match storage
+                .abort_multipart_upload(&bucket_name, &object_name, &query["uploadId"])
+                .await
+            {
+                Ok(()) => respond_empty(StatusCode::NO_CONTENT),
+                Err(err) => respond_storage_error(err),
+            }
+        }
+        Method::DELETE if query.is_empty() => {
+            match storage.delete_object(&bucket_name, &object_name).await {
+                Ok(()) => respond_empty(StatusCode::NO_CONTENT),
+                Err(err) => respond_storage_error(err),
+            }
+        }
+        Method::GET | Method::HEAD
+            if query
+                .keys()
+                .all(|key| key == "versionId" || key.starts_with("X-Amz-")) =>
+        {
+            get_object(
+                storage,
+                parts,
+                &bucket_name,
+                &object_name,
+                query.get("versionId").cloned(),
+            )
+            .await
+        }
+        _ => not_implemented(),
+    }
+}
+
+async fn list_buckets(storage: &InMemoryStorage) -> Response {
+    let buckets = match storage.list_buckets().await {
+        Ok(buckets) => buckets,
+        Err(err) => return respond_storage_error(err),
+    };
+
+    let buckets = buckets
+        .iter()
+        .map(|bucket| {
+            format!(
+                "<Bucket>{}{}</Bucket>",
+                xml::element("Name", bucket.name().unwrap_or_default()),
+                xml::element(
+                    "CreationDate",
+                    &bucket.creation_date().map(iso_date).unwrap_or_default()
+                )
+            )
+        })
+        .collect::<String>();
+
+    respond_xml(
+        StatusCode::OK,
+        "ListAllMyBucketsResult",
+        format!(
+            "<Owner><ID>minior</ID><DisplayName>minior</DisplayName></Owner><Buckets>{}</Buckets>",
+            buckets
+        ),
+    )
+}
+
+async fn list_objects_v2(
+    storage: &InMemoryStorage,
+    bucket_name: &str,
+    query: &HashMap<String, String>,
+) -> Response {
+    let objects = match storage.list_bucket_objects(bucket_name).await {
+        Ok(objects) => objects,
+        Err(err) => return respond_storage_error(err),
+    };
+
+    let prefix = query.get("prefix").cloned().unwrap_or_default();
+    let delimiter = query
+        .get("delimiter")
+        .filter(|delimiter| !delimiter.is_empty());
+    let max_keys = query
+        .get("max-keys")
+        .and_then(|max_keys| max_keys.parse::<usize>().ok())
+        .unwrap_or(MAX_KEYS)
+        .min(MAX_KEYS);
+
+    // Continuation tokens are the last key or common prefix listed
+    let start_after = query
+        .get("continuation-token")
+        .or(query.get("start-after"))
+        .cloned()
+        .unwrap_or_default();
+    let skips = |object_name: &str| {
+        object_name <= start_after.as_str()
+            || delimiter.is_some_and(|delimiter| {
+                start_after.ends_with(delimiter.as_str()) && object_name.starts_with(&start_after)
+            })
+    };
+
+    let mut contents = String::new();
+    let mut common_prefixes: Vec<String> = vec![];
+    let mut key_count = 0;
+    let mut last_listed = None;
+    let mut is_truncated = false;
+
+    for object in &objects {
+        let Some(object_name) = object.key() else {
+            continue;
+        };
+
+        if !object_name.starts_with(&prefix) || skips(object_name) {
+            continue;
+        }
+
+        let common_prefix = delimiter.and_then(|delimiter| {
+            object_name[prefix.len()..]
+                .find(delimiter.as_str())
+                .map(|index| object_name[..prefix.len() + index + delimiter.len()].to_string())
+        });
+
+        if let Some(ref common_prefix) = common_prefix {
+            if common_prefixes.last() == Some(common_prefix) {
+                continue;
+            }
+        }
+
+        if key_count == max_keys {
+            is_truncated = true;
+            break;
+        }
+
+        key_count += 1;
+
+        match common_prefix {
+            Some(common_prefix) => {
+                last_listed = Some(common_prefix.clone());
+                common_prefixes.push(common_prefix);
+            }
+            None => {
+                last_listed = Some(object_name.to_string());
+                contents.push_str(&format!(
+                    "<Contents>{}{}{}{}<StorageClass>STANDARD</StorageClass></Contents>",
+                    xml::element("Key", object_name),
+                    xml::element(
+                        "LastModified",
+                        &object.last_modified().map(iso_date).unwrap_or_default()
+                    ),
+                    xml::element("ETag", object.e_tag().unwrap_or_default()),
+                    xml::element("Size", &object.size().unwrap_or_default().to_string())
+                ));
+            }
+        }
+    }
+
+    let mut result = format!(
+        "{}{}{}{}",
+        xml::element("Name", bucket_name),
+        xml::element("Prefix", &prefix),
+        xml::element("KeyCount", &key_count.to_string()),
+        xml::element("MaxKeys", &max_keys.to_string())
+    );
+
+    if let Some(delimiter) = delimiter {
+        result.push_str(&xml::element("Delimiter", delimiter));
+    }
+
+    result.push_str(&xml::element("IsTruncated", &is_truncated.to_string()));
+
+    if let Some(continuation_token) = query.get("continuation-token") {
+        result.push_str(&xml::element("ContinuationToken", continuation_token));
+    }
+
+    if let (true, Some(last_listed)) = (is_truncated, last_listed) {
+        result.push_str(&xml::element("NextContinuationToken", &last_listed));
+    }
+
+    result.push_str(&contents);
+
+    for common_prefix in common_prefixes {
+        result.push_str(&format!(
+            "<CommonPrefixes>{}</CommonPrefixes>",
+            xml::element("Prefix", &common_prefix)
+        ));
+    }
+
+    respond_xml(StatusCode::OK, "ListBucketResult", result)
+}
+
+async fn delete_objects(storage: &InMemoryStorage, bucket_name: &str, body: &str) -> Response {
+    let quiet = xml::value(body, "Quiet").is_some_and(|quiet| quiet == "true");
+    let mut result = String::new();
+
+    for object in xml::blocks(body, "Object") {
+        let Some(object_name) = xml::value(object, "Key") else {
+            return respond_error(
+                StatusCode::BAD_REQUEST,
+                "MalformedXML",
+                "An object to delete has no key",
+            );
+        };
+
+        match storage.delete_object(bucket_name, &object_name).await {
+            Ok(()) if quiet => {}
+            Ok(()) => result.push_str(&format!(
+                "<Deleted>{}</Deleted>",
+                xml::element("Key", &object_name)
+            )),
+            Err(err) => return respond_storage_error(err),
+        }
+    }
+
+    respond_xml(StatusCode::OK, "DeleteResult", result)
+}
+
+async fn put_object(
+    storage: &InMemoryStorage,
+    parts: &Parts,
+    bucket_name: &str,
+    object_name: &str,
+    body: Vec<u8>,
+) -> Response {
+    let uploaded = storage
+        .upload_object(
+            bucket_name,
+            object_name,
+            body.as_slice(),
+            Some(UploadObjectAdditionalOptions {
+                metadata: Some(request_metadata(parts)),
+                content_type: request_content_type(parts),
+                ..Default::default()
+            }),
+        )
+        .await;
+
+    if let Err(err) = uploaded {
+        return respond_storage_error(err);
+    }
+
+    match storage.stat_object(bucket_name, object_name, None).await {
+        Ok(Some(stat)) => {
+            let mut headers = vec![];
+
+            if let Some(e_tag) = stat.e_tag() {
+                headers.push(("etag".to_string(), e_tag.to_string()));
+            }
+
+            if let Some(version_id) = stat.version_id() {
+                headers.push(("x-amz-version-id".to_string(), version_id.to_string()));
+            }
+
+            respond(StatusCode::OK, headers, vec![])
+        }
+        Ok(None) => respond_empty(StatusCode::OK),
+        Err(err) => respond_storage_error(err),
+    }
+}
+
+/// The bucket, object and version named by a `x-amz-copy-source` header
+struct CopySource {
+    bucket_name: String,
+    object_name: String,
+    version_id: Option<String>,
+}
+
+impl CopySource {
+    fn parse(copy_source: &str) -> Option<CopySource> {
+        let copy_source = copy_source.trim_start_matches('/');
+        let (copy_source, version_id) = match copy_source.split_once("?versionId=") {
+            Some((copy_source, version_id)) => (copy_source, Some(version_id.to_string())),
+            None => (copy_source, None),
+        };
+        let (bucket_name, object_name) = copy_source.split_once('/')?;
+
+        Some(CopySource {
+            bucket_name: sigv4::uri_decode(bucket_name),
+            object_name: sigv4::uri_decode(object_name),
+            version_id,
+        })
+    }
+}
+
+/// Reads the `range` of the `copy_source` object, or returns the
+/// error response
+async fn read_copy_source(
+    storage: &InMemoryStorage,
+    copy_source: &CopySource,
+    range: Option<(usize, usize)>,
+) -> Result<Vec<u8>, Response> {
+    let stream = storage
+        .get_object(
+            &copy_source.bucket_name,
+            &copy_source.object_name,
+            Some(GetObjectAdditionalOptions {
+                range,
+                version_id: copy_source.version_id.clone(),
+                #[cfg(feature = "sse")]
+                encryption: None,
+                #[cfg(feature = "checksum")]
+                checksum: None,
+                #[cfg(feature = "compression")]
+                raw: true,
+            }),
+        )
+        .await;
+
+    match stream {
+        Ok(Some(mut stream)) => {
+            let mut bytes = vec![];
+
+            if let Err(err) = tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut bytes).await {
+                return Err(respond_storage_error(Error::StdIo(err.kind())));
+            }
+
+            Ok(bytes)
+        }
+        Ok(None) => Err(respond_error(
+            StatusCode::NOT_FOUND,
+            "NoSuchKey",
+            "The copy source does not exist",
+        )),
+        Err(err) => Err(respond_storage_error(err)),
+    }
+}
+
+/// Copies the `copy_source` object, keeping its metadata and
+/// `Content-Type` unless the `x-amz-metadata-directive` is `REPLACE`
+async fn copy_object(
+    storage: &InMemoryStorage,
+    parts: &Parts,
+    bucket_name: &str,
+    object_name: &str,
+    copy_source: &str,
+) -> Response {
+    let Some(copy_source) = CopySource::parse(copy_source) else {
+        return respond_error(
+            StatusCode::BAD_REQUEST,
+            "InvalidArgument",
+            "The copy source is invalid",
+        );
+    };
+    let bytes = match read_copy_source(storage, &copy_source, None).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+
+    let replace = parts
+        .headers
+        .get("x-amz-metadata-directive")
+        .is_some_and(|directive| directive == "REPLACE");
+    let (metadata, content_type) = match replace {
+        true => (request_metadata(parts), request_content_type(parts)),
+        false => match storage
+            .stat_object(
+                &copy_source.bucket_name,
+                &copy_source.object_name,
+                Some(StatObjectAdditionalOptions {
+                    version_id: copy_source.version_id.clone(),
+                    #[cfg(feature = "sse")]
+                    encryption: None,
+                }),
+            )
+            .await
+        {
+            Ok(Some(stat)) => (
+                stat.metadata().cloned().unwrap_or_default(),
+                stat.content_type().map(str::to_string),
+            ),
+            Ok(None) => (HashMap::new(), None),
+            Err(err) => return respond_storage_error(err),
+        },
+    };
+
+    let uploaded = storage
+        .upload_object(
+            bucket_name,
+            object_name,
+            bytes.as_slice(),
+            Some(UploadObjectAdditionalOptions {
+                metadata: Some(metadata),
+                content_type,
+                ..Default::default()
+            }),
+        )
+        .await;
+
+    if let Err(err) = uploaded {
+        return respond_storage_error(err);
+    }
+
+    match storage.stat_object(bucket_name, object_name, None).await {
+        Ok(stat) => respond_xml(
+            StatusCode::OK,
+            "CopyObjectResult",
+            format!(
+                "{}{}",
+                xml::element(
+                    "ETag",
+                    stat.as_ref()
+                        .and_then(|stat| stat.e_tag())
+                        .unwrap_or_default()
+                ),
+                xml::element(
+                    "LastModified",
+                    &iso_date(&DateTime::from(SystemTime::now()))
+                )
+            ),
+        ),
+        Err(err) => respond_storage_error(err),
+    }
+}
+
+/// Uploads the `x-amz-copy-source-range` of the `copy_source` object
+/// as the part by `part_number` of the upload by `upload_id`
+async fn upload_part_copy(
+    storage: &InMemoryStorage,
+    parts: &Parts,
+    (bucket_name, object_name): (&str, &str),
+    upload_id: &str,
+    part_number: usize,
+    copy_source: &str,
+) -> Response {
+    let range = parts
+        .headers
+        .get("x-amz-copy-source-range")
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.strip_prefix("bytes="))
+        .and_then(|range| {
+            let (start, end) = range.split_once('-')?;
+
+            Some((start.parse().ok()?, end.parse().ok()?))
+        });
+
+    let Some(copy_source) = CopySource::parse(copy_source) else {
+        return respond_error(
+            StatusCode::BAD_REQUEST,
+            "InvalidArgument",
+            "The copy source is invalid",
+        );
+    };
+    let bytes = match read_copy_source(storage, &copy_source, range).await {
+        Ok(bytes) => bytes,
+        Err(response) => return response,
+    };
+
+    match storage
+        .upload_part(bucket_name, object_name, upload_id, part_number, bytes)
+        .await
+    {
+        Ok(ETag { e_tag, .. }) => respond_xml(
+            StatusCode::OK,
+            "CopyPartResult",
+            format!(
+                "{}{}",
+                xml::element("ETag", &e_tag),
+                xml::element(
+                    "LastModified",
+                    &iso_date(&DateTime::from(SystemTime::now()))
+                )
+            ),
+        ),
+        Err(err) => respond_storage_error(err),
+    }
+}
+
+async fn complete_multipart_upload(
+    storage: &InMemoryStorage,
+    bucket_name: &str,
+    object_name: &str,
+    upload_id: &str,
+    body: &str,
+) -> Response {
+    let mut e_tags = vec![];
+
+    for part in xml::blocks(body, "Part") {
+        let part_number = xml::value(part, "PartNumber")
+            .and_then(|part_number| part_number.parse::<usize>().ok());
+
+        match (part_number, xml::value(part, "ETag")) {
+            (Some(part_number), Some(e_tag)) => e_tags.push(ETag { e_tag, part_number }),
+            _ => {
+                return respond_error(
+                    StatusCode::BAD_REQUEST,
+                    "MalformedXML",
+                    "A part has no part number or ETag",
+                )
+            }
+        }
+    }
+
+    if let Err(err) = storage
+        .complete_multipart_upload(bucket_name, object_name, upload_id, e_tags)
+        .await
+    {
+        return respond_storage_error(err);
+    }
+
+    let e_tag = match storage.stat_object(bucket_name, object_name, None).await {
+        Ok(stat) => stat
+            .and_then(|stat| stat.e_tag().map(str::to_string))
+            .unwrap_or_default(),
+        Err(err) => return respond_storage_error(err),
+    };
+
+    respond_xml(
+        StatusCode::OK,
+        "CompleteMultipartUploadResult",
+        format!(
+            "{}{}{}{}",
+            xml::element("Location", &format!("/{}/{}", bucket_name, object_name)),
+            xml::element("Bucket", bucket_name),
+            xml::element("Key", object_name),
+            xml::element("ETag", &e_tag)
+        ),
+    )
+}
+
+async fn get_object(
+    storage: &InMemoryStorage,
+    parts: &Parts,
+    bucket_name: &str,
+    object_name: &str,
+    version_id: Option<String>,
+) -> Response {
+    let stat = match storage
+        .stat_object(
+            bucket_name,
+            object_name,
+            Some(StatObjectAdditionalOptions {
+                version_id: version_id.clone(),
+                #[cfg(feature = "sse")]
+                encryption: None,
+            }),
+        )
+        .await
+    {
+        Ok(Some(stat)) => stat,
+        Ok(None) => {
+            return respond_error(
+                StatusCode::NOT_FOUND,
+                "NoSuchKey",
+                "The object does not exist",
+            )
+        }
+        Err(err) => return respond_storage_error(err),
+    };
+
+    let size = stat.content_length().unwrap_or_default() as usize;
+    let mut headers = object_headers(&stat);
+
+    if parts.method == Method::HEAD {
+        headers.push(("content-length".to_string(), size.to_string()));
+        return respond(StatusCode::OK, headers, vec![]);
+    }
+
+    let range = parts
+        .headers
+        .get("range")
+        .and_then(|range| range.to_str().ok())
+        .map(|range| parse_range(range, size));
+
+    let range = match range {
+        Some(Some(range)) => Some(range),
+        Some(None) => {
+            return respond_error(
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                "InvalidRange",
+                "The range is malformed",
+            )
+        }
+        None => None,
+    };
+
+    let stream = storage
+        .get_object(
+            bucket_name,
+            object_name,
+            Some(GetObjectAdditionalOptions {
+                range,
+                version_id,
+                #[cfg(feature = "sse")]
+                encryption: None,
+                #[cfg(feature = "checksum")]
+                checksum: None,
+                #[cfg(feature = "compression")]
+                raw: false,
+            }),
+        )
+        .await;
+
+    let bytes = match stream {
+        Ok(Some(mut stream)) => {
+            let mut bytes = vec![];
+
+            if let Err(err) = tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut bytes).await {
+                return respond_storage_error(Error::StdIo(err.kind()));
+            }
+
+            bytes
+        }
+        Ok(None) => {
+            return respond_error(
+                StatusCode::NOT_FOUND,
+                "NoSuchKey",
+                "The object does not exist",
+            )
+        }
+        Err(err) => return respond_storage_error(err),
+    };
+
+    match range {
+        Some((start, _)) => {
+            headers.push((
+                "content-range".to_string(),
+                format!("bytes {}-{}/{}", start, start + bytes.len() - 1, size),
+            ));
+
+            respond(StatusCode::PARTIAL_CONTENT, headers, bytes)
+        }
+        None => respond(StatusCode::OK, headers, bytes),
+    }
+}
@@ -0,0 +1,253 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{iso_date, respond_error, respond_storage_error, sigv4, Response, ServerState};
+use crate::core::{presign::uri_encode, storage::ObjectStorage};
+use aws_sdk_s3::primitives::DateTime;
+use http_body_util::BodyExt;
+use hyper::{
+    body::{Body, Bytes, Frame},
+    http::request::Parts,
+    Method, StatusCode,
+};
+use serde_json::json;
+use std::{
+    convert::Infallible,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc,
+};
+
+/// How long a stream may go without an event before a keep-alive is written,
+/// the same as MinIO without a `ping` parameter
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How many events may be buffered for a slow listener before it misses some
+const LISTENER_CAPACITY: usize = 1_024;
+
+/// Sent to the bucket notification streams of the server
+#[derive(Debug, Clone)]
+pub(super) enum ListenerMessage {
+    Event(Arc<Event>),
+    /// Ends every stream
+    Disconnect,
+}
+
+#[derive(Debug)]
+pub(super) struct Event {
+    bucket_name: String,
+    object_name: String,
+    event_name: &'static str,
+    /// The JSON record of the event
+    record: String,
+}
+
+pub(super) type Listeners = broadcast::Sender<ListenerMessage>;
+
+pub(super) fn listeners() -> Listeners {
+    broadcast::channel(LISTENER_CAPACITY).0
+}
+
+/// The filters of a bucket notification stream
+struct Filter {
+    bucket_name: String,
+    prefix: String,
+    suffix: String,
+    events: Vec<String>,
+}
+
+impl Filter {
+    fn matches(&self, event: &Event) -> bool {
+        event.bucket_name == self.bucket_name
+            && event.object_name.starts_with(&self.prefix)
+            && event.object_name.ends_with(&self.suffix)
+            && self.events.iter().any(|name| match name.strip_suffix('*') {
+                Some(category) => event.event_name.starts_with(category),
+                None => name == event.event_name,
+            })
+    }
+}
+
+/// The body of a bucket notification stream, written until the stream ends
+struct ListenerBody(mpsc::Receiver<Bytes>);
+
+impl Body for ListenerBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        self.0
+            .poll_recv(cx)
+            .map(|chunk| chunk.map(|chunk| Ok(Frame::data(chunk))))
+    }
+}
+
+/// Streams the events of the bucket by `bucket_name` matching the `events`,
+/// `prefix` and `suffix` parameters of the request, as MinIO's
+/// ListenBucketNotification, until the client disconnects
+pub(super) async fn listen(state: &ServerState, parts: &Parts, bucket_name: &str) -> Response {
+    match state.storage.bucket_exists(bucket_name).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return respond_error(
+                StatusCode::NOT_FOUND,
+                "NoSuchBucket",
+                "The bucket does not exist",
+            )
+        }
+        Err(err) => return respond_storage_error(err),
+    }
+
+    let query = sigv4::query_pairs(parts.uri.query());
+    let value = |key: &str| {
+        query
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.clone())
+            .unwrap_or_default()
+    };
+    let filter = Filter {
+        bucket_name: bucket_name.to_string(),
+        prefix: value("prefix"),
+        suffix: value("suffix"),
+        events: query
+            .iter()
+            .filter(|(name, _)| name == "events")
+            .map(|(_, value)| value.clone())
+            .collect(),
+    };
+
+    let mut messages = state.listeners.subscribe();
+    let (sender, receiver) = mpsc::channel(16);
+
+    tokio::spawn(async move {
+        // Note: Flushes the headers, as MinIO's first keep-alive
+        if sender.send(Bytes::from_static(b" ")).await.is_err() {
+            return;
+        }
+
+        loop {
+            let chunk = match tokio::time::timeout(KEEP_ALIVE_INTERVAL, messages.recv()).await {
+                Err(_) => Bytes::from_static(b" "),
+                Ok(Ok(ListenerMessage::Event(event))) if filter.matches(&event) => {
+                    Bytes::from(format!("{{\"Records\":[{}]}}\n", event.record))
+                }
+                Ok(Ok(ListenerMessage::Event(_))) | Ok(Err(RecvError::Lagged(_))) => continue,
+                Ok(Ok(ListenerMessage::Disconnect)) | Ok(Err(RecvError::Closed)) => return,
+            };
+
+            if sender.send(chunk).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    hyper::Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/event-stream")
+        .body(ListenerBody(receiver).boxed())
+        .unwrap_or_else(|_| respond_error(StatusCode::INTERNAL_SERVER_ERROR, "InternalError", ""))
+}
+
+/// Publishes the event of a successful request to `parts` on an object,
+/// the puts, multipart upload completions, gets, heads and deletes
+pub(super) async fn publish(state: &ServerState, parts: &Parts, status: StatusCode) {
+    if !status.is_success() || state.listeners.receiver_count() == 0 {
+        return;
+    }
+
+    let Some((bucket_name, object_name)) = parts.uri.path().trim_start_matches('/').split_once('/')
+    else {
+        return;
+    };
+    let (bucket_name, object_name) = (
+        sigv4::uri_decode(bucket_name),
+        sigv4::uri_decode(object_name),
+    );
+    if object_name.is_empty() {
+        return;
+    }
+
+    let is_upload = sigv4::query_pairs(parts.uri.query())
+        .iter()
+        .any(|(key, _)| key == "uploadId");
+    let event_name = match parts.method {
+        Method::PUT if !is_upload => "s3:ObjectCreated:Put",
+        Method::POST if is_upload => "s3:ObjectCreated:CompleteMultipartUpload",
+        Method::DELETE if !is_upload => "s3:ObjectRemoved:Delete",
+        Method::GET if !is_upload => "s3:ObjectAccessed:Get",
+        Method::HEAD => "s3:ObjectAccessed:Head",
+        _ => return,
+    };
+
+    let mut object = json!({
+        // Note: MinIO escapes keys as query values
+        "key": uri_encode(&object_name, true).replace("%20", "+"),
+        "sequencer": format!(
+            "{:016X}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ),
+    });
+    if event_name != "s3:ObjectRemoved:Delete" {
+        if let Ok(Some(stat)) = state
+            .storage
+            .stat_object(&bucket_name, &object_name, None)
+            .await
+        {
+            object["size"] = json!(stat.content_length().unwrap_or_default());
+            object["eTag"] = json!(stat.e_tag().unwrap_or_default().trim_matches('"'));
+            object["contentType"] = json!(stat.content_type().unwrap_or_default());
+            object["userMetadata"] = json!(stat.metadata().cloned().unwrap_or_default());
+        }
+    }
+
+    let header = |name: &str| {
+        parts
+            .headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let record = json!({
+        "eventVersion": "2.0",
+        "eventSource": "minio:s3",
+        "awsRegion": "",
+        "eventTime": iso_date(&DateTime::from(SystemTime::now())),
+        "eventName": event_name,
+        "userIdentity": { "principalId": state.access_key_id },
+        "s3": {
+            "s3SchemaVersion": "1.0",
+            "configurationId": "Config",
+            "bucket": {
+                "name": bucket_name,
+                "ownerIdentity": { "principalId": state.access_key_id },
+                "arn": format!("arn:aws:s3:::{}", bucket_name),
+            },
+            "object": object,
+        },
+        "source": {
+            "host": "127.0.0.1",
+            "port": "",
+            "userAgent": header("user-agent"),
+        },
+    });
+
+    let _ = state.listeners.send(ListenerMessage::Event(Arc::new(Event {
+        bucket_name,
+        object_name,
+        event_name,
+        record: record.to_string(),
+    })));
+}
@@ -0,0 +1,325 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use crate::core::presign::{parse_amz_date, uri_encode};
+use hmac::{Hmac, KeyInit, Mac};
+use hyper::http::request::Parts;
+use sha2::{Digest, Sha256};
+use std::time::SystemTime;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+pub(super) const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Why the signature of a request was rejected, as an S3 error code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum AuthError {
+    Missing,
+    InvalidAccessKeyId,
+    Malformed,
+    Expired,
+    SignatureDoesNotMatch,
+}
+
+impl AuthError {
+    pub(super) fn code(&self) -> &'static str {
+        match self {
+            AuthError::Missing | AuthError::Expired => "AccessDenied",
+            AuthError::InvalidAccessKeyId => "InvalidAccessKeyId",
+            AuthError::Malformed => "AuthorizationHeaderMalformed",
+            AuthError::SignatureDoesNotMatch => "SignatureDoesNotMatch",
+        }
+    }
+
+    pub(super) fn message(&self) -> &'static str {
+        match self {
+            AuthError::Missing => "Request is not signed",
+            AuthError::InvalidAccessKeyId => "The access key id does not exist",
+            AuthError::Malformed => "The authorization of the request is malformed",
+            AuthError::Expired => "Request has expired",
+            AuthError::SignatureDoesNotMatch => {
+                "The request signature does not match the calculated signature"
+            }
+        }
+    }
+}
+
+/// The parts of a SigV4 signature, from the `Authorization`
+/// header or the query of a presigned request
+struct Signature {
+    access_key_id: String,
+    scope: String,
+    date: String,
+    region: String,
+    service: String,
+    amz_date: String,
+    signed_headers: Vec<String>,
+    signature: String,
+    payload_hash: String,
+}
+
+/// Percent-decodes a path segment or query component
+pub(super) fn uri_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Returns the decoded query parameters of a request, in order
+pub(super) fn query_pairs(query: Option<&str>) -> Vec<(String, String)> {
+    query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (uri_decode(key), uri_decode(value))
+        })
+        .collect()
+}
+
+pub(super) fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hmac(key: &[u8], message: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(message.as_bytes());
+
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Splits a credential `AK/20240101/us-east-1/s3/aws4_request`
+fn parse_credential(credential: &str, signature: &mut Signature) -> Result<(), AuthError> {
+    let (access_key_id, scope) = credential.split_once('/').ok_or(AuthError::Malformed)?;
+    let mut parts = scope.split('/');
+
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(date), Some(region), Some(service), Some("aws4_request")) => {
+            signature.access_key_id = access_key_id.to_string();
+            signature.scope = scope.to_string();
+            signature.date = date.to_string();
+            signature.region = region.to_string();
+            signature.service = service.to_string();
+
+            Ok(())
+        }
+        _ => Err(AuthError::Malformed),
+    }
+}
+
+fn header_value<'a>(parts: &'a Parts, name: &str) -> Option<&'a str> {
+    parts
+        .headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// Parses the signature of the request, checking the expiry
+/// of presigned requests
+fn parse_signature(parts: &Parts, query: &[(String, String)]) -> Result<Signature, AuthError> {
+    let mut signature = Signature {
+        access_key_id: String::new(),
+        scope: String::new(),
+        date: String::new(),
+        region: String::new(),
+        service: String::new(),
+        amz_date: String::new(),
+        signed_headers: vec![],
+        signature: String::new(),
+        payload_hash: String::new(),
+    };
+
+    let query_value = |name: &str| {
+        query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    if let Some(authorization) = header_value(parts, "authorization") {
+        let fields = authorization
+            .strip_prefix(ALGORITHM)
+            .ok_or(AuthError::Malformed)?;
+
+        for field in fields.split(',') {
+            match field.trim().split_once('=') {
+                Some(("Credential", credential)) => parse_credential(credential, &mut signature)?,
+                Some(("SignedHeaders", signed_headers)) => {
+                    signature.signed_headers =
+                        signed_headers.split(';').map(str::to_string).collect()
+                }
+                Some(("Signature", value)) => signature.signature = value.to_string(),
+                _ => return Err(AuthError::Malformed),
+            }
+        }
+
+        signature.amz_date = header_value(parts, "x-amz-date")
+            .ok_or(AuthError::Malformed)?
+            .to_string();
+        signature.payload_hash = header_value(parts, "x-amz-content-sha256")
+            .ok_or(AuthError::Malformed)?
+            .to_string();
+    } else if let Some(algorithm) = query_value("X-Amz-Algorithm") {
+        if algorithm != ALGORITHM {
+            return Err(AuthError::Malformed);
+        }
+
+        parse_credential(
+            query_value("X-Amz-Credential").ok_or(AuthError::Malformed)?,
+            &mut signature,
+        )?;
+
+        signature.amz_date = query_value("X-Amz-Date")
+            .ok_or(AuthError::Malformed)?
+            .to_string();
+        signature.signed_headers = query_value("X-Amz-SignedHeaders")
+            .ok_or(AuthError::Malformed)?
+            .split(';')
+            .map(str::to_string)
+            .collect();
+        signature.signature = query_value("X-Amz-Signature")
+            .ok_or(AuthError::Malformed)?
+            .to_string();
+        signature.payload_hash = query_value("X-Amz-Content-Sha256")
+            .unwrap_or(UNSIGNED_PAYLOAD)
+            .to_string();
+
+        let expires_secs = query_value("X-Amz-Expires")
+            .and_then(|expires| expires.parse::<i64>().ok())
+            .ok_or(AuthError::Malformed)?;
+        let signed_at = parse_amz_date(&signature.amz_date).ok_or(AuthError::Malformed)?;
+        let now = aws_sdk_s3::primitives::DateTime::from(SystemTime::now());
+
+        if signed_at.secs().saturating_add(expires_secs) < now.secs() {
+            return Err(AuthError::Expired);
+        }
+    } else {
+        return Err(AuthError::Missing);
+    }
+
+    if signature.signed_headers.is_empty() || signature.signature.is_empty() {
+        return Err(AuthError::Malformed);
+    }
+
+    Ok(signature)
+}
+
+/// Builds the canonical request of a SigV4 signature
+fn canonical_request(
+    parts: &Parts,
+    query: &[(String, String)],
+    signature: &Signature,
+) -> Result<String, AuthError> {
+    let mut canonical_query = query
+        .iter()
+        .filter(|(key, _)| key != "X-Amz-Signature")
+        .map(|(key, value)| (uri_encode(key, true), uri_encode(value, true)))
+        .collect::<Vec<_>>();
+    canonical_query.sort();
+
+    let canonical_query = canonical_query
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut canonical_headers = String::new();
+
+    for name in &signature.signed_headers {
+        let values = parts
+            .headers
+            .get_all(name.as_str())
+            .iter()
+            .map(|value| {
+                value
+                    .to_str()
+                    .map(|value| value.split_whitespace().collect::<Vec<_>>().join(" "))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| AuthError::Malformed)?;
+
+        if values.is_empty() {
+            return Err(AuthError::Malformed);
+        }
+
+        canonical_headers.push_str(&format!("{}:{}\n", name, values.join(",")));
+    }
+
+    let path = parts.uri.path();
+
+    Ok(format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        parts.method,
+        if path.is_empty() { "/" } else { path },
+        canonical_query,
+        canonical_headers,
+        signature.signed_headers.join(";"),
+        signature.payload_hash
+    ))
+}
+
+/// Verifies the SigV4 signature of a request, from its `Authorization`
+/// header or presigned query, returning its `x-amz-content-sha256`
+pub(super) fn verify(
+    parts: &Parts,
+    access_key_id: &str,
+    secret_access_key: &str,
+) -> Result<String, AuthError> {
+    let query = query_pairs(parts.uri.query());
+    let signature = parse_signature(parts, &query)?;
+
+    if signature.access_key_id != access_key_id {
+        return Err(AuthError::InvalidAccessKeyId);
+    }
+
+    let canonical_request = canonical_request(parts, &query, &signature)?;
+    let string_to_sign = format!(
+        "{}\n{}\n{}\n{}",
+        ALGORITHM,
+        signature.amz_date,
+        signature.scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let signing_key = [
+        signature.date.as_str(),
+        signature.region.as_str(),
+        signature.service.as_str(),
+        "aws4_request",
+    ]
+    .iter()
+    .fold(
+        format!("AWS4{}", secret_access_key).into_bytes(),
+        |key, message| hmac(&key, message),
+    );
+
+    if hex(&hmac(&signing_key, &string_to_sign)) != signature.signature {
+        return Err(AuthError::SignatureDoesNotMatch);
+    }
+
+    Ok(signature.payload_hash)
+}
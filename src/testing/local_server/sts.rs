@@ -0,0 +1,385 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+use super::{respond, sigv4, xml, Response, ACCEPT_BACKOFF};
+use crate::error::Error;
+use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, server::conn::http1, service::service_fn, Request, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+    task::JoinHandle,
+};
+use tokio_rustls::{
+    rustls::{
+        crypto::aws_lc_rs,
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+        server::WebPkiClientVerifier,
+        RootCertStore, ServerConfig,
+    },
+    TlsAcceptor,
+};
+
+const NAMESPACE: &str = "https://sts.amazonaws.com/doc/2011-06-15/";
+
+/// Lifetime of the credentials of requests without `DurationSeconds`
+const DEFAULT_DURATION_SECS: u64 = 3_600;
+
+/// A request received by a `LocalStsServer`
+#[derive(Debug, Clone)]
+pub struct StsRequest {
+    /// The decoded form parameters, I.E. `Action`
+    pub params: HashMap<String, String>,
+    /// If the client presented a certificate trusted by the server
+    pub client_certificate: bool,
+}
+
+/// State shared by the connections of a `LocalStsServer`
+struct StsState {
+    access_key_id: String,
+    secret_access_key: String,
+    issued: AtomicU64,
+    error: Mutex<Option<(String, String)>>,
+    requests: Mutex<Vec<StsRequest>>,
+}
+
+/// A MinIO STS API server on an ephemeral port of `127.0.0.1`, serving
+/// canned temporary credentials so `core::sts` can be tested without
+/// a MinIO server and identity provider
+///
+/// Accepts `AssumeRole` signed by `minioadmin`/`minioadmin`, any
+/// `AssumeRoleWithWebIdentity` token, any `AssumeRoleWithLDAPIdentity`
+/// username and password, and `AssumeRoleWithCertificate` from clients
+/// with a certificate of the client CA of a server started by `start_tls`.
+/// Every request issues new credentials, `STS00000000000000001` onwards,
+/// expiring after the `DurationSeconds` of the request.
+///
+/// The server stops when dropped.
+///
+/// ---
+/// Example Usage:
+/// ```
+///
+/// let server = LocalStsServer::start().await?;
+///
+/// let credentials = assume_role(
+///     server.url(),
+///     &StsIdentity::WebIdentity { token: "eyJhbGciOi".to_string() },
+///     &StsOptions::default(),
+/// ).await?;
+///
+/// assert_eq!(credentials.access_key_id(), "STS00000000000000001");
+/// ```
+pub struct LocalStsServer {
+    url: String,
+    state: Arc<StsState>,
+    join_handle: JoinHandle<()>,
+}
+
+impl std::fmt::Debug for LocalStsServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalStsServer")
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LocalStsServer {
+    /// Starts a http server
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let server = LocalStsServer::start().await?;
+    /// ```
+    pub async fn start() -> Result<Self, Error> {
+        Self::bind(None).await
+    }
+
+    /// Starts a https server with the PEM encoded certificate and private key,
+    /// trusting the client certificates issued by the PEM encoded `client_ca_pem`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// let server = LocalStsServer::start_tls(&server_pem, &server_key_pem, &ca_pem).await?;
+    /// ```
+    pub async fn start_tls(
+        certificate_pem: &[u8],
+        private_key_pem: &[u8],
+        client_ca_pem: &[u8],
+    ) -> Result<Self, Error> {
+        let provider = Arc::new(aws_lc_rs::default_provider());
+
+        let mut client_roots = RootCertStore::empty();
+        for certificate in CertificateDer::pem_slice_iter(client_ca_pem) {
+            client_roots
+                .add(certificate.map_err(Error::sdk)?)
+                .map_err(Error::sdk)?;
+        }
+
+        let client_verifier =
+            WebPkiClientVerifier::builder_with_provider(Arc::new(client_roots), provider.clone())
+                .allow_unauthenticated()
+                .build()
+                .map_err(Error::sdk)?;
+
+        let certificates = CertificateDer::pem_slice_iter(certificate_pem)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Error::sdk)?;
+        let private_key = PrivateKeyDer::from_pem_slice(private_key_pem).map_err(Error::sdk)?;
+
+        let config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(Error::sdk)?
+            .with_client_cert_verifier(client_verifier)
+            .with_single_cert(certificates, private_key)
+            .map_err(Error::sdk)?;
+
+        Self::bind(Some(TlsAcceptor::from(Arc::new(config)))).await
+    }
+
+    async fn bind(acceptor: Option<TlsAcceptor>) -> Result<Self, Error> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|err| Error::StdIo(err.kind()))?;
+        let url = format!(
+            "{}://{}",
+            if acceptor.is_some() { "https" } else { "http" },
+            listener
+                .local_addr()
+                .map_err(|err| Error::StdIo(err.kind()))?
+        );
+
+        let state = Arc::new(StsState {
+            access_key_id: "minioadmin".to_string(),
+            secret_access_key: "minioadmin".to_string(),
+            issued: AtomicU64::new(0),
+            error: Mutex::new(None),
+            requests: Mutex::new(vec![]),
+        });
+        let server_state = state.clone();
+
+        let join_handle = tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    tokio::time::sleep(ACCEPT_BACKOFF).await;
+                    continue;
+                };
+                let state = server_state.clone();
+                let acceptor = acceptor.clone();
+
+                tokio::spawn(async move {
+                    match acceptor {
+                        Some(acceptor) => {
+                            // Handshakes failed by the client are not errors of the server
+                            let Ok(stream) = acceptor.accept(stream).await else {
+                                return;
+                            };
+                            let client_certificate =
+                                stream.get_ref().1.peer_certificates().is_some();
+
+                            serve(state, stream, client_certificate).await;
+                        }
+                        None => serve(state, stream, false).await,
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            url,
+            state,
+            join_handle,
+        })
+    }
+
+    /// The url of the server, I.E. `http://127.0.0.1:49152`
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Responds to the following requests with an `ErrorResponse`
+    /// of `code` and `message`, until `respond_with_credentials`
+    ///
+    /// ---
+    /// Example Usage:
+    /// ```
+    ///
+    /// server.respond_with_error("ExpiredToken", "The web identity token has expired");
+    /// ```
+    pub fn respond_with_error(&self, code: &str, message: &str) {
+        if let Ok(mut error) = self.state.error.lock() {
+            *error = Some((code.to_string(), message.to_string()));
+        }
+    }
+
+    /// Issues credentials to the following requests again
+    pub fn respond_with_credentials(&self) {
+        if let Ok(mut error) = self.state.error.lock() {
+            *error = None;
+        }
+    }
+
+    /// The requests received by the server, in order
+    pub fn requests(&self) -> Vec<StsRequest> {
+        self.state
+            .requests
+            .lock()
+            .map(|requests| requests.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for LocalStsServer {
+    fn drop(&mut self) {
+        self.join_handle.abort();
+    }
+}
+
+async fn serve<S>(state: Arc<StsState>, stream: S, client_certificate: bool)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = service_fn(move |request| {
+        let state = state.clone();
+
+        async move { Ok::<_, Infallible>(handle(&state, request, client_certificate).await) }
+    });
+
+    // Connections closed by the client are not errors of the server
+    let _ = http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .await;
+}
+
+fn respond_sts_error(status: StatusCode, code: &str, message: &str) -> Response {
+    respond(
+        status,
+        vec![("content-type".to_string(), "application/xml".to_string())],
+        format!(
+            "{}<ErrorResponse xmlns=\"{}\"><Error>{}{}{}</Error></ErrorResponse>",
+            xml::DECLARATION,
+            NAMESPACE,
+            xml::element("Type", "Sender"),
+            xml::element("Code", code),
+            xml::element("Message", message)
+        )
+        .into_bytes(),
+    )
+}
+
+async fn handle(
+    state: &StsState,
+    request: Request<Incoming>,
+    client_certificate: bool,
+) -> Response {
+    let (parts, body) = request.into_parts();
+
+    let Ok(body) = body.collect().await.map(|body| body.to_bytes()) else {
+        return respond_sts_error(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            "The request body could not be read",
+        );
+    };
+
+    let params = sigv4::query_pairs(Some(&String::from_utf8_lossy(&body)))
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    if let Ok(mut requests) = state.requests.lock() {
+        requests.push(StsRequest {
+            params: params.clone(),
+            client_certificate,
+        });
+    }
+
+    if let Some((code, message)) = state.error.lock().ok().and_then(|error| error.clone()) {
+        return respond_sts_error(StatusCode::BAD_REQUEST, &code, &message);
+    }
+
+    let param = |name: &str| params.get(name).filter(|value| !value.is_empty());
+
+    let action = params.get("Action").map(String::as_str).unwrap_or_default();
+    let authorized = match action {
+        "AssumeRole" => {
+            match sigv4::verify(&parts, &state.access_key_id, &state.secret_access_key) {
+                Ok(payload_hash) if payload_hash == sigv4::sha256_hex(&body) => Ok(()),
+                Ok(_) => Err("The request body does not match its x-amz-content-sha256"),
+                Err(err) => Err(err.message()),
+            }
+        }
+        "AssumeRoleWithWebIdentity" => param("WebIdentityToken")
+            .map(|_| ())
+            .ok_or("WebIdentityToken is missing"),
+        "AssumeRoleWithLDAPIdentity" => param("LDAPUsername")
+            .and(param("LDAPPassword"))
+            .map(|_| ())
+            .ok_or("LDAPUsername or LDAPPassword is missing"),
+        "AssumeRoleWithCertificate" if client_certificate => Ok(()),
+        "AssumeRoleWithCertificate" => Err("No trusted client certificate was presented"),
+        _ => {
+            return respond_sts_error(
+                StatusCode::BAD_REQUEST,
+                "InvalidAction",
+                "The action is not supported",
+            )
+        }
+    };
+
+    if let Err(message) = authorized {
+        return respond_sts_error(StatusCode::FORBIDDEN, "AccessDenied", message);
+    }
+
+    let duration_secs = match params.get("DurationSeconds").map(|value| value.parse()) {
+        Some(Ok(duration_secs)) => duration_secs,
+        Some(Err(_)) => {
+            return respond_sts_error(
+                StatusCode::BAD_REQUEST,
+                "InvalidParameterValue",
+                "DurationSeconds is not a number",
+            )
+        }
+        None => DEFAULT_DURATION_SECS,
+    };
+
+    let issued = state.issued.fetch_add(1, Ordering::Relaxed) + 1;
+    let expiration = DateTime::from(SystemTime::now() + Duration::from_secs(duration_secs))
+        .fmt(DateTimeFormat::DateTime)
+        .unwrap_or_default();
+
+    respond(
+        StatusCode::OK,
+        vec![("content-type".to_string(), "application/xml".to_string())],
+        format!(
+            "{}<{}Response xmlns=\"{}\"><{}Result><Credentials>{}{}{}{}</Credentials></{}Result>\
+            <ResponseMetadata>{}</ResponseMetadata></{}Response>",
+            xml::DECLARATION,
+            action,
+            NAMESPACE,
+            action,
+            xml::element("AccessKeyId", &format!("STS{:017}", issued)),
+            xml::element("SecretAccessKey", &format!("sts-secret-{}", issued)),
+            xml::element("SessionToken", &format!("sts-token-{}", issued)),
+            xml::element("Expiration", &expiration),
+            action,
+            xml::element("RequestId", &format!("{:016X}", issued)),
+            action
+        )
+        .into_bytes(),
+    )
+}
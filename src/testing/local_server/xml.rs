@@ -0,0 +1,54 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+pub(super) const DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+pub(super) const NAMESPACE: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
+
+pub(super) fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Returns `<tag>value</tag>` with `value` escaped
+pub(super) fn element(tag: &str, value: &str) -> String {
+    format!("<{}>{}</{}>", tag, escape(value), tag)
+}
+
+/// Returns the raw contents of every `<tag>...</tag>` in `xml`, in order
+pub(super) fn blocks<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut blocks = vec![];
+    let mut rest = xml;
+
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+
+        blocks.push(&rest[..end]);
+        rest = &rest[end + close.len()..];
+    }
+
+    blocks
+}
+
+/// Returns the unescaped text of the first `<tag>...</tag>` in `xml`
+pub(super) fn value(xml: &str, tag: &str) -> Option<String> {
+    blocks(xml, tag).first().map(|value| unescape(value.trim()))
+}
@@ -0,0 +1,13 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+mod fault_injector;
+mod in_memory;
+mod local_server;
+
+pub use fault_injector::{Fault, FaultInjector};
+pub use in_memory::InMemoryStorage;
+pub use local_server::LocalS3Server;
+
+#[cfg(feature = "sts")]
+pub use local_server::{LocalStsServer, StsRequest};
@@ -0,0 +1,410 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "admin")]
+#[cfg(test)]
+mod tests {
+    use crate::core::admin::madmin::*;
+
+    #[cfg(feature = "testing")]
+    use crate::{
+        core::{
+            admin::{policies::PolicyEntity, service_accounts::*, AccountStatus},
+            storage::ObjectStorage,
+        },
+        testing::LocalS3Server,
+        MinioAdmin,
+    };
+    #[cfg(feature = "testing")]
+    use aws_sdk_s3::config::Credentials;
+
+    fn read_test_data(name: &str) -> Vec<u8> {
+        std::fs::read(format!("test_data/madmin/{}", name)).unwrap()
+    }
+
+    const USER_PAYLOAD: &[u8] = br#"{"secretKey":"great-white","status":"enabled"}"#;
+
+    #[cfg(feature = "testing")]
+    const SHARKS_READONLY: &str = r#"{
+        "Version": "2012-10-17",
+        "Statement": [{
+            "Effect": "Allow",
+            "Action": ["s3:GetObject"],
+            "Resource": ["arn:aws:s3:::sharks/*"]
+        }]
+    }"#;
+
+    #[test]
+    fn test_decrypt_data() {
+        assert_eq!(
+            decrypt_data("minioadmin", &read_test_data("argon2id_aes_gcm.bin")).unwrap(),
+            USER_PAYLOAD
+        );
+        assert_eq!(
+            decrypt_data(
+                "minioadmin",
+                &read_test_data("argon2id_chacha20_poly1305.bin")
+            )
+            .unwrap(),
+            USER_PAYLOAD
+        );
+        // Spans several fragments of the stream
+        assert_eq!(
+            decrypt_data("minioadmin", &read_test_data("pbkdf2_aes_gcm.bin")).unwrap(),
+            read_test_data("plaintext.bin")
+        );
+
+        assert!(decrypt_data("hammerhead", &read_test_data("argon2id_aes_gcm.bin")).is_err());
+
+        let mut tampered = read_test_data("pbkdf2_aes_gcm.bin");
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(decrypt_data("minioadmin", &tampered).is_err());
+
+        // A stream cut at a fragment boundary lacks its final fragment
+        let truncated = read_test_data("pbkdf2_aes_gcm.bin");
+        assert!(decrypt_data("minioadmin", &truncated[..41 + 16_384 + 16]).is_err());
+
+        assert!(decrypt_data("minioadmin", &[0; 40]).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_data() {
+        for size in [0usize, 1, 16_383, 16_384, 16_385, 40_000] {
+            let data = (0..size).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+            let encrypted = encrypt_data("minioadmin", &data).unwrap();
+
+            // Salt, id, nonce and a tag per fragment
+            let fragments = size.div_ceil(16_384).max(1);
+            assert_eq!(encrypted.len(), 41 + size + fragments * 16);
+            assert_eq!(encrypted[32], 0);
+
+            assert_eq!(decrypt_data("minioadmin", &encrypted).unwrap(), data);
+        }
+
+        assert_ne!(
+            encrypt_data("minioadmin", USER_PAYLOAD).unwrap(),
+            encrypt_data("minioadmin", USER_PAYLOAD).unwrap()
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_users_and_groups() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio_admin = MinioAdmin::from_url(&format!(
+            "{}?region=us-east-1",
+            server
+                .url()
+                .replace("http://", "http://minioadmin:minioadmin@")
+        ))
+        .unwrap();
+
+        minio_admin
+            .add_user("shark-job", "great-white")
+            .await
+            .unwrap();
+        minio_admin.add_user("reef-job", "blacktip").await.unwrap();
+
+        let users = minio_admin.list_users().await.unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!(users["shark-job"].status, AccountStatus::Enabled);
+
+        minio_admin
+            .set_user_status("reef-job", AccountStatus::Disabled)
+            .await
+            .unwrap();
+        assert_eq!(
+            minio_admin.user_info("reef-job").await.unwrap().status,
+            AccountStatus::Disabled
+        );
+
+        minio_admin
+            .add_group_members("marine-biologists", &["shark-job", "reef-job"])
+            .await
+            .unwrap();
+        assert_eq!(
+            minio_admin.list_groups().await.unwrap(),
+            vec!["marine-biologists"]
+        );
+        assert_eq!(
+            minio_admin.user_info("shark-job").await.unwrap().member_of,
+            vec!["marine-biologists"]
+        );
+
+        // A group with members is not removed
+        assert!(minio_admin.remove_group("marine-biologists").await.is_err());
+
+        minio_admin
+            .remove_group_members("marine-biologists", &["reef-job"])
+            .await
+            .unwrap();
+        minio_admin.remove_user("shark-job").await.unwrap();
+
+        let group_info = minio_admin.group_info("marine-biologists").await.unwrap();
+        assert!(group_info.members.is_empty());
+        assert_eq!(group_info.status, AccountStatus::Enabled);
+
+        minio_admin.remove_group("marine-biologists").await.unwrap();
+        assert!(minio_admin.list_groups().await.unwrap().is_empty());
+
+        let err = minio_admin.user_info("shark-job").await.unwrap_err();
+        assert!(err.to_string().contains("XMinioAdminNoSuchUser"));
+        assert!(minio_admin
+            .add_group_members("marine-biologists", &["shark-job"])
+            .await
+            .is_err());
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_policies() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio_admin = MinioAdmin::from_minio(server.url(), &server.minio()).unwrap();
+
+        minio_admin
+            .add_canned_policy("sharks-readonly", SHARKS_READONLY)
+            .await
+            .unwrap();
+        minio_admin
+            .add_user("shark-job", "great-white")
+            .await
+            .unwrap();
+        minio_admin
+            .add_group_members("marine-biologists", &["shark-job"])
+            .await
+            .unwrap();
+
+        let policy = minio_admin.canned_policy("sharks-readonly").await.unwrap();
+        assert_eq!(policy["Statement"][0]["Action"][0], "s3:GetObject");
+        assert!(minio_admin
+            .list_canned_policies()
+            .await
+            .unwrap()
+            .contains_key("sharks-readonly"));
+
+        assert_eq!(
+            minio_admin
+                .attach_policies(&["sharks-readonly"], PolicyEntity::User("shark-job"))
+                .await
+                .unwrap(),
+            vec!["sharks-readonly"]
+        );
+        assert_eq!(
+            minio_admin
+                .attach_policies(
+                    &["sharks-readonly"],
+                    PolicyEntity::Group("marine-biologists")
+                )
+                .await
+                .unwrap(),
+            vec!["sharks-readonly"]
+        );
+        assert_eq!(
+            minio_admin
+                .user_info("shark-job")
+                .await
+                .unwrap()
+                .policy_name,
+            "sharks-readonly"
+        );
+        assert_eq!(
+            minio_admin
+                .group_info("marine-biologists")
+                .await
+                .unwrap()
+                .policy,
+            "sharks-readonly"
+        );
+
+        // Attaching an attached policy is an error
+        assert!(minio_admin
+            .attach_policies(&["sharks-readonly"], PolicyEntity::User("shark-job"))
+            .await
+            .is_err());
+        assert!(minio_admin
+            .attach_policies(&["hammerhead"], PolicyEntity::User("shark-job"))
+            .await
+            .is_err());
+
+        assert_eq!(
+            minio_admin
+                .detach_policies(&["sharks-readonly"], PolicyEntity::User("shark-job"))
+                .await
+                .unwrap(),
+            vec!["sharks-readonly"]
+        );
+        assert!(minio_admin
+            .user_info("shark-job")
+            .await
+            .unwrap()
+            .policy_name
+            .is_empty());
+
+        minio_admin
+            .remove_canned_policy("sharks-readonly")
+            .await
+            .unwrap();
+        assert!(minio_admin.canned_policy("sharks-readonly").await.is_err());
+        assert!(minio_admin
+            .add_canned_policy("broken", "{not json")
+            .await
+            .is_err());
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_service_accounts() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio_admin = MinioAdmin::new(
+            server.url(),
+            Credentials::new("minioadmin", "minioadmin", None, None, "test"),
+        )
+        .unwrap();
+
+        minio_admin
+            .add_user("shark-job", "great-white")
+            .await
+            .unwrap();
+
+        let credentials = minio_admin
+            .add_service_account(AddServiceAccountOptions {
+                target_user: Some("shark-job".to_string()),
+                policy: Some(SHARKS_READONLY.to_string()),
+                name: Some("shark-tagger".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(credentials.access_key_id(), "SVCACCT0000000000001");
+        assert_eq!(credentials.secret_access_key(), "svcacct-secret-1");
+
+        let admin_credentials = minio_admin
+            .add_service_account(AddServiceAccountOptions {
+                access_key: Some("reef-tagger-key".to_string()),
+                secret_key: Some("reef-tagger-secret".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(admin_credentials.access_key_id(), "reef-tagger-key");
+        assert_eq!(admin_credentials.secret_access_key(), "reef-tagger-secret");
+
+        let service_accounts = minio_admin
+            .list_service_accounts(Some("shark-job"))
+            .await
+            .unwrap();
+        assert_eq!(service_accounts.len(), 1);
+        assert_eq!(service_accounts[0].access_key, "SVCACCT0000000000001");
+        assert_eq!(service_accounts[0].parent_user, "shark-job");
+        assert!(!service_accounts[0].implied_policy);
+
+        let service_accounts = minio_admin.list_service_accounts(None).await.unwrap();
+        assert_eq!(service_accounts.len(), 1);
+        assert!(service_accounts[0].implied_policy);
+
+        minio_admin
+            .update_service_account(
+                credentials.access_key_id(),
+                UpdateServiceAccountOptions {
+                    status: Some(AccountStatus::Disabled),
+                    description: Some("Tags sharks".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let service_account = minio_admin
+            .service_account_info(credentials.access_key_id())
+            .await
+            .unwrap();
+        assert_eq!(service_account.access_key, "SVCACCT0000000000001");
+        assert_eq!(service_account.account_status, AccountStatus::Disabled);
+        assert_eq!(service_account.name.as_deref(), Some("shark-tagger"));
+        assert_eq!(service_account.description.as_deref(), Some("Tags sharks"));
+        let policy: serde_json::Value =
+            serde_json::from_str(service_account.policy.as_deref().unwrap()).unwrap();
+        assert_eq!(policy["Statement"][0]["Effect"], "Allow");
+
+        minio_admin
+            .delete_service_account(credentials.access_key_id())
+            .await
+            .unwrap();
+        assert!(minio_admin
+            .service_account_info(credentials.access_key_id())
+            .await
+            .is_err());
+
+        assert!(minio_admin
+            .add_service_account(AddServiceAccountOptions {
+                target_user: Some("hammerhead".to_string()),
+                ..Default::default()
+            })
+            .await
+            .is_err());
+        assert!(minio_admin
+            .add_service_account(AddServiceAccountOptions {
+                policy: Some("{not json".to_string()),
+                ..Default::default()
+            })
+            .await
+            .is_err());
+
+        // Requests signed with other credentials are rejected
+        let impostor = MinioAdmin::new(
+            server.url(),
+            Credentials::new("minioadmin", "hammerhead", None, None, "test"),
+        )
+        .unwrap();
+        assert!(impostor.list_users().await.is_err());
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_bucket_quota_and_data_usage() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio_admin = MinioAdmin::from_minio(server.url(), &server.minio()).unwrap();
+
+        server.storage.create_bucket("sharks").await.unwrap();
+        server.storage.create_bucket("orcas").await.unwrap();
+        for object_name in ["great-white.jpg", "hammerhead.jpg"] {
+            server
+                .storage
+                .upload_object("sharks", object_name, [0u8; 1_024].as_slice(), None)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(minio_admin.get_bucket_quota("sharks").await.unwrap(), None);
+
+        minio_admin
+            .set_bucket_quota("sharks", Some(1_048_576))
+            .await
+            .unwrap();
+        assert_eq!(
+            minio_admin.get_bucket_quota("sharks").await.unwrap(),
+            Some(1_048_576)
+        );
+
+        minio_admin.set_bucket_quota("sharks", None).await.unwrap();
+        assert_eq!(minio_admin.get_bucket_quota("sharks").await.unwrap(), None);
+
+        assert!(minio_admin
+            .set_bucket_quota("dolphins", Some(1_024))
+            .await
+            .is_err());
+        assert!(minio_admin.get_bucket_quota("dolphins").await.is_err());
+
+        let data_usage_info = minio_admin.data_usage_info().await.unwrap();
+        assert_eq!(data_usage_info.buckets_count, 2);
+        assert_eq!(data_usage_info.objects_count, 2);
+        assert_eq!(data_usage_info.objects_total_size, 2_048);
+        assert!(data_usage_info.last_update.is_some());
+
+        let sharks_usage = &data_usage_info.buckets_usage["sharks"];
+        assert_eq!(sharks_usage.objects_count, 2);
+        assert_eq!(sharks_usage.size, 2_048);
+        assert_eq!(data_usage_info.buckets_usage["orcas"].objects_count, 0);
+    }
+}
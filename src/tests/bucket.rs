@@ -48,7 +48,7 @@ async fn test_object_exists() {
 
 #[tokio::test]
 async fn test_list_objects() {
-    let object_names = vec!["shark.png", "file1.txt", "owl.jpg"];
+    let object_names = ["shark.png", "file1.txt", "owl.jpg"];
     let test_client = TestClient::new().await;
 
     test_client
@@ -95,7 +95,7 @@ async fn test_create_bucket_delete() {
                 test_error!("Bucket {} did not exist by bucket_exists", new_bucket_name);
             }
 
-            minio.delete_bucket(&new_bucket_name, true).await?;
+            minio.delete_bucket(new_bucket_name, true).await?;
 
             if minio.bucket_exists(new_bucket_name).await? {
                 test_error!("Bucket {} did exist by bucket_exists", new_bucket_name);
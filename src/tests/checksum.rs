@@ -0,0 +1,334 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "checksum")]
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        checksum::{Checksum, ChecksumReader, ChecksumVerifier},
+        get::GetObjectAdditionalOptions,
+        upload::{
+            upload_object::UploadObjectAdditionalOptions,
+            upload_object_multi::UploadManagerAdditionalOptions,
+        },
+    };
+    use crate::test_error;
+    use crate::tests::util::{test_client::TestClient, *};
+    use tokio::io::AsyncReadExt;
+
+    async fn verify(bytes: &[u8], verifier: ChecksumVerifier) -> std::io::Result<Vec<u8>> {
+        let mut output = vec![];
+
+        ChecksumReader::new(bytes, Some(verifier))
+            .read_to_end(&mut output)
+            .await?;
+
+        Ok(output)
+    }
+
+    #[test]
+    fn test_checksum_headers() {
+        let check = Checksum::Crc32.headers(b"123456789");
+        assert_eq!(check.crc32.as_deref(), Some("y/Q5Jg=="));
+
+        let check = Checksum::Crc32C.headers(b"123456789");
+        assert_eq!(check.crc32_c.as_deref(), Some("4waSgw=="));
+
+        let check = Checksum::Sha256.headers(b"abc");
+        assert_eq!(
+            check.sha256.as_deref(),
+            Some("ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=")
+        );
+
+        let check = Checksum::Md5.headers(b"abc");
+        assert_eq!(
+            check.content_md5.as_deref(),
+            Some("kAFQmDzST7DWlj99KOF/cg==")
+        );
+        assert!(check.algorithm.is_none());
+
+        assert!(check.verify(None, None, None).is_ok());
+        assert!(Checksum::Crc32
+            .headers(b"123456789")
+            .verify(Some("AAAAAA=="), None, None)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_checksum_reader() {
+        let bytes = (0..10_000).map(|byte| byte as u8).collect::<Vec<u8>>();
+
+        for checksum in [
+            Checksum::Crc32,
+            Checksum::Crc32C,
+            Checksum::Sha256,
+            Checksum::Md5,
+        ] {
+            let mut hasher = checksum.hasher();
+            hasher.update(&bytes);
+            let digest = hasher.finalize();
+
+            let expected = match checksum {
+                Checksum::Md5 => digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+                _ => aws_smithy_types::base64::encode(&digest),
+            };
+
+            assert_eq!(
+                verify(
+                    &bytes,
+                    ChecksumVerifier::new(checksum, expected.clone(), vec![])
+                )
+                .await
+                .unwrap(),
+                bytes
+            );
+            assert!(verify(
+                &bytes[1..],
+                ChecksumVerifier::new(checksum, expected, vec![])
+            )
+            .await
+            .is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checksum_reader_composite() {
+        let bytes = (0..10_000).map(|byte| byte as u8).collect::<Vec<u8>>();
+        let part_sizes = vec![4_000, 4_000, 2_000];
+
+        for checksum in [Checksum::Crc32C, Checksum::Sha256] {
+            let part_checksums =
+                [&bytes[..4_000], &bytes[4_000..8_000], &bytes[8_000..]].map(|part| {
+                    let headers = checksum.headers(part);
+                    headers.crc32_c.or(headers.sha256).unwrap()
+                });
+            let expected = checksum
+                .composite(part_checksums.iter().map(String::as_str))
+                .unwrap();
+
+            assert!(expected.ends_with("-3"));
+            assert!(verify(
+                &bytes,
+                ChecksumVerifier::new(checksum, expected.clone(), part_sizes.clone())
+            )
+            .await
+            .is_ok());
+
+            let mut flipped = bytes.clone();
+            flipped[5_000] ^= 1;
+
+            assert!(verify(
+                &flipped,
+                ChecksumVerifier::new(checksum, expected.clone(), part_sizes.clone())
+            )
+            .await
+            .is_err());
+
+            // The same bytes split at different part boundaries do not match
+            assert!(verify(
+                &bytes,
+                ChecksumVerifier::new(checksum, expected, vec![5_000, 3_000, 2_000])
+            )
+            .await
+            .is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_get_checksum() {
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                let file_bytes = (0..5_242_880 * 2 + 1_337)
+                    .map(|byte| byte as u8)
+                    .collect::<Vec<u8>>();
+
+                for (object_name, checksum) in [
+                    ("crc32.bin", Checksum::Crc32),
+                    ("crc32c.bin", Checksum::Crc32C),
+                    ("sha256.bin", Checksum::Sha256),
+                    ("md5.bin", Checksum::Md5),
+                ] {
+                    for (size, suffix) in [(1_337, "single"), (file_bytes.len(), "multi")] {
+                        let object_name = format!("{}.{}", suffix, object_name);
+
+                        minio
+                            .upload_object(
+                                &bucket_name,
+                                &object_name,
+                                &file_bytes[..size],
+                                Some(UploadObjectAdditionalOptions {
+                                    checksum: Some(checksum),
+                                    ..Default::default()
+                                }),
+                            )
+                            .await?;
+
+                        let downloaded_bytes = read_file_stream(
+                            minio
+                                .get_object(
+                                    &bucket_name,
+                                    &object_name,
+                                    Some(GetObjectAdditionalOptions {
+                                        checksum: Some(checksum),
+                                        ..Default::default()
+                                    }),
+                                )
+                                .await?
+                                .ok_or("Object does not exist")?,
+                        )
+                        .await?;
+
+                        if downloaded_bytes != file_bytes[..size] {
+                            test_error!("Verified bytes of {} do not match", object_name);
+                        }
+                    }
+                }
+
+                if minio
+                    .get_object(
+                        &bucket_name,
+                        "single.crc32.bin",
+                        Some(GetObjectAdditionalOptions {
+                            range: Some((0, 10)),
+                            checksum: Some(Checksum::Crc32),
+                            ..Default::default()
+                        }),
+                    )
+                    .await
+                    .is_ok()
+                {
+                    test_error!("get_object verified a checksum of a range");
+                }
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+
+    // Other features add fields to `UploadManagerAdditionalOptions`
+    #[allow(clippy::needless_update)]
+    #[tokio::test]
+    async fn test_upload_manager_checksum() {
+        let object_name = "parts.bin";
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                let part_bytes = vec![7u8; 5_242_880];
+
+                let mut upload_manager = minio
+                    .upload_object_multi(
+                        &bucket_name,
+                        object_name,
+                        Some(UploadManagerAdditionalOptions {
+                            checksum: Some(Checksum::Sha256),
+                            ..Default::default()
+                        }),
+                    )
+                    .await?;
+
+                upload_manager
+                    .upload_part(&minio.client, part_bytes.clone())
+                    .await?;
+                upload_manager
+                    .upload_part(&minio.client, part_bytes[..1_337].to_vec())
+                    .await?;
+                upload_manager.complete(&minio.client).await?;
+
+                let downloaded_bytes = read_file_stream(
+                    minio
+                        .get_object(
+                            &bucket_name,
+                            object_name,
+                            Some(GetObjectAdditionalOptions {
+                                checksum: Some(Checksum::Sha256),
+                                ..Default::default()
+                            }),
+                        )
+                        .await?
+                        .ok_or("Object does not exist")?,
+                )
+                .await?;
+
+                if downloaded_bytes.len() != 5_242_880 + 1_337 {
+                    test_error!("Verified bytes do not match the uploaded parts");
+                }
+
+                if minio
+                    .get_object(
+                        &bucket_name,
+                        object_name,
+                        Some(GetObjectAdditionalOptions {
+                            checksum: Some(Checksum::Crc32),
+                            ..Default::default()
+                        }),
+                    )
+                    .await
+                    .is_ok()
+                {
+                    test_error!("get_object verified a checksum that was never stored");
+                }
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_verify_completed_upload_mismatch() {
+        use crate::core::{checksum::PartChecksums, upload::util::verify_completed_upload};
+        use crate::testing::LocalS3Server;
+        use aws_sdk_s3::{
+            operation::complete_multipart_upload::CompleteMultipartUploadOutput,
+            types::ChecksumAlgorithm,
+        };
+
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+
+        minio.create_bucket("sharks").await.unwrap();
+        minio
+            .upload_object("sharks", "parts.bin", [7u8; 1_337].as_slice(), None)
+            .await
+            .unwrap();
+
+        let part_checksums = PartChecksums {
+            algorithm: Some(ChecksumAlgorithm::Crc32),
+            values: [(1, Checksum::Crc32.headers(&[7u8; 1_337]).crc32.unwrap())].into(),
+        };
+
+        // Without a stored checksum, the verified parts are trusted
+        verify_completed_upload(
+            &minio.client,
+            "sharks",
+            "parts.bin",
+            &part_checksums,
+            &CompleteMultipartUploadOutput::builder().build(),
+        )
+        .await
+        .unwrap();
+        assert!(minio.object_exists("sharks", "parts.bin").await.unwrap());
+
+        let output = CompleteMultipartUploadOutput::builder()
+            .checksum_crc32("AAAAAA==-1")
+            .build();
+
+        assert!(verify_completed_upload(
+            &minio.client,
+            "sharks",
+            "parts.bin",
+            &part_checksums,
+            &output,
+        )
+        .await
+        .is_err());
+
+        // The corrupt object is not left committed
+        assert!(!minio.object_exists("sharks", "parts.bin").await.unwrap());
+    }
+}
@@ -0,0 +1,345 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(all(feature = "cli", feature = "testing"))]
+#[cfg(test)]
+mod tests {
+    use crate::cli::{Cli, Command, Config, Location, PresignMethod, RemotePath};
+    use crate::core::{
+        storage::ObjectStorage, upload::upload_object::UploadObjectAdditionalOptions,
+    };
+    use crate::error::Error;
+    use crate::testing::LocalS3Server;
+    use crate::tests::util::*;
+    use crate::Minio;
+    use std::path::PathBuf;
+    use tokio::fs;
+
+    fn parse(args: &[&str]) -> Result<Cli, Error> {
+        Cli::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    async fn run(minio: &Minio, args: &[&str]) -> Result<String, Error> {
+        let cli = parse(args)?;
+        let mut out = vec![];
+
+        cli.command.run(minio, cli.json, &mut out).await?;
+
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn remote(bucket_name: &str, key: &str) -> RemotePath {
+        RemotePath {
+            bucket_name: bucket_name.to_string(),
+            key: key.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse() {
+        assert_eq!(parse(&[]).unwrap().command, Command::Help);
+        assert_eq!(parse(&["rm", "--help"]).unwrap().command, Command::Help);
+
+        let cli = parse(&["--json", "ls", "-rl", "s3://sharks/images/"]).unwrap();
+        assert!(cli.json);
+        assert_eq!(
+            cli.command,
+            Command::Ls {
+                target: Some(remote("sharks", "images/")),
+                recursive: true,
+                long: true,
+            }
+        );
+
+        assert_eq!(
+            parse(&["cp", "./shark.jpg", "s3://sharks"])
+                .unwrap()
+                .command,
+            Command::Cp {
+                src: Location::Local(PathBuf::from("./shark.jpg")),
+                dst: Location::Remote(remote("sharks", "")),
+            }
+        );
+
+        assert_eq!(
+            parse(&["presign", "put", "sharks/shark.jpg", "--expiry=60"])
+                .unwrap()
+                .command,
+            Command::Presign {
+                method: PresignMethod::Put,
+                target: remote("sharks", "shark.jpg"),
+                expiry_secs: 60,
+            }
+        );
+
+        let cli = parse(&[
+            "mirror",
+            "--exclude",
+            "*.tmp",
+            "--config",
+            "./minior.json",
+            "./sharks",
+            "s3://sharks/images",
+            "--delete",
+        ])
+        .unwrap();
+        assert_eq!(cli.config, Some(PathBuf::from("./minior.json")));
+        assert_eq!(
+            cli.command,
+            Command::Mirror {
+                src: Location::Local(PathBuf::from("./sharks")),
+                dst: Location::Remote(remote("sharks", "images")),
+                delete: true,
+                dry_run: false,
+                exclude: vec!["*.tmp".to_string()],
+            }
+        );
+
+        assert!(parse(&["ls", "--force"]).is_err());
+        assert!(parse(&["rm"]).is_err());
+        assert!(parse(&["cp", "s3://sharks/shark.jpg"]).is_err());
+        assert!(parse(&["presign", "post", "sharks/shark.jpg"]).is_err());
+        assert!(parse(&["presign", "get", "sharks/shark.jpg", "--expiry"]).is_err());
+        assert!(parse(&["mb", "s3://"]).is_err());
+        assert!(parse(&["mv", "a", "b"]).is_err());
+        assert!(parse(&["ls", "-x"]).is_err());
+
+        assert_eq!(
+            remote("sharks", "").key_or_join("./dir/shark.jpg"),
+            "shark.jpg"
+        );
+        assert_eq!(
+            remote("sharks", "images/").key_or_join("shark.jpg"),
+            "images/shark.jpg"
+        );
+        assert_eq!(
+            remote("sharks", "whale.jpg").key_or_join("shark.jpg"),
+            "whale.jpg"
+        );
+    }
+
+    #[test]
+    fn test_config_debug() {
+        let config = Config {
+            url: Some("http://127.0.0.1:9000".to_string()),
+            access_key: Some("hammerhead".to_string()),
+            secret_key: Some("great-white".to_string()),
+            region: None,
+        };
+        let debug = format!("{:?}", config);
+
+        assert!(debug.contains("hammerhead"));
+        assert!(!debug.contains("great-white"));
+    }
+
+    #[tokio::test]
+    async fn test_cli_commands() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+
+        let dir = std::env::temp_dir().join(format!("minior-cli-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("images")).await.unwrap();
+        fs::write(dir.join("shark.jpg"), b"hammerhead")
+            .await
+            .unwrap();
+        fs::write(dir.join("images/whale.jpg"), b"whale shark")
+            .await
+            .unwrap();
+        let dir = dir.to_str().unwrap();
+
+        assert_eq!(
+            run(&minio, &["mb", "s3://sharks"]).await.unwrap(),
+            "Bucket sharks created\n"
+        );
+        assert!(run(&minio, &["mb", "s3://sharks"]).await.is_err());
+        assert!(run(&minio, &["mb", "-p", "s3://sharks"]).await.is_ok());
+
+        run(
+            &minio,
+            &["cp", &format!("{}/shark.jpg", dir), "s3://sharks/"],
+        )
+        .await
+        .unwrap();
+        let output = run(&minio, &["mirror", dir, "s3://sharks/backup"])
+            .await
+            .unwrap();
+        assert!(output.ends_with("2 copied, 0 removed, 0 unchanged, 21 bytes\n"));
+
+        assert_eq!(
+            run(&minio, &["ls", "s3://sharks"]).await.unwrap(),
+            "backup/\nshark.jpg\n"
+        );
+        assert_eq!(
+            run(&minio, &["ls", "-r", "s3://sharks/backup/"])
+                .await
+                .unwrap(),
+            "backup/images/whale.jpg\nbackup/shark.jpg\n"
+        );
+        assert!(run(&minio, &["ls", "-l", "sharks"])
+            .await
+            .unwrap()
+            .contains("          10 shark.jpg"));
+
+        let listed: serde_json::Value =
+            serde_json::from_str(&run(&minio, &["--json", "ls", "sharks"]).await.unwrap()).unwrap();
+        assert_eq!(listed[0]["key"], "backup/");
+        assert_eq!(listed[0]["dir"], true);
+        assert_eq!(listed[1]["key"], "shark.jpg");
+        assert_eq!(listed[1]["size"], 10);
+
+        assert_eq!(
+            run(&minio, &["cat", "s3://sharks/shark.jpg"])
+                .await
+                .unwrap(),
+            "hammerhead"
+        );
+        assert!(run(&minio, &["cat", "s3://sharks/whale.jpg"])
+            .await
+            .is_err());
+
+        let stat: serde_json::Value = serde_json::from_str(
+            &run(&minio, &["stat", "--json", "s3://sharks/shark.jpg"])
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(stat["size"], 10);
+
+        run(
+            &minio,
+            &["cp", "s3://sharks/shark.jpg", "s3://sharks/copies/"],
+        )
+        .await
+        .unwrap();
+        fs::write(format!("{}/empty.jpg", dir), b"").await.unwrap();
+        run(
+            &minio,
+            &["cp", &format!("{}/empty.jpg", dir), "s3://sharks/"],
+        )
+        .await
+        .unwrap();
+        run(
+            &minio,
+            &[
+                "cp",
+                "s3://sharks/empty.jpg",
+                "s3://sharks/copies/empty.jpg",
+            ],
+        )
+        .await
+        .unwrap();
+        assert_object(
+            &minio,
+            "sharks",
+            "copies/shark.jpg",
+            ObjectAssertions::BytesEqual(b"hammerhead".to_vec()),
+        )
+        .await
+        .unwrap();
+        assert!(server
+            .storage
+            .object_exists("sharks", "copies/empty.jpg")
+            .await
+            .unwrap());
+
+        // A remote copy keeps the headers and metadata of its source
+        minio
+            .upload_object(
+                "sharks",
+                "typed.jpg",
+                b"tiger".as_slice(),
+                Some(UploadObjectAdditionalOptions {
+                    metadata: Some([("species".to_string(), "tiger".to_string())].into()),
+                    content_type: Some("image/jpeg".to_string()),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+        run(
+            &minio,
+            &["cp", "s3://sharks/typed.jpg", "s3://sharks/typed-copy.jpg"],
+        )
+        .await
+        .unwrap();
+        let stat = server
+            .storage
+            .stat_object("sharks", "typed-copy.jpg", None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stat.content_type(), Some("image/jpeg"));
+        assert_eq!(
+            stat.metadata().and_then(|metadata| metadata.get("species")),
+            Some(&"tiger".to_string())
+        );
+
+        run(
+            &minio,
+            &[
+                "cp",
+                "s3://sharks/copies/shark.jpg",
+                &format!("{}/images", dir),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            fs::read(format!("{}/images/shark.jpg", dir)).await.unwrap(),
+            b"hammerhead"
+        );
+
+        assert_eq!(
+            run(&minio, &["du", "s3://sharks/backup"]).await.unwrap(),
+            "21\t2 objects\ts3://sharks/backup\n"
+        );
+
+        let url = run(&minio, &["presign", "get", "s3://sharks/shark.jpg"])
+            .await
+            .unwrap();
+        let response = reqwest::get(url.trim()).await.unwrap();
+        assert_eq!(response.bytes().await.unwrap().as_ref(), b"hammerhead");
+
+        assert_eq!(
+            run(&minio, &["rm", "-r", "--dry-run", "s3://sharks/copies/"])
+                .await
+                .unwrap(),
+            "Would remove s3://sharks/copies/empty.jpg\nWould remove s3://sharks/copies/shark.jpg\n"
+        );
+        assert!(server
+            .storage
+            .object_exists("sharks", "copies/shark.jpg")
+            .await
+            .unwrap());
+        run(
+            &minio,
+            &["cp", "s3://sharks/shark.jpg", "s3://sharks/copies-old.jpg"],
+        )
+        .await
+        .unwrap();
+        run(&minio, &["rm", "-r", "s3://sharks/copies"])
+            .await
+            .unwrap();
+        assert!(server
+            .storage
+            .object_exists("sharks", "copies-old.jpg")
+            .await
+            .unwrap());
+        assert!(!server
+            .storage
+            .object_exists("sharks", "copies/shark.jpg")
+            .await
+            .unwrap());
+        assert!(run(&minio, &["rm", "s3://sharks/copies/shark.jpg"])
+            .await
+            .is_err());
+
+        assert!(run(&minio, &["rb", "s3://sharks"]).await.is_err());
+        run(&minio, &["rb", "--force", "s3://sharks"])
+            .await
+            .unwrap();
+        assert!(!server.storage.bucket_exists("sharks").await.unwrap());
+
+        fs::remove_dir_all(dir).await.unwrap();
+    }
+}
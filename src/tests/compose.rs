@@ -0,0 +1,102 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+use super::util::{test_client::TestClient, *};
+use crate::{core::compose::ComposeSource, test_error};
+
+#[tokio::test]
+async fn test_compose_object() {
+    let object_name = "composed.bin";
+    let test_client = TestClient::new().await;
+
+    test_client
+        .run_test(|minio, bucket_name| async move {
+            let first_bytes = vec![7u8; 5_242_880];
+            let second_bytes = get_test_file_bytes("shark.png").await?;
+
+            minio
+                .upload_object(&bucket_name, "first.bin", &first_bytes[..], None)
+                .await?;
+            minio
+                .upload_object(&bucket_name, "shark.png", &second_bytes[..], None)
+                .await?;
+
+            let composed_bytes = minio
+                .compose_object(
+                    &bucket_name,
+                    object_name,
+                    vec![
+                        ComposeSource::new(&bucket_name, "first.bin"),
+                        ComposeSource {
+                            range: Some((10, 1_009)),
+                            ..ComposeSource::new(&bucket_name, "shark.png")
+                        },
+                    ],
+                    None,
+                )
+                .await?;
+
+            if composed_bytes != first_bytes.len() + 1_000 {
+                test_error!("compose_object bytes counter did not equal the sources size");
+            }
+
+            let mut expected_bytes = first_bytes;
+            expected_bytes.extend_from_slice(&second_bytes[10..1_010]);
+
+            assert_object(
+                &minio,
+                &bucket_name,
+                object_name,
+                ObjectAssertions::BytesEqual(expected_bytes),
+            )
+            .await?;
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_compose_object_small_source() {
+    let object_name = "composed.txt";
+    let test_client = TestClient::new().await;
+
+    test_client
+        .run_test(|minio, bucket_name| async move {
+            for name in ["file1.txt", "file2.txt"] {
+                let file = get_test_file(name).await?;
+
+                minio.upload_object(&bucket_name, name, file, None).await?;
+            }
+
+            let result = minio
+                .compose_object(
+                    &bucket_name,
+                    object_name,
+                    vec![
+                        ComposeSource::new(&bucket_name, "file1.txt"),
+                        ComposeSource::new(&bucket_name, "file2.txt"),
+                    ],
+                    None,
+                )
+                .await;
+
+            if result.is_ok() {
+                test_error!("compose_object accepted a small source that was not last");
+            }
+
+            assert_object(
+                &minio,
+                &bucket_name,
+                object_name,
+                ObjectAssertions::DoesNotExist,
+            )
+            .await?;
+
+            assert_object(&minio, &bucket_name, "file1.txt", ObjectAssertions::Exists).await?;
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+}
@@ -0,0 +1,159 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "compression")]
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        compression::{CompressingReader, Compression, DecompressingReader, METADATA_COMPRESSION},
+        get::{GetObjectAdditionalOptions, StatObjectAdditionalOptions},
+        upload::upload_object::UploadObjectAdditionalOptions,
+    };
+    use crate::test_error;
+    use crate::tests::util::{test_client::TestClient, *};
+    use std::collections::HashMap;
+    use tokio::io::{AsyncReadExt, BufReader};
+
+    fn log_lines() -> Vec<u8> {
+        (0..10_000)
+            .map(|index| format!("{{\"level\":\"info\",\"index\":{}}}\n", index))
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    #[tokio::test]
+    async fn test_compression_round_trip() {
+        let bytes = log_lines();
+
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let mut compressed = vec![];
+            CompressingReader::new(bytes.as_slice(), Some(compression))
+                .read_to_end(&mut compressed)
+                .await
+                .unwrap();
+
+            assert!(compressed.len() * 10 < bytes.len());
+
+            let mut decompressed = vec![];
+            DecompressingReader::new(BufReader::new(compressed.as_slice()), Some(compression))
+                .read_to_end(&mut decompressed)
+                .await
+                .unwrap();
+
+            assert_eq!(decompressed, bytes);
+
+            compressed.truncate(compressed.len() / 2);
+
+            assert!(DecompressingReader::new(
+                BufReader::new(compressed.as_slice()),
+                Some(compression)
+            )
+            .read_to_end(&mut vec![])
+            .await
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_compression_from_metadata() {
+        let metadata =
+            |value: &str| HashMap::from([(METADATA_COMPRESSION.to_string(), value.to_string())]);
+
+        assert_eq!(
+            Compression::from_metadata(Some(&metadata("zstd"))).unwrap(),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(
+            Compression::from_metadata(Some(&metadata("gzip"))).unwrap(),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(Compression::from_metadata(None).unwrap(), None);
+        assert!(Compression::from_metadata(Some(&metadata("brotli"))).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_upload_get_compressed() {
+        let object_name = "logs.json";
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                let bytes = log_lines();
+
+                for compression in [Compression::Gzip, Compression::Zstd] {
+                    let bytes_uploaded = minio
+                        .upload_object(
+                            &bucket_name,
+                            object_name,
+                            bytes.as_slice(),
+                            Some(UploadObjectAdditionalOptions {
+                                compression: Some(compression),
+                                ..Default::default()
+                            }),
+                        )
+                        .await?;
+
+                    let stat = minio
+                        .stat_object(
+                            &bucket_name,
+                            object_name,
+                            Some(StatObjectAdditionalOptions::default()),
+                        )
+                        .await?
+                        .ok_or("stat_object returned None for an existing object")?;
+
+                    if stat.content_length() != Some(bytes_uploaded as i64)
+                        || bytes_uploaded * 10 >= bytes.len()
+                    {
+                        test_error!("Object was not stored compressed");
+                    }
+
+                    assert_object(
+                        &minio,
+                        &bucket_name,
+                        object_name,
+                        ObjectAssertions::BytesEqual(bytes.clone()),
+                    )
+                    .await?;
+
+                    let raw_bytes = read_file_stream(
+                        minio
+                            .get_object(
+                                &bucket_name,
+                                object_name,
+                                Some(GetObjectAdditionalOptions {
+                                    raw: true,
+                                    ..Default::default()
+                                }),
+                            )
+                            .await?
+                            .ok_or("Object does not exist")?,
+                    )
+                    .await?;
+
+                    if raw_bytes.len() != bytes_uploaded {
+                        test_error!("Raw get did not return the compressed bytes");
+                    }
+
+                    if minio
+                        .get_object(
+                            &bucket_name,
+                            object_name,
+                            Some(GetObjectAdditionalOptions {
+                                range: Some((0, 10)),
+                                ..Default::default()
+                            }),
+                        )
+                        .await
+                        .is_ok()
+                    {
+                        test_error!("get_object decompressed a range");
+                    }
+                }
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,278 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(test)]
+mod tests {
+    use crate::core::credentials::*;
+    use aws_sdk_s3::config::Credentials;
+    use std::{
+        path::Path,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, SystemTime},
+    };
+
+    /// Returns a provider of `access_key`, expiring `expires_in` from now,
+    /// counting its calls into `calls`
+    fn counting(
+        calls: &Arc<AtomicUsize>,
+        access_key: &'static str,
+        expires_in: Option<Duration>,
+    ) -> impl CredentialsProvider {
+        let calls = calls.clone();
+
+        FnCredentials::new(move || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            let expiry = expires_in.map(|expires_in| SystemTime::now() + expires_in);
+
+            async move {
+                Ok(Credentials::new(
+                    access_key, "secret", None, expiry, "counting",
+                ))
+            }
+        })
+    }
+
+    async fn write_credentials(path: &Path, contents: &str, modified: SystemTime) {
+        tokio::fs::write(path, contents).await.unwrap();
+
+        // Sets the modification time explicitly, as writes within the
+        // resolution of the file system would not change it
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_credentials_cache() {
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let cache = CredentialsCache::new(counting(&calls, "hammerhead", None));
+        for _ in 0..3 {
+            assert_eq!(
+                cache.credentials().await.unwrap().access_key_id(),
+                "hammerhead"
+            );
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        cache.invalidate().unwrap();
+        cache.credentials().await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        cache
+            .set_provider(counting(&calls, "tiger", Some(Duration::from_secs(3_600))))
+            .unwrap();
+        for _ in 0..3 {
+            assert_eq!(cache.credentials().await.unwrap().access_key_id(), "tiger");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Credentials within `EXPIRY_BUFFER` of expiring are refreshed on every call
+        let calls = Arc::new(AtomicUsize::new(0));
+        cache
+            .set_provider(counting(&calls, "mako", Some(EXPIRY_BUFFER / 2)))
+            .unwrap();
+        for _ in 0..3 {
+            assert_eq!(cache.credentials().await.unwrap().access_key_id(), "mako");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cached_calls = calls.clone();
+        cache
+            .set_provider(
+                FnCredentials::new(move || {
+                    cached_calls.fetch_add(1, Ordering::SeqCst);
+
+                    async { Ok(Credentials::new("nurse", "secret", None, None, "ttl")) }
+                })
+                .with_cache_ttl(Duration::ZERO),
+            )
+            .unwrap();
+        cache.credentials().await.unwrap();
+        cache.credentials().await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        cache
+            .set_credentials(Credentials::new("whale", "secret", None, None, "static"))
+            .unwrap();
+        assert_eq!(cache.credentials().await.unwrap().access_key_id(), "whale");
+
+        cache
+            .set_provider(FnCredentials::new(|| async {
+                Err(crate::error::Error::internal("Vault is sealed"))
+            }))
+            .unwrap();
+        assert!(cache.credentials().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_credentials_cache_concurrent_refresh() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provided_calls = calls.clone();
+
+        let cache = CredentialsCache::new(FnCredentials::new(move || {
+            provided_calls.fetch_add(1, Ordering::SeqCst);
+
+            async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+
+                Ok(Credentials::new("lease", "secret", None, None, "vault"))
+            }
+        }));
+
+        // Every part upload of a rotation waits for the same lease
+        let results = tokio::join!(
+            cache.credentials(),
+            cache.credentials(),
+            cache.credentials(),
+            cache.credentials()
+        );
+        assert!([results.0, results.1, results.2, results.3]
+            .iter()
+            .all(Result::is_ok));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_file_credentials() {
+        let path =
+            std::env::temp_dir().join(format!("minior-credentials-{}", uuid::Uuid::new_v4()));
+        let modified = SystemTime::now() - Duration::from_secs(60);
+
+        write_credentials(
+            &path,
+            "# Rendered by the Vault agent\n\
+            [default]\n\
+            aws_access_key_id = hammerhead\n\
+            aws_secret_access_key = great-white\n\
+            \n\
+            [profile prod]\n\
+            aws_access_key_id = tiger\n\
+            aws_secret_access_key = bull\n\
+            aws_session_token = token\n",
+            modified,
+        )
+        .await;
+
+        let provider = FileCredentials::new(&path);
+        let credentials = provider.provide_credentials().await.unwrap();
+        assert_eq!(credentials.access_key_id(), "hammerhead");
+        assert_eq!(credentials.secret_access_key(), "great-white");
+        assert_eq!(credentials.session_token(), None);
+        assert_eq!(provider.cache_ttl(), Some(DEFAULT_POLL_INTERVAL));
+
+        let credentials = FileCredentials::new(&path)
+            .with_profile("prod")
+            .provide_credentials()
+            .await
+            .unwrap();
+        assert_eq!(credentials.access_key_id(), "tiger");
+        assert_eq!(credentials.session_token(), Some("token"));
+
+        assert!(FileCredentials::new(&path)
+            .with_profile("staging")
+            .provide_credentials()
+            .await
+            .is_err());
+
+        // Unchanged modification times are not re-read
+        write_credentials(
+            &path,
+            "aws_access_key_id = mako\naws_secret_access_key = lemon\n",
+            modified,
+        )
+        .await;
+        let cache = CredentialsCache::new(provider.with_poll_interval(Duration::ZERO));
+        assert_eq!(
+            cache.credentials().await.unwrap().access_key_id(),
+            "hammerhead"
+        );
+
+        write_credentials(
+            &path,
+            "aws_access_key_id = mako\naws_secret_access_key = lemon\n",
+            SystemTime::now(),
+        )
+        .await;
+        assert_eq!(cache.credentials().await.unwrap().access_key_id(), "mako");
+
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert!(cache.credentials().await.is_err());
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_set_credentials() {
+        use crate::{testing::LocalS3Server, Minio};
+
+        let server = LocalS3Server::start_with_credentials("hammerhead", "great-white")
+            .await
+            .unwrap();
+
+        let url = server.url().replace("://", "://hammerhead:rotated-out@");
+        let minio = Minio::from_url(&format!("{}?path_style=true", url))
+            .await
+            .unwrap();
+        assert!(minio.create_bucket("sharks").await.is_err());
+
+        minio
+            .set_credentials(Credentials::new(
+                "hammerhead",
+                "great-white",
+                None,
+                None,
+                "rotation",
+            ))
+            .unwrap();
+        minio.create_bucket("sharks").await.unwrap();
+        assert_eq!(
+            minio.credentials().await.unwrap().secret_access_key(),
+            "great-white"
+        );
+        assert_eq!(minio.endpoint_url(), Some(server.url()));
+
+        // The provider resolves the swapped credentials too
+        let credentials = minio
+            .credentials_provider()
+            .unwrap()
+            .provide_credentials()
+            .await
+            .unwrap();
+        assert_eq!(credentials.secret_access_key(), "great-white");
+
+        let path =
+            std::env::temp_dir().join(format!("minior-credentials-{}", uuid::Uuid::new_v4()));
+        write_credentials(
+            &path,
+            "aws_access_key_id = hammerhead\naws_secret_access_key = rotated-out\n",
+            SystemTime::now() - Duration::from_secs(60),
+        )
+        .await;
+
+        let local_minio = server.minio();
+        local_minio
+            .set_credentials_provider(
+                FileCredentials::new(&path).with_poll_interval(Duration::ZERO),
+            )
+            .unwrap();
+        assert!(local_minio.bucket_exists("sharks").await.is_err());
+
+        write_credentials(
+            &path,
+            "aws_access_key_id = hammerhead\naws_secret_access_key = great-white\n",
+            SystemTime::now(),
+        )
+        .await;
+        assert!(local_minio.bucket_exists("sharks").await.unwrap());
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
@@ -49,7 +49,7 @@ async fn test_delete_presigned() {
                 .delete_object_presigned(&bucket_name, object_name, 1_337)
                 .await?;
 
-            let delete_url = request.uri();
+            let delete_url = request.url;
 
             reqwest_client.delete(delete_url).send().await?;
 
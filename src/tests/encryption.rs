@@ -0,0 +1,254 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "encryption")]
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        encryption::{
+            key_provider::{KeyProvider, StaticKeyProvider},
+            stream::*,
+        },
+        get::GetObjectAdditionalOptions,
+    };
+    use crate::tests::util::{test_client::TestClient, *};
+    use crate::{test_error, EncryptedMinio, Minio};
+    use tokio::io::{AsyncReadExt, BufReader};
+
+    const CHUNK_SIZE: usize = 64;
+
+    async fn encrypt(plaintext: &[u8]) -> Vec<u8> {
+        let mut ciphertext = vec![];
+
+        EncryptingReader::new(plaintext, &[3; 32], [5; NONCE_PREFIX_SIZE], CHUNK_SIZE)
+            .read_to_end(&mut ciphertext)
+            .await
+            .unwrap();
+
+        ciphertext
+    }
+
+    async fn decrypt(
+        ciphertext: &[u8],
+        last_chunk_index: u32,
+        (start, end): (usize, usize),
+    ) -> std::io::Result<Vec<u8>> {
+        let first_chunk_index = start / CHUNK_SIZE;
+        let mut plaintext = vec![];
+
+        BufReader::new(DecryptingReader::new(
+            &ciphertext[first_chunk_index * (CHUNK_SIZE + TAG_SIZE)..],
+            &[3; 32],
+            [5; NONCE_PREFIX_SIZE],
+            CHUNK_SIZE,
+            DecryptingRange {
+                chunk_index: first_chunk_index as u32,
+                last_chunk_index,
+                skip: start - first_chunk_index * CHUNK_SIZE,
+                remaining: end - start + 1,
+            },
+        ))
+        .read_to_end(&mut plaintext)
+        .await?;
+
+        Ok(plaintext)
+    }
+
+    #[tokio::test]
+    async fn test_chunked_stream_round_trip() {
+        for size in [
+            1,
+            CHUNK_SIZE - 1,
+            CHUNK_SIZE,
+            CHUNK_SIZE + 1,
+            CHUNK_SIZE * 3,
+        ] {
+            let plaintext = (0..size).map(|byte| byte as u8).collect::<Vec<u8>>();
+            let ciphertext = encrypt(&plaintext).await;
+            let last_chunk_index = (size.div_ceil(CHUNK_SIZE) - 1) as u32;
+
+            assert_eq!(
+                ciphertext.len(),
+                size + size.div_ceil(CHUNK_SIZE) * TAG_SIZE
+            );
+            assert_eq!(
+                decrypt(&ciphertext, last_chunk_index, (0, size - 1))
+                    .await
+                    .unwrap(),
+                plaintext
+            );
+
+            let (start, end) = (size / 3, size - 1 - size / 4);
+
+            assert_eq!(
+                decrypt(&ciphertext, last_chunk_index, (start, end))
+                    .await
+                    .unwrap(),
+                plaintext[start..=end]
+            );
+        }
+
+        assert_eq!(encrypt(&[]).await.len(), TAG_SIZE);
+    }
+
+    #[tokio::test]
+    async fn test_chunked_stream_tampering() {
+        let plaintext = vec![7; CHUNK_SIZE * 2];
+        let ciphertext = encrypt(&plaintext).await;
+
+        let mut flipped = ciphertext.clone();
+        flipped[CHUNK_SIZE + TAG_SIZE + 1] ^= 1;
+
+        assert!(decrypt(&flipped, 1, (0, plaintext.len() - 1))
+            .await
+            .is_err());
+
+        // Dropping the last chunk must not decrypt as a shorter object
+        assert!(
+            decrypt(&ciphertext[..CHUNK_SIZE + TAG_SIZE], 0, (0, CHUNK_SIZE - 1))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_static_key_provider() {
+        let key_provider = StaticKeyProvider::new("tenant-1337", [1; 32]);
+
+        let wrapped_key = key_provider.wrap_key(&[9; 32]).await.unwrap();
+
+        assert_ne!(wrapped_key[12..44], [9; 32]);
+        assert_eq!(
+            key_provider
+                .unwrap_key("tenant-1337", &wrapped_key)
+                .await
+                .unwrap(),
+            vec![9; 32]
+        );
+        assert!(key_provider
+            .unwrap_key("tenant-7331", &wrapped_key)
+            .await
+            .is_err());
+        assert!(StaticKeyProvider::new("tenant-1337", [2; 32])
+            .unwrap_key("tenant-1337", &wrapped_key)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_upload_get() {
+        let object_name = "shark.png";
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                let file = get_test_file(object_name).await?;
+                let file_bytes = get_test_file_bytes(object_name).await?;
+
+                let encrypted_minio = EncryptedMinio {
+                    chunk_size: 1_024,
+                    ..EncryptedMinio::new(
+                        Minio {
+                            client: minio.client.clone(),
+                            credentials_provider: None,
+                            credentials_cache: None,
+                            endpoint_url: None,
+                            #[cfg(any(feature = "health", feature = "notifications"))]
+                            endpoint: None,
+                            #[cfg(feature = "metrics")]
+                            metrics: None,
+                        },
+                        StaticKeyProvider::new("tenant-1337", [1; 32]),
+                    )
+                };
+
+                let bytes_uploaded = encrypted_minio
+                    .upload_object(&bucket_name, object_name, file, None)
+                    .await?;
+
+                if bytes_uploaded != file_bytes.len() {
+                    test_error!("Encrypted upload did not report the plaintext size");
+                }
+
+                let stored_bytes = read_file_stream(
+                    minio
+                        .get_object(&bucket_name, object_name, None)
+                        .await?
+                        .ok_or("Encrypted object does not exist")?,
+                )
+                .await?;
+
+                if stored_bytes
+                    .windows(64)
+                    .any(|window| window == &file_bytes[..64])
+                {
+                    test_error!("Stored object contains plaintext");
+                }
+
+                let downloaded_bytes = read_file_stream(
+                    encrypted_minio
+                        .get_object(&bucket_name, object_name, None)
+                        .await?
+                        .ok_or("Encrypted object does not exist")?,
+                )
+                .await?;
+
+                if downloaded_bytes != file_bytes {
+                    test_error!("Decrypted bytes do not match the uploaded ones");
+                }
+
+                let ranged_bytes = read_file_stream(
+                    encrypted_minio
+                        .get_object(
+                            &bucket_name,
+                            object_name,
+                            Some(GetObjectAdditionalOptions {
+                                range: Some((1_000, 3_100)),
+                                ..Default::default()
+                            }),
+                        )
+                        .await?
+                        .ok_or("Encrypted object does not exist")?,
+                )
+                .await?;
+
+                if ranged_bytes != file_bytes[1_000..=3_100] {
+                    test_error!("Decrypted range does not match the uploaded bytes");
+                }
+
+                let wrong_key_minio = EncryptedMinio::new(
+                    Minio {
+                        client: minio.client.clone(),
+                        credentials_provider: None,
+                        credentials_cache: None,
+                        endpoint_url: None,
+                        #[cfg(any(feature = "health", feature = "notifications"))]
+                        endpoint: None,
+                        #[cfg(feature = "metrics")]
+                        metrics: None,
+                    },
+                    StaticKeyProvider::new("tenant-1337", [2; 32]),
+                );
+
+                if wrong_key_minio
+                    .get_object(&bucket_name, object_name, None)
+                    .await
+                    .is_ok()
+                {
+                    test_error!("Object was decrypted with the wrong key-encryption key");
+                }
+
+                if encrypted_minio
+                    .get_object(&bucket_name, "whale_shark.png", None)
+                    .await?
+                    .is_some()
+                {
+                    test_error!("get_object returned Some for a missing object");
+                }
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,138 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "testing")]
+#[cfg(test)]
+mod tests {
+    use crate::core::storage::ObjectStorage;
+    use crate::testing::{Fault, FaultInjector, LocalS3Server};
+    use crate::tests::util::*;
+    use crate::Minio;
+    use aws_sdk_s3::Client;
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_fault_injector_retries_slow_down() {
+        let server = LocalS3Server::start().await.unwrap();
+        server.storage.create_bucket("sharks").await.unwrap();
+
+        let fault_injector = FaultInjector::new().fail_nth("PutObject", 1, Fault::SlowDown);
+        let minio = fault_injector.attach(&server.minio());
+
+        minio
+            .upload_object("sharks", "shark.jpg", b"hammerhead".as_slice(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(fault_injector.requests("PutObject"), 2);
+        assert!(server
+            .storage
+            .object_exists("sharks", "shark.jpg")
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_fault_injector_aborts_failed_uploads() {
+        let server = LocalS3Server::start().await.unwrap();
+        server.storage.create_bucket("sharks").await.unwrap();
+
+        let fault_injector = FaultInjector::new().fail_nth(
+            "UploadPart",
+            2,
+            Fault::Error {
+                status: 403,
+                code: "AccessDenied".to_string(),
+            },
+        );
+        let minio = fault_injector.attach(&server.minio());
+
+        let whale_bytes = vec![7; 12_000_000];
+        assert!(minio
+            .upload_object("sharks", "whale.bin", whale_bytes.as_slice(), None)
+            .await
+            .is_err());
+
+        assert_eq!(fault_injector.requests("CompleteMultipartUpload"), 0);
+        assert_eq!(fault_injector.requests("AbortMultipartUpload"), 1);
+        assert!(!server
+            .storage
+            .object_exists("sharks", "whale.bin")
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_fault_injector_answers_without_a_server() {
+        let server = LocalS3Server::start().await.unwrap();
+
+        // Nothing listens on the port, so only injected responses are received
+        let unreachable = Minio::from_client(Client::from_conf(
+            server
+                .minio()
+                .client
+                .config()
+                .to_builder()
+                .endpoint_url("http://127.0.0.1:1")
+                .build(),
+        ));
+
+        let fault_injector = FaultInjector::new().always(
+            "PutObject",
+            Fault::Error {
+                status: 403,
+                code: "AccessDenied".to_string(),
+            },
+        );
+        let minio = fault_injector.attach(&unreachable);
+
+        let err = minio
+            .upload_object("sharks", "shark.jpg", b"hammerhead".as_slice(), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("AccessDenied"));
+        assert_eq!(fault_injector.requests("PutObject"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fault_injector_latency_and_dropped_connections() {
+        let server = LocalS3Server::start().await.unwrap();
+        server.storage.create_bucket("sharks").await.unwrap();
+        server
+            .storage
+            .upload_object("sharks", "shark.jpg", b"hammerhead".as_slice(), None)
+            .await
+            .unwrap();
+
+        let fault_injector = FaultInjector::new()
+            .always("HeadObject", Fault::Latency(Duration::from_millis(200)))
+            .always("GetObject", Fault::DropConnection { after_bytes: 4 })
+            .always("PutObject", Fault::DropConnection { after_bytes: 4 });
+        let minio = fault_injector.attach(&server.minio());
+
+        let start = Instant::now();
+        assert!(minio
+            .stat_object("sharks", "shark.jpg", None)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        let stream = minio
+            .get_object("sharks", "shark.jpg", None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(read_file_stream(stream).await.is_err());
+
+        assert!(minio
+            .upload_object("sharks", "whale.jpg", b"whale shark".as_slice(), None)
+            .await
+            .is_err());
+        assert!(!server
+            .storage
+            .object_exists("sharks", "whale.jpg")
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,139 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(all(feature = "health", feature = "testing"))]
+#[cfg(test)]
+mod tests {
+    use crate::core::health::*;
+    use crate::testing::{Fault, FaultInjector, LocalS3Server};
+    use crate::{error::Error, Minio};
+    use aws_sdk_s3::Client;
+    use std::{sync::Arc, time::Duration};
+
+    #[tokio::test]
+    async fn test_health() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+
+        for check in [
+            HealthCheck::Live,
+            HealthCheck::Ready,
+            HealthCheck::ClusterRead,
+        ] {
+            let status = minio.health(check).await.unwrap();
+
+            assert!(status.healthy);
+            assert_eq!(status.status_code, 200);
+            assert_eq!(status.write_quorum, None);
+            assert_eq!(status.safe_for_maintenance, None);
+        }
+
+        assert_eq!(
+            minio.health(HealthCheck::Cluster).await.unwrap(),
+            HealthStatus {
+                healthy: true,
+                status_code: 200,
+                server_status: None,
+                write_quorum: Some(1),
+                healing_drives: None,
+                storage_class_defaults: Some(true),
+                safe_for_maintenance: None,
+            }
+        );
+
+        // A single drive server never keeps quorum without itself
+        let status = minio.health(HealthCheck::Maintenance).await.unwrap();
+        assert!(!status.healthy);
+        assert_eq!(status.status_code, 412);
+        assert_eq!(status.safe_for_maintenance, Some(false));
+
+        server.set_healthy(false);
+
+        let status = minio.health(HealthCheck::Ready).await.unwrap();
+        assert!(!status.healthy);
+        assert_eq!(status.status_code, 503);
+        assert_eq!(status.server_status.as_deref(), Some("offline"));
+        assert!(!minio.health(HealthCheck::Cluster).await.unwrap().healthy);
+
+        // Only the health checks fail
+        minio.ping(Duration::from_secs(5)).await.unwrap();
+
+        server.set_healthy(true);
+        assert!(minio.health(HealthCheck::Ready).await.unwrap().healthy);
+
+        let unreachable = health("http://127.0.0.1:1", HealthCheck::Live).await;
+        assert!(unreachable.is_err());
+
+        let minio = Minio {
+            endpoint_url: None,
+            endpoint: None,
+            ..server.minio()
+        };
+        assert!(minio.health(HealthCheck::Live).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+
+        assert!(minio.ping(Duration::from_secs(5)).await.unwrap() < Duration::from_secs(5));
+
+        let slow_minio = FaultInjector::new()
+            .always("ListBuckets", Fault::Latency(Duration::from_secs(2)))
+            .attach(&minio);
+        assert!(matches!(
+            slow_minio.ping(Duration::from_millis(100)).await,
+            Err(Error::StdIo(std::io::ErrorKind::TimedOut))
+        ));
+
+        // Signed by credentials the server does not accept
+        let impostor = LocalS3Server::start_with_credentials("sharks", "tiger")
+            .await
+            .unwrap();
+        let impostor_minio = Minio {
+            client: Arc::new(Client::from_conf(
+                impostor
+                    .minio()
+                    .client
+                    .config()
+                    .to_builder()
+                    .endpoint_url(server.url())
+                    .build(),
+            )),
+            ..impostor.minio()
+        };
+        assert!(matches!(
+            impostor_minio.ping(Duration::from_secs(5)).await,
+            Err(Error::SdkError(_))
+        ));
+    }
+
+    #[cfg(feature = "sts")]
+    #[tokio::test]
+    async fn test_health_ca_certificates() {
+        use crate::testing::LocalStsServer;
+
+        let read_test_data = |name: &str| std::fs::read(format!("test_data/sts/{}", name)).unwrap();
+
+        let server = LocalStsServer::start_tls(
+            &read_test_data("server.pem"),
+            &read_test_data("server-key.pem"),
+            &read_test_data("ca.pem"),
+        )
+        .await
+        .unwrap();
+        let minio = Minio::from_url(&format!(
+            "{}?region=us-east-1",
+            server.url().replace("://", "://minioadmin:minioadmin@")
+        ))
+        .await
+        .unwrap();
+
+        // The self-signed server is not trusted without its CA
+        assert!(minio.health(HealthCheck::Live).await.is_err());
+
+        let minio = minio.with_ca_certificates(read_test_data("ca.pem"));
+        assert!(minio.health(HealthCheck::Live).await.is_ok());
+    }
+}
@@ -0,0 +1,249 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "testing")]
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        credentials::StaticCredentials, get::GetObjectAdditionalOptions, storage::ObjectStorage,
+        upload::upload_object::UploadObjectAdditionalOptions,
+    };
+    use crate::testing::LocalS3Server;
+    use crate::tests::util::*;
+    use crate::Minio;
+    use aws_sdk_s3::{
+        config::{BehaviorVersion, Credentials, Region},
+        Client,
+    };
+    use std::collections::HashMap;
+
+    async fn get_bytes(
+        minio: &Minio,
+        object_name: &str,
+        additional_options: Option<GetObjectAdditionalOptions>,
+    ) -> Option<Vec<u8>> {
+        let stream = minio
+            .get_object("sharks", object_name, additional_options)
+            .await
+            .unwrap()?;
+
+        Some(read_file_stream(stream).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_local_server_objects() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+
+        assert!(minio.create_bucket("sharks").await.unwrap());
+        assert!(!minio.create_bucket("sharks").await.unwrap());
+        assert!(server.storage.bucket_exists("sharks").await.unwrap());
+
+        minio
+            .upload_object(
+                "sharks",
+                "shark.jpg",
+                b"hammerhead".as_slice(),
+                Some(UploadObjectAdditionalOptions {
+                    metadata: Some(HashMap::from([(
+                        "species".to_string(),
+                        "hammerhead".to_string(),
+                    )])),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            get_bytes(&minio, "shark.jpg", None).await.unwrap(),
+            b"hammerhead"
+        );
+        assert_eq!(
+            get_bytes(
+                &minio,
+                "shark.jpg",
+                Some(GetObjectAdditionalOptions {
+                    range: Some((2, 5)),
+                    ..Default::default()
+                })
+            )
+            .await
+            .unwrap(),
+            b"mmer"
+        );
+        assert!(get_bytes(&minio, "whale_shark.jpg", None).await.is_none());
+
+        let stat = minio
+            .stat_object("sharks", "shark.jpg", None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(stat.content_length(), Some(10));
+        assert_eq!(stat.e_tag(), Some("\"bfb1d703cab9fe0906736f4e98864e2a\""));
+        assert_eq!(
+            stat.metadata().unwrap().get("species").map(String::as_str),
+            Some("hammerhead")
+        );
+
+        assert_object(
+            &minio,
+            "sharks",
+            "shark.jpg",
+            ObjectAssertions::BytesEqualPresigned(b"hammerhead".to_vec(), &reqwest::Client::new()),
+        )
+        .await
+        .unwrap();
+
+        let whale_bytes = (0..6_000_000).map(|byte| byte as u8).collect::<Vec<u8>>();
+        minio
+            .upload_object("sharks", "whale.bin", whale_bytes.as_slice(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            get_bytes(&minio, "whale.bin", None).await.unwrap(),
+            whale_bytes
+        );
+        assert!(minio
+            .stat_object("sharks", "whale.bin", None)
+            .await
+            .unwrap()
+            .unwrap()
+            .e_tag()
+            .unwrap()
+            .ends_with("-2\""));
+
+        let objects = minio.list_bucket_objects("sharks").await.unwrap();
+        assert_eq!(objects.len(), 2);
+
+        assert!(minio.delete_bucket("sharks", false).await.is_err());
+        assert!(minio.delete_bucket("sharks", true).await.unwrap());
+        assert!(minio.list_buckets().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_local_server_list_objects_v2() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+        minio.create_bucket("sharks").await.unwrap();
+
+        for object_name in ["a.txt", "b.txt", "images/c.jpg", "images/d.jpg", "z.txt"] {
+            server
+                .storage
+                .upload_object("sharks", object_name, object_name.as_bytes(), None)
+                .await
+                .unwrap();
+        }
+
+        let mut object_names = vec![];
+        let mut common_prefixes = vec![];
+        let mut continuation_token = None;
+
+        loop {
+            let response = minio
+                .client
+                .list_objects_v2()
+                .bucket("sharks")
+                .delimiter("/")
+                .max_keys(2)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await
+                .unwrap();
+
+            for object in response.contents() {
+                object_names.push(object.key().unwrap().to_string());
+            }
+
+            for common_prefix in response.common_prefixes() {
+                common_prefixes.push(common_prefix.prefix().unwrap().to_string());
+            }
+
+            continuation_token = response.next_continuation_token().map(str::to_string);
+
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(object_names, ["a.txt", "b.txt", "z.txt"]);
+        assert_eq!(common_prefixes, ["images/"]);
+
+        let objects = minio
+            .list_objects_with_prefix("sharks", "images/")
+            .await
+            .unwrap();
+        assert_eq!(objects.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_local_server_rejects_bad_signatures() {
+        let server = LocalS3Server::start_with_credentials("sharks", "hammerhead")
+            .await
+            .unwrap();
+        let impostor = LocalS3Server::start_with_credentials("sharks", "tiger")
+            .await
+            .unwrap();
+
+        let minio = Minio::from_client(Client::from_conf(
+            impostor
+                .minio()
+                .client
+                .config()
+                .to_builder()
+                .endpoint_url(server.url())
+                .build(),
+        ));
+
+        assert!(minio.create_bucket("sharks").await.is_err());
+        assert!(!server.storage.bucket_exists("sharks").await.unwrap());
+
+        let response = reqwest::Client::new()
+            .get(format!("{}/sharks/shark.jpg", server.url()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403);
+    }
+
+    #[tokio::test]
+    async fn test_minio_from_client() {
+        let server = LocalS3Server::start_with_credentials("sharks", "hammerhead")
+            .await
+            .unwrap();
+
+        let client = Client::from_conf(
+            aws_sdk_s3::Config::builder()
+                .behavior_version(BehaviorVersion::latest())
+                .endpoint_url(server.url())
+                .region(Region::new("us-east-1"))
+                .credentials_provider(Credentials::new("sharks", "tiger", None, None, "custom"))
+                .force_path_style(true)
+                .build(),
+        );
+
+        let minio = Minio::from_client(client);
+        assert!(minio.create_bucket("sharks").await.is_err());
+        assert!(minio.credentials().await.is_err());
+        assert_eq!(minio.endpoint_url(), None);
+
+        let minio =
+            minio.with_credentials_provider(StaticCredentials::new("sharks", "tiger", None));
+        assert!(minio.create_bucket("sharks").await.is_err());
+        minio
+            .set_credentials(Credentials::new(
+                "sharks",
+                "hammerhead",
+                None,
+                None,
+                "rotation",
+            ))
+            .unwrap();
+        minio.create_bucket("sharks").await.unwrap();
+
+        let minio = minio.with_endpoint_url(server.url()).unwrap();
+        assert_eq!(minio.endpoint_url(), Some(server.url()));
+    }
+}
@@ -0,0 +1,99 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(all(feature = "metrics", feature = "testing"))]
+#[cfg(test)]
+mod tests {
+    use crate::core::metrics::{LatencyHistogram, MetricsRegistry};
+    use crate::error::ErrorKind;
+    use crate::testing::{Fault, FaultInjector, LocalS3Server};
+    use crate::tests::util::*;
+    use std::{sync::Arc, time::Duration};
+
+    #[tokio::test]
+    async fn test_metrics_registry() {
+        let server = LocalS3Server::start().await.unwrap();
+        let metrics = MetricsRegistry::new();
+
+        let fault_injector = FaultInjector::new().fail_nth("UploadPart", 1, Fault::SlowDown);
+        let minio = fault_injector.attach(&server.minio().with_metrics(Arc::new(metrics.clone())));
+
+        let whale_bytes = vec![7; 6_000_000];
+
+        minio.create_bucket("sharks").await.unwrap();
+        minio
+            .upload_object("sharks", "whale.bin", whale_bytes.as_slice(), None)
+            .await
+            .unwrap();
+        let stream = minio
+            .get_object("sharks", "whale.bin", None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(read_file_stream(stream).await.unwrap(), whale_bytes);
+        assert!(minio
+            .stat_object("sharks", "whale.bin", None)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(minio.delete_bucket("sharks", false).await.is_err());
+
+        let snapshot = metrics.snapshot();
+
+        let upload_object = &snapshot.operations["upload_object"];
+        assert_eq!(upload_object.calls, 1);
+        assert!(upload_object.errors.is_empty());
+        assert_eq!(upload_object.latency.count, 1);
+        assert!(upload_object.latency.mean().is_some());
+
+        assert_eq!(
+            snapshot.operations["delete_bucket"].errors[&ErrorKind::SdkError],
+            1
+        );
+        assert_eq!(snapshot.errors(ErrorKind::SdkError), 1);
+
+        let upload_part = &snapshot.requests["UploadPart"];
+        assert_eq!(upload_part.attempts, 3);
+        assert_eq!(upload_part.failures, 1);
+        assert_eq!(upload_part.part_retries, 1);
+        assert_eq!(upload_part.bytes_uploaded, 6_000_000);
+        assert_eq!(snapshot.part_retries(), 1);
+
+        assert_eq!(snapshot.requests["GetObject"].bytes_downloaded, 6_000_000);
+        assert_eq!(snapshot.requests["HeadObject"].bytes_downloaded, 0);
+
+        // The XML bodies of the multipart requests are not object data
+        assert_eq!(
+            snapshot.requests["CompleteMultipartUpload"].bytes_uploaded,
+            0
+        );
+        assert_eq!(snapshot.bytes_uploaded(), 6_000_000);
+        assert_eq!(snapshot.bytes_downloaded(), 6_000_000);
+
+        metrics.reset();
+        assert!(metrics.snapshot().operations.is_empty());
+    }
+
+    #[test]
+    fn test_latency_histogram() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.quantile(0.5), None);
+
+        for millis in [3, 40, 45, 200, 60_000] {
+            histogram.observe(Duration::from_millis(millis));
+        }
+
+        assert_eq!(histogram.count, 5);
+        assert_eq!(histogram.buckets[0], 1);
+        assert_eq!(histogram.buckets[3], 2);
+        assert_eq!(histogram.quantile(0.5), Some(Duration::from_millis(50)));
+        assert_eq!(histogram.quantile(0.8), Some(Duration::from_millis(250)));
+        assert_eq!(histogram.quantile(1.0), None);
+        assert_eq!(histogram.mean(), Some(Duration::from_micros(12_057_600)));
+
+        // The count no longer fits a `u32` after 2^32 requests
+        histogram.count = 1 << 32;
+        histogram.sum = Duration::from_secs(1 << 32);
+        assert_eq!(histogram.mean(), Some(Duration::from_secs(1)));
+    }
+}
@@ -1,5 +1,27 @@
+pub mod admin;
 pub mod bucket;
+pub mod checksum;
+pub mod cli;
+pub mod compose;
+pub mod compression;
+pub mod connection;
+pub mod credentials;
 pub mod delete;
+pub mod encryption;
+pub mod fault_injector;
+pub mod health;
+pub mod local_server;
+pub mod metrics;
+pub mod notifications;
 pub mod pagination_iter;
+pub mod post_policy;
+pub mod presigned_session;
+pub mod replicate;
+pub mod sse;
+pub mod storage;
+pub mod sts;
+pub mod sync;
+pub mod telemetry;
 pub mod upload_get;
+pub mod usage;
 pub mod util;
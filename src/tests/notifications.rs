@@ -0,0 +1,191 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(all(feature = "notifications", feature = "testing"))]
+#[cfg(test)]
+mod tests {
+    use crate::core::notifications::*;
+    use crate::testing::LocalS3Server;
+    use crate::{error::Error, Minio};
+    use std::time::Duration;
+
+    async fn next_event(
+        stream: &mut NotificationStream,
+    ) -> Option<Result<NotificationEvent, Error>> {
+        tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("No notification within 5 seconds")
+    }
+
+    async fn wait_for_listeners(server: &LocalS3Server, count: usize) {
+        for _ in 0..200 {
+            if server.listener_count() == count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(25)).await;
+        }
+
+        panic!("The server never had {} listeners", count);
+    }
+
+    #[tokio::test]
+    async fn test_listen_bucket_notifications() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+
+        minio.create_bucket("sharks").await.unwrap();
+
+        let mut stream = minio.listen_bucket_notifications(
+            "sharks",
+            "images/",
+            ".jpg",
+            &[EventType::ObjectCreatedAll, EventType::ObjectRemovedDelete],
+        );
+        wait_for_listeners(&server, 1).await;
+
+        // Filtered by the prefix, suffix or event
+        for object_name in ["videos/shark.jpg", "images/shark.png"] {
+            minio
+                .upload_object("sharks", object_name, [0u8; 5].as_slice(), None)
+                .await
+                .unwrap();
+        }
+        minio
+            .upload_object(
+                "sharks",
+                "images/great white+1.jpg",
+                [0u8; 10].as_slice(),
+                None,
+            )
+            .await
+            .unwrap();
+        minio
+            .stat_object("sharks", "images/great white+1.jpg", None)
+            .await
+            .unwrap();
+        minio
+            .delete_object("sharks", "images/great white+1.jpg")
+            .await
+            .unwrap();
+
+        let created = next_event(&mut stream).await.unwrap().unwrap();
+        assert_eq!(created.event_name, EventType::ObjectCreatedPut);
+        assert!(created.event_name.is_object_created());
+        assert_eq!(created.bucket_name, "sharks");
+        assert_eq!(created.principal_id, "minioadmin");
+        assert_eq!(created.object.key, "images/great white+1.jpg");
+        assert_eq!(created.object.size, Some(10));
+        assert!(created.object.e_tag.is_some());
+        assert!(!created.object.sequencer.is_empty());
+
+        let removed = next_event(&mut stream).await.unwrap().unwrap();
+        assert_eq!(removed.event_name, EventType::ObjectRemovedDelete);
+        assert!(removed.event_name.is_object_removed());
+        assert_eq!(removed.object.key, "images/great white+1.jpg");
+        assert_eq!(removed.object.size, None);
+
+        // Keep-alives are not events
+        assert!(
+            tokio::time::timeout(Duration::from_millis(1_200), stream.next())
+                .await
+                .is_err()
+        );
+
+        drop(stream);
+        wait_for_listeners(&server, 0).await;
+    }
+
+    #[tokio::test]
+    async fn test_listen_bucket_notifications_reconnect() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+
+        minio.create_bucket("sharks").await.unwrap();
+
+        let mut stream =
+            minio.listen_bucket_notifications("sharks", "", "", &[EventType::ObjectCreatedAll]);
+        wait_for_listeners(&server, 1).await;
+
+        server.disconnect_listeners();
+        wait_for_listeners(&server, 0).await;
+        wait_for_listeners(&server, 1).await;
+
+        minio
+            .upload_object("sharks", "shark.jpg", [0u8; 5].as_slice(), None)
+            .await
+            .unwrap();
+
+        // The dropped connection is not an error
+        let event = next_event(&mut stream).await.unwrap().unwrap();
+        assert_eq!(event.event_name, EventType::ObjectCreatedPut);
+        assert_eq!(event.object.key, "shark.jpg");
+    }
+
+    #[tokio::test]
+    async fn test_listen_bucket_notifications_rejected() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+
+        let mut stream =
+            minio.listen_bucket_notifications("orcas", "", "", &[EventType::ObjectCreatedAll]);
+        assert!(matches!(
+            next_event(&mut stream).await,
+            Some(Err(Error::SdkError(_)))
+        ));
+        assert!(next_event(&mut stream).await.is_none());
+
+        let minio = Minio {
+            endpoint_url: None,
+            endpoint: None,
+            ..server.minio()
+        };
+        let mut stream =
+            minio.listen_bucket_notifications("sharks", "", "", &[EventType::ObjectCreatedAll]);
+        assert!(matches!(
+            next_event(&mut stream).await,
+            Some(Err(Error::Internal(_)))
+        ));
+        assert!(next_event(&mut stream).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_listen_bucket_notifications_unreachable() {
+        let mut stream = listen_bucket_notifications(
+            "http://127.0.0.1:1",
+            LocalS3Server::start()
+                .await
+                .unwrap()
+                .minio()
+                .credentials_provider()
+                .cloned()
+                .unwrap(),
+            "us-east-1",
+            "sharks",
+            "",
+            "",
+            &[EventType::ObjectCreatedAll],
+        );
+
+        // Retried rather than ended
+        assert!(next_event(&mut stream).await.unwrap().is_err());
+        assert!(next_event(&mut stream).await.unwrap().is_err());
+    }
+
+    #[test]
+    fn test_event_type() {
+        for event_type in [
+            EventType::ObjectCreatedAll,
+            EventType::ObjectCreatedCompleteMultipartUpload,
+            EventType::ObjectRemovedDeleteMarkerCreated,
+            EventType::ObjectAccessedHead,
+            EventType::Other("s3:ObjectCreated:PutTagging".to_string()),
+        ] {
+            assert_eq!(EventType::from(event_type.as_str()), event_type);
+        }
+
+        let put_tagging = EventType::from("s3:ObjectCreated:PutTagging");
+        assert!(put_tagging.is_object_created());
+        assert!(!put_tagging.is_object_removed());
+        assert!(EventType::ObjectAccessedGet.is_object_accessed());
+    }
+}
@@ -0,0 +1,107 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "post_policy")]
+#[cfg(test)]
+mod tests {
+    use crate::core::upload::upload_object_presigned_post::PostPolicy;
+    use crate::test_error;
+    use crate::tests::util::{test_client::TestClient, *};
+    use reqwest::multipart::{Form, Part};
+
+    fn policy_form(form_data: &std::collections::HashMap<String, String>, bytes: Vec<u8>) -> Form {
+        let mut form = Form::new();
+
+        for (field, value) in form_data {
+            form = form.text(field.clone(), value.clone());
+        }
+
+        form.part("file", Part::bytes(bytes))
+    }
+
+    #[tokio::test]
+    async fn test_upload_presigned_post() {
+        let object_name = "shark.png";
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                let file_bytes = get_test_file_bytes(object_name).await?;
+                let reqwest_client = reqwest::Client::new();
+
+                let policy = PostPolicy::new(&bucket_name, 1_337)
+                    .key(object_name)
+                    .content_type("image/png")
+                    .content_length_range(1, file_bytes.len())
+                    .metadata("uploaded-by", "minior")
+                    .success_action_status(201);
+
+                let presigned_post = minio.upload_object_presigned_post(policy).await?;
+
+                let response = reqwest_client
+                    .post(&presigned_post.url)
+                    .multipart(policy_form(&presigned_post.form_data, file_bytes.clone()))
+                    .send()
+                    .await?;
+
+                if response.status().as_u16() != 201 {
+                    test_error!("Presigned POST returned status {}", response.status());
+                }
+
+                assert_object(
+                    &minio,
+                    &bucket_name,
+                    object_name,
+                    ObjectAssertions::BytesEqual(file_bytes),
+                )
+                .await?;
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_upload_presigned_post_rejected() {
+        let object_name = "shark.png";
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                let file_bytes = get_test_file_bytes(object_name).await?;
+                let reqwest_client = reqwest::Client::new();
+
+                let policy = PostPolicy::new(&bucket_name, 1_337)
+                    .key_starts_with("avatars/")
+                    .content_length_range(1, 1_024);
+
+                let presigned_post = minio.upload_object_presigned_post(policy).await?;
+
+                let mut form_data = presigned_post.form_data.clone();
+                form_data.insert("key".to_string(), format!("avatars/{}", object_name));
+
+                let response = reqwest_client
+                    .post(&presigned_post.url)
+                    .multipart(policy_form(&form_data, file_bytes))
+                    .send()
+                    .await?;
+
+                if response.status().is_success() {
+                    test_error!("Presigned POST accepted an upload over the content length range");
+                }
+
+                assert_object(
+                    &minio,
+                    &bucket_name,
+                    &format!("avatars/{}", object_name),
+                    ObjectAssertions::DoesNotExist,
+                )
+                .await?;
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+}
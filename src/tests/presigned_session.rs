@@ -0,0 +1,318 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+use super::util::{test_client::TestClient, *};
+use crate::{core::presign::PresignedUrl, test_error};
+
+async fn put_part(
+    reqwest_client: &reqwest::Client,
+    part_request: &PresignedUrl,
+    bytes: Vec<u8>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let response = reqwest_client
+        .put(&part_request.url)
+        .body(bytes)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        test_error!("Part upload returned status {}", response.status());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_presigned_session() {
+    let object_name = "shark.png";
+    let test_client = TestClient::new().await;
+
+    test_client
+        .run_test(|minio, bucket_name| async move {
+            let reqwest_client = reqwest::Client::new();
+            let first_bytes = vec![7u8; 5_242_880];
+            let second_bytes = get_test_file_bytes(object_name).await?;
+
+            let mut session = minio
+                .upload_object_multi_presigned_session(&bucket_name, object_name, None)
+                .await?;
+
+            let part_requests = session.next_parts(&minio.client, 2, 1_337).await?;
+
+            if part_requests.iter().map(|(_, n)| *n).collect::<Vec<_>>() != vec![1, 2] {
+                test_error!("next_parts did not number the parts sequentially");
+            }
+
+            // Parts may arrive out of order
+            put_part(&reqwest_client, &part_requests[1].0, second_bytes.clone()).await?;
+            put_part(&reqwest_client, &part_requests[0].0, first_bytes.clone()).await?;
+
+            if session.list_parts(&minio.client).await?.len() != 2 {
+                test_error!("list_parts did not reconcile both uploaded parts");
+            }
+
+            session.complete(&minio.client).await?;
+
+            let mut expected_bytes = first_bytes;
+            expected_bytes.extend_from_slice(&second_bytes);
+
+            assert_object(
+                &minio,
+                &bucket_name,
+                object_name,
+                ObjectAssertions::BytesEqual(expected_bytes),
+            )
+            .await?;
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[tokio::test]
+async fn test_presigned_session_serde() {
+    use crate::core::upload::upload_object_multi_presigned_session::PresignedMultipartSession;
+
+    let object_name = "shark.png";
+    let test_client = TestClient::new().await;
+
+    test_client
+        .run_test(|minio, bucket_name| async move {
+            let reqwest_client = reqwest::Client::new();
+            let file_bytes = get_test_file_bytes(object_name).await?;
+
+            let mut session = minio
+                .upload_object_multi_presigned_session(&bucket_name, object_name, None)
+                .await?;
+
+            let (part_request, _) = session
+                .next_parts(&minio.client, 1, 1_337)
+                .await?
+                .pop()
+                .ok_or(crate::error::Error::internal(
+                    "next_parts returned no parts",
+                ))?;
+
+            let stored_session = serde_json::to_string(&session)?;
+
+            put_part(&reqwest_client, &part_request, file_bytes.clone()).await?;
+
+            let restored_session: PresignedMultipartSession =
+                serde_json::from_str(&stored_session)?;
+
+            if restored_session != session {
+                test_error!("PresignedMultipartSession did not survive a serde round trip");
+            }
+
+            restored_session.complete(&minio.client).await?;
+
+            assert_object(
+                &minio,
+                &bucket_name,
+                object_name,
+                ObjectAssertions::BytesEqual(file_bytes),
+            )
+            .await?;
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "presigned_multipart")]
+#[tokio::test]
+async fn test_presigned_session_complete_presigned() {
+    let object_name = "shark.png";
+    let test_client = TestClient::new().await;
+
+    test_client
+        .run_test(|minio, bucket_name| async move {
+            let reqwest_client = reqwest::Client::new();
+            let file_bytes = get_test_file_bytes(object_name).await?;
+            let credentials = minio.credentials().await?;
+
+            let mut session = minio
+                .upload_object_multi_presigned_session(&bucket_name, object_name, None)
+                .await?;
+
+            let (part_request, _) = session
+                .next_parts(&minio.client, 1, 1_337)
+                .await?
+                .pop()
+                .ok_or(crate::error::Error::internal(
+                    "next_parts returned no parts",
+                ))?;
+
+            put_part(&reqwest_client, &part_request, file_bytes.clone()).await?;
+
+            let list_request = session
+                .list_parts_presigned(&minio.client, &credentials, 1_337)
+                .await?;
+
+            let list_body = reqwest_client
+                .get(&list_request.url)
+                .send()
+                .await?
+                .text()
+                .await?;
+
+            if !list_body.contains("<PartNumber>1</PartNumber>") {
+                test_error!("Presigned list parts did not list the uploaded part");
+            }
+
+            let completion = session
+                .complete_presigned(&minio.client, &credentials, 1_337)
+                .await?;
+
+            let response = reqwest_client
+                .post(&completion.request.url)
+                .body(completion.body)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                test_error!("Presigned complete returned status {}", response.status());
+            }
+
+            assert_object(
+                &minio,
+                &bucket_name,
+                object_name,
+                ObjectAssertions::BytesEqual(file_bytes),
+            )
+            .await?;
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "presigned_multipart")]
+#[tokio::test]
+async fn test_presigned_session_abort_presigned() {
+    let object_name = "shark.png";
+    let test_client = TestClient::new().await;
+
+    test_client
+        .run_test(|minio, bucket_name| async move {
+            let reqwest_client = reqwest::Client::new();
+            let credentials = minio.credentials().await?;
+
+            let session = minio
+                .upload_object_multi_presigned_session(&bucket_name, object_name, None)
+                .await?;
+
+            let abort_request = session
+                .abort_presigned(&minio.client, &credentials, 1_337)
+                .await?;
+
+            let response = reqwest_client.delete(&abort_request.url).send().await?;
+
+            if !response.status().is_success() {
+                test_error!("Presigned abort returned status {}", response.status());
+            }
+
+            if session.list_parts(&minio.client).await.is_ok() {
+                test_error!("Multipart upload still existed after a presigned abort");
+            }
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+}
+
+#[cfg(all(feature = "sse", feature = "testing", feature = "presigned_multipart"))]
+#[tokio::test]
+async fn test_presigned_session_sse_c() {
+    use crate::core::{
+        sse::Encryption, upload::upload_object_multi_presigned::PresignedMultipartAdditionalOptions,
+    };
+    use crate::testing::LocalS3Server;
+    use reqwest::header::{HeaderMap, HeaderName};
+
+    let server = LocalS3Server::start().await.unwrap();
+    let minio = server.minio();
+    let reqwest_client = reqwest::Client::new();
+    let credentials = minio.credentials().await.unwrap();
+
+    minio.create_bucket("sharks").await.unwrap();
+
+    let additional_options = PresignedMultipartAdditionalOptions {
+        encryption: Some(Encryption::SseC { key: [0; 32] }),
+    };
+    let mut upload_manager = minio
+        .upload_object_multi_presigned("sharks", "shark.jpg", Some(additional_options.clone()))
+        .await
+        .unwrap();
+    let mut session = minio
+        .upload_object_multi_presigned_session("sharks", "shark.png", Some(additional_options))
+        .await
+        .unwrap();
+
+    let (part_request, _) = session
+        .next_parts(&minio.client, 1, 1_337)
+        .await
+        .unwrap()
+        .remove(0);
+    let requests = [
+        upload_manager
+            .next_part(&minio.client, 1_337)
+            .await
+            .unwrap()
+            .0,
+        part_request.clone(),
+        session
+            .list_parts_presigned(&minio.client, &credentials, 1_337)
+            .await
+            .unwrap(),
+    ];
+
+    for request in &requests {
+        assert_eq!(
+            request
+                .headers
+                .get("x-amz-server-side-encryption-customer-key-md5")
+                .map(String::as_str),
+            Some("cLyPS3KoaSFGi/joRB3OUQ==")
+        );
+        assert!(request
+            .url
+            .contains("x-amz-server-side-encryption-customer-key%3B"));
+    }
+
+    // The SSE-C headers are signed, so must be sent with the part
+    let response = reqwest_client
+        .put(&part_request.url)
+        .body(vec![7u8; 1_024])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let headers =
+        part_request
+            .headers
+            .iter()
+            .fold(HeaderMap::new(), |mut headers, (name, value)| {
+                headers.insert(
+                    HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                    value.parse().unwrap(),
+                );
+                headers
+            });
+    let response = reqwest_client
+        .put(&part_request.url)
+        .headers(headers)
+        .body(vec![7u8; 1_024])
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    session.abort(&minio.client).await.unwrap();
+    upload_manager.abort(&minio.client).await.unwrap();
+}
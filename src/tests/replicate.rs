@@ -0,0 +1,247 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "replicate")]
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        replicate::{replicate, ReplicateMode, ReplicateOptions},
+        upload::upload_object::UploadObjectAdditionalOptions,
+    };
+    use crate::test_error;
+    use crate::tests::util::{test_client::TestClient, *};
+    use aws_sdk_s3::types::{Tag, Tagging};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_replicate() {
+        let src_test_client = TestClient::new().await;
+        let dst_test_client = TestClient::new().await;
+
+        src_test_client
+            .run_test(|src, src_bucket| async move {
+                dst_test_client
+                    .run_test(|dst, dst_bucket| async move {
+                        for object_name in ["a.txt", "b.txt", "images/c.txt"] {
+                            src.upload_object(
+                                &src_bucket,
+                                object_name,
+                                object_name.as_bytes(),
+                                Some(UploadObjectAdditionalOptions {
+                                    metadata: Some(HashMap::from([(
+                                        "species".to_string(),
+                                        "hammerhead".to_string(),
+                                    )])),
+                                    content_type: Some("text/plain".to_string()),
+                                    ..Default::default()
+                                }),
+                            )
+                            .await?;
+                        }
+
+                        src.client
+                            .put_object_tagging()
+                            .bucket(&src_bucket)
+                            .key("a.txt")
+                            .tagging(
+                                Tagging::builder()
+                                    .tag_set(Tag::builder().key("ocean").value("pacific").build()?)
+                                    .build()?,
+                            )
+                            .send()
+                            .await?;
+
+                        // An interrupted earlier run already replicated `b.txt`
+                        let checkpoint = std::env::temp_dir()
+                            .join(format!("minior-replicate-{}", uuid::Uuid::new_v4()));
+                        tokio::fs::write(&checkpoint, "b.txt\n").await?;
+
+                        let report = replicate(
+                            &src,
+                            &src_bucket,
+                            &dst,
+                            &dst_bucket,
+                            ReplicateOptions {
+                                mode: ReplicateMode::Stream,
+                                checkpoint: Some(checkpoint.clone()),
+                                ..Default::default()
+                            },
+                        )
+                        .await?;
+
+                        if report.copied != ["a.txt", "images/c.txt"] || report.skipped != 1 {
+                            test_error!("Unexpected replication {:?}", report);
+                        }
+
+                        if tokio::fs::metadata(&checkpoint).await.is_ok() {
+                            test_error!("Checkpoint was not removed");
+                        }
+
+                        assert_object(
+                            &dst,
+                            &dst_bucket,
+                            "images/c.txt",
+                            ObjectAssertions::BytesEqual(b"images/c.txt".to_vec()),
+                        )
+                        .await?;
+
+                        let stat = dst
+                            .stat_object(&dst_bucket, "a.txt", None)
+                            .await?
+                            .ok_or("Replicated object does not exist")?;
+
+                        if stat.metadata().and_then(|metadata| metadata.get("species"))
+                            != Some(&"hammerhead".to_string())
+                        {
+                            test_error!("Metadata was not preserved");
+                        }
+
+                        if stat.content_type() != Some("text/plain") {
+                            test_error!("Content-Type was not preserved");
+                        }
+
+                        let tags = dst
+                            .client
+                            .get_object_tagging()
+                            .bucket(&dst_bucket)
+                            .key("a.txt")
+                            .send()
+                            .await?;
+
+                        if tags.tag_set().first().map(|tag| tag.value()) != Some("pacific") {
+                            test_error!("Tags were not preserved");
+                        }
+
+                        let report = replicate(
+                            &src,
+                            &src_bucket,
+                            &dst,
+                            &dst_bucket,
+                            ReplicateOptions {
+                                mode: ReplicateMode::ServerSideCopy,
+                                ..Default::default()
+                            },
+                        )
+                        .await?;
+
+                        if report.copied != ["b.txt"] || report.skipped != 2 {
+                            test_error!("Identical objects were replicated {:?}", report);
+                        }
+
+                        assert_object(
+                            &dst,
+                            &dst_bucket,
+                            "b.txt",
+                            ObjectAssertions::BytesEqual(b"b.txt".to_vec()),
+                        )
+                        .await?;
+
+                        Ok(())
+                    })
+                    .await
+            })
+            .await
+            .unwrap();
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_replicate_checkpoint_line_breaks() {
+        use crate::testing::LocalS3Server;
+
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+
+        minio.create_bucket("sharks").await.unwrap();
+        minio.create_bucket("sharks-backup").await.unwrap();
+
+        for object_name in ["great\nwhite.txt", "great"] {
+            minio
+                .upload_object("sharks", object_name, object_name.as_bytes(), None)
+                .await
+                .unwrap();
+        }
+
+        // Only the key holding a line break was replicated, which
+        // must not be read back as the keys `great` and `white.txt`
+        let checkpoint =
+            std::env::temp_dir().join(format!("minior-replicate-{}", uuid::Uuid::new_v4()));
+        tokio::fs::write(&checkpoint, "great%0Awhite.txt\n")
+            .await
+            .unwrap();
+
+        let report = replicate(
+            &minio,
+            "sharks",
+            &minio,
+            "sharks-backup",
+            ReplicateOptions {
+                mode: ReplicateMode::ServerSideCopy,
+                checkpoint: Some(checkpoint.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.copied, ["great"]);
+        assert_eq!(report.skipped, 1);
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_replicate_stops_on_failure() {
+        use crate::testing::{Fault, FaultInjector, LocalS3Server};
+        use std::time::Duration;
+
+        let server = LocalS3Server::start().await.unwrap();
+        server.minio().create_bucket("sharks").await.unwrap();
+        server.minio().create_bucket("sharks-backup").await.unwrap();
+
+        for object_name in ["a.txt", "b.txt", "c.txt"] {
+            server
+                .minio()
+                .upload_object("sharks", object_name, object_name.as_bytes(), None)
+                .await
+                .unwrap();
+        }
+
+        let fault_injector = FaultInjector::new()
+            .fail_nth(
+                "CopyObject",
+                1,
+                Fault::Error {
+                    status: 403,
+                    code: "AccessDenied".to_string(),
+                },
+            )
+            .always("CopyObject", Fault::Latency(Duration::from_secs(1)));
+        let minio = fault_injector.attach(&server.minio());
+
+        let checkpoint =
+            std::env::temp_dir().join(format!("minior-replicate-{}", uuid::Uuid::new_v4()));
+
+        assert!(replicate(
+            &minio,
+            "sharks",
+            &minio,
+            "sharks-backup",
+            ReplicateOptions {
+                mode: ReplicateMode::ServerSideCopy,
+                concurrency: Some(1),
+                checkpoint: Some(checkpoint.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .is_err());
+
+        // The copies after the failed one were stopped, and the
+        // checkpoint is kept to resume from
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(fault_injector.requests("CopyObject") < 3);
+        assert!(tokio::fs::metadata(&checkpoint).await.is_ok());
+
+        tokio::fs::remove_file(&checkpoint).await.unwrap();
+    }
+}
@@ -0,0 +1,249 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "sse")]
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        compose::{ComposeObjectAdditionalOptions, ComposeSource},
+        get::StatObjectAdditionalOptions,
+        sse::{Encryption, SseHeaders},
+        upload::{
+            upload_object::UploadObjectAdditionalOptions,
+            upload_object_multi::UploadManagerAdditionalOptions,
+            upload_object_presigned::UploadObjectPresignedAdditionalOptions,
+        },
+    };
+    use crate::test_error;
+    use crate::tests::util::{test_client::TestClient, *};
+    use aws_sdk_s3::types::ServerSideEncryption;
+
+    #[test]
+    fn test_sse_c_headers() {
+        let sse = SseHeaders::from(Some(&Encryption::SseC { key: [0; 32] }));
+
+        assert_eq!(sse.customer_algorithm.as_deref(), Some("AES256"));
+        assert_eq!(
+            sse.customer_key.as_deref(),
+            Some("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=")
+        );
+        assert_eq!(
+            sse.customer_key_md5.as_deref(),
+            Some("cLyPS3KoaSFGi/joRB3OUQ==")
+        );
+        assert!(sse.server_side_encryption.is_none());
+
+        assert!(!format!("{:?}", Encryption::SseC { key: [0; 32] }).contains('0'));
+    }
+
+    #[test]
+    fn test_sse_kms_headers() {
+        let sse = SseHeaders::from(Some(&Encryption::SseKms {
+            key_id: Some("minior-key".to_string()),
+            context: Some("{\"tenant\":\"sharks\"}".to_string()),
+        }));
+
+        assert_eq!(
+            sse.server_side_encryption,
+            Some(ServerSideEncryption::AwsKms)
+        );
+        assert_eq!(sse.ssekms_key_id.as_deref(), Some("minior-key"));
+        assert_eq!(
+            sse.ssekms_encryption_context.as_deref(),
+            Some("eyJ0ZW5hbnQiOiJzaGFya3MifQ==")
+        );
+        assert!(sse.customer_key.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_upload_stat_sse_s3() {
+        let object_name = "shark.png";
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                let file = get_test_file(object_name).await?;
+                let file_bytes = get_test_file_bytes(object_name).await?;
+
+                minio
+                    .upload_object(
+                        &bucket_name,
+                        object_name,
+                        file,
+                        Some(UploadObjectAdditionalOptions {
+                            encryption: Some(Encryption::SseS3),
+                            ..Default::default()
+                        }),
+                    )
+                    .await?;
+
+                let stat = minio
+                    .stat_object(
+                        &bucket_name,
+                        object_name,
+                        Some(StatObjectAdditionalOptions::default()),
+                    )
+                    .await?
+                    .ok_or("stat_object returned None for an existing object")?;
+
+                if stat.server_side_encryption() != Some(&ServerSideEncryption::Aes256) {
+                    test_error!("Object was not encrypted with SSE-S3");
+                }
+
+                assert_object(
+                    &minio,
+                    &bucket_name,
+                    object_name,
+                    ObjectAssertions::BytesEqual(file_bytes),
+                )
+                .await?;
+
+                if minio
+                    .stat_object(&bucket_name, "whale_shark.png", None)
+                    .await?
+                    .is_some()
+                {
+                    test_error!("stat_object returned Some for a missing object");
+                }
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+
+    // Other features add fields to `UploadManagerAdditionalOptions`
+    #[allow(clippy::needless_update)]
+    #[tokio::test]
+    async fn test_upload_multi_compose_sse_s3() {
+        let object_name = "composed.bin";
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                let file_bytes = vec![7u8; 5_242_880];
+
+                let mut upload_manager = minio
+                    .upload_object_multi(
+                        &bucket_name,
+                        "part.bin",
+                        Some(UploadManagerAdditionalOptions {
+                            encryption: Some(Encryption::SseS3),
+                            ..Default::default()
+                        }),
+                    )
+                    .await?;
+
+                upload_manager
+                    .upload_part(&minio.client, file_bytes.clone())
+                    .await?;
+                upload_manager.complete(&minio.client).await?;
+
+                minio
+                    .compose_object(
+                        &bucket_name,
+                        object_name,
+                        vec![ComposeSource::new(&bucket_name, "part.bin")],
+                        Some(ComposeObjectAdditionalOptions {
+                            encryption: Some(Encryption::SseS3),
+                            ..Default::default()
+                        }),
+                    )
+                    .await?;
+
+                for name in ["part.bin", object_name] {
+                    let stat = minio
+                        .stat_object(&bucket_name, name, None)
+                        .await?
+                        .ok_or("stat_object returned None for an existing object")?;
+
+                    if stat.server_side_encryption() != Some(&ServerSideEncryption::Aes256) {
+                        test_error!("Object {} was not encrypted with SSE-S3", name);
+                    }
+                }
+
+                assert_object(
+                    &minio,
+                    &bucket_name,
+                    object_name,
+                    ObjectAssertions::BytesEqual(file_bytes),
+                )
+                .await?;
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bucket_encryption() {
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                if minio.get_bucket_encryption(&bucket_name).await?.is_some() {
+                    test_error!("New bucket had a default encryption");
+                }
+
+                minio
+                    .set_bucket_encryption(&bucket_name, Encryption::SseS3)
+                    .await?;
+
+                if minio.get_bucket_encryption(&bucket_name).await? != Some(Encryption::SseS3) {
+                    test_error!("Bucket default encryption was not SSE-S3");
+                }
+
+                if minio
+                    .set_bucket_encryption(&bucket_name, Encryption::SseC { key: [1; 32] })
+                    .await
+                    .is_ok()
+                {
+                    test_error!("set_bucket_encryption accepted SSE-C");
+                }
+
+                minio.delete_bucket_encryption(&bucket_name).await?;
+
+                if minio.get_bucket_encryption(&bucket_name).await?.is_some() {
+                    test_error!("Bucket default encryption was not deleted");
+                }
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_upload_presigned_sse_c() {
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                let presigned_url = minio
+                    .upload_object_presigned(
+                        &bucket_name,
+                        "shark.png",
+                        1_337,
+                        Some(UploadObjectPresignedAdditionalOptions {
+                            encryption: Some(Encryption::SseC { key: [0; 32] }),
+                            ..Default::default()
+                        }),
+                    )
+                    .await?;
+
+                if presigned_url
+                    .headers
+                    .get("x-amz-server-side-encryption-customer-key-md5")
+                    .map(String::as_str)
+                    != Some("cLyPS3KoaSFGi/joRB3OUQ==")
+                {
+                    test_error!("PresignedUrl headers did not bind the SSE-C key");
+                }
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+}
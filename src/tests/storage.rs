@@ -0,0 +1,259 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "testing")]
+#[cfg(test)]
+mod tests {
+    use crate::core::{
+        get::{GetObjectAdditionalOptions, StatObjectAdditionalOptions},
+        storage::ObjectStorage,
+        upload::upload_object::UploadObjectAdditionalOptions,
+    };
+    use crate::error::ErrorKind;
+    use crate::testing::{InMemoryStorage, LocalS3Server};
+    use crate::tests::util::read_file_stream;
+    use std::collections::HashMap;
+
+    async fn get_bytes(
+        storage: &impl ObjectStorage,
+        object_name: &str,
+        additional_options: Option<GetObjectAdditionalOptions>,
+    ) -> Option<Vec<u8>> {
+        let stream = storage
+            .get_object("sharks", object_name, additional_options)
+            .await
+            .unwrap()?;
+
+        Some(read_file_stream(stream).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_objects() {
+        let storage = InMemoryStorage::new();
+
+        assert!(storage.create_bucket("sharks").await.unwrap());
+        assert!(!storage.create_bucket("sharks").await.unwrap());
+        assert!(storage.bucket_exists("sharks").await.unwrap());
+        assert!(storage
+            .upload_object("whales", "whale.jpg", b"whale".as_slice(), None)
+            .await
+            .is_err());
+
+        storage
+            .upload_object(
+                "sharks",
+                "shark.jpg",
+                b"hammerhead".as_slice(),
+                Some(UploadObjectAdditionalOptions {
+                    metadata: Some(HashMap::from([(
+                        "species".to_string(),
+                        "hammerhead".to_string(),
+                    )])),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            get_bytes(&storage, "shark.jpg", None).await.unwrap(),
+            b"hammerhead"
+        );
+        assert_eq!(
+            get_bytes(
+                &storage,
+                "shark.jpg",
+                Some(GetObjectAdditionalOptions {
+                    range: Some((2, 5)),
+                    ..Default::default()
+                })
+            )
+            .await
+            .unwrap(),
+            b"mmer"
+        );
+        assert!(get_bytes(&storage, "whale_shark.jpg", None).await.is_none());
+
+        let stat = storage
+            .stat_object("sharks", "shark.jpg", None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(stat.content_length(), Some(10));
+        assert_eq!(stat.e_tag(), Some("\"bfb1d703cab9fe0906736f4e98864e2a\""));
+        assert_eq!(
+            stat.metadata().unwrap().get("species").map(String::as_str),
+            Some("hammerhead")
+        );
+
+        let objects = storage.list_bucket_objects("sharks").await.unwrap();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].key(), Some("shark.jpg"));
+
+        let presigned_url = storage
+            .get_object_presigned("sharks", "shark.jpg", 3_600, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(presigned_url.method, "GET");
+        assert!(presigned_url.expires_at.is_some());
+
+        assert!(storage.delete_bucket("sharks", false).await.is_err());
+
+        storage.delete_object("sharks", "shark.jpg").await.unwrap();
+        assert!(!storage.object_exists("sharks", "shark.jpg").await.unwrap());
+        assert!(storage.delete_bucket("sharks", false).await.unwrap());
+        assert!(storage.list_buckets().await.unwrap().is_empty());
+    }
+
+    // Other features add fields to `StatObjectAdditionalOptions`
+    #[allow(clippy::needless_update)]
+    #[tokio::test]
+    async fn test_in_memory_versioning() {
+        let storage = InMemoryStorage::new();
+
+        storage.create_bucket("sharks").await.unwrap();
+        storage.set_bucket_versioning("sharks", true).await.unwrap();
+
+        let mut version_ids = vec![];
+
+        for bytes in [b"first".as_slice(), b"second".as_slice()] {
+            storage
+                .upload_object("sharks", "shark.jpg", bytes, None)
+                .await
+                .unwrap();
+
+            version_ids.push(
+                storage
+                    .stat_object("sharks", "shark.jpg", None)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .version_id()
+                    .unwrap()
+                    .to_string(),
+            );
+        }
+
+        storage.delete_object("sharks", "shark.jpg").await.unwrap();
+
+        assert!(!storage.object_exists("sharks", "shark.jpg").await.unwrap());
+        assert_eq!(
+            get_bytes(
+                &storage,
+                "shark.jpg",
+                Some(GetObjectAdditionalOptions {
+                    version_id: Some(version_ids[0].clone()),
+                    ..Default::default()
+                })
+            )
+            .await
+            .unwrap(),
+            b"first"
+        );
+        assert!(storage
+            .stat_object(
+                "sharks",
+                "shark.jpg",
+                Some(StatObjectAdditionalOptions {
+                    version_id: Some(version_ids[1].clone()),
+                    ..Default::default()
+                })
+            )
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_multipart() {
+        let storage = InMemoryStorage::new();
+        storage.create_bucket("sharks").await.unwrap();
+
+        let upload_id = storage
+            .start_multipart_upload("sharks", "shark.bin")
+            .await
+            .unwrap();
+
+        let small_part = storage
+            .upload_part("sharks", "shark.bin", &upload_id, 1, vec![1; 1_337])
+            .await
+            .unwrap();
+        let last_part = storage
+            .upload_part("sharks", "shark.bin", &upload_id, 2, vec![2; 1_337])
+            .await
+            .unwrap();
+
+        assert!(storage
+            .complete_multipart_upload(
+                "sharks",
+                "shark.bin",
+                &upload_id,
+                vec![small_part, last_part],
+            )
+            .await
+            .is_err());
+
+        let upload_id = storage
+            .start_multipart_upload("sharks", "shark.bin")
+            .await
+            .unwrap();
+
+        let mut e_tags = vec![];
+        for (part_number, bytes) in [(2, vec![2; 1_337]), (1, vec![1; 5_242_880])] {
+            e_tags.push(
+                storage
+                    .upload_part("sharks", "shark.bin", &upload_id, part_number, bytes)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        storage
+            .complete_multipart_upload("sharks", "shark.bin", &upload_id, e_tags)
+            .await
+            .unwrap();
+
+        let bytes = get_bytes(&storage, "shark.bin", None).await.unwrap();
+        assert_eq!(bytes.len(), 5_242_880 + 1_337);
+        assert_eq!(bytes[5_242_880], 2);
+
+        let stat = storage
+            .stat_object("sharks", "shark.bin", None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stat.e_tag().unwrap().ends_with("-2\""));
+
+        assert!(storage
+            .upload_part("sharks", "shark.bin", &upload_id, 3, vec![])
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_error_kinds() {
+        let storage = InMemoryStorage::new();
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+
+        let in_memory_err = storage
+            .upload_object("whales", "whale.jpg", b"whale".as_slice(), None)
+            .await
+            .unwrap_err();
+        let minio_err =
+            ObjectStorage::upload_object(&minio, "whales", "whale.jpg", b"whale".as_slice(), None)
+                .await
+                .unwrap_err();
+
+        assert_eq!(in_memory_err.kind(), ErrorKind::SdkError);
+        assert_eq!(in_memory_err.kind(), minio_err.kind());
+
+        let upload_err = storage
+            .upload_part("whales", "whale.jpg", "missing", 1, vec![])
+            .await
+            .unwrap_err();
+        assert_eq!(upload_err.kind(), ErrorKind::SdkError);
+    }
+}
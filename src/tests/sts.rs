@@ -0,0 +1,273 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(all(feature = "sts", feature = "testing"))]
+#[cfg(test)]
+mod tests {
+    use crate::{core::sts::*, error::Error, testing::LocalStsServer};
+    use aws_sdk_s3::config::Credentials;
+    use std::time::{Duration, SystemTime};
+
+    fn read_test_data(name: &str) -> Vec<u8> {
+        std::fs::read(format!("test_data/sts/{}", name)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_assume_role() {
+        let server = LocalStsServer::start().await.unwrap();
+
+        let credentials = assume_role(
+            server.url(),
+            &StsIdentity::AssumeRole {
+                credentials: Credentials::new("minioadmin", "minioadmin", None, None, "test"),
+            },
+            &StsOptions {
+                duration_secs: Some(900),
+                policy: Some(r#"{"Version": "2012-10-17", "Statement": []}"#.to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(credentials.access_key_id(), "STS00000000000000001");
+        assert_eq!(credentials.secret_access_key(), "sts-secret-1");
+        assert_eq!(credentials.session_token(), Some("sts-token-1"));
+
+        let expires_in = credentials
+            .expiry()
+            .unwrap()
+            .duration_since(SystemTime::now())
+            .unwrap();
+        assert!(expires_in > Duration::from_secs(890) && expires_in <= Duration::from_secs(900));
+
+        let request = &server.requests()[0];
+        assert_eq!(request.params["Action"], "AssumeRole");
+        assert_eq!(request.params["Version"], "2011-06-15");
+        assert_eq!(request.params["DurationSeconds"], "900");
+        assert_eq!(
+            request.params["Policy"],
+            r#"{"Version": "2012-10-17", "Statement": []}"#
+        );
+
+        assert!(assume_role(
+            server.url(),
+            &StsIdentity::AssumeRole {
+                credentials: Credentials::new("minioadmin", "hammerhead", None, None, "test"),
+            },
+            &StsOptions::default(),
+        )
+        .await
+        .is_err());
+
+        let credentials = assume_role(
+            server.url(),
+            &StsIdentity::WebIdentity {
+                token: "eyJhbGciOi".to_string(),
+            },
+            &StsOptions {
+                role_arn: Some("arn:minio:iam:::role/sharks".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(credentials.access_key_id(), "STS00000000000000002");
+
+        let request = &server.requests()[2];
+        assert_eq!(request.params["Action"], "AssumeRoleWithWebIdentity");
+        assert_eq!(request.params["WebIdentityToken"], "eyJhbGciOi");
+        assert_eq!(request.params["RoleArn"], "arn:minio:iam:::role/sharks");
+
+        let path = std::env::temp_dir().join(format!("minior-sts-{}", uuid::Uuid::new_v4()));
+        tokio::fs::write(&path, "eyJyb3RhdGVk\n").await.unwrap();
+
+        assume_role(
+            server.url(),
+            &StsIdentity::WebIdentityTokenFile { path: path.clone() },
+            &StsOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            server.requests()[3].params["WebIdentityToken"],
+            "eyJyb3RhdGVk"
+        );
+
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assume_role(
+            server.url(),
+            &StsIdentity::Ldap {
+                username: "shark-job".to_string(),
+                password: "fin & tail".to_string(),
+            },
+            &StsOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let request = &server.requests()[4];
+        assert_eq!(request.params["Action"], "AssumeRoleWithLDAPIdentity");
+        assert_eq!(request.params["LDAPUsername"], "shark-job");
+        assert_eq!(request.params["LDAPPassword"], "fin & tail");
+
+        assert!(assume_role(
+            server.url(),
+            &StsIdentity::Certificate {
+                certificate_pem: read_test_data("client.pem"),
+                private_key_pem: read_test_data("client-key.pem"),
+            },
+            &StsOptions::default(),
+        )
+        .await
+        .is_err());
+        assert_eq!(server.requests().len(), 5);
+
+        server.respond_with_error("ExpiredToken", "The web identity token has expired");
+        let err = assume_role(
+            server.url(),
+            &StsIdentity::WebIdentity {
+                token: "eyJhbGciOi".to_string(),
+            },
+            &StsOptions::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(&err, Error::SdkError(message) if message.contains("ExpiredToken: The web identity token has expired"))
+        );
+
+        server.respond_with_credentials();
+        assert!(assume_role(
+            server.url(),
+            &StsIdentity::WebIdentity {
+                token: "eyJhbGciOi".to_string(),
+            },
+            &StsOptions::default(),
+        )
+        .await
+        .is_ok());
+    }
+
+    #[test]
+    fn test_endpoint_tls_config() {
+        use crate::core::http::{Endpoint, TlsOptions};
+        use std::sync::Arc;
+
+        let endpoint = Endpoint::parse("https://127.0.0.1:9000")
+            .unwrap()
+            .with_tls(TlsOptions {
+                ca_certificates_pem: Some(read_test_data("ca.pem")),
+                client_certificate: None,
+            });
+
+        // Built once, and shared by the clones of the endpoint
+        let tls_config = endpoint.tls_config().unwrap();
+        assert!(Arc::ptr_eq(&tls_config, &endpoint.tls_config().unwrap()));
+        assert!(Arc::ptr_eq(
+            &tls_config,
+            &endpoint.clone().tls_config().unwrap()
+        ));
+
+        let endpoint = endpoint.with_tls(TlsOptions::default());
+        assert!(!Arc::ptr_eq(&tls_config, &endpoint.tls_config().unwrap()));
+
+        let invalid = Endpoint::parse("https://127.0.0.1:9000")
+            .unwrap()
+            .with_tls(TlsOptions {
+                ca_certificates_pem: Some(b"-----BEGIN CERTIFICATE-----\nAAAA".to_vec()),
+                client_certificate: None,
+            });
+        assert!(invalid.tls_config().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_assume_role_with_certificate() {
+        let server = LocalStsServer::start_tls(
+            &read_test_data("server.pem"),
+            &read_test_data("server-key.pem"),
+            &read_test_data("ca.pem"),
+        )
+        .await
+        .unwrap();
+
+        let identity = StsIdentity::Certificate {
+            certificate_pem: read_test_data("client.pem"),
+            private_key_pem: read_test_data("client-key.pem"),
+        };
+        let options = StsOptions {
+            ca_certificates_pem: Some(read_test_data("ca.pem")),
+            ..Default::default()
+        };
+
+        let credentials = assume_role(server.url(), &identity, &options)
+            .await
+            .unwrap();
+        assert_eq!(credentials.access_key_id(), "STS00000000000000001");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.params["Action"], "AssumeRoleWithCertificate");
+        assert!(request.client_certificate);
+
+        // Other identities connect without a client certificate
+        assert!(assume_role(
+            server.url(),
+            &StsIdentity::WebIdentity {
+                token: "eyJhbGciOi".to_string(),
+            },
+            &options,
+        )
+        .await
+        .is_ok());
+        assert!(!server.requests()[1].client_certificate);
+
+        // The self-signed server is not trusted without its CA
+        assert!(assume_role(server.url(), &identity, &StsOptions::default())
+            .await
+            .is_err());
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sts_credentials() {
+        use crate::testing::LocalS3Server;
+
+        let server = LocalStsServer::start().await.unwrap();
+        let s3_server = LocalS3Server::start().await.unwrap();
+        let minio = s3_server.minio();
+
+        let provider = |duration_secs| {
+            StsCredentials::new(
+                server.url(),
+                StsIdentity::WebIdentity {
+                    token: "eyJhbGciOi".to_string(),
+                },
+            )
+            .with_options(StsOptions {
+                duration_secs: Some(duration_secs),
+                ..Default::default()
+            })
+        };
+
+        minio.set_credentials_provider(provider(3_600)).unwrap();
+        for _ in 0..3 {
+            assert_eq!(
+                minio.credentials().await.unwrap().access_key_id(),
+                "STS00000000000000001"
+            );
+        }
+
+        // Credentials expiring within the `EXPIRY_BUFFER` are refreshed on every use
+        minio.set_credentials_provider(provider(30)).unwrap();
+        assert_eq!(
+            minio.credentials().await.unwrap().access_key_id(),
+            "STS00000000000000002"
+        );
+        assert_eq!(
+            minio.credentials().await.unwrap().access_key_id(),
+            "STS00000000000000003"
+        );
+        assert_eq!(server.requests().len(), 3);
+    }
+}
@@ -0,0 +1,404 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "sync")]
+#[cfg(test)]
+mod tests {
+    use crate::core::sync::{
+        normalize_prefix, walk_dir, SyncCompare, SyncFilter, SyncOptions, SyncProgress,
+    };
+    use crate::test_error;
+    use crate::tests::util::{test_client::TestClient, *};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, UNIX_EPOCH};
+    use tokio::{fs, sync::mpsc};
+
+    async fn temp_dir(files: &[(&str, &[u8])]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("minior-sync-{}", uuid::Uuid::new_v4()));
+
+        for (relative_path, bytes) in files {
+            write_file(&dir, relative_path, bytes).await;
+        }
+
+        dir
+    }
+
+    async fn write_file(dir: &Path, relative_path: &str, bytes: &[u8]) {
+        let path = dir.join(relative_path);
+
+        fs::create_dir_all(path.parent().unwrap()).await.unwrap();
+        fs::write(path, bytes).await.unwrap();
+    }
+
+    #[test]
+    fn test_sync_filter() {
+        let filter = SyncFilter::new(&[], &["**/*.tmp".to_string()]).unwrap();
+
+        assert!(filter.matches("shark.jpg"));
+        assert!(filter.matches("images/shark.jpg"));
+        assert!(!filter.matches("shark.tmp"));
+        assert!(!filter.matches("images/shark.tmp"));
+
+        let filter = SyncFilter::new(&["images/*.jpg".to_string()], &[]).unwrap();
+
+        assert!(filter.matches("images/shark.jpg"));
+        assert!(!filter.matches("images/great-whites/shark.jpg"));
+        assert!(!filter.matches("shark.jpg"));
+
+        assert!(SyncFilter::new(&["images/[".to_string()], &[]).is_err());
+
+        assert_eq!(normalize_prefix(""), "");
+        assert_eq!(normalize_prefix("images"), "images/");
+        assert_eq!(normalize_prefix("images/"), "images/");
+    }
+
+    #[tokio::test]
+    async fn test_walk_dir() {
+        let dir = temp_dir(&[
+            ("shark.jpg", b"shark"),
+            ("images/great-whites/shark.jpg", b"great white"),
+            ("images/hammerhead.jpg", b"hammerhead"),
+        ])
+        .await;
+
+        let files = walk_dir(&dir).await.unwrap();
+
+        assert_eq!(
+            files.keys().map(String::as_str).collect::<Vec<&str>>(),
+            vec![
+                "images/great-whites/shark.jpg",
+                "images/hammerhead.jpg",
+                "shark.jpg"
+            ]
+        );
+        assert_eq!(files["images/hammerhead.jpg"].size, 10);
+
+        fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sync_dir_to_prefix() {
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                let dir = temp_dir(&[
+                    ("shark.jpg", b"shark"),
+                    ("images/hammerhead.jpg", b"hammerhead"),
+                    ("images/scratch.tmp", b"scratch"),
+                ])
+                .await;
+                let options = SyncOptions {
+                    exclude: vec!["**/*.tmp".to_string()],
+                    ..Default::default()
+                };
+
+                let report = minio
+                    .sync_dir_to_prefix(&dir, &bucket_name, "backup", options.clone())
+                    .await?;
+
+                if report.transferred != ["images/hammerhead.jpg", "shark.jpg"]
+                    || report.bytes_transferred != 15
+                {
+                    test_error!("Unexpected first sync {:?}", report);
+                }
+
+                assert_object(
+                    &minio,
+                    &bucket_name,
+                    "backup/images/hammerhead.jpg",
+                    ObjectAssertions::BytesEqual(b"hammerhead".to_vec()),
+                )
+                .await?;
+
+                let report = minio
+                    .sync_dir_to_prefix(&dir, &bucket_name, "backup", options.clone())
+                    .await?;
+
+                if !report.transferred.is_empty() || report.unchanged != 2 {
+                    test_error!("Unchanged files were synced {:?}", report);
+                }
+
+                write_file(&dir, "shark.jpg", b"SHARK").await;
+                fs::remove_file(dir.join("images/hammerhead.jpg")).await?;
+
+                for compare in [SyncCompare::Size, SyncCompare::Checksum] {
+                    let report = minio
+                        .sync_dir_to_prefix(
+                            &dir,
+                            &bucket_name,
+                            "backup",
+                            SyncOptions {
+                                compare,
+                                delete_extras: true,
+                                dry_run: true,
+                                ..options.clone()
+                            },
+                        )
+                        .await?;
+
+                    let expected_transferred: &[&str] = match compare {
+                        SyncCompare::Size => &[],
+                        _ => &["shark.jpg"],
+                    };
+
+                    if report.transferred != expected_transferred
+                        || report.deleted != ["images/hammerhead.jpg"]
+                    {
+                        test_error!("Unexpected dry run by {:?} {:?}", compare, report);
+                    }
+                }
+
+                if minio
+                    .list_objects_with_prefix(&bucket_name, "backup/")
+                    .await?
+                    .len()
+                    != 2
+                {
+                    test_error!("Dry run changed the bucket");
+                }
+
+                minio
+                    .sync_dir_to_prefix(
+                        &dir,
+                        &bucket_name,
+                        "backup",
+                        SyncOptions {
+                            delete_extras: true,
+                            ..options
+                        },
+                    )
+                    .await?;
+
+                assert_object(
+                    &minio,
+                    &bucket_name,
+                    "backup/shark.jpg",
+                    ObjectAssertions::BytesEqual(b"SHARK".to_vec()),
+                )
+                .await?;
+
+                assert_object(
+                    &minio,
+                    &bucket_name,
+                    "backup/images/hammerhead.jpg",
+                    ObjectAssertions::DoesNotExist,
+                )
+                .await?;
+
+                fs::remove_dir_all(&dir).await?;
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sync_prefix_to_dir() {
+        let test_client = TestClient::new().await;
+
+        test_client
+            .run_test(|minio, bucket_name| async move {
+                for (object_name, bytes) in [
+                    ("dataset/shark.jpg", b"shark".as_slice()),
+                    ("dataset/images/hammerhead.jpg", b"hammerhead".as_slice()),
+                    ("other/whale.jpg", b"whale".as_slice()),
+                ] {
+                    minio
+                        .upload_object(&bucket_name, object_name, bytes, None)
+                        .await?;
+                }
+
+                let dir = temp_dir(&[("stale.jpg", b"stale")]).await;
+                let (sender, mut receiver) = mpsc::unbounded_channel();
+
+                let report = minio
+                    .sync_prefix_to_dir(
+                        &bucket_name,
+                        "dataset",
+                        &dir,
+                        SyncOptions {
+                            compare: SyncCompare::ModifiedTime,
+                            delete_extras: true,
+                            progress: Some(sender),
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+
+                if report.transferred != ["images/hammerhead.jpg", "shark.jpg"]
+                    || report.deleted != ["stale.jpg"]
+                {
+                    test_error!("Unexpected first sync {:?}", report);
+                }
+
+                let mut progress: Vec<SyncProgress> = vec![];
+                while let Some(update) = receiver.recv().await {
+                    progress.push(update);
+                }
+
+                if progress.len() != 2 || progress.iter().all(|update| update.completed != 2) {
+                    test_error!("Unexpected progress {:?}", progress);
+                }
+
+                if fs::read(dir.join("images/hammerhead.jpg")).await? != b"hammerhead"
+                    || fs::metadata(dir.join("stale.jpg")).await.is_ok()
+                {
+                    test_error!("Directory does not match the prefix");
+                }
+
+                let object = minio
+                    .list_objects_with_prefix(&bucket_name, "dataset/shark.jpg")
+                    .await?
+                    .remove(0);
+                let modified = fs::metadata(dir.join("shark.jpg")).await?.modified()?;
+
+                if Some(modified.duration_since(UNIX_EPOCH)?.as_secs() as i64)
+                    != object
+                        .last_modified()
+                        .map(|last_modified| last_modified.secs())
+                {
+                    test_error!("Modified time was not preserved");
+                }
+
+                let report = minio
+                    .sync_prefix_to_dir(
+                        &bucket_name,
+                        "dataset",
+                        &dir,
+                        SyncOptions {
+                            compare: SyncCompare::ModifiedTime,
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+
+                if !report.transferred.is_empty() || report.unchanged != 2 {
+                    test_error!("Unchanged objects were synced {:?}", report);
+                }
+
+                // Same size, but older than the object
+                write_file(&dir, "shark.jpg", b"SHARK").await;
+                std::fs::File::options()
+                    .write(true)
+                    .open(dir.join("shark.jpg"))?
+                    .set_modified(UNIX_EPOCH + Duration::from_secs(1))?;
+
+                minio
+                    .sync_prefix_to_dir(&bucket_name, "dataset", &dir, SyncOptions::default())
+                    .await?;
+
+                if fs::read(dir.join("shark.jpg")).await? != b"shark" {
+                    test_error!("Changed file was not restored");
+                }
+
+                fs::remove_dir_all(&dir).await?;
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_sync_dir_to_prefix_stops_on_failure() {
+        use crate::testing::{Fault, FaultInjector, LocalS3Server};
+
+        let server = LocalS3Server::start().await.unwrap();
+        server.minio().create_bucket("sharks").await.unwrap();
+
+        let fault_injector = FaultInjector::new()
+            .fail_nth(
+                "PutObject",
+                1,
+                Fault::Error {
+                    status: 403,
+                    code: "AccessDenied".to_string(),
+                },
+            )
+            .always("PutObject", Fault::Latency(Duration::from_secs(1)));
+        let minio = fault_injector.attach(&server.minio());
+
+        let dir = temp_dir(&[("a.jpg", b"a"), ("b.jpg", b"b"), ("c.jpg", b"c")]).await;
+
+        assert!(minio
+            .sync_dir_to_prefix(
+                &dir,
+                "sharks",
+                "",
+                SyncOptions {
+                    concurrency: Some(1),
+                    ..Default::default()
+                },
+            )
+            .await
+            .is_err());
+
+        // The uploads after the failed one were stopped rather than awaited
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(fault_injector.requests("PutObject") < 3);
+
+        fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_sync_prefix_to_dir_stops_on_failure() {
+        use crate::testing::{Fault, FaultInjector, LocalS3Server};
+
+        let server = LocalS3Server::start().await.unwrap();
+        server.minio().create_bucket("sharks").await.unwrap();
+
+        for object_name in ["a.jpg", "b.jpg", "c.jpg"] {
+            server
+                .minio()
+                .upload_object("sharks", object_name, b"shark".as_slice(), None)
+                .await
+                .unwrap();
+        }
+
+        let fault_injector = FaultInjector::new()
+            .fail_nth(
+                "GetObject",
+                1,
+                Fault::Error {
+                    status: 403,
+                    code: "AccessDenied".to_string(),
+                },
+            )
+            .always("GetObject", Fault::Latency(Duration::from_secs(1)));
+        let minio = fault_injector.attach(&server.minio());
+
+        let dir = temp_dir(&[]).await;
+        fs::create_dir_all(&dir).await.unwrap();
+
+        assert!(minio
+            .sync_prefix_to_dir(
+                "sharks",
+                "",
+                &dir,
+                SyncOptions {
+                    concurrency: Some(1),
+                    ..Default::default()
+                },
+            )
+            .await
+            .is_err());
+
+        // The downloads after the failed one were stopped, without
+        // leaving their temporary files behind
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(fault_injector.requests("GetObject") < 3);
+        assert!(fs::read_dir(&dir)
+            .await
+            .unwrap()
+            .next_entry()
+            .await
+            .unwrap()
+            .is_none());
+
+        fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
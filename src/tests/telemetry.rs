@@ -0,0 +1,192 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(all(feature = "tracing", feature = "testing"))]
+#[cfg(test)]
+mod tests {
+    use crate::testing::LocalS3Server;
+    use std::{
+        collections::HashMap,
+        fmt::Debug,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+    use tracing_core::span::Current;
+
+    #[derive(Debug, Clone)]
+    struct Recorded {
+        metadata: &'static Metadata<'static>,
+        fields: HashMap<String, String>,
+    }
+
+    /// Records every span and event, tracking the entered spans of the
+    /// single thread of the test runtime
+    #[derive(Debug, Clone, Default)]
+    struct Recorder {
+        spans: Arc<Mutex<Vec<Recorded>>>,
+        events: Arc<Mutex<Vec<Recorded>>>,
+        entered: Arc<Mutex<Vec<Id>>>,
+    }
+
+    struct FieldVisitor<'fv>(&'fv mut HashMap<String, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    impl Recorder {
+        fn spans(&self, name: &str) -> Vec<HashMap<String, String>> {
+            self.spans
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|span| span.metadata.name() == name)
+                .map(|span| span.fields.clone())
+                .collect()
+        }
+
+        /// The values of the spans and events of this crate
+        fn values(&self) -> Vec<String> {
+            let spans = self.spans.lock().unwrap();
+            let events = self.events.lock().unwrap();
+
+            spans
+                .iter()
+                .chain(events.iter())
+                .filter(|recorded| recorded.metadata.target().starts_with("minior"))
+                .flat_map(|recorded| recorded.fields.values().cloned())
+                .collect()
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+            let mut fields = HashMap::new();
+            attributes.record(&mut FieldVisitor(&mut fields));
+
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(Recorded {
+                metadata: attributes.metadata(),
+                fields,
+            });
+
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut spans = self.spans.lock().unwrap();
+            let span = &mut spans[span.into_u64() as usize - 1];
+
+            values.record(&mut FieldVisitor(&mut span.fields));
+        }
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = HashMap::new();
+            event.record(&mut FieldVisitor(&mut fields));
+
+            self.events.lock().unwrap().push(Recorded {
+                metadata: event.metadata(),
+                fields,
+            });
+        }
+
+        fn enter(&self, span: &Id) {
+            self.entered.lock().unwrap().push(span.clone());
+        }
+
+        fn exit(&self, span: &Id) {
+            let mut entered = self.entered.lock().unwrap();
+
+            if let Some(index) = entered.iter().rposition(|id| id == span) {
+                entered.remove(index);
+            }
+        }
+
+        fn current_span(&self) -> Current {
+            match self.entered.lock().unwrap().last() {
+                Some(id) => Current::new(
+                    id.clone(),
+                    self.spans.lock().unwrap()[id.into_u64() as usize - 1].metadata,
+                ),
+                None => Current::none(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tracing_spans() {
+        let recorder = Recorder::default();
+        let _guard = tracing::subscriber::set_default(recorder.clone());
+
+        let server = LocalS3Server::start_with_credentials("hammerhead", "great-white-secret")
+            .await
+            .unwrap();
+        let minio = server.minio();
+
+        minio.create_bucket("sharks").await.unwrap();
+        let bytes = minio
+            .upload_object("sharks", "whale.bin", vec![7; 6_000_000].as_slice(), None)
+            .await
+            .unwrap();
+        minio
+            .get_object_presigned("sharks", "whale.bin", 3_600, None)
+            .await
+            .unwrap()
+            .unwrap();
+        minio
+            .list_objects_with_prefix("sharks", "whale")
+            .await
+            .unwrap();
+        assert!(minio.delete_bucket("sharks", false).await.is_err());
+
+        let list_spans = recorder.spans("list_objects_with_prefix");
+        assert_eq!(list_spans.len(), 1);
+        assert_eq!(list_spans[0]["prefix"], "whale");
+
+        let upload_spans = recorder.spans("upload_object");
+        assert_eq!(upload_spans.len(), 1);
+        assert_eq!(upload_spans[0]["bucket"], "sharks");
+        assert_eq!(upload_spans[0]["key"], "whale.bin");
+        assert_eq!(upload_spans[0]["bytes"], bytes.to_string());
+
+        let mut part_spans = recorder.spans("upload_part");
+        part_spans.sort_by_key(|span| span["part_number"].clone());
+        assert_eq!(part_spans.len(), 2);
+        assert_eq!(part_spans[0]["part_number"], "1");
+        assert_eq!(part_spans[0]["bytes"], "5300000");
+        assert_eq!(part_spans[1]["part_number"], "2");
+        assert_eq!(part_spans[0]["upload_id"], part_spans[1]["upload_id"]);
+
+        let events = recorder.events.lock().unwrap().clone();
+        assert!(events
+            .iter()
+            .any(|event| event.fields.contains_key("request_id")
+                && event.fields.get("operation").map(String::as_str) == Some("UploadPart")));
+        assert!(events.iter().any(|event| event
+            .fields
+            .get("error")
+            .is_some_and(|error| error.contains("BucketNotEmpty"))));
+
+        assert!(recorder
+            .values()
+            .iter()
+            .all(|value| !value.contains("X-Amz-Signature") && !value.contains("great-white")));
+    }
+}
@@ -1,7 +1,14 @@
 // Authors: Robert Lopez
 // License: MIT (See `LICENSE.md`)
 use super::util::{test_client::TestClient, *};
-use crate::{error::Error, test_error, ETag};
+use crate::{
+    core::{
+        get::GetObjectPresignedAdditionalOptions,
+        upload::upload_object_presigned::UploadObjectPresignedAdditionalOptions,
+    },
+    error::Error,
+    test_error, ETag,
+};
 
 #[tokio::test]
 async fn test_upload_get() {
@@ -47,10 +54,10 @@ async fn test_upload_get_presigned() {
             let reqwest_client = reqwest::Client::new();
 
             let presigned_request = minio
-                .upload_object_presigned(&bucket_name, object_name, 1_337)
+                .upload_object_presigned(&bucket_name, object_name, 1_337, None)
                 .await?;
 
-            let upload_url = presigned_request.uri();
+            let upload_url = presigned_request.url;
 
             reqwest_client
                 .put(upload_url)
@@ -81,7 +88,9 @@ async fn test_upload_multi_get() {
         .run_test(|minio, bucket_name| async move {
             let file_bytes = get_test_file_bytes(object_name).await?;
 
-            let mut upload_manager = minio.upload_object_multi(&bucket_name, object_name).await?;
+            let mut upload_manager = minio
+                .upload_object_multi(&bucket_name, object_name, None)
+                .await?;
 
             upload_manager
                 .upload_part(&minio.client, file_bytes.clone())
@@ -120,13 +129,13 @@ async fn test_upload_multi_get_presigned() {
             let mut e_tags = vec![];
 
             let mut upload_manager = minio
-                .upload_object_multi_presigned(&bucket_name, object_name)
+                .upload_object_multi_presigned(&bucket_name, object_name, None)
                 .await?;
 
             let (presigned_request, part_number) =
                 upload_manager.next_part(&minio.client, 1_337).await?;
 
-            let upload_url = presigned_request.uri();
+            let upload_url = presigned_request.url;
 
             let e_tag = reqwest_client
                 .put(upload_url)
@@ -156,3 +165,172 @@ async fn test_upload_multi_get_presigned() {
         .await
         .unwrap();
 }
+
+#[tokio::test]
+async fn test_get_presigned_response_overrides() {
+    let object_name = "shark.png";
+    let test_client = TestClient::new().await;
+
+    test_client
+        .run_test(|minio, bucket_name| async move {
+            let file = get_test_file(object_name).await?;
+            let reqwest_client = reqwest::Client::new();
+
+            minio
+                .upload_object(&bucket_name, object_name, file, None)
+                .await?;
+
+            let content_disposition = "attachment; filename=\"great-white.png\"";
+
+            let presigned_request = minio
+                .get_object_presigned(
+                    &bucket_name,
+                    object_name,
+                    1_337,
+                    Some(GetObjectPresignedAdditionalOptions {
+                        response_content_disposition: Some(content_disposition.to_string()),
+                        response_content_type: Some("image/png".to_string()),
+                        endpoint_url: Some("http://localhost:9000".to_string()),
+                        ..Default::default()
+                    }),
+                )
+                .await?
+                .ok_or(Error::internal("Could not presign get"))?;
+
+            if !presigned_request.url.starts_with("http://localhost:9000") {
+                test_error!("Presigned get was not signed for the custom endpoint_url");
+            }
+
+            let response = reqwest_client.get(&presigned_request.url).send().await?;
+
+            if response
+                .headers()
+                .get("content-disposition")
+                .map(|v| v.as_bytes())
+                != Some(content_disposition.as_bytes())
+            {
+                test_error!("Presigned get did not override content-disposition");
+            }
+
+            if response.headers().get("content-type").map(|v| v.as_bytes())
+                != Some("image/png".as_bytes())
+            {
+                test_error!("Presigned get did not override content-type");
+            }
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_upload_presigned_bound_headers() {
+    let object_name = "shark.png";
+    let test_client = TestClient::new().await;
+
+    test_client
+        .run_test(|minio, bucket_name| async move {
+            let file_bytes = get_test_file_bytes(object_name).await?;
+            let reqwest_client = reqwest::Client::new();
+
+            let presigned_request = minio
+                .upload_object_presigned(
+                    &bucket_name,
+                    object_name,
+                    1_337,
+                    Some(UploadObjectPresignedAdditionalOptions {
+                        content_type: Some("image/png".to_string()),
+                        metadata: Some([("uploaded-by".to_string(), "minior".to_string())].into()),
+                        ..Default::default()
+                    }),
+                )
+                .await?;
+
+            let rejected_response = reqwest_client
+                .put(&presigned_request.url)
+                .header("content-type", "text/plain")
+                .body(file_bytes.clone())
+                .send()
+                .await?;
+
+            if rejected_response.status().is_success() {
+                test_error!("Presigned upload accepted a different content-type");
+            }
+
+            let mut request = reqwest_client
+                .put(&presigned_request.url)
+                .body(file_bytes.clone());
+
+            for (name, value) in &presigned_request.headers {
+                request = request.header(name, value);
+            }
+
+            if !request.send().await?.status().is_success() {
+                test_error!("Presigned upload rejected the bound headers");
+            }
+
+            assert_object(
+                &minio,
+                &bucket_name,
+                object_name,
+                ObjectAssertions::BytesEqual(file_bytes),
+            )
+            .await?;
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[tokio::test]
+async fn test_presigned_url_serde() {
+    let object_name = "shark.png";
+    let test_client = TestClient::new().await;
+
+    test_client
+        .run_test(|minio, bucket_name| async move {
+            let file = get_test_file(object_name).await?;
+            let file_bytes = get_test_file_bytes(object_name).await?;
+            let reqwest_client = reqwest::Client::new();
+
+            minio
+                .upload_object(&bucket_name, object_name, file, None)
+                .await?;
+
+            let presigned_url = minio
+                .get_object_presigned(&bucket_name, object_name, 1_337, None)
+                .await?
+                .ok_or(Error::internal("Could not presign get"))?;
+
+            let serialized = serde_json::to_string(&presigned_url)?;
+            let deserialized: crate::core::presign::PresignedUrl =
+                serde_json::from_str(&serialized)?;
+
+            if deserialized != presigned_url {
+                test_error!("PresignedUrl did not survive a serde round trip");
+            }
+
+            if deserialized.method != "GET" || deserialized.expires_at.is_none() {
+                test_error!("PresignedUrl had an unexpected method or expiry");
+            }
+
+            let downloaded_bytes = reqwest_client
+                .get(&deserialized.url)
+                .send()
+                .await?
+                .bytes()
+                .await?
+                .to_vec();
+
+            if downloaded_bytes != file_bytes {
+                test_error!("Deserialized PresignedUrl did not get the object");
+            }
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+}
@@ -0,0 +1,140 @@
+// Authors: Robert Lopez
+// License: MIT (See `LICENSE.md`)
+
+#[cfg(feature = "testing")]
+#[cfg(test)]
+mod tests {
+    use crate::core::{storage::ObjectStorage, usage::*};
+    use crate::testing::LocalS3Server;
+    use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+    use std::time::SystemTime;
+
+    #[tokio::test]
+    async fn test_usage() {
+        let server = LocalS3Server::start().await.unwrap();
+        let minio = server.minio();
+
+        server.storage.create_bucket("sharks").await.unwrap();
+
+        // Past the 1,000 objects of a single page
+        for i in 0..1_200 {
+            let tenant = if i % 3 == 0 { "tenant-1" } else { "tenant-2" };
+
+            server
+                .storage
+                .upload_object(
+                    "sharks",
+                    &format!("tenants/{}/images/{}.jpg", tenant, i),
+                    [0u8; 10].as_slice(),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+        for object_name in ["tenants/index.json", "readme.txt"] {
+            server
+                .storage
+                .upload_object("sharks", object_name, [0u8; 5].as_slice(), None)
+                .await
+                .unwrap();
+        }
+
+        let usage = minio.usage("sharks", "", None).await.unwrap();
+        assert_eq!(
+            usage.total,
+            UsageTotals {
+                objects: 1_202,
+                bytes: 12_010,
+            }
+        );
+        assert!(usage.groups.is_empty());
+
+        let usage = minio
+            .usage(
+                "sharks",
+                "tenants/",
+                Some(UsageOptions {
+                    group_by: Some(UsageGroupBy::PrefixDepth(1)),
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(usage.total.objects, 1_201);
+        assert_eq!(
+            usage.groups.into_iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    "tenants/".to_string(),
+                    UsageTotals {
+                        objects: 1,
+                        bytes: 5,
+                    }
+                ),
+                (
+                    "tenants/tenant-1/".to_string(),
+                    UsageTotals {
+                        objects: 400,
+                        bytes: 4_000,
+                    }
+                ),
+                (
+                    "tenants/tenant-2/".to_string(),
+                    UsageTotals {
+                        objects: 800,
+                        bytes: 8_000,
+                    }
+                ),
+            ]
+        );
+
+        let usage = minio
+            .usage(
+                "sharks",
+                "tenants/tenant-1/",
+                Some(UsageOptions {
+                    group_by: Some(UsageGroupBy::PrefixDepth(3)),
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            usage.groups.keys().collect::<Vec<_>>(),
+            vec!["tenants/tenant-1/images/"]
+        );
+
+        let usage = minio
+            .usage(
+                "sharks",
+                "",
+                Some(UsageOptions {
+                    group_by: Some(UsageGroupBy::StorageClass),
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(usage.groups["STANDARD"], usage.total);
+
+        let usage = minio
+            .usage(
+                "sharks",
+                "",
+                Some(UsageOptions {
+                    group_by: Some(UsageGroupBy::LastModified(UsageInterval::Month)),
+                }),
+            )
+            .await
+            .unwrap();
+        let this_month = DateTime::from(SystemTime::now())
+            .fmt(DateTimeFormat::DateTime)
+            .unwrap()[..7]
+            .to_string();
+        assert_eq!(usage.groups.len(), 1);
+        assert_eq!(usage.groups[&this_month], usage.total);
+
+        assert_eq!(
+            minio.usage("sharks", "orcas/", None).await.unwrap(),
+            Usage::default()
+        );
+        assert!(minio.usage("orcas", "", None).await.is_err());
+    }
+}
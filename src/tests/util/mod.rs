@@ -74,7 +74,7 @@ pub async fn assert_object<'ao>(
             test_error!("Object {} in Bucket: {} exists", object_name, bucket_name);
         }
         ObjectAssertions::BytesEqual(bytes) => {
-            if let Some(file_stream) = minio.get_object(&bucket_name, object_name).await? {
+            if let Some(file_stream) = minio.get_object(bucket_name, object_name, None).await? {
                 let downloaded_bytes = read_file_stream(file_stream).await?;
 
                 if bytes != downloaded_bytes {
@@ -94,10 +94,10 @@ pub async fn assert_object<'ao>(
         }
         ObjectAssertions::BytesEqualPresigned(bytes, reqwest_client) => {
             if let Some(presigned_request) = minio
-                .get_object_presigned(&bucket_name, object_name, 1_337)
+                .get_object_presigned(bucket_name, object_name, 1_337, None)
                 .await?
             {
-                let get_url = presigned_request.uri();
+                let get_url = presigned_request.url;
 
                 let downloaded_bytes = reqwest_client
                     .get(get_url)
@@ -17,7 +17,7 @@ impl TestClient {
         minio
             .create_bucket(&bucket_name)
             .await
-            .expect(&format!("Failed to create bucket: {}", bucket_name));
+            .unwrap_or_else(|_| panic!("Failed to create bucket: {}", bucket_name));
 
         Self {
             minio: Arc::new(minio),
@@ -29,7 +29,7 @@ impl TestClient {
         self.minio
             .delete_bucket(&self.bucket_name, true)
             .await
-            .expect(&format!("Failed to delete bucket: {}", self.bucket_name));
+            .unwrap_or_else(|_| panic!("Failed to delete bucket: {}", self.bucket_name));
     }
 
     pub async fn run_test<T, Fut>(self, test: T) -> Result<(), Box<dyn std::error::Error>>
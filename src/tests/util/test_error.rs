@@ -56,13 +56,13 @@ pub struct TestError(pub String);
 #[macro_export]
 macro_rules! test_error {
     ($fmt:expr $(, $arg:expr)*) => {
-        Err(crate::tests::util::test_error::TestError(format!($fmt $(, $arg)*)))?
+        Err($crate::tests::util::test_error::TestError(format!($fmt $(, $arg)*)))?
     };
 }
 
 impl fmt::Display for TestError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self)
+        write!(f, "{}", self.0)
     }
 }
 